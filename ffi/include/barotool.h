@@ -0,0 +1,93 @@
+/*
+ * A C interface to the barotool library, for reading and writing Barotrauma
+ * save files and submarines.
+ *
+ * Functions that can fail return NULL or -1 and record a message that can be
+ * fetched with barotool_last_error. Objects returned by the library must be
+ * released with the matching _free function. Paths are NUL-terminated strings
+ * in the platform's encoding on Unix and UTF-8 elsewhere.
+ */
+#ifndef BAROTOOL_H
+#define BAROTOOL_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A save file, read whole into memory. */
+typedef struct BarotoolSave BarotoolSave;
+
+/* A submarine. */
+typedef struct BarotoolSubmarine BarotoolSubmarine;
+
+/*
+ * Returns a message describing the most recent failure on the calling thread,
+ * or NULL if nothing has failed. The message remains valid until the next call
+ * into the library on the same thread.
+ */
+const char *barotool_last_error(void);
+
+/* Frees a string returned by the library. */
+void barotool_string_free(char *string);
+
+/* Reads a save file into memory. Returns NULL on failure. */
+BarotoolSave *barotool_save_open(const char *path);
+
+/* Frees a save file. */
+void barotool_save_free(BarotoolSave *save);
+
+/* Returns the number of members in a save file. */
+size_t barotool_save_member_count(const BarotoolSave *save);
+
+/*
+ * Returns the filename of a member of a save file, valid until the save is
+ * freed. Returns NULL if the index is out of range.
+ */
+const char *barotool_save_member_name(const BarotoolSave *save, size_t index);
+
+/*
+ * Returns the contents of a member of a save file, valid until the save is
+ * freed, and stores its size in *size. Returns NULL if the index is out of
+ * range; an empty member may also be returned as NULL with a size of zero.
+ */
+const uint8_t *barotool_save_member_data(const BarotoolSave *save, size_t index, size_t *size);
+
+/* Writes a member of a save file to a file. Returns 0 or -1. */
+int barotool_save_extract(const BarotoolSave *save, size_t index, const char *path);
+
+/*
+ * Packs count files into a new save file at path, each becoming a member named
+ * by its path as given. Returns 0 or -1.
+ */
+int barotool_save_pack(const char *path, const char *const *members, size_t count);
+
+/* Reads a submarine from a .sub file. Returns NULL on failure. */
+BarotoolSubmarine *barotool_submarine_load(const char *path);
+
+/*
+ * Parses a submarine from the gzip-compressed contents of a .sub file, such as
+ * a save file member. Returns NULL on failure.
+ */
+BarotoolSubmarine *barotool_submarine_load_bytes(const uint8_t *data, size_t size);
+
+/* Frees a submarine. */
+void barotool_submarine_free(BarotoolSubmarine *submarine);
+
+/* Writes a submarine to a .sub file. Returns 0 or -1. */
+int barotool_submarine_save(const BarotoolSubmarine *submarine, const char *path);
+
+/*
+ * Serializes a submarine to JSON, as a tree of elements with their attributes
+ * and children. The string must be freed with barotool_string_free. Returns
+ * NULL on failure.
+ */
+char *barotool_submarine_to_json(const BarotoolSubmarine *submarine);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,396 @@
+//! A C interface to the [barotool](https://gitlab.com/Hawk777/barotool) library, for programs in
+//! other languages that need to read Barotrauma save files and submarines.
+//!
+//! The declarations are in `include/barotool.h`. A save file is read whole into memory by
+//! [`barotool_save_open`], after which its members can be listed and extracted; a submarine is
+//! parsed by [`barotool_submarine_load`] or [`barotool_submarine_load_bytes`] and can be written
+//! back out or serialized to JSON.
+//!
+//! Functions that can fail return a null pointer or `-1`, and record a message describing the
+//! failure that can be fetched with [`barotool_last_error`]. Objects returned by the library must be
+//! released with the matching `_free` function.
+#![warn(
+	// Turn on extra language lints.
+	future_incompatible,
+	missing_abi,
+	missing_docs,
+	nonstandard_style,
+	rust_2018_idioms,
+	// Disabled due to <https://github.com/rust-lang/rust/issues/69952>.
+	// single_use_lifetimes,
+	trivial_casts,
+	trivial_numeric_casts,
+	unsafe_op_in_unsafe_fn,
+	unused,
+	unused_crate_dependencies,
+	unused_import_braces,
+	unused_lifetimes,
+	unused_qualifications,
+
+	// Turn on extra Rustdoc lints.
+	rustdoc::all,
+
+	// Turn on extra Clippy lints.
+	clippy::cargo,
+	clippy::pedantic,
+)]
+// Duplicate transitive dependency versions are outside this crate’s control.
+#![allow(clippy::multiple_crate_versions)]
+
+use barotool::save;
+use barotool::submarine::Submarine;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::os::raw::{c_char, c_int};
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::ptr;
+
+/// The result of an operation that may fail for any reason.
+type Outcome<T> = Result<T, Box<dyn std::error::Error>>;
+
+thread_local! {
+	/// The message describing the most recent failure on this thread.
+	static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Runs an operation, returning `failure` instead if it fails or panics.
+///
+/// A message describing the failure is recorded for [`barotool_last_error`]. Panics are caught
+/// because unwinding into a foreign caller is undefined behaviour.
+fn guard<T>(failure: T, f: impl FnOnce() -> Outcome<T>) -> T {
+	let message = match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+		Ok(Ok(value)) => return value,
+		Ok(Err(e)) => e.to_string(),
+		Err(_) => "Internal error in barotool".to_owned(),
+	};
+	let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+	LAST_ERROR.with(|i| *i.borrow_mut() = Some(message));
+	failure
+}
+
+/// Converts a C string naming a file into a path.
+///
+/// # Safety
+/// `path` must be null or point to a NUL-terminated string.
+unsafe fn path_of(path: *const c_char) -> Outcome<PathBuf> {
+	if path.is_null() {
+		return Err("Null path".into());
+	}
+	// SAFETY: the caller guarantees that a non-null path is NUL-terminated.
+	let path = unsafe { CStr::from_ptr(path) };
+	#[cfg(unix)]
+	{
+		use std::os::unix::ffi::OsStrExt;
+		Ok(std::ffi::OsStr::from_bytes(path.to_bytes()).into())
+	}
+	#[cfg(not(unix))]
+	{
+		Ok(path.to_str()?.into())
+	}
+}
+
+/// Borrows the object behind a handle.
+///
+/// # Safety
+/// `handle` must be null or have been returned by this library and not yet freed.
+unsafe fn borrow<'a, T>(handle: *const T) -> Outcome<&'a T> {
+	// SAFETY: the caller guarantees that a non-null handle points to a live object.
+	unsafe { handle.as_ref() }.ok_or_else(|| "Null handle".into())
+}
+
+/// Returns a message describing the most recent failure on the calling thread.
+///
+/// The message remains valid until the next call into the library on the same thread. If nothing
+/// has failed, null is returned.
+#[no_mangle]
+pub extern "C" fn barotool_last_error() -> *const c_char {
+	LAST_ERROR.with(|i| i.borrow().as_ref().map_or(ptr::null(), |i| i.as_ptr()))
+}
+
+/// Frees a string returned by the library.
+///
+/// # Safety
+/// `string` must be null or have been returned by this library and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_string_free(string: *mut c_char) {
+	if !string.is_null() {
+		// SAFETY: the caller guarantees that the string came from CString::into_raw.
+		drop(unsafe { CString::from_raw(string) });
+	}
+}
+
+/// A member of a save file held in memory.
+struct SaveMember {
+	/// The member’s filename.
+	name: CString,
+
+	/// The member’s contents.
+	data: Vec<u8>,
+}
+
+/// A save file, read whole into memory.
+pub struct BarotoolSave {
+	/// The members, in order.
+	members: Vec<SaveMember>,
+}
+
+impl BarotoolSave {
+	/// Returns the member at an index.
+	fn member(&self, index: usize) -> Outcome<&SaveMember> {
+		self.members.get(index).ok_or_else(|| {
+			format!(
+				"Member index {index} out of range; the save has {} members",
+				self.members.len()
+			)
+			.into()
+		})
+	}
+}
+
+/// Reads a save file into memory.
+///
+/// Returns null on failure.
+///
+/// # Safety
+/// `path` must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_save_open(path: *const c_char) -> *mut BarotoolSave {
+	guard(ptr::null_mut(), || {
+		// SAFETY: passed on from the caller.
+		let path = unsafe { path_of(path) }?;
+		let mut reader = save::open(path.as_os_str())?;
+		let mut members = Vec::new();
+		while let Some(mut member) = reader.next()? {
+			let name = CString::new(member.name())?;
+			let data = member.read_to_vec()?;
+			members.push(SaveMember { name, data });
+		}
+		Ok(Box::into_raw(Box::new(BarotoolSave { members })))
+	})
+}
+
+/// Frees a save file.
+///
+/// # Safety
+/// `save` must be null or have been returned by [`barotool_save_open`] and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_save_free(save: *mut BarotoolSave) {
+	if !save.is_null() {
+		// SAFETY: the caller guarantees that the save came from Box::into_raw.
+		drop(unsafe { Box::from_raw(save) });
+	}
+}
+
+/// Returns the number of members in a save file.
+///
+/// # Safety
+/// `save` must have been returned by [`barotool_save_open`] and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_save_member_count(save: *const BarotoolSave) -> usize {
+	// SAFETY: passed on from the caller.
+	guard(0, || Ok(unsafe { borrow(save) }?.members.len()))
+}
+
+/// Returns the filename of a member of a save file.
+///
+/// The name remains valid until the save is freed. Returns null if the index is out of range.
+///
+/// # Safety
+/// `save` must have been returned by [`barotool_save_open`] and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_save_member_name(
+	save: *const BarotoolSave,
+	index: usize,
+) -> *const c_char {
+	guard(ptr::null(), || {
+		// SAFETY: passed on from the caller.
+		Ok(unsafe { borrow(save) }?.member(index)?.name.as_ptr())
+	})
+}
+
+/// Returns the contents of a member of a save file, storing its size in bytes in `size`.
+///
+/// The contents remain valid until the save is freed. Returns null if the index is out of range;
+/// an empty member may also be returned as null, with a size of zero.
+///
+/// # Safety
+/// `save` must have been returned by [`barotool_save_open`] and not yet freed, and `size` must point
+/// to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_save_member_data(
+	save: *const BarotoolSave,
+	index: usize,
+	size: *mut usize,
+) -> *const u8 {
+	guard(ptr::null(), || {
+		// SAFETY: passed on from the caller.
+		let member = unsafe { borrow(save) }?.member(index)?;
+		if size.is_null() {
+			return Err("Null size pointer".into());
+		}
+		// SAFETY: the caller guarantees that size is writable.
+		unsafe { size.write(member.data.len()) };
+		Ok(member.data.as_ptr())
+	})
+}
+
+/// Writes a member of a save file to a file.
+///
+/// Returns 0 on success or -1 on failure.
+///
+/// # Safety
+/// `save` must have been returned by [`barotool_save_open`] and not yet freed, and `path` must
+/// point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_save_extract(
+	save: *const BarotoolSave,
+	index: usize,
+	path: *const c_char,
+) -> c_int {
+	guard(-1, || {
+		// SAFETY: passed on from the caller.
+		let member = unsafe { borrow(save) }?.member(index)?;
+		// SAFETY: passed on from the caller.
+		let path = unsafe { path_of(path) }?;
+		std::fs::write(path, &member.data)?;
+		Ok(0)
+	})
+}
+
+/// Packs files into a new save file.
+///
+/// Each file becomes a member named by its path, as given. Returns 0 on success or -1 on failure.
+///
+/// # Safety
+/// `path` must point to a NUL-terminated string, and `members` must point to `count` pointers to
+/// NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_save_pack(
+	path: *const c_char,
+	members: *const *const c_char,
+	count: usize,
+) -> c_int {
+	guard(-1, || {
+		// SAFETY: passed on from the caller.
+		let path = unsafe { path_of(path) }?;
+		let members: &[*const c_char] = if count == 0 {
+			&[]
+		} else if members.is_null() {
+			return Err("Null member list".into());
+		} else {
+			// SAFETY: the caller guarantees that members points to count pointers.
+			unsafe { std::slice::from_raw_parts(members, count) }
+		};
+		let names = members
+			.iter()
+			.map(|&i| {
+				if i.is_null() {
+					return Err("Null member name".into());
+				}
+				// SAFETY: the caller guarantees that each name is NUL-terminated.
+				Ok(unsafe { CStr::from_ptr(i) }.to_str()?)
+			})
+			.collect::<Outcome<Vec<&str>>>()?;
+		save::pack(path.as_os_str(), &names)?;
+		Ok(0)
+	})
+}
+
+/// A submarine.
+pub struct BarotoolSubmarine(Submarine);
+
+/// Reads a submarine from a `.sub` file.
+///
+/// Returns null on failure.
+///
+/// # Safety
+/// `path` must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_submarine_load(path: *const c_char) -> *mut BarotoolSubmarine {
+	guard(ptr::null_mut(), || {
+		// SAFETY: passed on from the caller.
+		let path = unsafe { path_of(path) }?;
+		let submarine = Submarine::parse(BufReader::new(File::open(path)?))?;
+		Ok(Box::into_raw(Box::new(BarotoolSubmarine(submarine))))
+	})
+}
+
+/// Parses a submarine from the gzip-compressed contents of a `.sub` file, such as a save file
+/// member.
+///
+/// Returns null on failure.
+///
+/// # Safety
+/// `data` must point to `size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_submarine_load_bytes(
+	data: *const u8,
+	size: usize,
+) -> *mut BarotoolSubmarine {
+	guard(ptr::null_mut(), || {
+		if data.is_null() {
+			return Err("Null data pointer".into());
+		}
+		// SAFETY: the caller guarantees that data points to size bytes.
+		let data = unsafe { std::slice::from_raw_parts(data, size) };
+		let submarine = Submarine::parse(data)?;
+		Ok(Box::into_raw(Box::new(BarotoolSubmarine(submarine))))
+	})
+}
+
+/// Frees a submarine.
+///
+/// # Safety
+/// `submarine` must be null or have been returned by [`barotool_submarine_load`] or
+/// [`barotool_submarine_load_bytes`] and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_submarine_free(submarine: *mut BarotoolSubmarine) {
+	if !submarine.is_null() {
+		// SAFETY: the caller guarantees that the submarine came from Box::into_raw.
+		drop(unsafe { Box::from_raw(submarine) });
+	}
+}
+
+/// Writes a submarine to a `.sub` file.
+///
+/// Returns 0 on success or -1 on failure.
+///
+/// # Safety
+/// `submarine` must be a live submarine returned by this library, and `path` must point to a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_submarine_save(
+	submarine: *const BarotoolSubmarine,
+	path: *const c_char,
+) -> c_int {
+	guard(-1, || {
+		// SAFETY: passed on from the caller.
+		let submarine = unsafe { borrow(submarine) }?;
+		// SAFETY: passed on from the caller.
+		let path = unsafe { path_of(path) }?;
+		let mut writer = BufWriter::new(File::create(path)?);
+		submarine.0.write(&mut writer)?;
+		writer.flush()?;
+		Ok(0)
+	})
+}
+
+/// Serializes a submarine to JSON, as a tree of elements with their attributes and children.
+///
+/// The string must be freed with [`barotool_string_free`]. Returns null on failure.
+///
+/// # Safety
+/// `submarine` must be a live submarine returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn barotool_submarine_to_json(
+	submarine: *const BarotoolSubmarine,
+) -> *mut c_char {
+	guard(ptr::null_mut(), || {
+		// SAFETY: passed on from the caller.
+		let submarine = unsafe { borrow(submarine) }?;
+		Ok(CString::new(serde_json::to_string(&submarine.0)?)?.into_raw())
+	})
+}
@@ -0,0 +1,284 @@
+//! Python bindings to the [barotool](https://gitlab.com/Hawk777/barotool) library, built as the
+//! `barotool` extension module.
+//!
+//! The module offers `Submarine`, which wraps [`barotool::submarine::Submarine`], and
+//! `ArchiveReader` and `ArchiveWriter`, which read and write the members of `.save` files. Lists of
+//! upgrades and items are returned as the plain dictionaries and lists that their JSON
+//! serialization would decode to. Failures of the underlying files raise `OSError`; anything wrong
+//! with the contents of a file raises `barotool.Error`.
+#![warn(
+	// Turn on extra language lints.
+	future_incompatible,
+	missing_abi,
+	missing_docs,
+	nonstandard_style,
+	rust_2018_idioms,
+	// Disabled due to <https://github.com/rust-lang/rust/issues/69952>.
+	// single_use_lifetimes,
+	trivial_casts,
+	trivial_numeric_casts,
+	unused,
+	unused_crate_dependencies,
+	unused_import_braces,
+	unused_lifetimes,
+	unused_qualifications,
+
+	// Turn on extra Rustdoc lints.
+	rustdoc::all,
+
+	// Turn on extra Clippy lints.
+	clippy::cargo,
+	clippy::pedantic,
+)]
+// Duplicate transitive dependency versions are outside this crate’s control.
+#![allow(clippy::multiple_crate_versions)]
+// Python passes arguments by value.
+#![allow(clippy::needless_pass_by_value)]
+
+use barotool::save;
+use barotool::submarine::Submarine;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+create_exception!(
+	barotool,
+	Error,
+	PyException,
+	"A save file or submarine that cannot be read or understood."
+);
+
+/// Converts a library error into a Python exception.
+///
+/// I/O errors become the matching `OSError` subclass, and everything else becomes `barotool.Error`.
+fn to_py_err(e: barotool::Error) -> PyErr {
+	match e {
+		barotool::Error::Io(e) => e.into(),
+		e => Error::new_err(e.to_string()),
+	}
+}
+
+/// Converts a value to Python objects by way of its JSON serialization.
+fn to_python<'py, T: Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+	let json = serde_json::to_string(value).map_err(|e| Error::new_err(e.to_string()))?;
+	py.import("json")?.call_method1("loads", (json,))
+}
+
+/// Locks a mutex, ignoring poisoning.
+///
+/// A panic while the lock was held has already been raised in Python as an exception; the reader
+/// or writer is then in no worse a state than after any other failure.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A submarine, as stored in a `.sub` file.
+#[pyclass(name = "Submarine", module = "barotool")]
+struct PySubmarine(Submarine);
+
+#[pymethods]
+impl PySubmarine {
+	/// Parses a submarine from the gzip-compressed contents of a `.sub` file, such as a save file
+	/// member.
+	#[staticmethod]
+	fn parse(data: &[u8]) -> PyResult<Self> {
+		Ok(Self(Submarine::parse(data).map_err(to_py_err)?))
+	}
+
+	/// Reads a submarine from a `.sub` file.
+	#[staticmethod]
+	fn load(path: PathBuf) -> PyResult<Self> {
+		let file = BufReader::new(File::open(path)?);
+		Ok(Self(Submarine::parse(file).map_err(to_py_err)?))
+	}
+
+	/// Parses a submarine from its JSON serialization.
+	#[staticmethod]
+	fn from_json(json: &str) -> PyResult<Self> {
+		Ok(Self(
+			serde_json::from_str(json).map_err(|e| Error::new_err(e.to_string()))?,
+		))
+	}
+
+	/// Returns the gzip-compressed contents of a `.sub` file holding the submarine.
+	fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+		let mut data = Vec::new();
+		self.0.write(&mut data).map_err(to_py_err)?;
+		Ok(PyBytes::new(py, &data))
+	}
+
+	/// Writes the submarine to a `.sub` file.
+	fn save(&self, path: PathBuf) -> PyResult<()> {
+		let mut file = BufWriter::new(File::create(path)?);
+		self.0.write(&mut file).map_err(to_py_err)?;
+		file.flush()?;
+		Ok(())
+	}
+
+	/// Serializes the submarine to JSON, as a tree of elements with their attributes and children.
+	fn to_json(&self) -> PyResult<String> {
+		serde_json::to_string(&self.0).map_err(|e| Error::new_err(e.to_string()))
+	}
+
+	/// Sets the name of the submarine.
+	fn set_name(&mut self, name: &str) {
+		self.0.set_name(name);
+	}
+
+	/// Returns the submarine-wide upgrades installed on the submarine, as dictionaries with
+	/// `identifier` and `level` keys.
+	fn upgrades<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+		to_python(py, &self.0.upgrades().map_err(to_py_err)?)
+	}
+
+	/// Returns the items placed in the submarine, as dictionaries.
+	fn items<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+		to_python(py, &self.0.items().map_err(to_py_err)?)
+	}
+
+	/// Sets the installed level of a submarine-wide upgrade; a level of zero removes it.
+	fn set_upgrade_level(&mut self, identifier: &str, level: u32) {
+		self.0.set_upgrade_level(identifier, level);
+	}
+
+	/// Copies upgrades and item state from an older revision of the submarine, returning the
+	/// numbers of upgrades and items copied.
+	fn carry_over_from(&mut self, old: PyRef<'_, Self>) -> (usize, usize) {
+		let carried = self.0.carry_over_from(&old.0);
+		(carried.upgrades, carried.items)
+	}
+}
+
+/// The source of a save file being read.
+type Source = Box<dyn Read + Send>;
+
+/// A save file being read, one member at a time.
+///
+/// Iterating yields `(name, data)` tuples in the order the members are stored.
+#[pyclass(name = "ArchiveReader", module = "barotool")]
+struct PyArchiveReader(Mutex<save::ArchiveReader<Source>>);
+
+#[pymethods]
+impl PyArchiveReader {
+	/// Starts reading a save file from a path or from its contents as bytes.
+	#[new]
+	fn new(source: &Bound<'_, PyAny>) -> PyResult<Self> {
+		let source: Source = if let Ok(data) = source.extract::<&[u8]>() {
+			Box::new(std::io::Cursor::new(data.to_vec()))
+		} else {
+			Box::new(BufReader::new(File::open(source.extract::<PathBuf>()?)?))
+		};
+		Ok(Self(Mutex::new(
+			save::ArchiveReader::new(source).map_err(to_py_err)?,
+		)))
+	}
+
+	fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+		slf
+	}
+
+	fn __next__<'py>(&self, py: Python<'py>) -> PyResult<Option<(String, Bound<'py, PyBytes>)>> {
+		let mut reader = lock(&self.0);
+		match reader.next().map_err(to_py_err)? {
+			Some(mut member) => {
+				let data = member.read_to_vec().map_err(to_py_err)?;
+				Ok(Some((member.name().to_owned(), PyBytes::new(py, &data))))
+			}
+			None => Ok(None),
+		}
+	}
+}
+
+/// The destination of a save file being written.
+enum Sink {
+	/// A file.
+	File(BufWriter<File>),
+
+	/// Memory, to be returned as bytes.
+	Memory(Vec<u8>),
+}
+
+impl Write for Sink {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		match self {
+			Self::File(file) => file.write(buf),
+			Self::Memory(data) => data.write(buf),
+		}
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		match self {
+			Self::File(file) => file.flush(),
+			Self::Memory(_) => Ok(()),
+		}
+	}
+}
+
+/// A save file being written, one member at a time.
+///
+/// The file is complete only once `finish` has been called.
+#[pyclass(name = "ArchiveWriter", module = "barotool")]
+struct PyArchiveWriter(Mutex<Option<save::ArchiveWriter<Sink>>>);
+
+impl PyArchiveWriter {
+	/// Returns an error for a writer that has already been finished.
+	fn finished() -> PyErr {
+		Error::new_err("The save file has already been finished")
+	}
+}
+
+#[pymethods]
+impl PyArchiveWriter {
+	/// Starts writing a save file to a path, or to memory if no path is given.
+	#[new]
+	#[pyo3(signature = (path=None))]
+	fn new(path: Option<PathBuf>) -> PyResult<Self> {
+		let sink = match path {
+			Some(path) => Sink::File(BufWriter::new(File::create(path)?)),
+			None => Sink::Memory(Vec::new()),
+		};
+		Ok(Self(Mutex::new(Some(
+			save::ArchiveWriter::new(sink).map_err(to_py_err)?,
+		))))
+	}
+
+	/// Writes a member.
+	fn write_member(&self, name: &str, data: &[u8]) -> PyResult<()> {
+		lock(&self.0)
+			.as_mut()
+			.ok_or_else(Self::finished)?
+			.write_member(name, data)
+			.map_err(to_py_err)
+	}
+
+	/// Completes the save file.
+	///
+	/// If the file was written to memory, its contents are returned; otherwise `None` is.
+	fn finish<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyBytes>>> {
+		let writer = lock(&self.0).take().ok_or_else(Self::finished)?;
+		match writer.finish().map_err(to_py_err)? {
+			Sink::File(mut file) => {
+				file.flush()?;
+				Ok(None)
+			}
+			Sink::Memory(data) => Ok(Some(PyBytes::new(py, &data))),
+		}
+	}
+}
+
+/// Reads and writes Barotrauma save files and submarines.
+#[pymodule]
+#[pyo3(name = "barotool")]
+fn barotool_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+	m.add("Error", m.py().get_type::<Error>())?;
+	m.add_class::<PySubmarine>()?;
+	m.add_class::<PyArchiveReader>()?;
+	m.add_class::<PyArchiveWriter>()?;
+	Ok(())
+}
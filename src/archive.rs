@@ -0,0 +1,1138 @@
+use crate::color::Style;
+use crate::diff;
+use crate::dryrun;
+use crate::editor;
+use crate::exit;
+use crate::output::{self, outln, Format};
+use crate::stream;
+use barotool::index::{self, Index};
+use barotool::save::{
+	self, ArchiveReader, ArchiveWriter, MemberFilter, Oversize, PackOptions, UnpackOptions,
+};
+use barotool::{compression, submarine};
+use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Result, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// Opens a save file for reading, or standard input if the filename is `-`.
+fn open(filename: &OsStr) -> Result<ArchiveReader<Box<dyn Read>>> {
+	Ok(ArchiveReader::new(stream::open_input(filename)?)?)
+}
+
+/// Opens a save file for reading, as [`open`] does, decoding member names lossily if asked to.
+fn open_lossy(filename: &OsStr, lossy_names: bool) -> Result<ArchiveReader<Box<dyn Read>>> {
+	let reader = open(filename)?;
+	Ok(if lossy_names {
+		reader.lossy_names()
+	} else {
+		reader
+	})
+}
+
+/// A member of a save file, as listed by [`list`].
+#[derive(Debug, Serialize)]
+struct Listed {
+	/// The member’s filename.
+	name: String,
+
+	/// The size of the member, in bytes.
+	size: usize,
+
+	/// The member’s position in the archive, counting from 0, whether or not those before it are
+	/// listed.
+	index: usize,
+
+	/// The member’s SHA-256 digest, in hexadecimal, if asked for.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	sha256: Option<String>,
+
+	/// The member’s CRC-32, in hexadecimal, if asked for.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	crc32: Option<String>,
+}
+
+/// Which digests of its members’ contents [`list`] should print.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Digests {
+	/// Whether to print the SHA-256 digest.
+	pub sha256: bool,
+
+	/// Whether to print the CRC-32.
+	pub crc32: bool,
+}
+
+/// Computes digests of data written to it.
+struct Hasher {
+	/// The SHA-256 digest so far, if asked for.
+	sha256: Option<Sha256>,
+
+	/// The CRC-32 so far, if asked for.
+	crc32: Option<crc32fast::Hasher>,
+}
+
+impl Write for Hasher {
+	fn write(&mut self, buf: &[u8]) -> Result<usize> {
+		if let Some(sha256) = &mut self.sha256 {
+			sha256.update(buf);
+		}
+		if let Some(crc32) = &mut self.crc32 {
+			crc32.update(buf);
+		}
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> Result<()> {
+		Ok(())
+	}
+}
+
+/// Lists the members of a save file chosen by a filter, with the digests asked for.
+///
+/// Each member is read in full only if a digest is asked for, and then one at a time, so no more
+/// than a buffer’s worth of it is held in memory. With `lossy_names`, member names that are not
+/// valid UTF-16 are listed with U+FFFD in place of what could not be decoded.
+pub fn list(
+	filename: &OsStr,
+	filter: &mut MemberFilter,
+	digests: Digests,
+	lossy_names: bool,
+	format: Format,
+) -> Result<()> {
+	let mut members = Vec::new();
+	let mut reader = open_lossy(filename, lossy_names)?;
+	let mut index = 0;
+	while let Some(mut member) = reader.next()? {
+		if filter.matches(member.name()) {
+			let mut hasher = Hasher {
+				sha256: digests.sha256.then(Sha256::new),
+				crc32: digests.crc32.then(crc32fast::Hasher::new),
+			};
+			if digests.sha256 || digests.crc32 {
+				std::io::copy(&mut member, &mut hasher)?;
+			}
+			members.push(Listed {
+				name: member.name().to_owned(),
+				size: member.size(),
+				index,
+				sha256: hasher.sha256.map(|i| format!("{:x}", i.finalize())),
+				crc32: hasher.crc32.map(|i| format!("{:08x}", i.finalize())),
+			});
+		}
+		index += 1;
+	}
+	output::emit(format, &members, |members| {
+		for member in members {
+			let mut line = format!("{}\t{}", member.name, Style::Number.paint(member.size));
+			for digest in member.sha256.iter().chain(&member.crc32) {
+				line.push('\t');
+				line.push_str(digest);
+			}
+			outln!("{line}");
+		}
+	})
+}
+
+/// Returns the name, size, and SHA-256 digest of each member of a save file, in order.
+fn digest_members(filename: &OsStr) -> Result<Vec<(String, usize, String)>> {
+	let mut members = Vec::new();
+	let mut reader = save::open(filename)?;
+	while let Some(mut member) = reader.next()? {
+		let mut hasher = Hasher {
+			sha256: Some(Sha256::new()),
+			crc32: None,
+		};
+		std::io::copy(&mut member, &mut hasher)?;
+		let digest = hasher.sha256.map(|i| format!("{:x}", i.finalize()));
+		members.push((
+			member.name().to_owned(),
+			member.size(),
+			digest.unwrap_or_default(),
+		));
+	}
+	Ok(members)
+}
+
+/// How a member differs between two save files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Change {
+	/// The member is only in the new save file.
+	Added,
+
+	/// The member is only in the old save file.
+	Removed,
+
+	/// The member is in both save files, with different contents.
+	Changed,
+}
+
+impl Change {
+	/// Returns the change’s name, as printed.
+	fn name(self) -> &'static str {
+		match self {
+			Self::Added => "added",
+			Self::Removed => "removed",
+			Self::Changed => "changed",
+		}
+	}
+
+	/// Returns the style to print the change in.
+	fn style(self) -> Style {
+		match self {
+			Self::Added => Style::Good,
+			Self::Removed => Style::Error,
+			Self::Changed => Style::Warning,
+		}
+	}
+}
+
+/// A member that differs between two save files, as printed by [`diff`].
+#[derive(Debug, Serialize)]
+struct MemberChange {
+	/// The member’s filename.
+	member: String,
+
+	/// How the member differs.
+	change: Change,
+
+	/// The member’s size in the old save file, in bytes, if it is there.
+	old_size: Option<usize>,
+
+	/// The member’s size in the new save file, in bytes, if it is there.
+	new_size: Option<usize>,
+}
+
+/// Returns the members that differ between two save files, given the name, size, and digest of
+/// each of their members: those added or changed in the new one’s order, then those removed in the
+/// old one’s.
+///
+/// Members are matched by name, and, should several share a name, in order.
+fn member_changes(
+	old: Vec<(String, usize, String)>,
+	new: Vec<(String, usize, String)>,
+) -> Vec<MemberChange> {
+	// Keys members by name and how many before them share it.
+	let keyed = |members: Vec<(String, usize, String)>| {
+		let mut counts: HashMap<String, usize> = HashMap::new();
+		members
+			.into_iter()
+			.map(|(name, size, digest)| {
+				let count = counts.entry(name.clone()).or_default();
+				*count += 1;
+				((name, *count), (size, digest))
+			})
+			.collect::<Vec<_>>()
+	};
+	let old = keyed(old);
+	let new = keyed(new);
+	let old_by_key: HashMap<_, _> = old.iter().map(|(k, v)| (k, v)).collect();
+	let new_keys: HashSet<_> = new.iter().map(|(k, _)| k).collect();
+	let mut changes = Vec::new();
+	for (key, (size, digest)) in &new {
+		match old_by_key.get(key) {
+			None => changes.push(MemberChange {
+				member: key.0.clone(),
+				change: Change::Added,
+				old_size: None,
+				new_size: Some(*size),
+			}),
+			Some((old_size, old_digest)) if old_digest != digest => changes.push(MemberChange {
+				member: key.0.clone(),
+				change: Change::Changed,
+				old_size: Some(*old_size),
+				new_size: Some(*size),
+			}),
+			Some(_) => (),
+		}
+	}
+	for (key, (size, _)) in &old {
+		if !new_keys.contains(key) {
+			changes.push(MemberChange {
+				member: key.0.clone(),
+				change: Change::Removed,
+				old_size: Some(*size),
+				new_size: None,
+			});
+		}
+	}
+	changes
+}
+
+/// The contents of a member as text, as returned by [`dryrun::text_of`], or `None` if the save
+/// file has no such member.
+type MemberText = Option<Option<String>>;
+
+/// Prints the differences between the old and new contents of a member as a unified diff, where
+/// `old_label` and `new_label` name the member in each save file.
+fn print_member_diff(
+	member: &str,
+	(old_label, old_text): (&str, &MemberText),
+	(new_label, new_text): (&str, &MemberText),
+) {
+	match (old_text, new_text) {
+		(Some(None), _) | (_, Some(None)) => {
+			outln!("Binary member {member} differs");
+		}
+		(old_text, new_text) => {
+			diff::print_unified(
+				if old_text.is_some() {
+					old_label
+				} else {
+					"/dev/null"
+				},
+				if new_text.is_some() {
+					new_label
+				} else {
+					"/dev/null"
+				},
+				old_text
+					.as_ref()
+					.and_then(Option::as_deref)
+					.unwrap_or_default(),
+				new_text
+					.as_ref()
+					.and_then(Option::as_deref)
+					.unwrap_or_default(),
+				dryrun::CONTEXT,
+			);
+		}
+	}
+}
+
+/// Compares the members of two save files, printing those added, removed, or changed, and failing
+/// with [`exit::DIFFERENCES`] if there are any.
+///
+/// Members are matched by name, and their contents compared by SHA-256 digest, so each save file is
+/// read once, a member at a time. With `text`, a unified diff of each differing member that is text
+/// is printed as well; XML members, including submarines, are first written the way barotool writes
+/// them, so that only real changes show.
+pub fn diff(old: &OsStr, new: &OsStr, text: bool, format: Format) -> Result<()> {
+	let changes = member_changes(digest_members(old)?, digest_members(new)?);
+	// The texts to compare are read up front, as printing cannot fail.
+	let mut texts: Vec<(MemberText, MemberText)> = Vec::new();
+	if text && format == Format::Text {
+		for change in &changes {
+			let text_of = |filename| -> Result<MemberText> {
+				Ok(save::read_member(filename, &change.member)?
+					.map(|data| dryrun::text_of(&change.member, &data)))
+			};
+			texts.push((text_of(old)?, text_of(new)?));
+		}
+	}
+	let old_file = Path::new(old).display().to_string();
+	let new_file = Path::new(new).display().to_string();
+	output::emit(format, &changes, |changes| {
+		if changes.is_empty() {
+			output::note("No differences");
+		}
+		for change in changes {
+			let sizes = match (change.old_size, change.new_size) {
+				(Some(old), Some(new)) => format!(
+					"{} → {}",
+					Style::Number.paint(old),
+					Style::Number.paint(new)
+				),
+				(Some(size), None) | (None, Some(size)) => Style::Number.paint(size),
+				(None, None) => String::new(),
+			};
+			outln!(
+				"{}\t{}\t{sizes}",
+				change.change.style().paint(change.change.name()),
+				change.member
+			);
+		}
+		for (change, (old_text, new_text)) in changes.iter().zip(&texts) {
+			print_member_diff(
+				&change.member,
+				(&format!("{old_file}/{}", change.member), old_text),
+				(&format!("{new_file}/{}", change.member), new_text),
+			);
+		}
+	})?;
+	if changes.is_empty() {
+		return Ok(());
+	}
+	Err(exit::error(
+		exit::DIFFERENCES,
+		format!("{} members differ", changes.len()),
+	))
+}
+
+/// A line of a save file member matching a pattern, as printed by [`grep`].
+#[derive(Debug, Serialize)]
+struct Match {
+	/// The member’s filename.
+	member: String,
+
+	/// The line’s number, counting from 1.
+	line: usize,
+
+	/// The line, without its line ending.
+	text: String,
+}
+
+/// Prints each line of the members of a save file chosen by a filter that matches a pattern.
+///
+/// Members are read a line at a time, so no more than a line of each is held in memory. Submarine
+/// members are decompressed, so that their XML is searched. Text that is not valid UTF-8 is searched
+/// with the invalid bytes replaced.
+pub fn grep(
+	filename: &OsStr,
+	pattern: &Regex,
+	filter: &mut MemberFilter,
+	format: Format,
+) -> Result<()> {
+	let mut matches = Vec::new();
+	let mut reader = open(filename)?;
+	while let Some(member) = reader.next()? {
+		let name = member.name().to_owned();
+		if !filter.matches(&name) {
+			continue;
+		}
+		let source: Box<dyn BufRead + '_> =
+			if Path::new(&name).extension() == Some(OsStr::new("sub")) {
+				Box::new(BufReader::new(compression::Decoder::new(member)?))
+			} else {
+				Box::new(BufReader::new(member))
+			};
+		for (index, line) in source.split(b'\n').enumerate() {
+			let line = line?;
+			let text = String::from_utf8_lossy(&line);
+			let text = text.strip_suffix('\r').unwrap_or(&text);
+			if pattern.is_match(text) {
+				matches.push(Match {
+					member: name.clone(),
+					line: index + 1,
+					text: text.to_owned(),
+				});
+			}
+		}
+	}
+	output::emit(format, &matches, |matches| {
+		for i in matches {
+			outln!(
+				"{}:{}:{}",
+				Style::Heading.paint(&i.member),
+				Style::Number.paint(i.line),
+				i.text
+			);
+		}
+	})
+}
+
+/// The integrity of a save file, as reported by [`verify`].
+#[derive(Debug, Serialize)]
+struct Integrity {
+	/// The number of members read intact.
+	members: usize,
+
+	/// The total size of the members read intact, in bytes.
+	size: u64,
+
+	/// Where the save file is damaged, if it is.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	damage: Option<DamageReport>,
+}
+
+/// Where a save file is damaged, as printed by [`verify`].
+#[derive(Debug, Serialize)]
+struct DamageReport {
+	/// The offset of the damage in the decompressed archive.
+	offset: u64,
+
+	/// The member whose data is damaged, if the damage is not in a header.
+	member: Option<String>,
+
+	/// The last member read intact before the damage, if any.
+	after: Option<String>,
+
+	/// What is wrong.
+	error: String,
+}
+
+impl DamageReport {
+	/// Describes where the damage is.
+	fn location(&self) -> String {
+		match (&self.member, &self.after) {
+			(Some(member), _) => format!("in member {member}"),
+			(None, Some(after)) => format!("after member {after}"),
+			(None, None) => "before the first member".to_owned(),
+		}
+	}
+}
+
+/// Checks the integrity of a save file, as [`save::verify`] does, printing what was found and
+/// failing if it is damaged.
+pub fn verify(filename: &OsStr, format: Format) -> Result<()> {
+	let verification = save::verify(stream::open_input(filename)?);
+	let after = verification.members.last().map(|i| i.name.clone());
+	let mut code = exit::SUCCESS;
+	let integrity = Integrity {
+		members: verification.members.len(),
+		// Cast is sound because u64 ≥ usize.
+		size: verification.members.iter().map(|i| i.size as u64).sum(),
+		damage: verification.damage.map(|damage| {
+			let error = std::io::Error::from(damage.error);
+			code = exit::code(&error);
+			DamageReport {
+				offset: damage.offset,
+				member: damage.member,
+				after,
+				error: error.to_string(),
+			}
+		}),
+	};
+	output::emit(format, &integrity, |integrity| match &integrity.damage {
+		None => outln!(
+			"{}: {} members, {} bytes",
+			Style::Good.paint("Intact"),
+			Style::Number.paint(integrity.members),
+			Style::Number.paint(integrity.size)
+		),
+		Some(damage) => outln!(
+			"{} at offset {} {}: {}",
+			Style::Error.paint("Damaged"),
+			Style::Number.paint(damage.offset),
+			damage.location(),
+			damage.error
+		),
+	})?;
+	match &integrity.damage {
+		None => Ok(()),
+		Some(damage) => Err(exit::error(
+			code,
+			format!(
+				"Save file is damaged at offset {} {}",
+				damage.offset,
+				damage.location()
+			),
+		)),
+	}
+}
+
+/// Packs a save file from files stored under the given member names, as [`save::pack_named`] does,
+/// or in a dry run reports what would be written.
+///
+/// Standard output cannot seek, so a save file written there cannot be segmented.
+pub fn pack(filename: &OsStr, members: &[(&str, &Path)], options: PackOptions) -> Result<()> {
+	if filename == "-" && options.segmented {
+		return Err(exit::error(
+			exit::USAGE,
+			"--segmented cannot be used with standard output".to_owned(),
+		));
+	}
+	if dryrun::is_enabled() {
+		for (_, path) in members {
+			let size = std::fs::metadata(path)?.len();
+			if size > save::MAX_MEMBER_SIZE && options.oversize == Oversize::Refuse {
+				return Err(barotool::Error::TooLarge(format!(
+					"{} is {size} bytes, more than a save file member can hold",
+					path.display()
+				))
+				.into());
+			}
+		}
+		dryrun::would(format!(
+			"write {} with {} member(s)",
+			Path::new(filename).display(),
+			members.len()
+		));
+		return Ok(());
+	}
+	if filename == "-" {
+		let sink = BufWriter::new(std::io::stdout().lock());
+		save::pack_into(sink, members, options.oversize)?.flush()?;
+		return Ok(());
+	}
+	Ok(save::pack_named(filename, members, options)?)
+}
+
+/// Returns every file within a directory and its subdirectories, in sorted order, as pairs of member
+/// name and path, each named by its path relative to the directory with `/` between components.
+pub fn dir_members(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+	/// Appends the files within a directory, named relative to `prefix`, to `members`.
+	fn walk(dir: &Path, prefix: &str, members: &mut Vec<(String, PathBuf)>) -> Result<()> {
+		let mut entries = std::fs::read_dir(dir)?
+			.map(|i| i.map(|i| i.path()))
+			.collect::<Result<Vec<PathBuf>>>()?;
+		entries.sort();
+		for entry in entries {
+			let file_name = entry.file_name().unwrap_or_default();
+			let Some(file_name) = file_name.to_str() else {
+				return Err(std::io::Error::new(
+					std::io::ErrorKind::InvalidInput,
+					format!("{} is not a valid Unicode name", entry.display()),
+				));
+			};
+			let name = format!("{prefix}{file_name}");
+			if entry.is_dir() {
+				walk(&entry, &format!("{name}/"), members)?;
+			} else {
+				log::trace!("Found {}", entry.display());
+				members.push((name, entry));
+			}
+		}
+		Ok(())
+	}
+
+	let mut members = Vec::new();
+	walk(dir, "", &mut members)?;
+	Ok(members)
+}
+
+/// Returns the index of a save file, as [`index::cached`] does, or fails if the save file is
+/// standard input, which cannot be indexed.
+fn cached_index(filename: &OsStr) -> Result<Index> {
+	if filename == "-" {
+		return Err(exit::error(
+			exit::USAGE,
+			"--index cannot be used with standard input".to_owned(),
+		));
+	}
+	Ok(index::cached(filename)?)
+}
+
+/// Writes the contents of a member of a save file to standard output, finding it through the save
+/// file’s index if `indexed` is set.
+///
+/// The member is streamed, so it is never held in memory whole. Standard output being closed early,
+/// as by a pager that was quit, is not an error.
+pub fn cat(filename: &OsStr, member: &str, indexed: bool) -> Result<()> {
+	let not_found = || {
+		std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!(
+				"{member} is not a member of {}",
+				Path::new(filename).display()
+			),
+		)
+	};
+	if indexed {
+		let index = cached_index(filename)?;
+		let entry = index.find(member).ok_or_else(not_found)?;
+		let mut reader = entry.open(BufReader::new(File::open(filename)?))?;
+		return write_stdout(&mut entry.member(&mut reader)?);
+	}
+	let mut reader = open(filename)?;
+	while let Some(mut entry) = reader.next()? {
+		if entry.name() == member {
+			return write_stdout(&mut entry);
+		}
+	}
+	Err(not_found())
+}
+
+/// Copies a member to standard output, treating standard output being closed early as success.
+fn write_stdout<R: Read>(member: &mut save::Member<'_, R>) -> Result<()> {
+	let mut stdout = BufWriter::new(std::io::stdout().lock());
+	let result = std::io::copy(member, &mut stdout).and_then(|_| stdout.flush());
+	match result {
+		Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+		result => result,
+	}
+}
+
+/// Unpacks a save file into a directory, or the current directory if `dir` is `None`, as
+/// [`save::unpack_to`] does, or in a dry run reports what would be extracted.
+///
+/// With `lossy_names`, member names that are not valid UTF-16 are decoded with U+FFFD in place of
+/// what could not be decoded, rather than making the rest of the save file unreadable. With
+/// `indexed`, only the members chosen are read, found through the save file’s index, as
+/// [`save::unpack_indexed`] does.
+pub fn unpack(
+	filename: &OsStr,
+	dir: Option<&Path>,
+	members: &mut MemberFilter,
+	options: UnpackOptions,
+	lossy_names: bool,
+	indexed: bool,
+) -> Result<()> {
+	if indexed && !dryrun::is_enabled() {
+		let index = cached_index(filename)?;
+		return Ok(save::unpack_indexed(
+			filename,
+			&index,
+			dir.unwrap_or_else(|| Path::new(".")),
+			members,
+			options,
+		)?);
+	}
+	let mut reader = open_lossy(filename, lossy_names)?;
+	if dryrun::is_enabled() {
+		while let Some(member) = reader.next()? {
+			let member = member.name();
+			if members.matches(member) {
+				let path = options.names.path(member).unwrap_or_default();
+				let path = dir.map_or_else(|| path.clone(), |dir| dir.join(&path));
+				if options.existing.should_extract(&path)? {
+					dryrun::would(format!("extract {}", path.display()));
+				} else {
+					dryrun::would(format!("skip {}, which already exists", path.display()));
+				}
+			}
+		}
+		return Ok(());
+	}
+	Ok(save::unpack_reader(
+		reader,
+		dir.unwrap_or_else(|| Path::new(".")),
+		members,
+		options,
+	)?)
+}
+
+/// Returns the size of a file to be stored as a member of a save file, or fails if it is too large
+/// to be one.
+fn member_size(name: &str, size: u64) -> Result<u32> {
+	u32::try_from(size).map_err(|_| {
+		std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			format!("{name} is too large to be a member of a save file"),
+		)
+	})
+}
+
+/// Returns the number of members in a save file.
+fn count_members(filename: &OsStr) -> Result<usize> {
+	let mut reader = open(filename)?;
+	let mut count = 0;
+	while reader.next()?.is_some() {
+		count += 1;
+	}
+	Ok(count)
+}
+
+/// Creates a save file, or writes to standard output if the filename is `-`, with its contents
+/// written by `write`.
+fn create_save(filename: &OsStr, write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+	if filename == "-" {
+		let mut sink = BufWriter::new(std::io::stdout().lock());
+		write(&mut sink)?;
+		return sink.flush();
+	}
+	let mut sink = BufWriter::new(File::create(filename)?);
+	write(&mut sink)?;
+	sink.into_inner()
+		.map_err(std::io::IntoInnerError::into_error)?
+		.sync_all()
+}
+
+/// Copies every member of a save file into a zip archive, as an entry of the same name, in the same
+/// order.
+fn save_to_zip<R: Read, W: Write + Seek>(source: R, sink: W) -> Result<W> {
+	let mut reader = ArchiveReader::new(source)?;
+	let mut writer = zip::ZipWriter::new(sink);
+	let options = zip::write::SimpleFileOptions::default()
+		.compression_method(zip::CompressionMethod::Deflated);
+	while let Some(mut member) = reader.next()? {
+		writer.start_file(member.name(), options)?;
+		std::io::copy(&mut member, &mut writer)?;
+	}
+	Ok(writer.finish()?)
+}
+
+/// Copies every file entry of a zip archive into a save file, as a member of the same name, in the
+/// same order.
+///
+/// Directory entries, which archive tools add for the parents of files, are skipped, as a save file
+/// has no directories.
+fn zip_to_save<R: Read + Seek, W: Write>(source: R, sink: W) -> Result<W> {
+	let mut archive = zip::ZipArchive::new(source)?;
+	let mut writer = ArchiveWriter::new(sink)?;
+	for i in 0..archive.len() {
+		let entry = archive.by_index(i)?;
+		if entry.is_dir() {
+			continue;
+		}
+		let name = entry.name().to_owned();
+		let size = member_size(&name, entry.size())?;
+		writer.copy_member(&name, size, entry)?;
+	}
+	Ok(writer.finish()?)
+}
+
+/// Converts a save file into a zip archive holding its members, or in a dry run reports what would
+/// be written.
+///
+/// Member names map to entry names one to one, and the order of the members is kept, so that
+/// [`from_zip`] gives back the same members.
+pub fn to_zip(filename: &OsStr, zip: &OsStr) -> Result<()> {
+	if dryrun::is_enabled() {
+		let count = count_members(filename)?;
+		dryrun::would(format!(
+			"write {} with {count} member(s)",
+			Path::new(zip).display()
+		));
+		return Ok(());
+	}
+	save_to_zip(stream::open_input(filename)?, File::create(zip)?)?.sync_all()
+}
+
+/// Converts a zip archive into a save file holding its files, as the reverse of [`to_zip`], or in
+/// a dry run reports what would be written.
+pub fn from_zip(zip: &OsStr, filename: &OsStr) -> Result<()> {
+	let source = BufReader::new(File::open(zip)?);
+	if dryrun::is_enabled() {
+		let archive = zip::ZipArchive::new(source)?;
+		let count = archive.file_names().filter(|i| !i.ends_with('/')).count();
+		dryrun::would(format!(
+			"write {} with {count} member(s)",
+			Path::new(filename).display()
+		));
+		return Ok(());
+	}
+	create_save(filename, |sink| zip_to_save(source, sink).map(drop))
+}
+
+/// Copies every member of a save file into a tar archive, as a regular file of the same name and
+/// size, in the same order.
+fn save_to_tar<R: Read, W: Write>(source: R, sink: W) -> Result<W> {
+	let mut reader = ArchiveReader::new(source)?;
+	let mut builder = tar::Builder::new(sink);
+	while let Some(member) = reader.next()? {
+		let mut header = tar::Header::new_gnu();
+		header.set_entry_type(tar::EntryType::Regular);
+		header.set_mode(0o644);
+		// Cast is sound because u64 ≥ usize.
+		header.set_size(member.size() as u64);
+		let name = member.name().to_owned();
+		builder.append_data(&mut header, name, member)?;
+	}
+	builder.into_inner()
+}
+
+/// Returns the member name of a file in a tar archive: its path with `/` between components, and
+/// without any leading `./`.
+fn tar_member_name(path: &Path) -> Result<String> {
+	let invalid = || {
+		std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			format!("{} is not a valid member name", path.display()),
+		)
+	};
+	let mut components = Vec::new();
+	for component in path.components() {
+		match component {
+			std::path::Component::CurDir => (),
+			std::path::Component::Normal(component) => {
+				components.push(component.to_str().ok_or_else(invalid)?);
+			}
+			_ => return Err(invalid()),
+		}
+	}
+	Ok(components.join("/"))
+}
+
+/// Copies every regular file in a tar archive into a save file, as a member of the same name, in
+/// the same order.
+///
+/// Directories are skipped, as a save file has none; any other kind of entry, such as a link, is an
+/// error.
+fn tar_to_save<R: Read, W: Write>(source: R, sink: W) -> Result<W> {
+	let mut archive = tar::Archive::new(source);
+	let mut writer = ArchiveWriter::new(sink)?;
+	for entry in archive.entries()? {
+		let entry = entry?;
+		let kind = entry.header().entry_type();
+		if kind.is_dir() || kind.is_pax_global_extensions() {
+			continue;
+		}
+		let name = tar_member_name(&entry.path()?)?;
+		if !kind.is_file() {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				format!("{name} is not a regular file"),
+			));
+		}
+		let size = member_size(&name, entry.size())?;
+		writer.copy_member(&name, size, entry)?;
+	}
+	Ok(writer.finish()?)
+}
+
+/// Converts a save file into a tar archive holding its members, or in a dry run reports what would
+/// be written.
+///
+/// Either file may be `-`, for standard input or output, so that a save can be piped through
+/// ordinary tar tools.
+pub fn to_tar(filename: &OsStr, tar: &OsStr) -> Result<()> {
+	if dryrun::is_enabled() {
+		let count = count_members(filename)?;
+		dryrun::would(format!(
+			"write {} with {count} member(s)",
+			Path::new(tar).display()
+		));
+		return Ok(());
+	}
+	let mut sink = save_to_tar(stream::open_input(filename)?, stream::create_output(tar)?)?;
+	sink.flush()
+}
+
+/// Converts a tar archive into a save file holding its files, as the reverse of [`to_tar`], or in
+/// a dry run reports what would be written.
+pub fn from_tar(tar: &OsStr, filename: &OsStr) -> Result<()> {
+	let source = stream::open_input(tar)?;
+	if dryrun::is_enabled() {
+		let mut count = 0;
+		for entry in tar::Archive::new(source).entries()? {
+			if entry?.header().entry_type().is_file() {
+				count += 1;
+			}
+		}
+		dryrun::would(format!(
+			"write {} with {count} member(s)",
+			Path::new(filename).display()
+		));
+		return Ok(());
+	}
+	create_save(filename, |sink| tar_to_save(source, sink).map(drop))
+}
+
+/// Adds files to a save file as new members, given as pairs of member name and path.
+///
+/// Fails without changing anything if any of the members already exists, or is named twice.
+pub fn add(filename: &OsStr, members: &[(&str, &Path)]) -> Result<()> {
+	let mut existing: HashSet<String> = save::member_names(filename)?.into_iter().collect();
+	for (name, _) in members {
+		if !existing.insert((*name).to_owned()) {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::AlreadyExists,
+				format!(
+					"{name} is already a member of {}",
+					Path::new(filename).display()
+				),
+			));
+		}
+	}
+	let changes = members
+		.iter()
+		.map(|(name, path)| {
+			let data = std::fs::read(path)
+				.map_err(|e| std::io::Error::new(e.kind(), format!("{}: {e}", path.display())))?;
+			Ok((*name, Some(data)))
+		})
+		.collect::<Result<Vec<_>>>()?;
+	update(filename, &changes)
+}
+
+/// Writes a save file holding every member of `base`, with those of `overlay` replacing members of
+/// the same name or, if `base` has none, added after them, or in a dry run shows the changes.
+///
+/// `out` may be `base` itself, which is then modified in place.
+pub fn merge(out: &OsStr, base: &OsStr, overlay: &OsStr) -> Result<()> {
+	let mut names = Vec::new();
+	let mut contents = Vec::new();
+	let mut reader = save::open(overlay)?;
+	while let Some(mut member) = reader.next()? {
+		names.push(member.name().to_owned());
+		contents.push(Some(member.read_to_vec()?));
+	}
+	let changes: Vec<(&str, Option<Vec<u8>>)> =
+		names.iter().map(String::as_str).zip(contents).collect();
+	if dryrun::is_enabled() {
+		if out != base {
+			dryrun::would(format!(
+				"copy {} to {}",
+				Path::new(base).display(),
+				Path::new(out).display()
+			));
+		}
+		return dryrun::preview(base, &changes);
+	}
+	if out != base {
+		std::fs::copy(base, out)?;
+	}
+	Ok(save::update(out, &changes)?)
+}
+
+/// Opens a member of a save file in an editor and, once the editor exits, writes the member back
+/// if it was changed.
+///
+/// The member is edited in a temporary file named after it, so that the editor can tell what kind
+/// of file it is. A submarine member is decompressed for editing, as plain XML, and compressed
+/// again afterwards.
+pub fn edit(filename: &OsStr, member: &str, editor: Option<&OsStr>) -> Result<()> {
+	let Some(data) = save::read_member(filename, member)? else {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!(
+				"{member} is not a member of {}",
+				Path::new(filename).display()
+			),
+		));
+	};
+	let is_submarine = Path::new(member).extension() == Some(OsStr::new("sub"));
+	let original = if is_submarine {
+		let mut xml = Vec::new();
+		submarine::decompress(&data[..], &mut xml)?;
+		xml
+	} else {
+		data
+	};
+	let base_name = Path::new(member)
+		.file_name()
+		.map_or_else(|| member.into(), OsStr::to_owned);
+	let mut temp_name = OsString::from(format!("barotool-{}-", std::process::id()));
+	temp_name.push(base_name);
+	if is_submarine {
+		temp_name.push(".xml");
+	}
+	let temp = std::env::temp_dir().join(temp_name);
+	std::fs::OpenOptions::new()
+		.write(true)
+		.create_new(true)
+		.open(&temp)?
+		.write_all(&original)?;
+	let edited = editor::edit(editor, &temp).and_then(|()| std::fs::read(&temp));
+	let _ = std::fs::remove_file(&temp);
+	let edited = edited?;
+	if edited == original {
+		output::note(format!("No changes to {member}"));
+		return Ok(());
+	}
+	let edited = if is_submarine {
+		let mut compressed = Vec::new();
+		submarine::compress(&edited[..], &mut compressed)?;
+		compressed
+	} else {
+		edited
+	};
+	update(filename, &[(member, Some(edited))])
+}
+
+/// Applies a unified diff, read from a file or from standard input if the filename is `-`, to a
+/// member of a save file, writing the member back, or in a dry run shows the changes.
+///
+/// A submarine member is patched as plain XML, as [`edit`] edits it. A diff that does not apply to
+/// the member as it is, such as one printed by `diff-save --text`, is tried on the member’s XML as
+/// barotool writes it. Only text members can be patched.
+pub fn patch(filename: &OsStr, member: &str, patch_file: &OsStr) -> Result<()> {
+	let mut patch = String::new();
+	stream::open_input(patch_file)?
+		.read_to_string(&mut patch)
+		.map_err(|e| {
+			std::io::Error::new(
+				e.kind(),
+				format!("{}: {e}", Path::new(patch_file).display()),
+			)
+		})?;
+	let Some(data) = save::read_member(filename, member)? else {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!(
+				"{member} is not a member of {}",
+				Path::new(filename).display()
+			),
+		));
+	};
+	let is_submarine = Path::new(member).extension() == Some(OsStr::new("sub"));
+	let original = if is_submarine {
+		let mut xml = Vec::new();
+		submarine::decompress(&data[..], &mut xml)?;
+		xml
+	} else {
+		data.clone()
+	};
+	let text = String::from_utf8(original).map_err(|_| {
+		std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			format!("{member} is not text, so cannot be patched"),
+		)
+	})?;
+	let patched = match diff::apply_unified(&text, &patch) {
+		Ok(patched) => patched,
+		Err(e) => match dryrun::text_of(member, &data) {
+			Some(written) if written != text => {
+				log::debug!("Patching {member} as barotool writes it");
+				diff::apply_unified(&written, &patch).map_err(|_| e)?
+			}
+			_ => return Err(e),
+		},
+	};
+	let patched = if is_submarine {
+		let mut compressed = Vec::new();
+		submarine::compress(patched.as_bytes(), &mut compressed)?;
+		compressed
+	} else {
+		patched.into_bytes()
+	};
+	update(filename, &[(member, Some(patched))])
+}
+
+/// Removes members from a save file.
+///
+/// On return, `members` contains those of its original names that were not found in the archive.
+/// If none were found, the save file is left as it was.
+pub fn remove(filename: &OsStr, members: &mut HashSet<&str>) -> Result<()> {
+	let mut changes = Vec::new();
+	for name in save::member_names(filename)? {
+		if let Some(member) = members.take(name.as_str()) {
+			changes.push((member, None));
+		}
+	}
+	if changes.is_empty() {
+		return Ok(());
+	}
+	update(filename, &changes)
+}
+
+/// Replaces the contents of members of a save file with those of files, given as pairs of member
+/// name and path.
+///
+/// Fails without changing anything if any of the members does not exist.
+pub fn replace(filename: &OsStr, members: &[(&str, &Path)]) -> Result<()> {
+	let existing: HashSet<String> = save::member_names(filename)?.into_iter().collect();
+	if let Some((name, _)) = members.iter().find(|(i, _)| !existing.contains(*i)) {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!(
+				"{name} is not a member of {}",
+				Path::new(filename).display()
+			),
+		));
+	}
+	let changes = members
+		.iter()
+		.map(|(name, path)| {
+			let data = std::fs::read(path)
+				.map_err(|e| std::io::Error::new(e.kind(), format!("{}: {e}", path.display())))?;
+			Ok((*name, Some(data)))
+		})
+		.collect::<Result<Vec<_>>>()?;
+	update(filename, &changes)
+}
+
+/// Updates members of a save file, as [`save::update`] does, or in a dry run shows the changes.
+pub fn update(filename: &OsStr, changes: &[(&str, Option<Vec<u8>>)]) -> Result<()> {
+	if dryrun::is_enabled() {
+		dryrun::preview(filename, changes)
+	} else {
+		Ok(save::update(filename, changes)?)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_round_trip() {
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		writer
+			.write_member("gamesession.xml", b"<Gamesession />")
+			.unwrap();
+		writer.write_member("Sub/Orca.sub", b"\x1f\x8b").unwrap();
+		writer.write_member("empty", b"").unwrap();
+		let save = writer.finish().unwrap();
+		let zip = save_to_zip(&save[..], std::io::Cursor::new(Vec::new())).unwrap();
+		assert_eq!(zip_to_save(zip, Vec::new()).unwrap(), save);
+		let tar = save_to_tar(&save[..], Vec::new()).unwrap();
+		assert_eq!(tar_to_save(&tar[..], Vec::new()).unwrap(), save);
+	}
+}
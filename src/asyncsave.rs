@@ -0,0 +1,273 @@
+//! Reading and writing `.save` files without blocking, using Tokio.
+//!
+//! These are the asynchronous counterparts of [`ArchiveReader`](crate::save::ArchiveReader) and
+//! the writing functions in [`save`], for servers that stream members to and from
+//! clients without tying up a thread per transfer. The file format is the same.
+
+use crate::compression;
+use crate::error::{self, Error};
+use crate::limits::{self, Usage};
+use crate::save::{self, MemberInfo};
+use crate::Result;
+use async_compression::tokio::bufread::GzipDecoder;
+use async_compression::tokio::write::GzipEncoder;
+use async_compression::Level;
+use std::convert::{TryFrom, TryInto};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+/// A save file being read asynchronously.
+#[derive(Debug)]
+pub struct AsyncArchiveReader<R: AsyncBufRead + Unpin> {
+	/// The gzip decoder decoding the file.
+	decoder: GzipDecoder<R>,
+
+	/// The number of bytes remaining in the current member’s file content.
+	member_bytes_left: usize,
+
+	/// What has been read so far, as counted against the limits.
+	usage: Usage,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncArchiveReader<R> {
+	/// Starts reading a save file from a stream.
+	///
+	/// Nothing is read until the first member is requested, so a stream that is not a save file is
+	/// only reported then. As with [`ArchiveReader`](crate::save::ArchiveReader), the save file is
+	/// held to the [`Limits`](crate::limits::Limits) in effect now.
+	pub fn new(source: R) -> Self {
+		let mut decoder = GzipDecoder::new(source);
+		// Segmented save files hold a gzip member for each archive member.
+		decoder.multiple_members(true);
+		Self {
+			decoder,
+			member_bytes_left: 0,
+			usage: Usage::new(limits::limits()),
+		}
+	}
+
+	/// Returns the next member in the file.
+	///
+	/// If there are no more members in the archive, `None` is returned. As with
+	/// [`ArchiveReader::next`](crate::save::ArchiveReader::next), the member borrows the reader, and
+	/// any of it left unread is skipped when the next member is requested.
+	pub async fn next(&mut self) -> Result<Option<AsyncMember<'_, R>>> {
+		// If the current member isn’t finished, finish it.
+		if self.member_bytes_left != 0 {
+			self.finish_current_member().await?;
+		}
+
+		// Read the name, which comprises a little-endian 32-bit length followed by that many
+		// little-endian UTF-16 code units.
+		let name_bytes = match self.read_u32().await? {
+			Some(n) => u64::from(n) * 2,
+			None => return Ok(None),
+		};
+		self.usage.header(8 + name_bytes)?;
+		// The buffer grows as it is filled, so a corrupt length costs no more than the data there.
+		let mut name_buf = Vec::new();
+		(&mut self.decoder)
+			.take(name_bytes)
+			.read_to_end(&mut name_buf)
+			.await
+			.map_err(error::gzip)?;
+		// Cast is sound because u64 ≥ usize.
+		if name_buf.len() as u64 != name_bytes {
+			return Err(Error::Truncated);
+		}
+		let name = save::decode_name(&name_buf)?;
+
+		// Read the file length, a little-endian 32-bit length. Cast is sound because usize ≥ 32.
+		let size = self.decoder.read_u32_le().await.map_err(save::read_error)? as usize;
+		self.usage.member(&name, size)?;
+		self.member_bytes_left = size;
+		log::trace!("Archive member {name}, {size} bytes");
+
+		Ok(Some(AsyncMember {
+			name,
+			size,
+			container: self,
+		}))
+	}
+
+	/// Reads to the end of the current member’s file content.
+	async fn finish_current_member(&mut self) -> Result<()> {
+		let mut buffer = [0_u8; 4096];
+		while self.member_bytes_left != 0 {
+			let to_read = self.member_bytes_left.min(buffer.len());
+			let bytes_read = self
+				.decoder
+				.read(&mut buffer[..to_read])
+				.await
+				.map_err(error::gzip)?;
+			if bytes_read == 0 {
+				return Err(Error::Truncated);
+			}
+			self.member_bytes_left -= bytes_read;
+		}
+		Ok(())
+	}
+
+	/// Reads a single `u32`.
+	///
+	/// If the file is at EOF, `None` is returned. If four bytes can be read successfully, their
+	/// value as a little-endian `u32` is returned. If at least one byte, but not four, can be read,
+	/// [`Error::Truncated`] is returned.
+	async fn read_u32(&mut self) -> Result<Option<u32>> {
+		let mut buffer = [0_u8; 4];
+		let mut buffer_filled = 0;
+		while buffer_filled != 4 {
+			let bytes_read = self
+				.decoder
+				.read(&mut buffer[buffer_filled..])
+				.await
+				.map_err(error::gzip)?;
+			if bytes_read == 0 {
+				return if buffer_filled == 0 {
+					Ok(None)
+				} else {
+					Err(Error::Truncated)
+				};
+			}
+			buffer_filled += bytes_read;
+		}
+		Ok(Some(u32::from_le_bytes(buffer)))
+	}
+}
+
+/// A single member of a save file being read asynchronously.
+#[derive(Debug)]
+pub struct AsyncMember<'file, R: AsyncBufRead + Unpin> {
+	/// The member’s filename.
+	name: String,
+
+	/// The size of the member, in bytes.
+	size: usize,
+
+	/// The containing save file.
+	container: &'file mut AsyncArchiveReader<R>,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncMember<'_, R> {
+	/// Returns the member’s filename.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Returns the size of the member, in bytes.
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	/// Returns the member’s name and size.
+	pub fn info(&self) -> MemberInfo {
+		MemberInfo {
+			name: self.name.clone(),
+			size: self.size,
+		}
+	}
+
+	/// Reads the rest of the member into memory.
+	///
+	/// If the save file ends before the member does, [`Error::Truncated`] is returned.
+	pub async fn read_to_vec(&mut self) -> Result<Vec<u8>> {
+		let mut data = Vec::with_capacity(self.container.member_bytes_left);
+		self.read_to_end(&mut data).await.map_err(error::gzip)?;
+		if self.container.member_bytes_left != 0 {
+			return Err(Error::Truncated);
+		}
+		Ok(data)
+	}
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for AsyncMember<'_, R> {
+	fn poll_read(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut ReadBuf<'_>,
+	) -> Poll<std::io::Result<()>> {
+		let container = &mut *self.container;
+		let to_read = buf.remaining().min(container.member_bytes_left);
+		if to_read == 0 {
+			return Poll::Ready(Ok(()));
+		}
+		// The decoder is only offered as much as is left of this member.
+		let mut limited = ReadBuf::new(buf.initialize_unfilled_to(to_read));
+		match Pin::new(&mut container.decoder).poll_read(cx, &mut limited) {
+			Poll::Ready(Ok(())) => {
+				let bytes_read = limited.filled().len();
+				container.member_bytes_left -= bytes_read;
+				buf.advance(bytes_read);
+				Poll::Ready(Ok(()))
+			}
+			other => other,
+		}
+	}
+}
+
+/// A save file being written asynchronously.
+///
+/// Members are written in order with [`write_member`](Self::write_member) or
+/// [`copy_member`](Self::copy_member), and the file is completed by [`finish`](Self::finish).
+#[derive(Debug)]
+pub struct AsyncArchiveWriter<W: AsyncWrite + Unpin> {
+	/// The gzip encoder encoding the file.
+	encoder: GzipEncoder<W>,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncArchiveWriter<W> {
+	/// Starts writing a save file to a stream, at the configured compression level.
+	pub fn new(sink: W) -> Self {
+		let level = i32::try_from(compression::level()).unwrap_or(i32::MAX);
+		Self {
+			encoder: GzipEncoder::with_quality(sink, Level::Precise(level)),
+		}
+	}
+
+	/// Writes a complete member from memory.
+	pub async fn write_member(&mut self, name: &str, data: &[u8]) -> Result<()> {
+		let size: u32 = data
+			.len()
+			.try_into()
+			.map_err(|_| Error::TooLarge("Member too large".to_owned()))?;
+		self.encoder
+			.write_all(&save::member_header(name, size)?)
+			.await?;
+		self.encoder.write_all(data).await?;
+		Ok(())
+	}
+
+	/// Writes a member of the given size, streaming its contents from `source`.
+	///
+	/// Exactly `size` bytes are read from `source`; if it ends sooner, an error of kind
+	/// [`std::io::ErrorKind::UnexpectedEof`] is returned and the save file is left incomplete.
+	pub async fn copy_member<S: AsyncRead + Unpin>(
+		&mut self,
+		name: &str,
+		size: u32,
+		source: S,
+	) -> Result<()> {
+		self.encoder
+			.write_all(&save::member_header(name, size)?)
+			.await?;
+		let copied = tokio::io::copy(&mut source.take(size.into()), &mut self.encoder).await?;
+		if copied != u64::from(size) {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::UnexpectedEof,
+				format!("Member {name} ended after {copied} of {size} bytes"),
+			)
+			.into());
+		}
+		Ok(())
+	}
+
+	/// Completes the save file and returns the stream it was written to.
+	///
+	/// The stream is shut down, as [`AsyncWriteExt::shutdown`] does, once the last of the
+	/// compressed data has been written.
+	pub async fn finish(mut self) -> Result<W> {
+		self.encoder.shutdown().await?;
+		Ok(self.encoder.into_inner())
+	}
+}
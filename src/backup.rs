@@ -0,0 +1,241 @@
+use crate::dryrun;
+use crate::output::{self, outln, Format};
+use crate::time::DateTime;
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{Read, Result};
+use std::path::{Path, PathBuf};
+
+/// Returns the directory in which backups of a save file are kept.
+///
+/// If `dir` is `None`, a directory named after the save file, with `.backups` appended, alongside
+/// the save file is used.
+fn backup_dir(filename: &OsStr, dir: Option<&OsStr>) -> PathBuf {
+	if let Some(dir) = dir {
+		return PathBuf::from(dir);
+	}
+	let mut name = filename.to_owned();
+	name.push(".backups");
+	PathBuf::from(name)
+}
+
+/// Returns the stem shared by all backups of a save file.
+fn backup_stem(filename: &OsStr) -> Result<String> {
+	Path::new(filename)
+		.file_stem()
+		.and_then(OsStr::to_str)
+		.map(str::to_owned)
+		.ok_or_else(|| {
+			std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"Save filename must be valid Unicode",
+			)
+		})
+}
+
+/// Returns the paths of the existing backups of a save file, oldest first.
+fn existing_backups(filename: &OsStr, dir: Option<&OsStr>) -> Result<Vec<PathBuf>> {
+	let dir = backup_dir(filename, dir);
+	let prefix = format!("{}-", backup_stem(filename)?);
+	let entries = match std::fs::read_dir(&dir) {
+		Ok(entries) => entries,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(e) => return Err(e),
+	};
+	let mut ret = Vec::new();
+	for entry in entries {
+		let path = entry?.path();
+		if path.extension() != Some(OsStr::new("save")) {
+			continue;
+		}
+		let Some(suffix) = path
+			.file_stem()
+			.and_then(OsStr::to_str)
+			.and_then(|i| i.strip_prefix(&prefix))
+		else {
+			continue;
+		};
+		// A suffix is a timestamp, followed by a counter if several snapshots were taken within
+		// the same second.
+		let key = match suffix.split_once('-') {
+			Some((stamp, counter)) => (stamp.to_owned(), counter.parse().unwrap_or(0)),
+			None => (suffix.to_owned(), 0_u32),
+		};
+		ret.push((key, path));
+	}
+	ret.sort();
+	let ret = ret.into_iter().map(|(_, path)| path).collect();
+	Ok(ret)
+}
+
+/// Reads a whole file into memory.
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+	let mut data = Vec::new();
+	File::open(path)?.read_to_end(&mut data)?;
+	Ok(data)
+}
+
+/// Takes a snapshot of a save file.
+///
+/// The snapshot is written to `dir`, or to the default backup directory if `dir` is `None`. If the
+/// save file is identical to the most recent snapshot, no new snapshot is taken. If `keep` is
+/// `Some`, the oldest snapshots are then deleted so that at most that many remain.
+pub fn backup(filename: &OsStr, dir: Option<&OsStr>, keep: Option<usize>) -> Result<()> {
+	let data = read_file(Path::new(filename))?;
+	let existing = existing_backups(filename, dir)?;
+	if let Some(latest) = existing.last() {
+		if read_file(latest)? == data {
+			output::note(format!("Save is unchanged since {}", latest.display()));
+			return prune(existing, keep);
+		}
+	}
+
+	let backup_dir = backup_dir(filename, dir);
+	if !dryrun::is_enabled() {
+		std::fs::create_dir_all(&backup_dir)?;
+	}
+	let stem = backup_stem(filename)?;
+	let stamp = DateTime::now().compact();
+	let mut path = backup_dir.join(format!("{stem}-{stamp}.save"));
+	let mut counter = 1;
+	while path.exists() {
+		path = backup_dir.join(format!("{stem}-{stamp}-{counter}.save"));
+		counter += 1;
+	}
+	if dryrun::is_enabled() {
+		dryrun::would(format!("back up to {}", path.display()));
+	} else {
+		// Save files are already gzip-compressed, so they are stored as-is.
+		std::fs::write(&path, &data)?;
+		output::note(format!("Backed up to {}", path.display()));
+	}
+
+	let mut existing = existing;
+	existing.push(path);
+	prune(existing, keep)
+}
+
+/// Deletes the oldest of a list of snapshots so that at most `keep` remain.
+fn prune(mut backups: Vec<PathBuf>, keep: Option<usize>) -> Result<()> {
+	let Some(keep) = keep else {
+		return Ok(());
+	};
+	let excess = backups.len().saturating_sub(keep);
+	for path in backups.drain(..excess) {
+		if dryrun::is_enabled() {
+			dryrun::would(format!("delete {}", path.display()));
+			continue;
+		}
+		std::fs::remove_file(&path)?;
+		output::note(format!("Deleted {}", path.display()));
+	}
+	Ok(())
+}
+
+/// Lists the snapshots of a save file, oldest first.
+pub fn list_backups(filename: &OsStr, dir: Option<&OsStr>, format: Format) -> Result<()> {
+	let paths: Vec<String> = existing_backups(filename, dir)?
+		.iter()
+		.map(|i| i.display().to_string())
+		.collect();
+	output::emit(format, &paths, |paths| {
+		for path in paths {
+			outln!("{path}");
+		}
+	})
+}
+
+/// Restores a save file from a snapshot.
+///
+/// If `snapshot` is `None`, the most recent snapshot in the backup directory is used. The save file
+/// is only replaced once the snapshot has been copied in full.
+pub fn restore(filename: &OsStr, dir: Option<&OsStr>, snapshot: Option<&OsStr>) -> Result<()> {
+	let snapshot = match snapshot {
+		Some(snapshot) => PathBuf::from(snapshot),
+		None => existing_backups(filename, dir)?.pop().ok_or_else(|| {
+			std::io::Error::new(
+				std::io::ErrorKind::NotFound,
+				format!(
+					"No backups found in {}",
+					backup_dir(filename, dir).display()
+				),
+			)
+		})?,
+	};
+	if dryrun::is_enabled() {
+		log::info!("Comparing with {}", snapshot.display());
+		return dryrun::preview_replacement(filename, snapshot.as_os_str());
+	}
+	let mut temp_filename: OsString = filename.to_owned();
+	temp_filename.push(".tmp");
+	if let Err(e) = std::fs::copy(&snapshot, &temp_filename) {
+		let _ = std::fs::remove_file(&temp_filename);
+		return Err(e);
+	}
+	std::fs::rename(&temp_filename, filename)?;
+	output::note(format!("Restored from {}", snapshot.display()));
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Returns the names of the snapshots of a save file, oldest first.
+	fn names(filename: &OsStr) -> Vec<String> {
+		existing_backups(filename, None)
+			.unwrap()
+			.iter()
+			.map(|i| i.file_name().unwrap().to_str().unwrap().to_owned())
+			.collect()
+	}
+
+	#[test]
+	fn test_backup() {
+		let dir = std::env::temp_dir().join(format!("barotool-test-backup-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		let backups = dir.join("game.save.backups");
+		std::fs::create_dir_all(&backups).unwrap();
+		let filename = dir.join("game.save");
+		let filename = filename.as_os_str();
+		std::fs::write(filename, b"new").unwrap();
+		for (name, data) in [
+			("game-20240101T000000Z-1.save", &b"second"[..]),
+			("game-20240101T000000Z.save", b"first"),
+			("game-20240102T000000Z.save", b"new"),
+			("other-20240103T000000Z.save", b"other"),
+			("game-20240104T000000Z.txt", b"notes"),
+		] {
+			std::fs::write(backups.join(name), data).unwrap();
+		}
+		assert_eq!(
+			names(filename),
+			[
+				"game-20240101T000000Z.save",
+				"game-20240101T000000Z-1.save",
+				"game-20240102T000000Z.save",
+			]
+		);
+
+		// The latest snapshot matches the save, so none is taken, but old ones are still pruned.
+		backup(filename, None, Some(2)).unwrap();
+		assert_eq!(
+			names(filename),
+			["game-20240101T000000Z-1.save", "game-20240102T000000Z.save"]
+		);
+
+		std::fs::write(filename, b"newer").unwrap();
+		backup(filename, None, None).unwrap();
+		let snapshots = names(filename);
+		assert_eq!(snapshots.len(), 3);
+		assert_eq!(
+			std::fs::read(backups.join(&snapshots[2])).unwrap(),
+			b"newer"
+		);
+
+		backup(filename, None, Some(1)).unwrap();
+		assert_eq!(names(filename), snapshots[2..]);
+		assert!(backups.join("other-20240103T000000Z.save").exists());
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,222 @@
+use crate::color::Style;
+use crate::exit;
+use crate::output::{self, outln};
+use barotool::diagnostic;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+/// The number of files processed at once, or zero for one per CPU.
+static JOBS: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the number of files processed at once.
+///
+/// Zero, the default, processes as many at once as there are CPUs; one processes them in turn.
+pub fn set_jobs(jobs: usize) {
+	JOBS.store(jobs, Ordering::Relaxed);
+}
+
+/// Expands a list of paths into the files to process.
+///
+/// Files are taken as given. Directories are replaced by the files within them whose extension is
+/// one of `extensions`, in sorted order; if `recursive` is `true`, subdirectories are searched as
+/// well.
+pub fn expand<'a, I: IntoIterator<Item = &'a OsStr>>(
+	paths: I,
+	extensions: &[&str],
+	recursive: bool,
+) -> Result<Vec<PathBuf>> {
+	let mut ret = Vec::new();
+	for path in paths {
+		let path = PathBuf::from(path);
+		if path.is_dir() {
+			log::debug!(
+				"Searching {} for .{} files",
+				path.display(),
+				extensions.join(", .")
+			);
+			expand_dir(path, extensions, recursive, &mut ret)?;
+		} else {
+			ret.push(path);
+		}
+	}
+	Ok(ret)
+}
+
+/// Appends the matching files within a directory to `files`.
+fn expand_dir(
+	dir: PathBuf,
+	extensions: &[&str],
+	recursive: bool,
+	files: &mut Vec<PathBuf>,
+) -> Result<()> {
+	let mut entries = std::fs::read_dir(dir)?
+		.map(|i| i.map(|i| i.path()))
+		.collect::<Result<Vec<PathBuf>>>()?;
+	entries.sort();
+	for entry in entries {
+		if entry.is_dir() {
+			if recursive {
+				expand_dir(entry, extensions, recursive, files)?;
+			}
+		} else if entry
+			.extension()
+			.is_some_and(|i| extensions.iter().any(|j| i == *j))
+		{
+			log::trace!("Found {}", entry.display());
+			files.push(entry);
+		}
+	}
+	Ok(())
+}
+
+/// Runs an operation on each of a list of files.
+///
+/// With a single file, the operation’s result is returned directly. With several, they are
+/// processed in parallel, as configured by [`set_jobs`], but each file’s output is printed in the
+/// order of `files`, all at once when it and those before it are done, and preceded by a header
+/// naming it if `headers` is `true`. A failure on one file does not stop the others from being
+/// processed, and the failures are listed at the end. The error returned then carries the most
+/// severe exit status of any failure.
+///
+/// Problems with a file’s contents are reported with where in the file they were found.
+pub fn run<F: Fn(&OsStr) -> Result<()> + Sync>(
+	files: &[PathBuf],
+	headers: bool,
+	f: F,
+) -> Result<()> {
+	if let [file] = files {
+		return f(file.as_os_str())
+			.map_err(|e| diagnostic::in_save(e.into(), file.as_os_str()).into());
+	}
+	let mut report = Report {
+		headers,
+		failures: Vec::new(),
+		code: exit::SUCCESS,
+	};
+	if JOBS.load(Ordering::Relaxed) == 1 {
+		for (i, file) in files.iter().enumerate() {
+			report.header(i, file);
+			report.result(file, f(file.as_os_str()));
+		}
+	} else {
+		let pool = pool(files.len())?;
+		let (sender, receiver) = mpsc::channel();
+		std::thread::scope(|scope| {
+			scope.spawn(|| {
+				pool.install(|| {
+					files
+						.par_iter()
+						.enumerate()
+						.for_each_with(sender, |sender, (i, file)| {
+							let result = output::capture(|| f(file.as_os_str()));
+							// The receiver only goes away if the main thread has panicked.
+							let _ = sender.send((i, result));
+						});
+				});
+			});
+			// Results arrive in whatever order the files finish, and are held until all the files
+			// before them have been reported.
+			let mut pending = BTreeMap::new();
+			let mut next = 0;
+			for (i, result) in receiver {
+				pending.insert(i, result);
+				while let Some((result, captured)) = pending.remove(&next) {
+					let file = &files[next];
+					report.header(next, file);
+					let _ = output::Stdout.write_all(&captured);
+					report.result(file, result);
+					next += 1;
+				}
+			}
+		});
+	}
+	report.finish(files.len())
+}
+
+/// Runs an operation on each of a list of files, returning the results in the order of `files`.
+///
+/// The files are processed in parallel, as configured by [`set_jobs`]. Unlike [`run`], nothing is
+/// printed; `f` must not print either, as its output would be interleaved with that of the others.
+pub fn map<T: Send, F: Fn(&Path) -> T + Sync>(files: &[PathBuf], f: F) -> Result<Vec<T>> {
+	if JOBS.load(Ordering::Relaxed) == 1 {
+		return Ok(files.iter().map(|i| f(i)).collect());
+	}
+	Ok(pool(files.len())?.install(|| files.par_iter().map(|i| f(i)).collect()))
+}
+
+/// Builds the thread pool for processing `count` files, with as many threads as configured by
+/// [`set_jobs`].
+fn pool(count: usize) -> Result<rayon::ThreadPool> {
+	let pool = rayon::ThreadPoolBuilder::new()
+		.num_threads(JOBS.load(Ordering::Relaxed))
+		.build()
+		.map_err(std::io::Error::other)?;
+	log::debug!(
+		"Processing {count} files with {} threads",
+		pool.current_num_threads()
+	);
+	Ok(pool)
+}
+
+/// The progress of [`run`] in reporting on the files processed.
+struct Report<'a> {
+	/// Whether to print a header naming each file.
+	headers: bool,
+
+	/// The files that failed.
+	failures: Vec<&'a Path>,
+
+	/// The most severe exit status of any failure.
+	code: u8,
+}
+
+impl<'a> Report<'a> {
+	/// Prints the header naming a file, which is the `i`th processed, if headers are wanted.
+	fn header(&self, i: usize, file: &Path) {
+		if self.headers {
+			if i != 0 {
+				outln!();
+			}
+			outln!(
+				"{}",
+				Style::Heading.paint(format!("==> {} <==", file.display()))
+			);
+		}
+	}
+
+	/// Notes the outcome of processing a file, reporting any error.
+	fn result(&mut self, file: &'a Path, result: Result<()>) {
+		if let Err(e) = result {
+			let e = diagnostic::in_save(e.into(), file.as_os_str());
+			// A problem with the file’s contents already names the file.
+			if e.diagnostic().is_some() {
+				log::error!("{e}");
+			} else {
+				log::error!("{}: {e}", file.display());
+			}
+			let e = e.into();
+			self.code = self.code.max(exit::code(&e));
+			self.failures.push(file);
+		}
+	}
+
+	/// Lists the files that failed, out of `total`, and returns the overall outcome.
+	fn finish(self, total: usize) -> Result<()> {
+		if self.failures.is_empty() {
+			return Ok(());
+		}
+		eprintln!("{} of {total} files failed:", self.failures.len());
+		for file in &self.failures {
+			eprintln!("{}", file.display());
+		}
+		Err(exit::error(
+			self.code,
+			format!("{} of {total} files failed", self.failures.len()),
+		))
+	}
+}
@@ -0,0 +1,1711 @@
+use crate::archive;
+use crate::color::Style;
+use crate::exit;
+use crate::output::{self, outln, Format};
+use crate::selector::{Candidate, Selection};
+use barotool::character::{self, Appearance, Character, CharacterEntry, InventoryItem, Skill};
+use barotool::characterdata::CharacterData;
+use barotool::diagnostic;
+use barotool::gamesession::{
+	Faction, GameMode, GameSession, LevelChoice, LevelData, Location, MapState, PendingUpgrade,
+	Progress,
+};
+use barotool::save;
+use barotool::submarine::{Submarine, Upgrade};
+use barotool::xml;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufReader, Read, Result};
+use std::path::Path;
+use xmltree::Element;
+
+/// Loads the game session from a save file.
+fn load(filename: &OsStr) -> Result<GameSession> {
+	log::debug!(
+		"Loading game session from {}",
+		Path::new(filename).display()
+	);
+	match save::read_member(filename, GameSession::MEMBER_NAME)? {
+		Some(data) => GameSession::parse(&data[..]).map_err(|e| {
+			diagnostic::in_file(e, Path::new(filename), Some(GameSession::MEMBER_NAME)).into()
+		}),
+		None => Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("Save file has no {} member", GameSession::MEMBER_NAME),
+		)),
+	}
+}
+
+/// Writes a modified game session back into a save file.
+fn store(filename: &OsStr, session: &GameSession) -> Result<()> {
+	let mut data = Vec::new();
+	session.write(&mut data)?;
+	archive::update(filename, &[(GameSession::MEMBER_NAME, Some(data))])
+}
+
+/// A change to a member of a save file, as passed to [`archive::update`].
+type Change = (&'static str, Option<Vec<u8>>);
+
+/// A campaign’s game session, along with the per-player character data of a multiplayer campaign.
+///
+/// Single-player campaigns keep the whole crew in the game session. Multiplayer campaigns keep the
+/// bots there, but keep each player’s character, wallet, inventory, and health in a separate
+/// `CharacterData.xml` member.
+struct Campaign {
+	/// The game session.
+	session: GameSession,
+
+	/// The character data, if the save has any.
+	characters: Option<CharacterData>,
+}
+
+impl Campaign {
+	/// Loads the game session and, if present, the character data from a save file.
+	fn load(filename: &OsStr) -> Result<Self> {
+		let session = load(filename)?;
+		let characters = match save::read_member(filename, CharacterData::MEMBER_NAME)? {
+			Some(data) => Some(CharacterData::parse(&data[..]).map_err(|e| {
+				diagnostic::in_file(e, Path::new(filename), Some(CharacterData::MEMBER_NAME))
+			})?),
+			None => None,
+		};
+		Ok(Self {
+			session,
+			characters,
+		})
+	}
+
+	/// Returns the game mode of the campaign.
+	///
+	/// Only multiplayer campaigns have character data, so its presence settles the question even
+	/// if the game session’s campaign element is not one this tool recognizes.
+	fn game_mode(&self) -> Option<GameMode> {
+		if self.characters.is_some() {
+			Some(GameMode::MultiPlayer)
+		} else {
+			self.session.game_mode()
+		}
+	}
+
+	/// Returns the whole crew: the characters in the game session followed by the players’.
+	fn crew(&self) -> Result<Vec<Character>> {
+		let mut crew = self.session.crew()?;
+		if let Some(characters) = &self.characters {
+			crew.extend(characters.characters()?);
+		}
+		Ok(crew)
+	}
+
+	/// Returns the largest entity ID of any item or crew member in the game session or character
+	/// data.
+	///
+	/// Items and characters share the same entity IDs.
+	fn max_entity_id(&self) -> Result<u16> {
+		let mut max = self.session.max_item_id()?;
+		if let Some(characters) = &self.characters {
+			max = max.max(characters.max_item_id()?);
+		}
+		for character in self.crew()? {
+			max = max.max(character.id.unwrap_or(0));
+		}
+		Ok(max)
+	}
+
+	/// Returns an entity ID not used by any item or crew member in the game session or character
+	/// data.
+	fn unused_entity_id(&self) -> Result<u16> {
+		self.max_entity_id()?
+			.checked_add(1)
+			.ok_or_else(no_free_entity_ids)
+	}
+
+	/// Fails if a crew member already has the given name.
+	fn check_unused_name(&self, name: &str) -> Result<()> {
+		if self.crew()?.iter().any(|i| i.name == name) {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::AlreadyExists,
+				format!("There is already a crew member named {name}"),
+			));
+		}
+		Ok(())
+	}
+
+	/// Modifies crew members, returning the changed members of the save file to write back.
+	///
+	/// Characters are looked up among the crew in the game session and, for multiplayer campaigns,
+	/// among the players in the character data. If `name` is `Some`, `modify` is applied only to
+	/// the character of that name, which must exist; otherwise, it is applied to every character.
+	fn modify_crew<F: FnMut(&mut CharacterEntry<'_>) -> Result<()>>(
+		&mut self,
+		name: Option<&str>,
+		mut modify: F,
+	) -> Result<Vec<Change>> {
+		let mut changes = Vec::new();
+		let mut found = false;
+		for mut entry in self.session.crew_mut() {
+			if name.is_none() || entry.name() == name {
+				modify(&mut entry)?;
+				found = true;
+			}
+		}
+		if found {
+			let mut data = Vec::new();
+			self.session.write(&mut data)?;
+			changes.push((GameSession::MEMBER_NAME, Some(data)));
+		}
+		if name.is_none() || !found {
+			if let Some(characters) = &mut self.characters {
+				let mut found_here = false;
+				for mut entry in characters.characters_mut() {
+					if name.is_none() || entry.name() == name {
+						modify(&mut entry)?;
+						found_here = true;
+					}
+				}
+				if found_here {
+					let mut data = Vec::new();
+					characters.write(&mut data)?;
+					changes.push((CharacterData::MEMBER_NAME, Some(data)));
+					found = true;
+				}
+			}
+		}
+		if let (Some(name), false) = (name, found) {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::NotFound,
+				format!("No crew member named {name}"),
+			));
+		}
+		Ok(changes)
+	}
+}
+
+/// Returns the error reported when every entity ID is taken.
+fn no_free_entity_ids() -> std::io::Error {
+	std::io::Error::other("No free entity IDs")
+}
+
+/// A summary of a campaign, as printed by [`show`].
+#[derive(Debug, Serialize)]
+struct Summary {
+	/// The name of the campaign, taken from the save filename.
+	campaign: String,
+
+	/// The version of the game that last wrote the save.
+	game_version: Option<String>,
+
+	/// The game mode.
+	game_mode: Option<GameMode>,
+
+	/// The crew’s current location.
+	location: Option<Location>,
+
+	/// The amount of money in the shared campaign bank.
+	money: Option<i32>,
+
+	/// The name of the submarine the crew is using.
+	active_submarine: Option<String>,
+
+	/// The names of the submarines the crew owns.
+	owned_submarines: Vec<String>,
+
+	/// The number of characters in the crew, not counting players.
+	crew: usize,
+
+	/// The names of the players, in a multiplayer campaign.
+	players: Option<Vec<String>>,
+
+	/// The number of missions completed.
+	completed_missions: usize,
+}
+
+/// Prints a summary of the campaign in a save file.
+pub fn show(filename: &OsStr, format: Format) -> Result<()> {
+	let campaign = Campaign::load(filename)?;
+	let session = &campaign.session;
+	let summary = Summary {
+		campaign: Path::new(filename)
+			.file_stem()
+			.unwrap_or(filename)
+			.to_string_lossy()
+			.into_owned(),
+		game_version: session.version().map(str::to_owned),
+		game_mode: campaign.game_mode(),
+		location: session.map()?.as_ref().and_then(|m| m.current()).cloned(),
+		money: session.money()?,
+		active_submarine: session.submarine().map(str::to_owned),
+		owned_submarines: session
+			.owned_submarines()
+			.into_iter()
+			.map(|i| i.name)
+			.collect(),
+		crew: session.crew_count(),
+		players: campaign
+			.characters
+			.as_ref()
+			.map(|i| i.names().into_iter().map(str::to_owned).collect()),
+		completed_missions: session.completed_missions().len(),
+	};
+	output::emit(format, &summary, |summary| {
+		outln!("Campaign: {}", summary.campaign);
+		if let Some(version) = &summary.game_version {
+			outln!("Game version: {version}");
+		}
+		match &summary.game_mode {
+			Some(GameMode::SinglePlayer) => outln!("Game mode: single-player"),
+			Some(GameMode::MultiPlayer) => outln!("Game mode: multiplayer"),
+			Some(GameMode::Other(mode)) => outln!("Game mode: {mode}"),
+			None => (),
+		}
+		if let Some(location) = &summary.location {
+			outln!("Location: {} ({})", location.name, location.kind);
+		}
+		if let Some(money) = summary.money {
+			outln!("Money: {money}");
+		}
+		let subs = summary
+			.owned_submarines
+			.iter()
+			.map(|i| {
+				if Some(i) == summary.active_submarine.as_ref() {
+					format!("{i} (active)")
+				} else {
+					i.clone()
+				}
+			})
+			.collect::<Vec<String>>();
+		outln!("Owned submarines: {}", subs.join(", "));
+		outln!("Crew: {}", summary.crew);
+		if let Some(players) = &summary.players {
+			outln!("Players: {}", players.join(", "));
+		}
+		outln!("Completed missions: {}", summary.completed_missions);
+	})
+}
+
+/// Sets the amount of money in a campaign.
+///
+/// If `character` is `None`, the shared campaign bank is modified. Otherwise, the wallet of the
+/// named player in a multiplayer campaign is modified.
+pub fn set_money(filename: &OsStr, money: i32, character: Option<&str>) -> Result<()> {
+	let mut data = Vec::new();
+	let mut campaign = Campaign::load(filename)?;
+	let member = if let Some(character) = character {
+		let Some(characters) = campaign.characters.as_mut() else {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"Only multiplayer campaigns have per-player wallets",
+			));
+		};
+		if !characters.set_money(character, money) {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::NotFound,
+				format!(
+					"No player named {} (players are: {})",
+					character,
+					characters.names().join(", ")
+				),
+			));
+		}
+		characters.write(&mut data)?;
+		CharacterData::MEMBER_NAME
+	} else {
+		campaign.session.set_money(money)?;
+		campaign.session.write(&mut data)?;
+		GameSession::MEMBER_NAME
+	};
+	archive::update(filename, &[(member, Some(data))])
+}
+
+/// Checks a save file for inconsistencies that would stop the game from loading it.
+///
+/// The game session is cross-referenced against the archive’s members: every owned submarine, and
+/// the active one, must have a `.sub` member that parses, a multiplayer campaign must have character
+/// data, and every `.sub` member should belong to an owned submarine. Each problem found is printed,
+/// and an error is returned if there were any.
+pub fn validate(filename: &OsStr, format: Format) -> Result<()> {
+	let problems = problems(filename)?;
+	output::emit(format, &problems, |problems| {
+		if problems.is_empty() {
+			outln!("{}", Style::Good.paint("No problems found"));
+		}
+		for problem in problems {
+			outln!("{}", Style::Error.paint(problem));
+		}
+	})?;
+	if problems.is_empty() {
+		return Ok(());
+	}
+	Err(exit::error(
+		exit::INVALID,
+		format!("Save file has {} problems", problems.len()),
+	))
+}
+
+/// Returns a description of each problem that [`validate`] finds in a save file.
+fn problems(filename: &OsStr) -> Result<Vec<String>> {
+	let members = save::member_names(filename)?;
+	let mut problems = Vec::new();
+	let mut seen = HashSet::new();
+	for member in &members {
+		if !seen.insert(member.as_str()) {
+			problems.push(format!("Member {member} appears more than once"));
+		}
+	}
+	let campaign = Campaign::load(filename)?;
+	let session = &campaign.session;
+
+	let owned = session.owned_submarines();
+	let mut expected: Vec<&str> = owned.iter().map(|i| i.name.as_str()).collect();
+	if let Some(active) = session.submarine() {
+		if !expected.contains(&active) {
+			problems.push(format!("Active submarine {active} is not owned"));
+			expected.push(active);
+		}
+	}
+	for name in &expected {
+		let member = submarine_member_name(name);
+		match save::read_member(filename, &member)? {
+			None => problems.push(format!("Submarine {name} has no {member} member")),
+			Some(data) => {
+				if let Err(e) = Submarine::parse(&data[..]) {
+					problems.push(format!("Member {member} is not a valid submarine: {e}"));
+				}
+			}
+		}
+	}
+	for member in &members {
+		if let Some(name) = member.strip_suffix(".sub") {
+			if !expected.contains(&name) {
+				problems.push(format!(
+					"Member {member} does not belong to any owned submarine"
+				));
+			}
+		}
+	}
+
+	if session.game_mode() == Some(GameMode::MultiPlayer) && campaign.characters.is_none() {
+		problems.push(format!(
+			"Multiplayer campaign has no {} member",
+			CharacterData::MEMBER_NAME
+		));
+	}
+	Ok(problems)
+}
+
+/// Returns the name of the save file member holding a submarine.
+fn submarine_member_name(name: &str) -> String {
+	format!("{name}.sub")
+}
+
+/// An owned submarine, as listed by [`list_submarines`].
+#[derive(Debug, Serialize)]
+struct SubmarineStatus {
+	/// The name of the submarine.
+	name: String,
+
+	/// Whether the crew is using the submarine.
+	active: bool,
+
+	/// Whether the submarine’s `.sub` member is missing from the save file.
+	missing: bool,
+}
+
+/// Lists the submarines owned in a campaign.
+pub fn list_submarines(filename: &OsStr, format: Format) -> Result<()> {
+	let session = load(filename)?;
+	let members = save::member_names(filename)?;
+	let subs = session
+		.owned_submarines()
+		.into_iter()
+		.map(|sub| SubmarineStatus {
+			active: Some(sub.name.as_str()) == session.submarine(),
+			missing: !members.contains(&submarine_member_name(&sub.name)),
+			name: sub.name,
+		})
+		.collect::<Vec<_>>();
+	output::emit(format, &subs, |subs| {
+		for sub in subs {
+			let mut notes = Vec::new();
+			if sub.active {
+				notes.push("active");
+			}
+			if sub.missing {
+				notes.push("missing");
+			}
+			if notes.is_empty() {
+				outln!("{}", sub.name);
+			} else {
+				outln!("{}\t{}", sub.name, notes.join(", "));
+			}
+		}
+	})
+}
+
+/// Adds a submarine to the list of owned submarines in a campaign.
+///
+/// The submarine is named after the `.sub` file, which is stored in the save file, replacing any
+/// existing copy.
+pub fn add_submarine(filename: &OsStr, sub_filename: &OsStr) -> Result<()> {
+	let path = Path::new(sub_filename);
+	let name = match path.file_stem().and_then(OsStr::to_str) {
+		Some(name) if path.extension() == Some(OsStr::new("sub")) => name,
+		_ => {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"Submarine filename must be a valid Unicode name ending in .sub",
+			))
+		}
+	};
+	let mut sub_data = Vec::new();
+	File::open(path)?.read_to_end(&mut sub_data)?;
+	let mut session = load(filename)?;
+	session.add_owned_submarine(name);
+	let mut session_data = Vec::new();
+	session.write(&mut session_data)?;
+	let member = submarine_member_name(name);
+	archive::update(
+		filename,
+		&[
+			(GameSession::MEMBER_NAME, Some(session_data)),
+			(&member, Some(sub_data)),
+		],
+	)
+}
+
+/// Removes a submarine from the list of owned submarines in a campaign, deleting it from the save
+/// file.
+pub fn remove_submarine(filename: &OsStr, name: &str) -> Result<()> {
+	let mut session = load(filename)?;
+	if session.submarine() == Some(name) {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!("{name} is the active submarine and cannot be removed"),
+		));
+	}
+	if !session.remove_owned_submarine(name) {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("{name} is not an owned submarine"),
+		));
+	}
+	let mut session_data = Vec::new();
+	session.write(&mut session_data)?;
+	let member = submarine_member_name(name);
+	archive::update(
+		filename,
+		&[
+			(GameSession::MEMBER_NAME, Some(session_data)),
+			(&member, None),
+		],
+	)
+}
+
+/// Changes which owned submarine the crew is currently using.
+///
+/// The submarine must be owned and its `.sub` member must be present in the save file.
+pub fn switch_submarine(filename: &OsStr, name: &str) -> Result<()> {
+	let mut session = load(filename)?;
+	if !session.owned_submarines().iter().any(|i| i.name == name) {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("{name} is not an owned submarine"),
+		));
+	}
+	let member = submarine_member_name(name);
+	if !save::member_names(filename)?.contains(&member) {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("Save file has no {member} member"),
+		));
+	}
+	session.set_submarine(name);
+	store(filename, &session)
+}
+
+/// Replaces an owned submarine with a newer revision of the same design.
+///
+/// The new revision is named after its `.sub` file. By default it replaces the owned submarine of
+/// the same name; `replace` names a different owned submarine to replace instead, in which case the
+/// game session’s references to it are renamed. Upgrades and item condition are carried over from
+/// the old revision as described in [`Submarine::carry_over_from`].
+pub fn upgrade_submarine(
+	filename: &OsStr,
+	sub_filename: &OsStr,
+	replace: Option<&str>,
+) -> Result<()> {
+	let path = Path::new(sub_filename);
+	let new_name = match path.file_stem().and_then(OsStr::to_str) {
+		Some(name) if path.extension() == Some(OsStr::new("sub")) => name,
+		_ => {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"Submarine filename must be a valid Unicode name ending in .sub",
+			))
+		}
+	};
+	let old_name = replace.unwrap_or(new_name);
+	let mut session = load(filename)?;
+	if !session
+		.owned_submarines()
+		.iter()
+		.any(|i| i.name == old_name)
+	{
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("{old_name} is not an owned submarine"),
+		));
+	}
+	let old_member = submarine_member_name(old_name);
+	let Some(old_data) = save::read_member(filename, &old_member)? else {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("Save file has no {old_member} member"),
+		));
+	};
+	let old_sub = Submarine::parse(&old_data[..])
+		.map_err(|e| diagnostic::in_file(e, Path::new(filename), Some(&old_member)))?;
+	let mut new_sub =
+		Submarine::parse(File::open(path)?).map_err(|e| diagnostic::in_file(e, path, None))?;
+	new_sub.set_name(new_name);
+	let carried = new_sub.carry_over_from(&old_sub);
+	output::note(format!(
+		"Carried over {} upgrades and the state of {} items",
+		carried.upgrades, carried.items
+	));
+	let mut sub_data = Vec::new();
+	new_sub.write(&mut sub_data)?;
+
+	let new_member = submarine_member_name(new_name);
+	let mut changes = vec![(new_member.as_str(), Some(sub_data))];
+	if old_name != new_name {
+		session.remove_owned_submarine(new_name);
+		session.rename_owned_submarine(old_name, new_name);
+		let mut session_data = Vec::new();
+		session.write(&mut session_data)?;
+		changes.push((GameSession::MEMBER_NAME, Some(session_data)));
+		changes.push((&old_member, None));
+	}
+	archive::update(filename, &changes)
+}
+
+/// Loads the active submarine from a campaign.
+fn load_active_submarine(filename: &OsStr, session: &GameSession) -> Result<(String, Submarine)> {
+	let Some(name) = session.submarine() else {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			"Campaign has no active submarine",
+		));
+	};
+	let member = submarine_member_name(name);
+	let Some(data) = save::read_member(filename, &member)? else {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("Save file has no {member} member"),
+		));
+	};
+	let sub = Submarine::parse(&data[..])
+		.map_err(|e| diagnostic::in_file(e, Path::new(filename), Some(&member)))?;
+	Ok((member, sub))
+}
+
+/// The upgrades of a submarine, as listed by [`list_upgrades`].
+#[derive(Debug, Serialize)]
+struct Upgrades {
+	/// The upgrades installed on the submarine.
+	installed: Vec<Upgrade>,
+
+	/// The upgrades bought but not yet installed.
+	pending: Vec<PendingUpgrade>,
+}
+
+/// Lists the upgrades installed on the active submarine and those bought but not yet installed in
+/// a campaign.
+pub fn list_upgrades(filename: &OsStr, format: Format) -> Result<()> {
+	let session = load(filename)?;
+	let (_, sub) = load_active_submarine(filename, &session)?;
+	let upgrades = Upgrades {
+		installed: sub.upgrades()?,
+		pending: session.pending_upgrades()?,
+	};
+	output::emit(format, &upgrades, |upgrades| {
+		for upgrade in &upgrades.installed {
+			outln!("installed\t{}\t{}", upgrade.identifier, upgrade.level);
+		}
+		for upgrade in &upgrades.pending {
+			outln!("pending\t{}\t{}", upgrade.prefab, upgrade.level);
+		}
+	})
+}
+
+/// Sets the installed level of an upgrade on the active submarine in a campaign.
+///
+/// Any purchase of the same upgrade still waiting to be installed is cancelled, so that the game
+/// does not apply it on top of the new level.
+pub fn set_upgrade(filename: &OsStr, identifier: &str, level: u32) -> Result<()> {
+	let mut session = load(filename)?;
+	let (member, mut sub) = load_active_submarine(filename, &session)?;
+	sub.set_upgrade_level(identifier, level);
+	let mut sub_data = Vec::new();
+	sub.write(&mut sub_data)?;
+	let mut changes = vec![(member.as_str(), Some(sub_data))];
+	let cancelled = session.remove_pending_upgrades(identifier)?;
+	if cancelled != 0 {
+		output::note(format!("Cancelled {cancelled} pending upgrades"));
+		let mut session_data = Vec::new();
+		session.write(&mut session_data)?;
+		changes.push((GameSession::MEMBER_NAME, Some(session_data)));
+	}
+	archive::update(filename, &changes)
+}
+
+/// Loads the crew from a campaign.
+///
+/// For multiplayer campaigns, the players’ characters are included as well as the bots.
+fn load_crew(filename: &OsStr) -> Result<Vec<Character>> {
+	Campaign::load(filename)?.crew()
+}
+
+/// Lists the crew in a campaign.
+///
+/// For multiplayer campaigns, the players’ characters are listed as well as the bots.
+pub fn list_crew(filename: &OsStr, format: Format) -> Result<()> {
+	output::emit(format, &load_crew(filename)?, |crew| {
+		for character in crew {
+			outln!(
+				"{}\t{}\t{}\t{}",
+				character.name,
+				character
+					.job
+					.as_deref()
+					.or(character.species.as_deref())
+					.unwrap_or("-"),
+				character.experience,
+				health_summary(character)
+			);
+		}
+	})
+}
+
+/// A pet, as listed by [`list_pets`].
+#[derive(Debug, Serialize)]
+struct PetStatus {
+	/// The species of the pet.
+	species: String,
+
+	/// The name of the character that owns the pet, if any.
+	owner: Option<String>,
+
+	/// How hungry the pet is, from 0 to 100.
+	hunger: Option<f32>,
+
+	/// How happy the pet is, from 0 to 100.
+	happiness: Option<f32>,
+}
+
+/// Lists the pets in a campaign.
+pub fn list_pets(filename: &OsStr, format: Format) -> Result<()> {
+	let campaign = Campaign::load(filename)?;
+	let crew = campaign.crew()?;
+	let pets = campaign
+		.session
+		.pets()?
+		.into_iter()
+		.map(|pet| PetStatus {
+			owner: pet.owner.map(|id| {
+				crew.iter()
+					.find(|i| i.id == Some(id))
+					.map_or_else(|| "unknown".to_owned(), |i| i.name.clone())
+			}),
+			species: pet.species,
+			hunger: pet.hunger,
+			happiness: pet.happiness,
+		})
+		.collect::<Vec<_>>();
+	output::emit(format, &pets, |pets| {
+		let level =
+			|value: Option<f32>| value.map_or_else(|| "-".to_owned(), |i| format!("{i:.0}"));
+		for pet in pets {
+			outln!(
+				"{}\t{}\thunger {}\thappiness {}",
+				pet.species,
+				pet.owner.as_deref().unwrap_or("-"),
+				level(pet.hunger),
+				level(pet.happiness)
+			);
+		}
+	})
+}
+
+/// Returns a short description of a character’s health.
+fn health_summary(character: &Character) -> String {
+	if character.afflictions.is_empty() {
+		"healthy".to_owned()
+	} else {
+		character
+			.afflictions
+			.iter()
+			.map(|i| format!("{} {:.0}", i.identifier, i.strength))
+			.collect::<Vec<String>>()
+			.join(", ")
+	}
+}
+
+/// Modifies crew members in a campaign, as [`Campaign::modify_crew`] does, then writes the modified
+/// members back to the save file.
+fn modify_crew<F: FnMut(&mut CharacterEntry<'_>) -> Result<()>>(
+	filename: &OsStr,
+	name: Option<&str>,
+	modify: F,
+) -> Result<()> {
+	let changes = Campaign::load(filename)?.modify_crew(name, modify)?;
+	archive::update(filename, &changes)
+}
+
+/// Sets skill levels of a crew member in a campaign.
+pub fn set_skills(filename: &OsStr, name: &str, skills: &[(&str, f32)]) -> Result<()> {
+	modify_crew(filename, Some(name), |character| {
+		for (identifier, level) in skills {
+			character.set_skill(identifier, *level)?;
+		}
+		Ok(())
+	})
+}
+
+/// Adds experience points to a crew member in a campaign.
+pub fn grant_experience(filename: &OsStr, name: &str, experience: u32) -> Result<()> {
+	let mut total = 0;
+	modify_crew(filename, Some(name), |character| {
+		total = character.grant_experience(experience)?;
+		Ok(())
+	})?;
+	output::note(format!("{name} now has {total} experience points"));
+	Ok(())
+}
+
+/// Unlocks talents for a crew member in a campaign.
+///
+/// Talents that are already unlocked are left alone.
+pub fn unlock_talents(filename: &OsStr, name: &str, talents: &[&str]) -> Result<()> {
+	let mut unlocked = 0;
+	modify_crew(filename, Some(name), |character| {
+		for talent in talents {
+			if character.unlock_talent(talent)? {
+				unlocked += 1;
+			}
+		}
+		Ok(())
+	})?;
+	output::note(format!("Unlocked {unlocked} talents"));
+	Ok(())
+}
+
+/// Removes afflictions from crew members in a campaign.
+///
+/// If `name` is `None`, every crew member is healed. If `afflictions` is empty, all afflictions are
+/// removed; otherwise, only those with the listed identifiers are.
+pub fn heal_crew(filename: &OsStr, name: Option<&str>, afflictions: &[&str]) -> Result<()> {
+	let mut removed = 0;
+	modify_crew(filename, name, |character| {
+		removed +=
+			character.remove_afflictions(|i| afflictions.is_empty() || afflictions.contains(&i));
+		Ok(())
+	})?;
+	output::note(format!("Removed {removed} afflictions"));
+	Ok(())
+}
+
+/// Renames a crew member in a campaign.
+pub fn rename_crew(filename: &OsStr, old: &str, new: &str) -> Result<()> {
+	let mut campaign = Campaign::load(filename)?;
+	campaign.check_unused_name(new)?;
+	let changes = campaign.modify_crew(Some(old), |character| Ok(character.rename(new)?))?;
+	archive::update(filename, &changes)
+}
+
+/// Changes a crew member’s name, job, or appearance in a campaign.
+pub fn edit_crew(
+	filename: &OsStr,
+	name: &str,
+	new_name: Option<&str>,
+	job: Option<&str>,
+	appearance: &Appearance,
+) -> Result<()> {
+	let mut campaign = Campaign::load(filename)?;
+	if let Some(new_name) = new_name.filter(|i| *i != name) {
+		campaign.check_unused_name(new_name)?;
+	}
+	let changes = campaign.modify_crew(Some(name), |character| {
+		if let Some(new_name) = new_name {
+			character.rename(new_name)?;
+		}
+		if let Some(job) = job {
+			character.set_job(job)?;
+		}
+		character.set_appearance(appearance)?;
+		Ok(())
+	})?;
+	archive::update(filename, &changes)
+}
+
+/// Removes a crew member from a campaign.
+///
+/// In multiplayer campaigns, a player’s wallet, inventory, and health are removed along with
+/// their character. Pets owned by the removed character are kept, but no longer have an owner.
+pub fn remove_crew(filename: &OsStr, name: &str) -> Result<()> {
+	let mut campaign = Campaign::load(filename)?;
+	let id = campaign
+		.crew()?
+		.into_iter()
+		.find(|i| i.name == name)
+		.and_then(|i| i.id);
+	let not_found = || {
+		std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("No crew member named {name}"),
+		)
+	};
+	let mut changes = Vec::new();
+	let bot = campaign.session.remove_crew_member(name);
+	if !bot {
+		let Some(characters) = &mut campaign.characters else {
+			return Err(not_found());
+		};
+		if !characters.remove_character(name) {
+			return Err(not_found());
+		}
+		let mut data = Vec::new();
+		characters.write(&mut data)?;
+		changes.push((CharacterData::MEMBER_NAME, Some(data)));
+	}
+	let pets = campaign.session.disown_pets(id.as_slice())?;
+	if bot || pets != 0 {
+		let mut data = Vec::new();
+		campaign.session.write(&mut data)?;
+		changes.push((GameSession::MEMBER_NAME, Some(data)));
+	}
+	if pets != 0 {
+		output::note(format!("Cleared the owner of {pets} pets"));
+	}
+	archive::update(filename, &changes)
+}
+
+/// Loads a character from an XML file whose root is a `Character` element.
+fn load_character_template(template: &OsStr) -> Result<Element> {
+	let character = xml::parse(BufReader::new(File::open(template)?))?;
+	if !xml::is_named(&character, "Character") {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			format!("Expected Character root element, found {}", character.name),
+		));
+	}
+	Ok(character)
+}
+
+/// Adds a crew member to a campaign.
+///
+/// `template` names an XML file whose root is a `Character` element, such as one copied out of
+/// another save. In multiplayer campaigns the character joins the crew as a bot. The character
+/// and every item it carries are given entity IDs not used by anything else in the save, so that
+/// a character copied from another save does not clash with this one’s.
+pub fn add_crew(filename: &OsStr, template: &OsStr) -> Result<()> {
+	let mut character = load_character_template(template)?;
+	let name = Character::from_xml(&character)?.name;
+	let mut campaign = Campaign::load(filename)?;
+	campaign.check_unused_name(&name)?;
+	let id = campaign.unused_entity_id()?;
+	character::renumber(&mut character, id).ok_or_else(no_free_entity_ids)?;
+	campaign.session.add_crew_member(character)?;
+	store(filename, &campaign.session)?;
+	output::note(format!("Added {name} with ID {id}"));
+	Ok(())
+}
+
+/// A skill preset for a bot added by [`add_bot`].
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Preset {
+	/// A new recruit, as first hired.
+	Novice,
+
+	/// A crew member with a few missions behind them.
+	Experienced,
+
+	/// A crew member near the top of their field.
+	Veteran,
+}
+
+impl Preset {
+	/// Returns the levels of the job’s primary skill and of every other skill.
+	fn levels(self) -> (f32, f32) {
+		match self {
+			Self::Novice => (40.0, 10.0),
+			Self::Experienced => (65.0, 25.0),
+			Self::Veteran => (90.0, 40.0),
+		}
+	}
+}
+
+/// The skills every human character has.
+const SKILLS: [&str; 5] = ["electrical", "helm", "mechanical", "medical", "weapons"];
+
+/// Returns the skill a vanilla job is best at, if it is one of the vanilla jobs.
+fn primary_skill(job: &str) -> Option<&'static str> {
+	match job {
+		"captain" => Some("helm"),
+		"engineer" => Some("electrical"),
+		"mechanic" => Some("mechanical"),
+		"medicaldoctor" => Some("medical"),
+		"securityofficer" => Some("weapons"),
+		_ => None,
+	}
+}
+
+/// A description of a bot to add, as read by [`add_bot`].
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct BotDescription {
+	/// The bot’s name.
+	name: String,
+
+	/// The identifier of the bot’s job, such as `mechanic`.
+	job: String,
+
+	/// The preset that sets the bot’s skills.
+	#[serde(default = "default_preset")]
+	preset: Preset,
+
+	/// Skill levels that override the preset’s, keyed by skill identifier.
+	#[serde(default)]
+	skills: BTreeMap<String, f32>,
+}
+
+/// Returns the preset used when a bot description does not name one.
+fn default_preset() -> Preset {
+	Preset::Novice
+}
+
+impl BotDescription {
+	/// Reads a bot description from a TOML file or, if its name ends in `.json`, a JSON file.
+	fn load(path: &Path) -> Result<Self> {
+		let text = std::fs::read_to_string(path)?;
+		let json = path
+			.extension()
+			.is_some_and(|i| i.eq_ignore_ascii_case("json"));
+		let description: Self = if json {
+			serde_json::from_str(&text).map_err(|e| e.to_string())
+		} else {
+			toml::from_str(&text).map_err(|e| e.to_string())
+		}
+		.map_err(|e| {
+			diagnostic::in_file(diagnostic::invalid(e.trim_end().to_owned()), path, None)
+		})?;
+		if let Some((identifier, _)) = description
+			.skills
+			.iter()
+			.find(|(_, level)| !(0.0..=100.0).contains(*level))
+		{
+			return Err(diagnostic::in_file(
+				diagnostic::invalid(format!(
+					"Level of skill {identifier} must be between 0 and 100"
+				)),
+				path,
+				None,
+			)
+			.into());
+		}
+		Ok(description)
+	}
+
+	/// Returns the bot’s skills: those set by its preset, followed by any others it names.
+	fn skills(&self) -> Vec<Skill> {
+		let (primary, other) = self.preset.levels();
+		let primary_skill = primary_skill(&self.job);
+		let mut skills = SKILLS
+			.iter()
+			.map(|&identifier| Skill {
+				identifier: identifier.to_owned(),
+				level: self.skills.get(identifier).copied().unwrap_or(
+					if Some(identifier) == primary_skill {
+						primary
+					} else {
+						other
+					},
+				),
+			})
+			.collect::<Vec<Skill>>();
+		for (identifier, &level) in &self.skills {
+			if !SKILLS.contains(&identifier.as_str()) {
+				skills.push(Skill {
+					identifier: identifier.clone(),
+					level,
+				});
+			}
+		}
+		skills
+	}
+}
+
+/// Adds a new bot to the crew of a campaign, built from a description in a TOML or JSON file.
+///
+/// The description names the bot, its job, and optionally a skill preset (`novice`, the default,
+/// `experienced`, or `veteran`) and a table of skill levels overriding the preset’s. The bot is
+/// given an entity ID not used by any item or character in the save.
+///
+/// The bot is written to the game session, even in a multiplayer campaign: `CharacterData.xml`
+/// only holds the characters of players, and the game keeps bots with the rest of the crew.
+pub fn add_bot(filename: &OsStr, description: &OsStr) -> Result<()> {
+	let description = BotDescription::load(Path::new(description))?;
+	let mut campaign = Campaign::load(filename)?;
+	campaign.check_unused_name(&description.name)?;
+	let id = campaign.unused_entity_id()?;
+	campaign.session.add_crew_member(character::new_bot(
+		&description.name,
+		id,
+		&description.job,
+		&description.skills(),
+	))?;
+	store(filename, &campaign.session)?;
+	output::note(format!("Added {} with ID {id}", description.name));
+	Ok(())
+}
+
+/// Removes the crew members recorded as dead from a campaign.
+///
+/// Dead bots are removed from the game session and dead players’ characters from the character
+/// data. Pets owned by a removed character are kept, but no longer have an owner.
+pub fn prune_crew(filename: &OsStr) -> Result<()> {
+	let mut campaign = Campaign::load(filename)?;
+	let mut changes = Vec::new();
+	let mut removed = campaign.session.remove_dead_crew()?;
+	let bots = removed.len();
+	if let Some(characters) = &mut campaign.characters {
+		let players = characters.remove_dead()?;
+		if !players.is_empty() {
+			let mut data = Vec::new();
+			characters.write(&mut data)?;
+			changes.push((CharacterData::MEMBER_NAME, Some(data)));
+			removed.extend(players);
+		}
+	}
+	if removed.is_empty() {
+		output::note("No dead crew members");
+		return Ok(());
+	}
+	let ids = removed.iter().filter_map(|i| i.id).collect::<Vec<u16>>();
+	let pets = campaign.session.disown_pets(&ids)?;
+	if bots != 0 || pets != 0 {
+		let mut data = Vec::new();
+		campaign.session.write(&mut data)?;
+		changes.push((GameSession::MEMBER_NAME, Some(data)));
+	}
+	for character in &removed {
+		output::note(format!("Removed {}", character.name));
+	}
+	if pets != 0 {
+		output::note(format!("Cleared the owner of {pets} pets"));
+	}
+	archive::update(filename, &changes)
+}
+
+/// Lists the characters available for hire at the crew’s current location in a campaign.
+pub fn list_hires(filename: &OsStr, format: Format) -> Result<()> {
+	output::emit(format, &load(filename)?.hireables()?, |hireables| {
+		for character in hireables {
+			let skills = character
+				.skills
+				.iter()
+				.map(|i| format!("{} {:.0}", i.identifier, i.level))
+				.collect::<Vec<String>>()
+				.join(", ");
+			outln!(
+				"{}\t{}\t{}",
+				character.name,
+				character.job.as_deref().unwrap_or("-"),
+				skills
+			);
+		}
+	})
+}
+
+/// Adds a character to those available for hire at the crew’s current location in a campaign.
+///
+/// `template` names an XML file whose root is a `Character` element, as for [`add_crew`].
+pub fn add_hire(filename: &OsStr, template: &OsStr) -> Result<()> {
+	let character = load_character_template(template)?;
+	let mut session = load(filename)?;
+	session.add_hireable(character)?;
+	store(filename, &session)
+}
+
+/// Removes a character from those available for hire at the crew’s current location in a
+/// campaign.
+pub fn remove_hire(filename: &OsStr, name: &str) -> Result<()> {
+	let mut session = load(filename)?;
+	if !session.remove_hireable(name)? {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("No character named {name} is available for hire here"),
+		));
+	}
+	store(filename, &session)
+}
+
+/// Lists the items in a crew member’s inventory.
+///
+/// Only the items chosen by `selection` are listed, along with the containers holding them.
+pub fn list_inventory(
+	filename: &OsStr,
+	name: &str,
+	selection: &Selection,
+	format: Format,
+) -> Result<()> {
+	let Some(character) = load_crew(filename)?.into_iter().find(|i| i.name == name) else {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("No crew member named {name}"),
+		));
+	};
+	let items = select_items(character.inventory, selection);
+	output::emit(format, &items, |items| print_items(items, 0))
+}
+
+/// Keeps the items that are selected or that contain selected items.
+fn select_items(items: Vec<InventoryItem>, selection: &Selection) -> Vec<InventoryItem> {
+	items
+		.into_iter()
+		.filter_map(|mut item| {
+			let selected = selection.matches(&Candidate::of_inventory_item(&item));
+			item.contained = select_items(item.contained, selection);
+			(selected || !item.contained.is_empty()).then_some(item)
+		})
+		.collect()
+}
+
+/// Prints a list of items, indenting contained items beneath their containers.
+fn print_items(items: &[InventoryItem], depth: usize) {
+	for item in items {
+		let slot = item.slot.map_or_else(|| "-".to_owned(), |i| i.to_string());
+		let id = item.id.map_or_else(|| "-".to_owned(), |i| i.to_string());
+		outln!(
+			"{}{}\t{}\t{}",
+			"  ".repeat(depth),
+			item.identifier,
+			slot,
+			id
+		);
+		print_items(&item.contained, depth + 1);
+	}
+}
+
+/// Adds an item to a crew member’s inventory.
+///
+/// The item is given an entity ID not used by any item or character in the save.
+pub fn add_item(filename: &OsStr, name: &str, identifier: &str, slot: Option<usize>) -> Result<()> {
+	let mut campaign = Campaign::load(filename)?;
+	let id = campaign.unused_entity_id()?;
+	let changes = campaign.modify_crew(Some(name), |character| {
+		character.add_item(identifier, id, slot);
+		Ok(())
+	})?;
+	archive::update(filename, &changes)
+}
+
+/// Removes items from a crew member’s inventory.
+///
+/// Every item chosen by `selection` is removed, along with anything inside it.
+pub fn remove_item(filename: &OsStr, name: &str, selection: &Selection) -> Result<()> {
+	let mut removed = 0;
+	modify_crew(filename, Some(name), |character| {
+		removed += character.remove_items(|i| selection.matches(&Candidate::of_element(i, None)));
+		Ok(())
+	})?;
+	output::note(format!("Removed {removed} items"));
+	Ok(())
+}
+
+/// Swaps the contents of two slots in a crew member’s inventory.
+pub fn swap_items(filename: &OsStr, name: &str, a: usize, b: usize) -> Result<()> {
+	let mut moved = 0;
+	modify_crew(filename, Some(name), |character| {
+		moved = character.swap_slots(a, b);
+		Ok(())
+	})?;
+	output::note(format!("Moved {moved} items"));
+	Ok(())
+}
+
+/// The crew’s reputation, as shown by [`show_reputation`].
+#[derive(Debug, Serialize)]
+struct Reputation {
+	/// The crew’s reputation with each faction.
+	factions: Vec<Faction>,
+
+	/// The crew’s reputation with each location that tracks it.
+	locations: Vec<LocationReputation>,
+}
+
+/// The crew’s reputation with a single location.
+#[derive(Debug, Serialize)]
+struct LocationReputation {
+	/// The location’s name.
+	name: String,
+
+	/// The crew’s reputation with the location.
+	reputation: f32,
+}
+
+/// Shows the crew’s reputation with each faction and location in a campaign.
+pub fn show_reputation(filename: &OsStr, format: Format) -> Result<()> {
+	let session = load(filename)?;
+	let reputation = Reputation {
+		factions: session.factions()?,
+		locations: session
+			.map()?
+			.map(|m| m.locations)
+			.unwrap_or_default()
+			.into_iter()
+			.filter_map(|i| {
+				Some(LocationReputation {
+					reputation: i.reputation?,
+					name: i.name,
+				})
+			})
+			.collect(),
+	};
+	output::emit(format, &reputation, |reputation| {
+		for faction in &reputation.factions {
+			outln!("faction\t{}\t{}", faction.identifier, faction.reputation);
+		}
+		for location in &reputation.locations {
+			outln!("location\t{}\t{}", location.name, location.reputation);
+		}
+	})
+}
+
+/// Sets the crew’s reputation with a faction or, if `location` is `true`, a location.
+pub fn set_reputation(
+	filename: &OsStr,
+	target: &str,
+	reputation: f32,
+	location: bool,
+) -> Result<()> {
+	let mut session = load(filename)?;
+	let found = if location {
+		session.set_location_reputation(target, reputation)?
+	} else {
+		session.set_faction_reputation(target, reputation)?
+	};
+	if !found {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!(
+				"No {} named {target}",
+				if location { "location" } else { "faction" }
+			),
+		));
+	}
+	store(filename, &session)
+}
+
+/// Lists the items bought but not yet delivered in a campaign.
+pub fn list_cargo(filename: &OsStr, format: Format) -> Result<()> {
+	output::emit(format, &load(filename)?.purchased_items()?, |items| {
+		for item in items {
+			outln!("{}\t{}", item.identifier, item.quantity);
+		}
+	})
+}
+
+/// Sets the number of a kind of item bought but not yet delivered in a campaign.
+///
+/// A quantity of zero cancels the order for that item.
+pub fn set_cargo(filename: &OsStr, identifier: &str, quantity: u32) -> Result<()> {
+	let mut session = load(filename)?;
+	session.set_purchased_quantity(identifier, quantity)?;
+	store(filename, &session)
+}
+
+/// Cancels the delivery of every item bought but not yet delivered in a campaign.
+pub fn clear_cargo(filename: &OsStr) -> Result<()> {
+	let mut session = load(filename)?;
+	let removed = session.clear_purchased_items()?;
+	output::note(format!("Cancelled {removed} orders"));
+	store(filename, &session)
+}
+
+/// Shows the progress counters of a campaign.
+pub fn show_progress(filename: &OsStr, format: Format) -> Result<()> {
+	output::emit(format, &load(filename)?.progress()?, |progress| {
+		if let Some(play_time) = progress.play_time {
+			outln!("Play time: {play_time:.0} seconds");
+		}
+		if let Some(passed_levels) = progress.passed_levels {
+			outln!("Levels passed: {passed_levels}");
+		}
+	})
+}
+
+/// Changes the progress counters of a campaign.
+pub fn set_progress(filename: &OsStr, progress: Progress) -> Result<()> {
+	let mut session = load(filename)?;
+	session.set_progress(progress)?;
+	store(filename, &session)
+}
+
+/// Lists the settings chosen when a campaign was created.
+pub fn list_settings(filename: &OsStr, format: Format) -> Result<()> {
+	output::emit(format, &load(filename)?.settings(), |settings| {
+		for setting in settings {
+			outln!("{}\t{}", setting.name, setting.value);
+		}
+	})
+}
+
+/// Changes settings chosen when a campaign was created.
+pub fn set_settings(filename: &OsStr, settings: &[(&str, &str)]) -> Result<()> {
+	let mut session = load(filename)?;
+	for (name, value) in settings {
+		if !session.set_setting(name, value)? {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::NotFound,
+				format!("No campaign setting named {name}"),
+			));
+		}
+	}
+	store(filename, &session)
+}
+
+/// Loads the campaign map from a game session.
+fn load_map(session: &GameSession) -> Result<MapState> {
+	session.map()?.ok_or_else(|| {
+		std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			"Campaign does not contain a map",
+		)
+	})
+}
+
+/// Marks locations on the campaign map as discovered.
+///
+/// If `radius` is `Some`, only locations within that many connections of the current location are
+/// revealed; otherwise the whole map is.
+pub fn reveal_map(filename: &OsStr, radius: Option<usize>) -> Result<()> {
+	let mut session = load(filename)?;
+	let changed = match radius {
+		Some(radius) => {
+			let nearby = load_map(&session)?.within(radius);
+			session.set_discovered(true, |i| nearby.contains(&i))?
+		}
+		None => session.set_discovered(true, |_| true)?,
+	};
+	output::note(format!("Revealed {changed} locations"));
+	store(filename, &session)
+}
+
+/// Marks locations on the campaign map as undiscovered.
+///
+/// Locations within `keep` connections of the current location are left alone; all others are
+/// hidden.
+pub fn hide_map(filename: &OsStr, keep: usize) -> Result<()> {
+	let mut session = load(filename)?;
+	let nearby = load_map(&session)?.within(keep);
+	let changed = session.set_discovered(false, |i| !nearby.contains(&i))?;
+	output::note(format!("Hid {changed} locations"));
+	store(filename, &session)
+}
+
+/// Lists the missions offered and accepted in a campaign.
+pub fn list_missions(filename: &OsStr, format: Format) -> Result<()> {
+	output::emit(format, &load(filename)?.missions()?, |missions| {
+		for mission in missions {
+			outln!(
+				"{}\t{}\t{}",
+				mission.identifier,
+				mission.location,
+				if mission.selected {
+					"accepted"
+				} else {
+					"available"
+				}
+			);
+		}
+	})
+}
+
+/// Removes a mission from a campaign, whether offered or accepted.
+pub fn remove_mission(filename: &OsStr, identifier: &str) -> Result<()> {
+	let mut session = load(filename)?;
+	if session.remove_missions(identifier)? == 0 {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			format!("No mission with identifier {identifier}"),
+		));
+	}
+	store(filename, &session)
+}
+
+/// The levels around the crew, as shown by [`show_levels`].
+#[derive(Debug, Serialize)]
+struct Levels {
+	/// The level at the crew’s current location.
+	location: Option<LevelData>,
+
+	/// The level on the way to the crew’s selected destination.
+	next: Option<LevelData>,
+}
+
+/// Shows the parameters of the current and next levels in a campaign.
+pub fn show_levels(filename: &OsStr, format: Format) -> Result<()> {
+	let session = load(filename)?;
+	let levels = Levels {
+		location: session.level(LevelChoice::Location)?,
+		next: session.level(LevelChoice::Next)?,
+	};
+	output::emit(format, &levels, |levels| {
+		for (label, level) in [("location", &levels.location), ("next", &levels.next)] {
+			if let Some(level) = level {
+				let difficulty = level
+					.difficulty
+					.map_or_else(|| "-".to_owned(), |i| i.to_string());
+				outln!("{label}\t{}\t{difficulty}", level.seed);
+			}
+		}
+	})
+}
+
+/// Sets the seed of a level in a campaign.
+pub fn set_level_seed(filename: &OsStr, choice: LevelChoice, seed: &str) -> Result<()> {
+	let mut session = load(filename)?;
+	if !session.set_level_seed(choice, seed)? {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::NotFound,
+			match choice {
+				LevelChoice::Location => "The current location has no level",
+				LevelChoice::Next => "There is no level on the way to the selected destination",
+			},
+		));
+	}
+	store(filename, &session)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use barotool::save::ArchiveWriter;
+	use std::io::Write;
+	use std::path::PathBuf;
+
+	/// A multiplayer game session with a shared bank, one owned submarine, and one bot.
+	const SESSION: &str = r#"<Gamesession submarine="Orca">
+  <ownedsubmarines>
+    <sub name="Orca" />
+  </ownedsubmarines>
+  <MultiPlayerCampaign>
+    <Wallet money="1000" />
+    <crew>
+      <Character name="Carol" ID="5" />
+    </crew>
+  </MultiPlayerCampaign>
+</Gamesession>"#;
+
+	/// The character data of two players, only the first of whom has a wallet.
+	const CHARACTERS: &str = r#"<CharacterData>
+  <CharacterCampaignData name="Alice">
+    <Character name="Alice" ID="7" />
+    <Wallet money="500" />
+  </CharacterCampaignData>
+  <CharacterCampaignData name="Bob">
+    <Character name="Bob" ID="8" />
+  </CharacterCampaignData>
+</CharacterData>"#;
+
+	/// Creates an empty directory for a test to work in.
+	fn test_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!(
+			"barotool-test-campaign-{name}-{}",
+			std::process::id()
+		));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	/// Writes a save file holding the given members.
+	fn write_save(path: &Path, members: &[(&str, &[u8])]) {
+		let mut writer = ArchiveWriter::new(File::create(path).unwrap()).unwrap();
+		for (name, data) in members {
+			writer.write_member(name, data).unwrap();
+		}
+		writer.finish().unwrap();
+	}
+
+	/// Returns the text of a member of a save file.
+	fn read_text(path: &Path, name: &str) -> String {
+		String::from_utf8(save::read_member(path.as_os_str(), name).unwrap().unwrap()).unwrap()
+	}
+
+	#[test]
+	fn test_set_money() {
+		let dir = test_dir("money");
+		let path = dir.join("campaign.save");
+		write_save(
+			&path,
+			&[
+				(GameSession::MEMBER_NAME, SESSION.as_bytes()),
+				(CharacterData::MEMBER_NAME, CHARACTERS.as_bytes()),
+			],
+		);
+		set_money(path.as_os_str(), 1234, None).unwrap();
+		assert_eq!(load(path.as_os_str()).unwrap().money().unwrap(), Some(1234));
+		set_money(path.as_os_str(), 750, Some("Alice")).unwrap();
+		set_money(path.as_os_str(), 20, Some("Bob")).unwrap();
+		let characters = read_text(&path, CharacterData::MEMBER_NAME);
+		assert!(characters.contains(r#"<Wallet money="750" />"#));
+		assert!(characters.contains(r#"<Wallet money="20" />"#));
+		assert_eq!(load(path.as_os_str()).unwrap().money().unwrap(), Some(1234));
+		let error = set_money(path.as_os_str(), 1, Some("Dave")).unwrap_err();
+		assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+		assert!(error.to_string().contains("Alice, Bob"));
+
+		write_save(&path, &[(GameSession::MEMBER_NAME, SESSION.as_bytes())]);
+		let error = set_money(path.as_os_str(), 1, Some("Alice")).unwrap_err();
+		assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn test_problems() {
+		let dir = test_dir("problems");
+		let path = dir.join("campaign.save");
+		let mut encoder = barotool::compression::encoder(Vec::new()).unwrap();
+		encoder.write_all(br#"<Submarine name="Orca" />"#).unwrap();
+		let sub = encoder.finish().unwrap();
+		write_save(
+			&path,
+			&[
+				(GameSession::MEMBER_NAME, SESSION.as_bytes()),
+				(CharacterData::MEMBER_NAME, CHARACTERS.as_bytes()),
+				("Orca.sub", &sub),
+			],
+		);
+		assert!(problems(path.as_os_str()).unwrap().is_empty());
+
+		write_save(
+			&path,
+			&[
+				(GameSession::MEMBER_NAME, SESSION.as_bytes()),
+				(CharacterData::MEMBER_NAME, CHARACTERS.as_bytes()),
+				("Orca.sub", b"<Gamesession />"),
+				("Kastrull.sub", &sub),
+				("Kastrull.sub", &sub),
+			],
+		);
+		let found = problems(path.as_os_str()).unwrap();
+		assert_eq!(found.len(), 4);
+		assert_eq!(found[0], "Member Kastrull.sub appears more than once");
+		assert!(found[1].starts_with("Member Orca.sub is not a valid submarine"));
+		assert_eq!(
+			found[2..],
+			[
+				"Member Kastrull.sub does not belong to any owned submarine",
+				"Member Kastrull.sub does not belong to any owned submarine",
+			]
+		);
+
+		write_save(
+			&path,
+			&[(
+				GameSession::MEMBER_NAME,
+				SESSION
+					.replace(r#"submarine="Orca""#, r#"submarine="Humpback""#)
+					.as_bytes(),
+			)],
+		);
+		assert_eq!(
+			problems(path.as_os_str()).unwrap(),
+			[
+				"Active submarine Humpback is not owned",
+				"Submarine Orca has no Orca.sub member",
+				"Submarine Humpback has no Humpback.sub member",
+				"Multiplayer campaign has no CharacterData.xml member",
+			]
+		);
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn test_bot_description() {
+		let dir = test_dir("bot");
+		let toml = dir.join("bot.toml");
+		std::fs::write(
+			&toml,
+			"name = \"Dave\"\njob = \"mechanic\"\npreset = \"veteran\"\n\n[skills]\nmedical = 55\nfishing = 12.5\n",
+		)
+		.unwrap();
+		let bot = BotDescription::load(&toml).unwrap();
+		assert_eq!(bot.name, "Dave");
+		assert_eq!(bot.job, "mechanic");
+		let skill = |identifier: &str, level| Skill {
+			identifier: identifier.to_owned(),
+			level,
+		};
+		assert_eq!(
+			bot.skills(),
+			[
+				skill("electrical", 40.0),
+				skill("helm", 40.0),
+				skill("mechanical", 90.0),
+				skill("medical", 55.0),
+				skill("weapons", 40.0),
+				skill("fishing", 12.5),
+			]
+		);
+
+		let json = dir.join("bot.JSON");
+		std::fs::write(&json, r#"{"name": "Erin", "job": "captain"}"#).unwrap();
+		let bot = BotDescription::load(&json).unwrap();
+		assert_eq!(bot.name, "Erin");
+		assert_eq!(
+			bot.skills(),
+			[
+				skill("electrical", 10.0),
+				skill("helm", 40.0),
+				skill("mechanical", 10.0),
+				skill("medical", 10.0),
+				skill("weapons", 10.0),
+			]
+		);
+		std::fs::write(
+			&json,
+			r#"{"name": "Erin", "job": "assistant", "preset": "experienced"}"#,
+		)
+		.unwrap();
+		assert!(BotDescription::load(&json)
+			.unwrap()
+			.skills()
+			.iter()
+			.all(|i| i.level.to_bits() == 25.0_f32.to_bits()));
+
+		std::fs::write(&toml, "name = \"Dave\"\njob = \"mechanic\"\nage = 40\n").unwrap();
+		assert!(BotDescription::load(&toml).is_err());
+		std::fs::write(
+			&toml,
+			"name = \"Dave\"\njob = \"mechanic\"\npreset = \"expert\"\n",
+		)
+		.unwrap();
+		assert!(BotDescription::load(&toml).is_err());
+		std::fs::write(
+			&toml,
+			"name = \"Dave\"\njob = \"mechanic\"\n\n[skills]\nhelm = 101\n",
+		)
+		.unwrap();
+		let error = BotDescription::load(&toml).unwrap_err();
+		assert!(error
+			.to_string()
+			.contains("Level of skill helm must be between 0 and 100"));
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
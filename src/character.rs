@@ -0,0 +1,772 @@
+//! Reading and modifying characters, as stored in the game session and character data.
+
+use crate::diagnostic;
+use crate::xml;
+use crate::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use xmltree::{Element, XMLNode};
+
+/// A skill that a character has.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Skill {
+	/// The skill identifier, such as `helm` or `medical`.
+	pub identifier: String,
+
+	/// The skill level.
+	pub level: f32,
+}
+
+/// An affliction that a character is suffering from.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Affliction {
+	/// The affliction identifier, such as `huskinfection`.
+	pub identifier: String,
+
+	/// The strength of the affliction.
+	pub strength: f32,
+}
+
+/// An item in a character’s inventory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct InventoryItem {
+	/// The item’s prefab identifier.
+	pub identifier: String,
+
+	/// The item’s entity ID.
+	pub id: Option<u16>,
+
+	/// The inventory slot the item occupies, if it is directly in the character’s inventory.
+	///
+	/// An item held in several slots, such as one held in both hands, gives the first of them.
+	pub slot: Option<usize>,
+
+	/// The item’s tags, if they differ from its prefab’s.
+	pub tags: Vec<String>,
+
+	/// The items contained within this item, such as the oxygen tank in a diving suit.
+	pub contained: Vec<InventoryItem>,
+}
+
+impl InventoryItem {
+	/// Extracts an item, and the items it contains, from an `Item` element.
+	fn from_xml(element: &Element) -> Result<Self> {
+		Ok(Self {
+			identifier: xml::attribute(element, "identifier")
+				.unwrap_or_default()
+				.to_owned(),
+			id: xml::parse_attribute(element, "ID")?,
+			slot: first_slot(element)?,
+			tags: xml::attribute(element, "tags")
+				.unwrap_or_default()
+				.split(',')
+				.map(str::trim)
+				.filter(|i| !i.is_empty())
+				.map(str::to_owned)
+				.collect(),
+			contained: items_of(element)?,
+		})
+	}
+}
+
+/// Returns the first of the inventory slots an `Item` element occupies, if it has any.
+///
+/// An item held in several slots lists them all, separated by commas.
+fn first_slot(element: &Element) -> Result<Option<usize>> {
+	let Some(slots) = xml::attribute(element, "i") else {
+		return Ok(None);
+	};
+	let first = slots.split(',').next().unwrap_or_default();
+	first.trim().parse().map(Some).map_err(|_| {
+		diagnostic::attribute(
+			element,
+			"i",
+			slots,
+			format!(
+				"Invalid value “{}” for attribute i of element {}",
+				slots, element.name
+			),
+		)
+	})
+}
+
+/// Extracts the `Item` children of an element.
+fn items_of(element: &Element) -> Result<Vec<InventoryItem>> {
+	xml::children(element, "Item")
+		.map(InventoryItem::from_xml)
+		.collect()
+}
+
+/// A pet owned by the crew.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Pet {
+	/// The species of the pet, such as `Peanut` or `Orangeboy`.
+	pub species: String,
+
+	/// The entity ID of the character that owns the pet, if any.
+	pub owner: Option<u16>,
+
+	/// How hungry the pet is, from 0 to 100.
+	pub hunger: Option<f32>,
+
+	/// How happy the pet is, from 0 to 100.
+	pub happiness: Option<f32>,
+}
+
+impl Pet {
+	/// Extracts a pet from a `pet` element.
+	pub fn from_xml(element: &Element) -> Result<Self> {
+		Ok(Self {
+			species: xml::attribute(element, "speciesname")
+				.unwrap_or_default()
+				.to_owned(),
+			owner: xml::parse_attribute(element, "ownerid")?,
+			hunger: xml::parse_attribute(element, "hunger")?,
+			happiness: xml::parse_attribute(element, "happiness")?,
+		})
+	}
+}
+
+/// A member of the crew, as stored in a campaign save.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Character {
+	/// The character’s name.
+	pub name: String,
+
+	/// The character’s ID, by which other parts of the save refer to it.
+	pub id: Option<u16>,
+
+	/// The species of the character, if it is not human.
+	///
+	/// Non-human characters, such as monsters recruited through mods, have no job or skills.
+	pub species: Option<String>,
+
+	/// The identifier of the character’s job.
+	pub job: Option<String>,
+
+	/// The number of experience points the character has earned.
+	pub experience: u32,
+
+	/// Whether the save records the character as dead.
+	pub dead: bool,
+
+	/// What killed the character, if the save records it.
+	pub cause_of_death: Option<String>,
+
+	/// The identifiers of the talents the character has unlocked, in the order they were unlocked.
+	pub talents: Vec<String>,
+
+	/// The character’s skills.
+	pub skills: Vec<Skill>,
+
+	/// The afflictions the character is suffering from.
+	pub afflictions: Vec<Affliction>,
+
+	/// The items the character is carrying.
+	pub inventory: Vec<InventoryItem>,
+}
+
+impl Character {
+	/// Extracts a character from its XML representation.
+	///
+	/// In single-player saves, `element` is the `Character` element, which holds everything about
+	/// the character. In multiplayer saves, it is the `CharacterCampaignData` element, which holds
+	/// the `Character` element alongside the player’s health and inventory.
+	pub fn from_xml(element: &Element) -> Result<Self> {
+		let (info, health) = if xml::is_named(element, "CharacterCampaignData") {
+			let info = xml::child(element, "Character").ok_or_else(|| {
+				diagnostic::invalid("CharacterCampaignData has no Character element".to_owned())
+			})?;
+			(info, xml::child(element, "health"))
+		} else {
+			(element, Some(element))
+		};
+		let job = xml::child(info, "job");
+		let mut skills = Vec::new();
+		for skill in job.into_iter().flat_map(|i| xml::children(i, "skill")) {
+			skills.push(Skill {
+				identifier: xml::attribute(skill, "identifier")
+					.unwrap_or_default()
+					.to_owned(),
+				level: xml::parse_attribute(skill, "level")?.unwrap_or(0.0),
+			});
+		}
+		let mut afflictions = Vec::new();
+		for affliction in health
+			.map(|i| xml::descendants(i, "Affliction"))
+			.unwrap_or_default()
+		{
+			afflictions.push(Affliction {
+				identifier: xml::attribute(affliction, "identifier")
+					.unwrap_or_default()
+					.to_owned(),
+				strength: xml::parse_attribute(affliction, "strength")?.unwrap_or(0.0),
+			});
+		}
+		let inventory = match xml::child(element, "inventory") {
+			Some(inventory) => items_of(inventory)?,
+			None => Vec::new(),
+		};
+		Ok(Self {
+			name: xml::attribute(info, "name").unwrap_or_default().to_owned(),
+			id: xml::parse_attribute(info, "ID")?,
+			species: xml::attribute(info, "speciesname")
+				.filter(|i| !i.eq_ignore_ascii_case("human"))
+				.map(str::to_owned),
+			job: job
+				.and_then(|j| xml::attribute(j, "identifier"))
+				.map(str::to_owned),
+			experience: xml::parse_attribute(info, "experiencepoints")?.unwrap_or(0),
+			dead: is_dead(element),
+			cause_of_death: xml::attribute(info, "causeofdeath")
+				.filter(|i| !i.is_empty())
+				.map(str::to_owned),
+			talents: xml::child(info, "Talents")
+				.into_iter()
+				.flat_map(|i| xml::children(i, "Talent"))
+				.filter_map(|i| xml::attribute(i, "identifier"))
+				.map(str::to_owned)
+				.collect(),
+			skills,
+			afflictions,
+			inventory,
+		})
+	}
+}
+
+/// Builds the `Character` element for a new human bot.
+///
+/// `id` must be an entity ID not used by any other item or character in the save. The game fills
+/// in the appearance, salary, and anything else left out when it loads the character.
+pub fn new_bot(name: &str, id: u16, job: &str, skills: &[Skill]) -> Element {
+	let mut element = Element::new("Character");
+	xml::set_attribute(&mut element, "name", name.to_owned());
+	xml::set_attribute(&mut element, "originalname", name.to_owned());
+	xml::set_attribute(&mut element, "speciesname", "human".to_owned());
+	xml::set_attribute(&mut element, "ID", id.to_string());
+	xml::set_attribute(&mut element, "experiencepoints", "0".to_owned());
+	let mut job_element = Element::new("job");
+	xml::set_attribute(&mut job_element, "identifier", job.to_owned());
+	for skill in skills {
+		let mut skill_element = Element::new("skill");
+		xml::set_attribute(&mut skill_element, "identifier", skill.identifier.clone());
+		xml::set_attribute(&mut skill_element, "level", xml::format_f32(skill.level));
+		job_element.children.push(XMLNode::Element(skill_element));
+	}
+	element.children.push(XMLNode::Element(job_element));
+	element
+}
+
+/// Gives a `Character` element, and every item it carries, fresh entity IDs.
+///
+/// The character is given `first` and its items, including those inside other items, the IDs
+/// after it in document order. Returns the last ID given, or `None` if the IDs would run past the
+/// largest entity ID, in which case the element is left partly renumbered.
+pub fn renumber(element: &mut Element, first: u16) -> Option<u16> {
+	xml::set_attribute(element, "ID", first.to_string());
+	renumber_items(element, first)
+}
+
+/// Gives every `Item` element beneath an element, at any depth, the IDs after `last`.
+///
+/// Returns the last ID given, or `None` if the IDs would run past the largest entity ID.
+fn renumber_items(element: &mut Element, mut last: u16) -> Option<u16> {
+	for child in xml::elements_mut(element) {
+		if xml::is_named(child, "Item") {
+			last = last.checked_add(1)?;
+			xml::set_attribute(child, "ID", last.to_string());
+		}
+		last = renumber_items(child, last)?;
+	}
+	Some(last)
+}
+
+/// Returns whether a character’s XML representation records it as dead.
+///
+/// `element` is either a `Character` or a `CharacterCampaignData` element, as for
+/// [`Character::from_xml`]. A character is dead if it has a cause of death or is marked
+/// `dead="true"`.
+pub(crate) fn is_dead(element: &Element) -> bool {
+	let info = if xml::is_named(element, "CharacterCampaignData") {
+		match xml::child(element, "Character") {
+			Some(info) => info,
+			None => return false,
+		}
+	} else {
+		element
+	};
+	xml::attribute(info, "causeofdeath").is_some_and(|i| !i.is_empty())
+		|| xml::attribute(info, "dead").is_some_and(|i| i.eq_ignore_ascii_case("true"))
+}
+
+/// Changes to a character’s appearance.
+///
+/// Each field that is `None` is left as it is.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Appearance {
+	/// The character’s gender, such as `male` or `female`.
+	pub gender: Option<String>,
+
+	/// The index of the character’s head sprite.
+	pub head_index: Option<u32>,
+
+	/// The index of the character’s hair style, or -1 for none.
+	pub hair_index: Option<i32>,
+
+	/// The character’s tags, comma-separated.
+	///
+	/// Recent game versions choose the head by these tags rather than by gender and head index.
+	pub tags: Option<String>,
+}
+
+/// A crew member’s XML representation, borrowed for modification.
+#[derive(Debug)]
+pub struct CharacterEntry<'e> {
+	/// The element holding the character.
+	///
+	/// In single-player saves this is the `Character` element itself; in multiplayer saves it is
+	/// the enclosing `CharacterCampaignData` element, which holds the character’s health and
+	/// inventory alongside it.
+	element: &'e mut Element,
+}
+
+impl<'e> CharacterEntry<'e> {
+	/// Wraps a single-player `Character` element or multiplayer `CharacterCampaignData` element.
+	pub fn new(element: &'e mut Element) -> Self {
+		Self { element }
+	}
+
+	/// Returns the character’s name.
+	pub fn name(&self) -> Option<&str> {
+		xml::attribute(self.element, "name")
+	}
+
+	/// Returns the `Character` element.
+	fn info(&self) -> Result<&Element> {
+		if xml::is_named(self.element, "CharacterCampaignData") {
+			xml::child(self.element, "Character").ok_or_else(|| {
+				diagnostic::invalid("CharacterCampaignData has no Character element".to_owned())
+			})
+		} else {
+			Ok(self.element)
+		}
+	}
+
+	/// Returns the `Character` element, mutably.
+	fn info_mut(&mut self) -> Result<&mut Element> {
+		if xml::is_named(self.element, "CharacterCampaignData") {
+			xml::child_mut(self.element, "Character").ok_or_else(|| {
+				diagnostic::invalid("CharacterCampaignData has no Character element".to_owned())
+			})
+		} else {
+			Ok(self.element)
+		}
+	}
+
+	/// Renames the character.
+	///
+	/// In multiplayer saves, both the `CharacterCampaignData` element, which ties the player’s
+	/// wallet and inventory to the character, and the `Character` element are updated.
+	pub fn rename(&mut self, name: &str) -> Result<()> {
+		xml::set_attribute(self.element, "name", name.to_owned());
+		xml::set_attribute(self.info_mut()?, "name", name.to_owned());
+		Ok(())
+	}
+
+	/// Changes the character’s job.
+	///
+	/// The character keeps its skills, including any that the new job does not normally have.
+	pub fn set_job(&mut self, identifier: &str) -> Result<()> {
+		let info = self.info_mut()?;
+		if xml::child(info, "job").is_none() {
+			info.children.push(XMLNode::Element(Element::new("job")));
+		}
+		let job = xml::child_mut(info, "job").unwrap();
+		xml::set_attribute(job, "identifier", identifier.to_owned());
+		Ok(())
+	}
+
+	/// Changes the character’s appearance.
+	pub fn set_appearance(&mut self, appearance: &Appearance) -> Result<()> {
+		let info = self.info_mut()?;
+		if let Some(gender) = &appearance.gender {
+			xml::set_attribute(info, "gender", gender.clone());
+		}
+		if let Some(head_index) = appearance.head_index {
+			xml::set_attribute(info, "headspriteid", head_index.to_string());
+		}
+		if let Some(hair_index) = appearance.hair_index {
+			xml::set_attribute(info, "hairindex", hair_index.to_string());
+		}
+		if let Some(tags) = &appearance.tags {
+			xml::set_attribute(info, "tags", tags.clone());
+		}
+		Ok(())
+	}
+
+	/// Sets the level of one of the character’s skills.
+	///
+	/// If the character does not yet have the skill, it is added.
+	pub fn set_skill(&mut self, identifier: &str, level: f32) -> Result<()> {
+		let Some(job) = xml::child_mut(self.info_mut()?, "job") else {
+			return Err(diagnostic::invalid("Character has no job".to_owned()));
+		};
+		let existing = xml::children_mut(job, "skill")
+			.find(|i| xml::attribute(i, "identifier") == Some(identifier));
+		if let Some(skill) = existing {
+			xml::set_attribute(skill, "level", xml::format_f32(level));
+		} else {
+			let mut skill = Element::new("skill");
+			xml::set_attribute(&mut skill, "identifier", identifier.to_owned());
+			xml::set_attribute(&mut skill, "level", xml::format_f32(level));
+			job.children.push(XMLNode::Element(skill));
+		}
+		Ok(())
+	}
+
+	/// Adds experience points to the character, returning the character’s new total.
+	///
+	/// The total stops at the largest number of experience points that can be stored. The talent
+	/// points that the experience earns are worked out by the game from the total.
+	pub fn grant_experience(&mut self, experience: u32) -> Result<u32> {
+		let info = self.info_mut()?;
+		let total = xml::parse_attribute::<u32>(info, "experiencepoints")?
+			.unwrap_or(0)
+			.saturating_add(experience);
+		xml::set_attribute(info, "experiencepoints", total.to_string());
+		Ok(total)
+	}
+
+	/// Returns whether the character has unlocked a talent.
+	pub fn has_talent(&self, identifier: &str) -> Result<bool> {
+		Ok(xml::child(self.info()?, "Talents").is_some_and(|talents| {
+			xml::children(talents, "Talent")
+				.any(|i| xml::attribute(i, "identifier") == Some(identifier))
+		}))
+	}
+
+	/// Unlocks a talent for the character, returning `false` if it was already unlocked.
+	///
+	/// The talent is not checked against the character’s job or talent points, which the game does
+	/// not check either when loading a save.
+	pub fn unlock_talent(&mut self, identifier: &str) -> Result<bool> {
+		if self.has_talent(identifier)? {
+			return Ok(false);
+		}
+		let info = self.info_mut()?;
+		if xml::child(info, "Talents").is_none() {
+			info.children
+				.push(XMLNode::Element(Element::new("Talents")));
+		}
+		let talents = xml::child_mut(info, "Talents").unwrap();
+		let mut talent = Element::new("Talent");
+		xml::set_attribute(&mut talent, "identifier", identifier.to_owned());
+		talents.children.push(XMLNode::Element(talent));
+		Ok(true)
+	}
+
+	/// Adds an item to the character’s inventory.
+	///
+	/// `id` must be an entity ID not used by any other item in the save. If `slot` is `None`, the
+	/// game places the item in the first free slot when the character is loaded.
+	pub fn add_item(&mut self, identifier: &str, id: u16, slot: Option<usize>) {
+		if xml::child(self.element, "inventory").is_none() {
+			self.element
+				.children
+				.push(XMLNode::Element(Element::new("inventory")));
+		}
+		let inventory = xml::child_mut(self.element, "inventory").unwrap();
+		let mut item = Element::new("Item");
+		xml::set_attribute(&mut item, "identifier", identifier.to_owned());
+		xml::set_attribute(&mut item, "ID", id.to_string());
+		if let Some(slot) = slot {
+			xml::set_attribute(&mut item, "i", slot.to_string());
+		}
+		inventory.children.push(XMLNode::Element(item));
+	}
+
+	/// Swaps the contents of two of the character’s inventory slots, returning the number of items
+	/// moved.
+	///
+	/// Only items directly in the inventory are moved; the items inside them move with them, keeping
+	/// their own slots within their containers. An item occupying several slots, such as one held in
+	/// both hands, has each of its slots swapped.
+	pub fn swap_slots(&mut self, a: usize, b: usize) -> usize {
+		let Some(inventory) = xml::child_mut(self.element, "inventory") else {
+			return 0;
+		};
+		let mut moved = 0;
+		for item in xml::children_mut(inventory, "Item") {
+			let Some(slots) = xml::attribute(item, "i") else {
+				continue;
+			};
+			let mut changed = false;
+			let slots = slots
+				.split(',')
+				.map(|slot| match slot.trim().parse::<usize>() {
+					Ok(slot) if slot == a || slot == b => {
+						changed = true;
+						if slot == a { b } else { a }.to_string()
+					}
+					_ => slot.to_owned(),
+				})
+				.collect::<Vec<String>>()
+				.join(",");
+			if changed {
+				xml::set_attribute(item, "i", slots);
+				moved += 1;
+			}
+		}
+		moved
+	}
+
+	/// Removes items from the character’s inventory.
+	///
+	/// `filter` is called with each `Item` element, and the item is removed, along with anything
+	/// inside it, if it returns `true`. Items inside other items are considered too. Returns the
+	/// number of items removed.
+	pub fn remove_items<F: FnMut(&Element) -> bool>(&mut self, mut filter: F) -> usize {
+		xml::child_mut(self.element, "inventory")
+			.map_or(0, |inventory| remove_items_from(inventory, &mut filter))
+	}
+
+	/// Removes afflictions from the character.
+	///
+	/// `filter` is called with each affliction’s identifier, and the affliction is removed if it
+	/// returns `true`. Returns the number of afflictions removed.
+	pub fn remove_afflictions<F: FnMut(&str) -> bool>(&mut self, mut filter: F) -> usize {
+		if xml::is_named(self.element, "CharacterCampaignData") {
+			xml::child_mut(self.element, "health")
+				.map_or(0, |health| remove_afflictions_from(health, &mut filter))
+		} else {
+			remove_afflictions_from(self.element, &mut filter)
+		}
+	}
+}
+
+/// Removes matching `Affliction` elements from beneath an element, at any depth.
+///
+/// Returns the number of elements removed.
+fn remove_afflictions_from<F: FnMut(&str) -> bool>(element: &mut Element, filter: &mut F) -> usize {
+	let before = element.children.len();
+	element.children.retain(|i| {
+		!i.as_element().is_some_and(|i| {
+			xml::is_named(i, "Affliction")
+				&& filter(xml::attribute(i, "identifier").unwrap_or_default())
+		})
+	});
+	let mut removed = before - element.children.len();
+	for child in xml::elements_mut(element) {
+		removed += remove_afflictions_from(child, filter);
+	}
+	removed
+}
+
+/// Removes matching `Item` elements from beneath an element, at any depth.
+///
+/// Returns the number of elements removed.
+fn remove_items_from<F: FnMut(&Element) -> bool>(element: &mut Element, filter: &mut F) -> usize {
+	let before = element.children.len();
+	element.children.retain(|i| {
+		!i.as_element()
+			.is_some_and(|i| xml::is_named(i, "Item") && filter(i))
+	});
+	let mut removed = before - element.children.len();
+	for child in xml::children_mut(element, "Item") {
+		removed += remove_items_from(child, filter);
+	}
+	removed
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// A character as a single-player save holds it.
+	const SINGLE: &str = r#"<Character name="Alice" ID="7" experiencepoints="100">
+  <job identifier="captain">
+    <skill identifier="helm" level="50" />
+  </job>
+  <inventory>
+    <Item identifier="divingsuit" ID="8" i="4">
+      <Item identifier="oxygentank" ID="9" i="0" />
+    </Item>
+    <Item identifier="crowbar" ID="10" i="0" />
+    <Item identifier="toolbelt" ID="11" i="5">
+      <Item identifier="crowbar" ID="12" i="0" />
+    </Item>
+  </inventory>
+</Character>"#;
+
+	/// A player’s character as a multiplayer save holds it.
+	const MULTI: &str = r#"<CharacterCampaignData name="Bob">
+  <Character name="Bob" ID="20">
+    <job identifier="mechanic" />
+    <Talents>
+      <Talent identifier="toolmaintenance" />
+    </Talents>
+  </Character>
+  <inventory>
+    <Item identifier="wrench" ID="21" i="1" />
+  </inventory>
+  <health>
+    <Affliction identifier="burn" strength="10" />
+  </health>
+</CharacterCampaignData>"#;
+
+	#[test]
+	fn test_edit() {
+		// Alice starts with experience but no talents, and Bob with a talent but no experience.
+		for (text, name, experience) in [(SINGLE, "Alice", 100), (MULTI, "Bob", 0)] {
+			let mut element = xml::parse(text.as_bytes()).unwrap();
+			let mut entry = CharacterEntry::new(&mut element);
+			assert_eq!(entry.name(), Some(name));
+			entry.rename("Carol").unwrap();
+			assert_eq!(entry.name(), Some("Carol"));
+			entry.set_skill("helm", 75.0).unwrap();
+			entry.set_skill("weapons", 30.0).unwrap();
+			assert_eq!(
+				entry.unlock_talent("toolmaintenance").unwrap(),
+				name == "Alice"
+			);
+			assert!(!entry.unlock_talent("toolmaintenance").unwrap());
+			assert_eq!(entry.grant_experience(50).unwrap(), experience + 50);
+			assert_eq!(entry.grant_experience(u32::MAX).unwrap(), u32::MAX);
+			let character = Character::from_xml(&element).unwrap();
+			assert_eq!(character.name, "Carol");
+			assert_eq!(
+				character.skills,
+				[
+					Skill {
+						identifier: "helm".to_owned(),
+						level: 75.0,
+					},
+					Skill {
+						identifier: "weapons".to_owned(),
+						level: 30.0,
+					},
+				]
+			);
+			assert_eq!(character.talents, ["toolmaintenance"]);
+			assert_eq!(character.experience, u32::MAX);
+		}
+
+		// In a multiplayer save, the player’s entry is renamed along with the character.
+		let mut element = xml::parse(MULTI.as_bytes()).unwrap();
+		CharacterEntry::new(&mut element).rename("Carol").unwrap();
+		assert_eq!(xml::attribute(&element, "name"), Some("Carol"));
+		assert_eq!(
+			xml::attribute(xml::child(&element, "Character").unwrap(), "name"),
+			Some("Carol")
+		);
+
+		// A character with no job has no skills to set.
+		let mut element = xml::parse(r#"<Character name="Dog" />"#.as_bytes()).unwrap();
+		assert!(CharacterEntry::new(&mut element)
+			.set_skill("helm", 1.0)
+			.is_err());
+	}
+
+	#[test]
+	fn test_items() {
+		for text in [SINGLE, MULTI] {
+			let mut element = xml::parse(text.as_bytes()).unwrap();
+			let mut entry = CharacterEntry::new(&mut element);
+			entry.add_item("screwdriver", 30, Some(2));
+			entry.add_item("flashlight", 31, None);
+			let inventory = Character::from_xml(&element).unwrap().inventory;
+			let added = &inventory[inventory.len() - 2..];
+			assert_eq!(added[0].identifier, "screwdriver");
+			assert_eq!(added[0].id, Some(30));
+			assert_eq!(added[0].slot, Some(2));
+			assert_eq!(added[1].slot, None);
+		}
+
+		// Items inside other items are removed too, along with anything inside removed items.
+		let mut element = xml::parse(SINGLE.as_bytes()).unwrap();
+		let mut entry = CharacterEntry::new(&mut element);
+		assert_eq!(
+			entry.remove_items(|i| xml::attribute(i, "identifier") == Some("crowbar")),
+			2
+		);
+		assert_eq!(
+			entry.remove_items(|i| xml::attribute(i, "identifier") == Some("divingsuit")),
+			1
+		);
+		let inventory = Character::from_xml(&element).unwrap().inventory;
+		assert_eq!(inventory.len(), 1);
+		assert_eq!(inventory[0].identifier, "toolbelt");
+		assert!(inventory[0].contained.is_empty());
+
+		// Swapping moves only the items directly in the inventory, taking their contents along.
+		let mut element = xml::parse(SINGLE.as_bytes()).unwrap();
+		assert_eq!(CharacterEntry::new(&mut element).swap_slots(0, 4), 2);
+		let inventory = Character::from_xml(&element).unwrap().inventory;
+		assert_eq!(inventory[0].slot, Some(0));
+		assert_eq!(inventory[0].contained[0].slot, Some(0));
+		assert_eq!(inventory[1].slot, Some(4));
+		assert_eq!(inventory[2].slot, Some(5));
+		assert_eq!(inventory[2].contained[0].slot, Some(0));
+		assert_eq!(CharacterEntry::new(&mut element).swap_slots(7, 8), 0);
+	}
+
+	#[test]
+	fn test_renumber() {
+		let mut element = xml::parse(
+			r#"<Character name="Alice" ID="7">
+  <inventory>
+    <Item identifier="divingsuit" ID="8" i="4">
+      <Item identifier="oxygentank" ID="9" />
+    </Item>
+    <Item identifier="crowbar" ID="10" i="0" />
+  </inventory>
+</Character>"#
+				.as_bytes(),
+		)
+		.unwrap();
+		assert_eq!(renumber(&mut element, 100), Some(103));
+		let character = Character::from_xml(&element).unwrap();
+		assert_eq!(character.id, Some(100));
+		assert_eq!(character.inventory[0].id, Some(101));
+		assert_eq!(character.inventory[0].contained[0].id, Some(102));
+		assert_eq!(character.inventory[1].id, Some(103));
+		assert_eq!(renumber(&mut element, u16::MAX - 1), None);
+	}
+
+	#[test]
+	fn test_swap_slots() {
+		let mut element = xml::parse(
+			r#"<Character name="Alice">
+  <inventory>
+    <Item identifier="crowbar" i="0" />
+    <Item identifier="toolbelt" i="1" />
+    <Item identifier="shield" i="2,3" />
+  </inventory>
+</Character>"#
+				.as_bytes(),
+		)
+		.unwrap();
+		let mut entry = CharacterEntry::new(&mut element);
+		assert_eq!(entry.swap_slots(1, usize::MAX), 1);
+		// An item held in several slots has each of them swapped.
+		assert_eq!(entry.swap_slots(3, 4), 1);
+		let slots = |element: &Element| {
+			Character::from_xml(element)
+				.unwrap()
+				.inventory
+				.iter()
+				.map(|i| i.slot)
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(slots(&element), [Some(0), Some(usize::MAX), Some(2)]);
+		let shield = xml::children(xml::child(&element, "inventory").unwrap(), "Item")
+			.nth(2)
+			.unwrap();
+		assert_eq!(xml::attribute(shield, "i"), Some("2,4"));
+	}
+}
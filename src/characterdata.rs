@@ -0,0 +1,186 @@
+//! Reading and modifying the per-player character data of multiplayer campaigns.
+
+use crate::character::{self, Character, CharacterEntry};
+use crate::diagnostic;
+#[cfg(feature = "serde")]
+use crate::element;
+use crate::xml;
+use crate::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::io::{Read, Write};
+use xmltree::Element;
+
+/// The contents of a multiplayer campaign save’s `CharacterData.xml` member.
+///
+/// This holds the per-player state—character, inventory, health, and wallet—for each client that
+/// has joined the campaign.
+#[derive(Clone, Debug)]
+pub struct CharacterData {
+	/// The root `CharacterData` element.
+	root: Element,
+}
+
+impl CharacterData {
+	/// The name of the save file member holding the character data.
+	pub const MEMBER_NAME: &'static str = "CharacterData.xml";
+
+	/// Parses a character data document.
+	pub fn parse<R: Read>(source: R) -> Result<Self> {
+		let root = xml::parse(source)?;
+		if !xml::is_named(&root, "CharacterData") {
+			return Err(diagnostic::invalid(format!(
+				"Expected CharacterData root element, found {}",
+				root.name
+			)));
+		}
+		Ok(Self { root })
+	}
+
+	/// Writes the character data document.
+	pub fn write<W: Write>(&self, sink: W) -> Result<()> {
+		xml::write(&self.root, sink)
+	}
+
+	/// Returns the names of the players whose data is stored.
+	pub fn names(&self) -> Vec<&str> {
+		xml::children(&self.root, "CharacterCampaignData")
+			.filter_map(|i| xml::attribute(i, "name"))
+			.collect()
+	}
+
+	/// Returns the players’ characters.
+	pub fn characters(&self) -> Result<Vec<Character>> {
+		xml::children(&self.root, "CharacterCampaignData")
+			.map(Character::from_xml)
+			.collect()
+	}
+
+	/// Returns the largest entity ID of any item in the character data.
+	pub fn max_item_id(&self) -> Result<u16> {
+		xml::max_item_id(&self.root)
+	}
+
+	/// Returns the players’ characters for modification.
+	pub fn characters_mut(&mut self) -> Vec<CharacterEntry<'_>> {
+		xml::children_mut(&mut self.root, "CharacterCampaignData")
+			.map(CharacterEntry::new)
+			.collect()
+	}
+
+	/// Removes a player’s character, along with their wallet, inventory, and health.
+	///
+	/// Returns `false` if there is no player with the given name.
+	pub fn remove_character(&mut self, name: &str) -> bool {
+		let before = self.root.children.len();
+		self.root.children.retain(|i| {
+			!i.as_element().is_some_and(|i| {
+				xml::is_named(i, "CharacterCampaignData") && xml::attribute(i, "name") == Some(name)
+			})
+		});
+		self.root.children.len() != before
+	}
+
+	/// Removes the players’ characters recorded as dead, along with their wallets, inventories, and
+	/// health, returning the characters.
+	pub fn remove_dead(&mut self) -> Result<Vec<Character>> {
+		let dead = xml::children(&self.root, "CharacterCampaignData")
+			.filter(|i| character::is_dead(i))
+			.map(Character::from_xml)
+			.collect::<Result<Vec<_>>>()?;
+		self.root.children.retain(|i| {
+			!i.as_element()
+				.is_some_and(|i| xml::is_named(i, "CharacterCampaignData") && character::is_dead(i))
+		});
+		Ok(dead)
+	}
+
+	/// Sets the amount of money in a player’s wallet.
+	///
+	/// Returns `false` if there is no player with the given name.
+	pub fn set_money(&mut self, name: &str, money: i32) -> bool {
+		let Some(entry) = xml::children_mut(&mut self.root, "CharacterCampaignData")
+			.find(|i| xml::attribute(i, "name") == Some(name))
+		else {
+			return false;
+		};
+		if let Some(wallet) = xml::child_mut(entry, "Wallet") {
+			xml::set_attribute(wallet, "money", money.to_string());
+		} else {
+			let mut wallet = Element::new("Wallet");
+			xml::set_attribute(&mut wallet, "money", money.to_string());
+			entry.children.push(xmltree::XMLNode::Element(wallet));
+		}
+		true
+	}
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for CharacterData {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		element::serialize(&self.root, serializer)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for CharacterData {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+		element::deserialize(deserializer, "CharacterData").map(|root| Self { root })
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const SAMPLE: &str = r#"<CharacterData>
+  <CharacterCampaignData name="Alice">
+    <Character name="Alice" ID="7" />
+    <inventory>
+      <Item identifier="crowbar" ID="30" i="0" />
+    </inventory>
+    <Wallet money="500" />
+  </CharacterCampaignData>
+  <CharacterCampaignData name="Bob">
+    <Character name="Bob" ID="8" causeofdeath="Drowning" />
+  </CharacterCampaignData>
+</CharacterData>"#;
+
+	#[test]
+	fn test_set_money() {
+		let mut data = CharacterData::parse(SAMPLE.as_bytes()).unwrap();
+		assert!(data.set_money("Alice", 750));
+		assert!(data.set_money("Bob", 20));
+		assert!(!data.set_money("Carol", 1));
+		let money = |data: &CharacterData, name: &str| {
+			let entry = xml::children(&data.root, "CharacterCampaignData")
+				.find(|i| xml::attribute(i, "name") == Some(name))
+				.unwrap();
+			let wallets = xml::children(entry, "Wallet").collect::<Vec<_>>();
+			assert_eq!(wallets.len(), 1);
+			xml::attribute(wallets[0], "money").unwrap().to_owned()
+		};
+		assert_eq!(money(&data, "Alice"), "750");
+		assert_eq!(money(&data, "Bob"), "20");
+	}
+
+	#[test]
+	fn test_characters() {
+		let mut data = CharacterData::parse(SAMPLE.as_bytes()).unwrap();
+		assert_eq!(data.names(), ["Alice", "Bob"]);
+		assert_eq!(data.max_item_id().unwrap(), 30);
+		let characters = data.characters().unwrap();
+		assert_eq!(characters[0].id, Some(7));
+		assert_eq!(characters[0].inventory[0].identifier, "crowbar");
+		assert!(characters[1].dead);
+
+		let dead = data.remove_dead().unwrap();
+		assert_eq!(dead.len(), 1);
+		assert_eq!(dead[0].name, "Bob");
+		assert_eq!(data.names(), ["Alice"]);
+		assert!(!data.remove_character("Bob"));
+		assert!(data.remove_character("Alice"));
+		assert!(data.names().is_empty());
+		assert!(CharacterData::parse("<Gamesession />".as_bytes()).is_err());
+	}
+}
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// When to color output.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum When {
+	/// Color output written to a terminal, unless the `NO_COLOR` environment variable is set.
+	Auto,
+
+	/// Always color output.
+	Always,
+
+	/// Never color output.
+	Never,
+}
+
+impl When {
+	/// The names of the choices, as accepted on the command line.
+	pub const NAMES: [&'static str; 3] = ["auto", "always", "never"];
+
+	/// Returns the choice with the given command-line name.
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"auto" => Some(Self::Auto),
+			"always" => Some(Self::Always),
+			"never" => Some(Self::Never),
+			_ => None,
+		}
+	}
+
+	/// Returns the choice’s command-line name.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Auto => "auto",
+			Self::Always => "always",
+			Self::Never => "never",
+		}
+	}
+
+	/// Returns whether to color a stream.
+	///
+	/// Following <https://no-color.org/>, a non-empty `NO_COLOR` environment variable disables
+	/// automatic coloring.
+	fn enabled<S: IsTerminal>(self, stream: &S) -> bool {
+		match self {
+			Self::Always => true,
+			Self::Never => false,
+			Self::Auto => {
+				stream.is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|i| i.is_empty())
+			}
+		}
+	}
+}
+
+/// Whether standard output is colored.
+static STDOUT: AtomicBool = AtomicBool::new(false);
+
+/// Whether standard error is colored.
+static STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Decides whether standard output and standard error are colored.
+pub fn init(when: When) {
+	STDOUT.store(when.enabled(&std::io::stdout()), Ordering::Relaxed);
+	STDERR.store(when.enabled(&std::io::stderr()), Ordering::Relaxed);
+}
+
+/// A way of highlighting text.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Style {
+	/// An error, or something that is wrong.
+	Error,
+
+	/// A warning, or something that may be wrong.
+	Warning,
+
+	/// Something that is as it should be.
+	Good,
+
+	/// A heading, such as the name of the file that the following output is about.
+	Heading,
+
+	/// A quantity, such as a size.
+	Number,
+
+	/// Low-priority detail.
+	Dim,
+
+	/// A line added by a change.
+	Added,
+
+	/// A line removed by a change.
+	Removed,
+}
+
+impl Style {
+	/// Returns the ANSI escape sequence that starts the style.
+	fn code(self) -> &'static str {
+		match self {
+			Self::Error => "\x1b[1;31m",
+			Self::Warning => "\x1b[1;33m",
+			Self::Good | Self::Added => "\x1b[32m",
+			Self::Heading => "\x1b[1m",
+			Self::Number => "\x1b[36m",
+			Self::Dim => "\x1b[2m",
+			Self::Removed => "\x1b[31m",
+		}
+	}
+
+	/// Formats text in this style for standard output.
+	pub fn paint<T: Display>(self, text: T) -> String {
+		self.paint_if(STDOUT.load(Ordering::Relaxed), text)
+	}
+
+	/// Formats text in this style for standard error.
+	pub fn paint_stderr<T: Display>(self, text: T) -> String {
+		self.paint_if(STDERR.load(Ordering::Relaxed), text)
+	}
+
+	/// Formats text in this style if `enabled` is `true`, or plainly otherwise.
+	fn paint_if<T: Display>(self, enabled: bool, text: T) -> String {
+		if enabled {
+			format!("{}{text}\x1b[0m", self.code())
+		} else {
+			text.to_string()
+		}
+	}
+}
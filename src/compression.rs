@@ -0,0 +1,202 @@
+//! Gzip compression of files written by this crate.
+//!
+//! By default, compression is done by the pure-Rust `libflate`. With the `zlib-ng` or `zlib-rs`
+//! feature, `flate2` is used instead, backed by zlib-ng or its Rust port; either is considerably
+//! faster, which matters mostly when packing large multiplayer saves.
+
+use crate::error;
+use crate::limits;
+use crate::Result;
+#[cfg(any(feature = "zlib-ng", feature = "zlib-rs"))]
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// The highest compression level.
+pub const MAX_LEVEL: u32 = 9;
+
+/// The compression level used for files written by this program.
+static LEVEL: AtomicU32 = AtomicU32::new(MAX_LEVEL);
+
+/// Sets the compression level used for files written by this program.
+///
+/// Level 0 stores data uncompressed. With `libflate`, levels 1 through 9 shrink the LZ77 window
+/// from the full 32 KiB at level 9 by half per level, trading compression ratio for memory use;
+/// with `flate2`, they are zlib’s usual levels, trading compression ratio for speed. Levels above 9
+/// are treated as 9.
+pub fn set_level(level: u32) {
+	LEVEL.store(level.min(MAX_LEVEL), Ordering::Relaxed);
+}
+
+/// Returns the compression level used for files written by this program.
+pub fn level() -> u32 {
+	LEVEL.load(Ordering::Relaxed)
+}
+
+/// The gzip encoder of the compression library in use.
+#[cfg(not(any(feature = "zlib-ng", feature = "zlib-rs")))]
+type InnerEncoder<W> = libflate::gzip::Encoder<W, libflate::lz77::DefaultLz77Encoder>;
+
+/// The gzip encoder of the compression library in use.
+#[cfg(any(feature = "zlib-ng", feature = "zlib-rs"))]
+type InnerEncoder<W> = flate2::write::GzEncoder<W>;
+
+/// The gzip decoder of the compression library in use, which reads every member of a stream.
+#[cfg(not(any(feature = "zlib-ng", feature = "zlib-rs")))]
+type InnerDecoder<R> = libflate::gzip::MultiDecoder<R>;
+
+/// The gzip decoder of the compression library in use, which reads every member of a stream.
+#[cfg(any(feature = "zlib-ng", feature = "zlib-rs"))]
+type InnerDecoder<R> = flate2::read::MultiGzDecoder<R>;
+
+/// A gzip encoder writing compressed data to a stream.
+///
+/// Created by [`encoder`]. The data is only complete once [`finish`](Self::finish) has been called.
+pub struct Encoder<W: Write>(InnerEncoder<W>);
+
+impl<W: Write> Encoder<W> {
+	/// Writes the end of the compressed data and returns the stream it was written to.
+	pub fn finish(self) -> Result<W> {
+		#[cfg(not(any(feature = "zlib-ng", feature = "zlib-rs")))]
+		let sink = self.0.finish().into_result()?;
+		#[cfg(any(feature = "zlib-ng", feature = "zlib-rs"))]
+		let sink = self.0.finish()?;
+		Ok(sink)
+	}
+}
+
+impl<W: Write> Write for Encoder<W> {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.0.write(buf)
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		self.0.flush()
+	}
+}
+
+/// Creates a gzip encoder at the configured compression level.
+pub fn encoder<W: Write>(sink: W) -> Result<Encoder<W>> {
+	encoder_with_subfield(sink, None)
+}
+
+/// Creates a gzip encoder at the configured compression level, optionally recording an extra
+/// subfield, given as its two-byte ID and its data, in the gzip header.
+///
+/// The subfield is the first thing in the header after the `XLEN` field, so its data starts 16
+/// bytes into the output.
+pub(crate) fn encoder_with_subfield<W: Write>(
+	sink: W,
+	subfield: Option<([u8; 2], Vec<u8>)>,
+) -> Result<Encoder<W>> {
+	let level = level();
+	#[cfg(not(any(feature = "zlib-ng", feature = "zlib-rs")))]
+	let inner = {
+		use libflate::gzip::EncodeOptions;
+		use libflate::lz77::{DefaultLz77EncoderBuilder, MAX_WINDOW_SIZE};
+
+		let window = MAX_WINDOW_SIZE >> (MAX_LEVEL - level.max(1));
+		let options =
+			EncodeOptions::with_lz77(DefaultLz77EncoderBuilder::new().window_size(window).build());
+		let options = if level == 0 {
+			options.no_compression()
+		} else {
+			options
+		};
+		let options = match subfield {
+			Some((id, data)) => options.header(
+				libflate::gzip::HeaderBuilder::new()
+					.extra_field(libflate::gzip::ExtraField {
+						subfields: vec![libflate::gzip::ExtraSubField { id, data }],
+					})
+					.finish(),
+			),
+			None => options,
+		};
+		libflate::gzip::Encoder::with_options(sink, options)?
+	};
+	#[cfg(any(feature = "zlib-ng", feature = "zlib-rs"))]
+	let inner = {
+		let mut builder = flate2::GzBuilder::new();
+		if let Some((id, data)) = subfield {
+			let length = u16::try_from(data.len()).map_err(|_| {
+				std::io::Error::new(
+					std::io::ErrorKind::InvalidInput,
+					"gzip extra subfield too long",
+				)
+			})?;
+			let mut extra = id.to_vec();
+			extra.extend_from_slice(&length.to_le_bytes());
+			extra.extend_from_slice(&data);
+			builder = builder.extra(extra);
+		}
+		builder.write(sink, flate2::Compression::new(level))
+	};
+	Ok(Encoder(inner))
+}
+
+/// A gzip decoder reading compressed data from a stream.
+///
+/// A stream of several gzip members, one after another, is decoded as the concatenation of their
+/// contents, as RFC 1952 specifies. Decoding more than the
+/// [`max_total_size`](crate::limits::Limits::max_total_size) in effect when the decoder was created
+/// fails with [`Error::Limit`](crate::Error::Limit).
+#[derive(Debug)]
+pub struct Decoder<R: Read> {
+	/// The decoder of the compression library in use.
+	inner: InnerDecoder<R>,
+
+	/// The limits in effect when decoding started.
+	limits: limits::Limits,
+
+	/// The number of bytes decoded so far.
+	decoded: u64,
+}
+
+impl<R: Read> Decoder<R> {
+	/// Starts decoding gzip-compressed data.
+	///
+	/// Depending on the compression library, a bad gzip header may be reported here or only by the
+	/// first read; either way, it is reported as [`Error::Gzip`](crate::Error::Gzip).
+	pub fn new(source: R) -> Result<Self> {
+		Self::with_limits(source, limits::limits())
+	}
+
+	/// Starts decoding gzip-compressed data, holding it to particular limits rather than those in
+	/// effect.
+	pub fn with_limits(source: R, limits: limits::Limits) -> Result<Self> {
+		#[cfg(not(any(feature = "zlib-ng", feature = "zlib-rs")))]
+		let inner = libflate::gzip::MultiDecoder::new(source).map_err(error::gzip)?;
+		#[cfg(any(feature = "zlib-ng", feature = "zlib-rs"))]
+		let inner = flate2::read::MultiGzDecoder::new(source);
+		Ok(Self {
+			inner,
+			limits,
+			decoded: 0,
+		})
+	}
+}
+
+impl<R: Read> Read for Decoder<R> {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		// libflate takes a read that returns nothing as the end of the current member, even if it
+		// was given nowhere to put anything.
+		if buf.is_empty() {
+			return Ok(0);
+		}
+		let bytes_read = self.inner.read(buf)?;
+		// Cast is sound because u64 ≥ usize.
+		self.decoded += bytes_read as u64;
+		limits::check_total_size(&self.limits, self.decoded)?;
+		Ok(bytes_read)
+	}
+}
+
+/// Decompresses a whole gzip stream into memory.
+pub(crate) fn decompress<R: Read>(source: R) -> Result<Vec<u8>> {
+	let mut data = Vec::new();
+	Decoder::new(source)?
+		.read_to_end(&mut data)
+		.map_err(error::gzip)?;
+	Ok(data)
+}
@@ -0,0 +1,280 @@
+use crate::color::When;
+use crate::output::{self, outln, Format};
+use barotool::compression;
+use barotool::diagnostic;
+use barotool::limits;
+use serde::{Deserialize, Serialize};
+use std::ffi::{OsStr, OsString};
+use std::io::Result;
+use std::path::PathBuf;
+
+/// The backup policy used by `save backup`.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Backup {
+	/// The directory to keep snapshots in, instead of one alongside each save.
+	pub dir: Option<PathBuf>,
+
+	/// The number of snapshots of each save to keep.
+	pub keep: Option<usize>,
+}
+
+/// The limits on what files read may contain, for handling files from untrusted sources.
+///
+/// An unset limit is left at the library’s default.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+// The fields are named as in the library, and so are the configuration file’s keys.
+#[allow(clippy::struct_field_names)]
+pub struct Limits {
+	/// The greatest number of members in a save file.
+	pub max_members: Option<usize>,
+
+	/// The greatest size, in bytes, of any one member of a save file.
+	pub max_member_size: Option<usize>,
+
+	/// The greatest size, in bytes, of the decompressed contents of a save file or submarine.
+	pub max_total_size: Option<u64>,
+
+	/// The greatest depth to which XML elements may be nested.
+	pub max_depth: Option<usize>,
+}
+
+impl Limits {
+	/// Returns the library’s limits with those that are set here replaced.
+	pub fn resolve(self) -> limits::Limits {
+		let default = limits::Limits::default();
+		limits::Limits {
+			max_members: self.max_members.or(default.max_members),
+			max_member_size: self.max_member_size.or(default.max_member_size),
+			max_total_size: self.max_total_size.or(default.max_total_size),
+			max_depth: self.max_depth.or(default.max_depth),
+		}
+	}
+}
+
+/// Defaults loaded from the configuration file.
+///
+/// Every setting is optional. Each can be overridden by the corresponding `BAROTOOL_*` environment
+/// variable, and that in turn by the corresponding command-line option.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+	/// The directory in which Barotrauma is installed.
+	pub game_dir: Option<PathBuf>,
+
+	/// The directory in which to look for campaigns, instead of the game’s usual save directory.
+	pub save_dir: Option<PathBuf>,
+
+	/// The gzip compression level for files written, from 0 to 9.
+	pub compression_level: Option<u32>,
+
+	/// The number of files to process at once, or zero for one per CPU.
+	pub jobs: Option<usize>,
+
+	/// The format in which to print the results of read-only commands.
+	pub format: Option<Format>,
+
+	/// When to color output.
+	pub color: Option<When>,
+
+	/// The backup policy.
+	pub backup: Backup,
+
+	/// The limits on what files read may contain.
+	pub limits: Limits,
+}
+
+impl Config {
+	/// Returns the path of the configuration file used when none is given on the command line.
+	///
+	/// This is `barotool/config.toml` in the platform’s configuration directory, such as
+	/// `~/.config` on Linux.
+	pub fn default_path() -> Option<PathBuf> {
+		dirs::config_dir().map(|i| i.join("barotool").join("config.toml"))
+	}
+
+	/// Loads the configuration file.
+	///
+	/// If `path` is `None`, the file named by the `BAROTOOL_CONFIG` environment variable is loaded,
+	/// or, if that is unset, the file at [`default_path`](Self::default_path) if it exists; if it
+	/// does not, every setting is left unset. A file named explicitly must exist.
+	pub fn load(path: Option<&OsStr>) -> Result<Self> {
+		let env_path = env("BAROTOOL_CONFIG");
+		let (path, required) = match path.or(env_path.as_deref()) {
+			Some(path) => (PathBuf::from(path), true),
+			None => match Self::default_path() {
+				Some(path) => (path, false),
+				None => return Ok(Self::default()),
+			},
+		};
+		let text = match std::fs::read_to_string(&path) {
+			Ok(text) => text,
+			Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => {
+				return Ok(Self::default())
+			}
+			Err(e) => {
+				return Err(std::io::Error::new(
+					e.kind(),
+					format!("{}: {e}", path.display()),
+				))
+			}
+		};
+		log::debug!("Loading configuration from {}", path.display());
+		let config: Self = toml::from_str(&text).map_err(|e| {
+			diagnostic::in_file(
+				diagnostic::invalid(e.to_string().trim_end().to_owned()),
+				&path,
+				None,
+			)
+		})?;
+		if let Some(level) = config.compression_level {
+			if level > compression::MAX_LEVEL {
+				return Err(diagnostic::in_file(
+					diagnostic::invalid(format!(
+						"compression-level must be between 0 and {}",
+						compression::MAX_LEVEL
+					)),
+					&path,
+					None,
+				)
+				.into());
+			}
+		}
+		Ok(config)
+	}
+
+	/// Applies the settings given by environment variables, which take precedence over the
+	/// configuration file.
+	///
+	/// Each setting’s variable is its name in upper case, with `-` and `.` replaced by `_`, prefixed
+	/// with `BAROTOOL_`, such as `BAROTOOL_GAME_DIR` and `BAROTOOL_BACKUP_KEEP`. Empty variables are
+	/// ignored.
+	pub fn apply_environment(&mut self) -> Result<()> {
+		if let Some(dir) = env("BAROTOOL_GAME_DIR") {
+			self.game_dir = Some(dir.into());
+		}
+		if let Some(dir) = env("BAROTOOL_SAVE_DIR") {
+			self.save_dir = Some(dir.into());
+		}
+		if let Some(level) = env_parsed("BAROTOOL_COMPRESSION_LEVEL", |i| {
+			i.parse()
+				.ok()
+				.filter(|i| *i <= compression::MAX_LEVEL)
+				.ok_or_else(|| format!("expected 0 to {}", compression::MAX_LEVEL))
+		})? {
+			self.compression_level = Some(level);
+		}
+		if let Some(jobs) = env_parsed("BAROTOOL_JOBS", |i| i.parse().map_err(|e| format!("{e}")))?
+		{
+			self.jobs = Some(jobs);
+		}
+		if let Some(format) = env_parsed("BAROTOOL_FORMAT", |i| {
+			Format::from_name(i).ok_or_else(|| format!("expected {}", Format::NAMES.join(", ")))
+		})? {
+			self.format = Some(format);
+		}
+		if let Some(when) = env_parsed("BAROTOOL_COLOR", |i| {
+			When::from_name(i).ok_or_else(|| format!("expected {}", When::NAMES.join(", ")))
+		})? {
+			self.color = Some(when);
+		}
+		if let Some(dir) = env("BAROTOOL_BACKUP_DIR") {
+			self.backup.dir = Some(dir.into());
+		}
+		if let Some(keep) = env_parsed("BAROTOOL_BACKUP_KEEP", |i| {
+			i.parse().map_err(|e| format!("{e}"))
+		})? {
+			self.backup.keep = Some(keep);
+		}
+		let count = |i: &str| i.parse().map_err(|e| format!("{e}"));
+		if let Some(max) = env_parsed("BAROTOOL_LIMITS_MAX_MEMBERS", count)? {
+			self.limits.max_members = Some(max);
+		}
+		if let Some(max) = env_parsed("BAROTOOL_LIMITS_MAX_MEMBER_SIZE", count)? {
+			self.limits.max_member_size = Some(max);
+		}
+		if let Some(max) = env_parsed("BAROTOOL_LIMITS_MAX_TOTAL_SIZE", |i| {
+			i.parse().map_err(|e| format!("{e}"))
+		})? {
+			self.limits.max_total_size = Some(max);
+		}
+		if let Some(max) = env_parsed("BAROTOOL_LIMITS_MAX_DEPTH", count)? {
+			self.limits.max_depth = Some(max);
+		}
+		Ok(())
+	}
+}
+
+/// Returns the value of an environment variable, treating an empty value as unset.
+fn env(name: &str) -> Option<OsString> {
+	std::env::var_os(name).filter(|i| !i.is_empty())
+}
+
+/// Returns the value of an environment variable, parsed by `parse`.
+///
+/// An empty or unset variable yields `None`; a value that does not parse is an error.
+fn env_parsed<T>(
+	name: &str,
+	parse: impl Fn(&str) -> std::result::Result<T, String>,
+) -> Result<Option<T>> {
+	let Some(value) = env(name) else {
+		return Ok(None);
+	};
+	let invalid = |message: String| {
+		std::io::Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!(
+				"Invalid value {} for {name}: {message}",
+				value.to_string_lossy()
+			),
+		)
+	};
+	let text = value
+		.to_str()
+		.ok_or_else(|| invalid("not valid UTF-8".to_owned()))?;
+	parse(text).map(Some).map_err(invalid)
+}
+
+/// Shows the settings in effect.
+pub fn show(config: &Config, format: Format) -> Result<()> {
+	output::emit(format, config, |config| {
+		let show = |name: &str, value: Option<String>| {
+			outln!("{name}\t{}", value.as_deref().unwrap_or("(unset)"));
+		};
+		show(
+			"game-dir",
+			config.game_dir.as_ref().map(|i| i.display().to_string()),
+		);
+		show(
+			"save-dir",
+			config.save_dir.as_ref().map(|i| i.display().to_string()),
+		);
+		show(
+			"compression-level",
+			config.compression_level.map(|i| i.to_string()),
+		);
+		show("jobs", config.jobs.map(|i| i.to_string()));
+		show("format", config.format.map(|i| i.name().to_owned()));
+		show("color", config.color.map(|i| i.name().to_owned()));
+		show(
+			"backup.dir",
+			config.backup.dir.as_ref().map(|i| i.display().to_string()),
+		);
+		show("backup.keep", config.backup.keep.map(|i| i.to_string()));
+		let limits = &config.limits;
+		show(
+			"limits.max-members",
+			limits.max_members.map(|i| i.to_string()),
+		);
+		show(
+			"limits.max-member-size",
+			limits.max_member_size.map(|i| i.to_string()),
+		);
+		show(
+			"limits.max-total-size",
+			limits.max_total_size.map(|i| i.to_string()),
+		);
+		show("limits.max-depth", limits.max_depth.map(|i| i.to_string()));
+	})
+}
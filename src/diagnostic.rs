@@ -0,0 +1,291 @@
+//! Errors that say where in a file a problem was found.
+
+use crate::error::Error;
+use crate::xml;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ffi::OsStr;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use xmltree::Element;
+
+/// A position within a document.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Position {
+	/// The line number, counting from 1.
+	pub line: u64,
+
+	/// The column number, in characters, counting from 1.
+	pub column: u64,
+
+	/// The offset from the start of the document, in bytes.
+	pub offset: u64,
+}
+
+impl Position {
+	/// Converts a zero-based line and character column within a document to a position.
+	pub fn of(data: &[u8], row: u64, column: u64) -> Self {
+		let line_start: usize = data
+			.split(|&i| i == b'\n')
+			.take(usize::try_from(row).unwrap_or(usize::MAX))
+			.map(|i| i.len() + 1)
+			.sum();
+		let line = &data[line_start.min(data.len())..];
+		let line = line.split(|&i| i == b'\n').next().unwrap_or_default();
+		let within: usize = String::from_utf8_lossy(line)
+			.chars()
+			.take(usize::try_from(column).unwrap_or(usize::MAX))
+			.map(char::len_utf8)
+			.sum();
+		Self {
+			line: row + 1,
+			column: column + 1,
+			offset: (line_start + within) as u64,
+		}
+	}
+
+	/// Converts a byte offset within a document to a position.
+	pub fn at(data: &[u8], offset: usize) -> Self {
+		let offset = offset.min(data.len());
+		let before = &data[..offset];
+		let line_start = before
+			.iter()
+			.rposition(|&i| i == b'\n')
+			.map_or(0, |i| i + 1);
+		let row = before.split(|&i| i == b'\n').count() - 1;
+		let column = String::from_utf8_lossy(&before[line_start..])
+			.chars()
+			.count();
+		Self {
+			line: row as u64 + 1,
+			column: column as u64 + 1,
+			offset: offset as u64,
+		}
+	}
+}
+
+/// An attribute whose value could not be understood.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct BadAttribute {
+	/// The name of the element holding the attribute.
+	element: String,
+
+	/// The name of the attribute.
+	attribute: String,
+
+	/// The value of the attribute.
+	value: String,
+}
+
+/// A problem with the contents of a file, along with where in the file it was found.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Diagnostic {
+	/// What is wrong.
+	message: String,
+
+	/// The file in which the problem was found.
+	file: Option<PathBuf>,
+
+	/// The member of a save file in which the problem was found.
+	member: Option<String>,
+
+	/// The position within the document at which the problem was found.
+	position: Option<Position>,
+
+	/// The path to the element at fault, such as `/Submarine/Item[12]`.
+	path: Option<String>,
+
+	/// The attribute at fault, kept so that the element holding it can be found later.
+	attribute: Option<BadAttribute>,
+}
+
+impl Display for Diagnostic {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.message)?;
+		if self.file.is_some() || self.member.is_some() || self.position.is_some() {
+			let mut location = Vec::new();
+			if let Some(file) = &self.file {
+				location.push(file.display().to_string());
+			}
+			if let Some(member) = &self.member {
+				location.push(member.clone());
+			}
+			if let Some(position) = &self.position {
+				location.push(format!(
+					"line {}, column {} (byte {})",
+					position.line, position.column, position.offset
+				));
+			}
+			write!(f, "\n  --> {}", location.join(": "))?;
+		}
+		if let Some(path) = &self.path {
+			write!(f, "\n  in {path}")?;
+		}
+		Ok(())
+	}
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Reports a document that is not well-formed XML.
+///
+/// The position at which parsing failed and the path to the element being read are given if they
+/// are known.
+pub fn syntax(message: String, position: Option<Position>, path: String) -> Error {
+	Error::Xml(Box::new(Diagnostic {
+		message,
+		position,
+		path: Some(path).filter(|i| !i.is_empty()),
+		..Diagnostic::default()
+	}))
+}
+
+/// Reports a document that is well-formed but not what was expected.
+pub fn invalid(message: String) -> Error {
+	Error::Invalid(Box::new(Diagnostic {
+		message,
+		..Diagnostic::default()
+	}))
+}
+
+/// Reports an attribute whose value could not be understood.
+pub fn attribute(element: &Element, attribute: &str, value: &str, message: String) -> Error {
+	Error::Invalid(Box::new(Diagnostic {
+		message,
+		attribute: Some(BadAttribute {
+			element: element.name.clone(),
+			attribute: attribute.to_owned(),
+			value: value.to_owned(),
+		}),
+		..Diagnostic::default()
+	}))
+}
+
+/// Reports an attribute whose value could not be understood, at a known position in a document.
+///
+/// This is for documents that are read without building an element tree, so the element holding
+/// the attribute cannot be searched for later.
+pub fn attribute_at(
+	element: &str,
+	attribute: &str,
+	value: &str,
+	message: String,
+	position: Position,
+) -> Error {
+	Error::Invalid(Box::new(Diagnostic {
+		message,
+		position: Some(position),
+		attribute: Some(BadAttribute {
+			element: element.to_owned(),
+			attribute: attribute.to_owned(),
+			value: value.to_owned(),
+		}),
+		..Diagnostic::default()
+	}))
+}
+
+/// Notes the file, and optionally the save file member, in which a problem was found.
+///
+/// I/O errors of kind [`std::io::ErrorKind::InvalidData`] become [`Error::Invalid`] diagnostics.
+/// Other errors without a diagnostic are returned unchanged, as are diagnostics that already name a
+/// file.
+pub fn in_file(mut e: Error, file: &Path, member: Option<&str>) -> Error {
+	if let Error::Io(io) = &e {
+		if io.kind() != std::io::ErrorKind::InvalidData {
+			return e;
+		}
+		e = invalid(io.to_string());
+	}
+	if let Some(diagnostic) = e.diagnostic_mut() {
+		if diagnostic.file.is_none() {
+			diagnostic.file = Some(file.to_owned());
+			diagnostic.member = member.map(str::to_owned);
+		}
+	}
+	e
+}
+
+/// Notes where in a document the element holding a bad attribute is.
+///
+/// If the error is about an attribute and does not yet say where it is, `root` is searched for an
+/// element with the same name and attribute value, and the path to the first one found is noted.
+/// Returns whether the element was found.
+pub fn locate(e: &mut Error, root: &Element) -> bool {
+	let Some(diagnostic) = e.diagnostic_mut() else {
+		return false;
+	};
+	if diagnostic.path.is_some() {
+		return false;
+	}
+	let Some(target) = &diagnostic.attribute else {
+		return false;
+	};
+	let mut path = Vec::new();
+	if find(root, target, &mut path) {
+		diagnostic.path = Some(format!("/{}", path.join("/")));
+		true
+	} else {
+		false
+	}
+}
+
+/// Searches for the element holding a bad attribute, at or beneath `element`.
+///
+/// On success, `path` holds the steps from the root to the element.
+fn find(element: &Element, target: &BadAttribute, path: &mut Vec<String>) -> bool {
+	if path.is_empty() {
+		path.push(element.name.clone());
+	}
+	if xml::is_named(element, &target.element)
+		&& xml::attribute(element, &target.attribute) == Some(target.value.as_str())
+	{
+		return true;
+	}
+	let mut counts: HashMap<&str, usize> = HashMap::new();
+	for child in xml::elements(element) {
+		let count = counts.entry(child.name.as_str()).or_default();
+		*count += 1;
+		path.push(format!("{}[{count}]", child.name));
+		if find(child, target, path) {
+			return true;
+		}
+		path.pop();
+	}
+	false
+}
+
+/// Notes where in a save file a problem was found.
+///
+/// The file is named in the error, as with [`in_file`]. If the error is about an attribute, the
+/// save’s XML and submarine members are also searched for the element holding it, and the member
+/// and element found are noted too.
+pub fn in_save(mut e: Error, filename: &OsStr) -> Error {
+	let (named, searchable) = e.diagnostic().map_or((false, false), |i| {
+		(i.file.is_some(), i.path.is_none() && i.attribute.is_some())
+	});
+	if named {
+		return e;
+	}
+	let path = Path::new(filename);
+	if !searchable {
+		return in_file(e, path, None);
+	}
+	if let Ok(members) = crate::save::member_names(filename) {
+		for member in members {
+			let Ok(Some(data)) = crate::save::read_member(filename, &member) else {
+				continue;
+			};
+			let root = if Path::new(&member).extension() == Some(OsStr::new("sub")) {
+				crate::compression::decompress(&data[..]).and_then(|i| xml::parse(&i[..]))
+			} else {
+				xml::parse(&data[..])
+			};
+			if let Ok(root) = root {
+				if locate(&mut e, &root) {
+					return in_file(e, path, Some(&member));
+				}
+			}
+		}
+	}
+	in_file(e, path, None)
+}
@@ -0,0 +1,442 @@
+use crate::color::Style;
+use crate::output::outln;
+use std::convert::TryFrom;
+
+/// One line of a comparison between two texts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Line<'a> {
+	/// A line present in both texts.
+	Same(&'a str),
+
+	/// A line present only in the old text.
+	Removed(&'a str),
+
+	/// A line present only in the new text.
+	Added(&'a str),
+}
+
+impl Line<'_> {
+	/// Returns whether the line differs between the texts.
+	fn is_change(self) -> bool {
+		!matches!(self, Self::Same(_))
+	}
+}
+
+/// Converts a position within the `v` array of Myers’ algorithm to an index.
+fn index(k: isize, offset: isize) -> usize {
+	usize::try_from(k + offset).unwrap()
+}
+
+/// Finds a shortest sequence of insertions and deletions turning `old` into `new`.
+///
+/// This is the greedy algorithm from Myers’ “An O(ND) Difference Algorithm and Its Variations”. Only
+/// the part of the `v` array reachable at each step is kept for backtracking, so memory grows with
+/// the square of the number of differences rather than with the size of the texts.
+// The variables are named as in the paper.
+#[allow(clippy::many_single_char_names)]
+fn edit_script<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+	let n = isize::try_from(old.len()).unwrap();
+	let m = isize::try_from(new.len()).unwrap();
+	let max = n + m;
+	let mut v = vec![0_isize; index(2 * max + 3, 0)];
+	let offset = max + 1;
+	let mut trace: Vec<Vec<isize>> = Vec::new();
+	'search: for d in 0..=max {
+		trace.push(v[index(-d - 1, offset)..=index(d + 1, offset)].to_vec());
+		for k in (-d..=d).step_by(2) {
+			let mut x = if k == -d || (k != d && v[index(k - 1, offset)] < v[index(k + 1, offset)])
+			{
+				v[index(k + 1, offset)]
+			} else {
+				v[index(k - 1, offset)] + 1
+			};
+			let mut y = x - k;
+			while x < n && y < m && old[index(x, 0)] == new[index(y, 0)] {
+				x += 1;
+				y += 1;
+			}
+			v[index(k, offset)] = x;
+			if x >= n && y >= m {
+				break 'search;
+			}
+		}
+	}
+
+	let mut script = Vec::new();
+	let (mut x, mut y) = (n, m);
+	for (d, v) in trace.iter().enumerate().rev() {
+		let d = isize::try_from(d).unwrap();
+		let offset = d + 1;
+		let k = x - y;
+		let prev_k = if k == -d || (k != d && v[index(k - 1, offset)] < v[index(k + 1, offset)]) {
+			k + 1
+		} else {
+			k - 1
+		};
+		let prev_x = v[index(prev_k, offset)];
+		let prev_y = prev_x - prev_k;
+		while x > prev_x && y > prev_y {
+			script.push(Line::Same(old[index(x - 1, 0)]));
+			x -= 1;
+			y -= 1;
+		}
+		if d > 0 {
+			if x == prev_x {
+				script.push(Line::Added(new[index(y - 1, 0)]));
+			} else {
+				script.push(Line::Removed(old[index(x - 1, 0)]));
+			}
+		}
+		x = prev_x;
+		y = prev_y;
+	}
+	script.reverse();
+	script
+}
+
+/// Compares two texts line by line.
+fn compare<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+	let old: Vec<&str> = old.lines().collect();
+	let new: Vec<&str> = new.lines().collect();
+	// Edits are usually confined to a small part of a large document, so the common beginning and
+	// end are set aside before searching.
+	let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+	let suffix = old[prefix..]
+		.iter()
+		.rev()
+		.zip(new[prefix..].iter().rev())
+		.take_while(|(a, b)| a == b)
+		.count();
+	let mut script: Vec<Line<'_>> = old[..prefix].iter().map(|i| Line::Same(i)).collect();
+	script.extend(edit_script(
+		&old[prefix..old.len() - suffix],
+		&new[prefix..new.len() - suffix],
+	));
+	script.extend(old[old.len() - suffix..].iter().map(|i| Line::Same(i)));
+	script
+}
+
+/// Formats the range of a hunk within one text, as `start,count`.
+fn range(before: usize, count: usize) -> String {
+	// An empty range is given by the line it follows.
+	let start = if count == 0 { before } else { before + 1 };
+	format!("{start},{count}")
+}
+
+/// Prints the differences between two texts as a unified diff with `context` lines of context.
+///
+/// `old_label` and `new_label` name the texts in the header. Nothing is printed if the texts have
+/// the same lines. Returns whether they differed.
+pub fn print_unified(
+	old_label: &str,
+	new_label: &str,
+	old: &str,
+	new: &str,
+	context: usize,
+) -> bool {
+	let script = compare(old, new);
+	if !script.iter().any(|i| i.is_change()) {
+		return false;
+	}
+	outln!("{}", Style::Heading.paint(format!("--- {old_label}")));
+	outln!("{}", Style::Heading.paint(format!("+++ {new_label}")));
+
+	// The number of lines of each text that come before each entry in the script.
+	let mut old_before = Vec::with_capacity(script.len() + 1);
+	let mut new_before = Vec::with_capacity(script.len() + 1);
+	let (mut old_count, mut new_count) = (0, 0);
+	for line in &script {
+		old_before.push(old_count);
+		new_before.push(new_count);
+		match line {
+			Line::Same(_) => {
+				old_count += 1;
+				new_count += 1;
+			}
+			Line::Removed(_) => old_count += 1,
+			Line::Added(_) => new_count += 1,
+		}
+	}
+	old_before.push(old_count);
+	new_before.push(new_count);
+
+	let mut next = 0;
+	while let Some(first) = script[next..].iter().position(|i| i.is_change()) {
+		let first = next + first;
+		// Changes separated by no more than twice the context share a hunk.
+		let mut last = first;
+		while let Some(gap) = script[last + 1..].iter().position(|i| i.is_change()) {
+			if gap > 2 * context {
+				break;
+			}
+			last += gap + 1;
+		}
+		let start = first.saturating_sub(context).max(next);
+		let end = (last + 1 + context).min(script.len());
+		outln!(
+			"{}",
+			Style::Number.paint(format!(
+				"@@ -{} +{} @@",
+				range(old_before[start], old_before[end] - old_before[start]),
+				range(new_before[start], new_before[end] - new_before[start]),
+			))
+		);
+		for line in &script[start..end] {
+			match line {
+				Line::Same(text) => outln!(" {text}"),
+				Line::Removed(text) => outln!("{}", Style::Removed.paint(format!("-{text}"))),
+				Line::Added(text) => outln!("{}", Style::Added.paint(format!("+{text}"))),
+			}
+		}
+		next = end;
+	}
+	true
+}
+
+/// A hunk of a unified diff.
+#[derive(Debug, Default)]
+struct Hunk<'a> {
+	/// The line of the old text that the hunk starts at, counting from 1, or the line it follows if
+	/// it covers none.
+	start: usize,
+
+	/// The lines of the old text that the hunk covers.
+	old: Vec<&'a str>,
+
+	/// The lines that replace them.
+	new: Vec<&'a str>,
+
+	/// Whether the old text ends, without a newline, at the hunk’s last line.
+	old_unterminated: bool,
+
+	/// Whether the new text ends, without a newline, at the hunk’s last line.
+	new_unterminated: bool,
+}
+
+/// Returns an error for a patch that is not a valid unified diff.
+fn invalid_patch(message: String) -> std::io::Error {
+	std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Parses the range of a hunk within one text, as `start,count` or `start`, the count then being 1.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+	match range.split_once(',') {
+		Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+		None => Some((range.parse().ok()?, 1)),
+	}
+}
+
+/// Parses the hunks of a unified diff of a single file.
+///
+/// Anything before the first hunk, such as the `---` and `+++` lines naming the file, is ignored.
+fn parse_hunks(patch: &str) -> std::io::Result<Vec<Hunk<'_>>> {
+	let mut hunks = Vec::new();
+	let mut lines = patch.split('\n').enumerate().peekable();
+	let mut headers = 0;
+	while let Some((number, line)) = lines.next() {
+		// Lines within hunks are read below, so this can only be the header naming a file.
+		if line.starts_with("--- ") {
+			headers += 1;
+			if headers > 1 {
+				return Err(invalid_patch("Patch changes more than one file".to_owned()));
+			}
+		}
+		let Some(header) = line.strip_prefix("@@ -") else {
+			continue;
+		};
+		let ranges = header
+			.split_once(" @@")
+			.and_then(|(ranges, _)| ranges.split_once(" +"))
+			.and_then(|(old, new)| Some((parse_range(old)?, parse_range(new)?)));
+		let Some(((start, mut old_left), (_, mut new_left))) = ranges else {
+			return Err(invalid_patch(format!(
+				"Invalid hunk header on line {}",
+				number + 1
+			)));
+		};
+		let mut hunk = Hunk {
+			start,
+			..Hunk::default()
+		};
+		while old_left != 0 || new_left != 0 {
+			let Some((_, line)) = lines.next() else {
+				return Err(invalid_patch(format!(
+					"Hunk starting on line {} ends early",
+					number + 1
+				)));
+			};
+			let mut chars = line.chars();
+			// Some tools strip the space from an empty line of context.
+			let kind = chars.next().unwrap_or(' ');
+			let text = chars.as_str();
+			match kind {
+				' ' if old_left != 0 && new_left != 0 => {
+					hunk.old.push(text);
+					hunk.new.push(text);
+					old_left -= 1;
+					new_left -= 1;
+				}
+				'-' if old_left != 0 => {
+					hunk.old.push(text);
+					old_left -= 1;
+				}
+				'+' if new_left != 0 => {
+					hunk.new.push(text);
+					new_left -= 1;
+				}
+				'\\' => (),
+				_ => {
+					return Err(invalid_patch(format!(
+						"Invalid line in hunk starting on line {}",
+						number + 1
+					)))
+				}
+			}
+			if let Some((_, next)) = lines.peek() {
+				if next.starts_with('\\') {
+					match kind {
+						'-' => hunk.old_unterminated = true,
+						'+' => hunk.new_unterminated = true,
+						_ => {
+							hunk.old_unterminated = true;
+							hunk.new_unterminated = true;
+						}
+					}
+				}
+			}
+		}
+		hunks.push(hunk);
+	}
+	if hunks.is_empty() {
+		return Err(invalid_patch("Patch holds no hunks".to_owned()));
+	}
+	Ok(hunks)
+}
+
+/// Applies a unified diff to a text, returning the patched text.
+///
+/// Each hunk must match the text exactly, but may be found above or below where its header says,
+/// as when lines have been added or removed elsewhere since the diff was made; the hunk is applied
+/// at the nearest place it matches. A hunk that matches nowhere is an error of kind
+/// [`std::io::ErrorKind::InvalidData`].
+pub fn apply_unified(text: &str, patch: &str) -> std::io::Result<String> {
+	let hunks = parse_hunks(patch)?;
+	let terminated = text.is_empty() || text.ends_with('\n');
+	let mut lines: Vec<&str> = text.split('\n').collect();
+	if terminated {
+		lines.pop();
+	}
+	let mut terminated = terminated;
+	let mut result: Vec<&str> = Vec::with_capacity(lines.len());
+	// The number of lines of the text that have been copied or replaced.
+	let mut done = 0;
+	// How far the hunks so far have been found from where their headers said.
+	let mut shift = 0_isize;
+	for (number, hunk) in hunks.iter().enumerate() {
+		let stated = if hunk.old.is_empty() {
+			hunk.start
+		} else {
+			hunk.start.saturating_sub(1)
+		};
+		let expected = usize::try_from(isize::try_from(stated).unwrap() + shift)
+			.unwrap_or(0)
+			.max(done);
+		let matches = |at: usize| {
+			at >= done
+				&& at + hunk.old.len() <= lines.len()
+				&& lines[at..at + hunk.old.len()] == hunk.old[..]
+		};
+		let found = (0..=expected.max(lines.len())).find_map(|distance| {
+			[
+				expected.checked_add(distance),
+				expected.checked_sub(distance),
+			]
+			.iter()
+			.flatten()
+			.copied()
+			.find(|at| matches(*at))
+		});
+		let Some(at) = found else {
+			return Err(invalid_patch(format!("Hunk {} does not apply", number + 1)));
+		};
+		shift = isize::try_from(at).unwrap() - isize::try_from(stated).unwrap();
+		result.extend(&lines[done..at]);
+		result.extend(&hunk.new);
+		done = at + hunk.old.len();
+		if done == lines.len() {
+			if hunk.new_unterminated {
+				terminated = false;
+			} else if hunk.old_unterminated {
+				terminated = true;
+			}
+		}
+	}
+	result.extend(&lines[done..]);
+	let mut patched = result.join("\n");
+	if terminated && !result.is_empty() {
+		patched.push('\n');
+	}
+	Ok(patched)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_compare() {
+		let cases = [
+			("", ""),
+			("a\nb\nc", "a\nb\nc"),
+			("", "a\nb"),
+			("a\nb", ""),
+			("a\nb\nc\na\nb\nb\na", "c\nb\na\nb\na\nc"),
+			("x\na\ny\nb\nz", "a\nq\nb\nr"),
+		];
+		for (old, new) in cases {
+			let script = compare(old, new);
+			let rebuilt_old: Vec<&str> = script
+				.iter()
+				.filter_map(|i| match i {
+					Line::Same(text) | Line::Removed(text) => Some(*text),
+					Line::Added(_) => None,
+				})
+				.collect();
+			let rebuilt_new: Vec<&str> = script
+				.iter()
+				.filter_map(|i| match i {
+					Line::Same(text) | Line::Added(text) => Some(*text),
+					Line::Removed(_) => None,
+				})
+				.collect();
+			assert_eq!(rebuilt_old, old.lines().collect::<Vec<&str>>());
+			assert_eq!(rebuilt_new, new.lines().collect::<Vec<&str>>());
+		}
+		// The example from Myers’ paper has an edit distance of five.
+		let script = compare("a\nb\nc\na\nb\nb\na", "c\nb\na\nb\na\nc");
+		assert_eq!(script.iter().filter(|i| i.is_change()).count(), 5);
+	}
+
+	#[test]
+	fn test_apply_unified() {
+		let text = "a\nb\nc\nd\ne\n";
+		let patch = "--- x\n+++ x\n@@ -2,3 +2,3 @@\n b\n-c\n+C\n d\n";
+		assert_eq!(apply_unified(text, patch).unwrap(), "a\nb\nC\nd\ne\n");
+
+		// A hunk is found where it has moved to.
+		let moved = format!("x\ny\n{text}");
+		assert_eq!(
+			apply_unified(&moved, patch).unwrap(),
+			"x\ny\na\nb\nC\nd\ne\n"
+		);
+
+		// Lines are added at the start, and the newline at the end is removed.
+		let patch = "@@ -0,0 +1 @@\n+z\n@@ -5 +6 @@\n-e\n+E\n\\ No newline at end of file\n";
+		assert_eq!(apply_unified(text, patch).unwrap(), "z\na\nb\nc\nd\nE");
+
+		// A hunk that matches nowhere, or a patch with no hunks, is refused.
+		assert!(apply_unified(text, "@@ -1 +1 @@\n-q\n+r\n").is_err());
+		assert!(apply_unified(text, "--- x\n+++ x\n").is_err());
+		assert!(apply_unified(text, "@@ -1,2 +1,2 @@\n a\n").is_err());
+	}
+}
@@ -0,0 +1,117 @@
+use crate::diff;
+use crate::output::{self, outln};
+use barotool::save;
+use barotool::submarine;
+use barotool::xml;
+use std::ffi::OsStr;
+use std::fmt::Display;
+use std::io::Result;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The number of unchanged lines shown around each change.
+pub const CONTEXT: usize = 3;
+
+/// Whether changes are previewed instead of made.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether changes are previewed instead of made.
+pub fn set_enabled(enabled: bool) {
+	ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether changes are previewed instead of made.
+pub fn is_enabled() -> bool {
+	ENABLED.load(Ordering::Relaxed)
+}
+
+/// Reports a change to the filesystem that was not made, such as `write foo.xml`.
+pub fn would<T: Display>(action: T) {
+	output::note(format!("Would {action}"));
+}
+
+/// Returns the contents of a save file member as text, for comparison.
+///
+/// Submarine members are decompressed. XML documents are rewritten the way barotool writes them, so
+/// that only real changes show up, and not differences in formatting from the game’s own output.
+/// Returns `None` if the member is not text.
+pub fn text_of(member: &str, data: &[u8]) -> Option<String> {
+	let mut data = data.to_vec();
+	if Path::new(member).extension() == Some(OsStr::new("sub")) {
+		let mut decompressed = Vec::new();
+		submarine::decompress(&data[..], &mut decompressed).ok()?;
+		data = decompressed;
+	}
+	if let Ok(root) = xml::parse(&data[..]) {
+		let mut written = Vec::new();
+		if xml::write(&root, &mut written).is_ok() {
+			data = written;
+		}
+	}
+	String::from_utf8(data).ok()
+}
+
+/// Shows the changes that [`save::update`] would make to a save file, as a unified diff.
+pub fn preview(filename: &OsStr, changes: &[(&str, Option<Vec<u8>>)]) -> Result<()> {
+	let file = Path::new(filename).display();
+	let mut shown = false;
+	for (member, data) in changes {
+		let old = save::read_member(filename, member)?;
+		if old == *data {
+			continue;
+		}
+		let label = format!("{file}/{member}");
+		let old_text = old.as_deref().map(|i| text_of(member, i));
+		let new_text = data.as_deref().map(|i| text_of(member, i));
+		match (old_text, new_text) {
+			(Some(None), _) | (_, Some(None)) => {
+				outln!("Binary member {label} would change");
+				shown = true;
+			}
+			(old_text, new_text) => {
+				let old_label = if old_text.is_some() {
+					&label
+				} else {
+					"/dev/null"
+				};
+				let new_label = if new_text.is_some() {
+					&label
+				} else {
+					"/dev/null"
+				};
+				shown |= diff::print_unified(
+					old_label,
+					new_label,
+					old_text.flatten().as_deref().unwrap_or_default(),
+					new_text.flatten().as_deref().unwrap_or_default(),
+					CONTEXT,
+				);
+			}
+		}
+	}
+	if !shown {
+		output::note(format!("No changes to {file}"));
+	}
+	Ok(())
+}
+
+/// Shows the changes that replacing one save file with another would make, as a unified diff.
+pub fn preview_replacement(filename: &OsStr, replacement: &OsStr) -> Result<()> {
+	let old_members = save::member_names(filename)?;
+	let new_members = save::member_names(replacement)?;
+	let mut contents = Vec::new();
+	for member in &new_members {
+		contents.push(save::read_member(replacement, member)?);
+	}
+	let mut changes: Vec<(&str, Option<Vec<u8>>)> = new_members
+		.iter()
+		.map(String::as_str)
+		.zip(contents)
+		.collect();
+	for member in &old_members {
+		if !new_members.contains(member) {
+			changes.push((member, None));
+		}
+	}
+	preview(filename, &changes)
+}
@@ -0,0 +1,66 @@
+use std::ffi::{OsStr, OsString};
+#[cfg(unix)]
+use std::io::IsTerminal;
+use std::io::Result;
+use std::path::Path;
+
+/// Returns the editor to use: `editor` if given, else the command in the `VISUAL` or `EDITOR`
+/// environment variable, else a platform default.
+fn command(editor: Option<&OsStr>) -> OsString {
+	editor
+		.map(OsStr::to_owned)
+		.or_else(|| std::env::var_os("VISUAL").filter(|i| !i.is_empty()))
+		.or_else(|| std::env::var_os("EDITOR").filter(|i| !i.is_empty()))
+		.unwrap_or_else(|| {
+			if cfg!(windows) {
+				"notepad".into()
+			} else {
+				"vi".into()
+			}
+		})
+}
+
+/// Opens a file in an editor and waits for it to exit.
+///
+/// The editor is as chosen by [`command`]. On Unix it is run by the shell, as Git runs it, so that
+/// it may include arguments; elsewhere it is run directly. An editor that exits unsuccessfully is
+/// reported as an error.
+///
+/// If standard output is not a terminal, as when it has been redirected, the editor is given the
+/// controlling terminal, if there is one, as its standard output instead.
+pub fn edit(editor: Option<&OsStr>, file: &Path) -> Result<()> {
+	let editor = command(editor);
+	log::debug!("Running editor {}", editor.to_string_lossy());
+	#[cfg(unix)]
+	let mut process = {
+		let mut script = editor.clone();
+		script.push(" \"$@\"");
+		let mut process = std::process::Command::new("sh");
+		process.arg("-c").arg(script).arg(&editor).arg(file);
+		if !std::io::stdout().is_terminal() {
+			if let Ok(tty) = std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+				process.stdout(tty);
+			}
+		}
+		process
+	};
+	#[cfg(not(unix))]
+	let mut process = {
+		let mut process = std::process::Command::new(&editor);
+		process.arg(file);
+		process
+	};
+	let status = process.status().map_err(|e| {
+		std::io::Error::new(
+			e.kind(),
+			format!("Cannot run editor {}: {e}", editor.to_string_lossy()),
+		)
+	})?;
+	if !status.success() {
+		return Err(std::io::Error::other(format!(
+			"Editor {} failed ({status}); nothing was changed",
+			editor.to_string_lossy()
+		)));
+	}
+	Ok(())
+}
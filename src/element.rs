@@ -0,0 +1,204 @@
+//! Serde support for whole XML documents, in a form that does not depend on XML syntax.
+//!
+//! An element is represented as a structure with its `name`, its `attributes` as a map in document
+//! order, and its `children` as a sequence. Each child is either an element, a string of text, or a
+//! single-entry map holding a `comment`, `cdata`, or `processing-instruction` (with its `data`).
+//! Empty attribute maps and child sequences are left out.
+
+use serde::de::{Deserializer, Error as _, MapAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
+use xmltree::{Element, XMLNode};
+
+/// An element to be serialized.
+struct ElementRef<'a>(&'a Element);
+
+impl Serialize for ElementRef<'_> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let element = self.0;
+		let fields = 1
+			+ usize::from(!element.attributes.is_empty())
+			+ usize::from(!element.children.is_empty());
+		let mut state = serializer.serialize_struct("Element", fields)?;
+		state.serialize_field("name", &element.name)?;
+		if element.attributes.is_empty() {
+			state.skip_field("attributes")?;
+		} else {
+			state.serialize_field("attributes", &AttributesRef(element))?;
+		}
+		if element.children.is_empty() {
+			state.skip_field("children")?;
+		} else {
+			state.serialize_field("children", &ChildrenRef(element))?;
+		}
+		state.end()
+	}
+}
+
+/// The attributes of an element to be serialized.
+struct AttributesRef<'a>(&'a Element);
+
+impl Serialize for AttributesRef<'_> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_map(self.0.attributes.iter())
+	}
+}
+
+/// The children of an element to be serialized.
+struct ChildrenRef<'a>(&'a Element);
+
+impl Serialize for ChildrenRef<'_> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(self.0.children.iter().map(NodeRef))
+	}
+}
+
+/// A child of an element to be serialized.
+struct NodeRef<'a>(&'a XMLNode);
+
+impl Serialize for NodeRef<'_> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match self.0 {
+			XMLNode::Element(element) => ElementRef(element).serialize(serializer),
+			XMLNode::Text(text) => serializer.serialize_str(text),
+			XMLNode::Comment(text) => {
+				let mut map = serializer.serialize_map(Some(1))?;
+				map.serialize_entry("comment", text)?;
+				map.end()
+			}
+			XMLNode::CData(text) => {
+				let mut map = serializer.serialize_map(Some(1))?;
+				map.serialize_entry("cdata", text)?;
+				map.end()
+			}
+			XMLNode::ProcessingInstruction(name, data) => {
+				let mut map = serializer.serialize_map(Some(2))?;
+				map.serialize_entry("processing-instruction", name)?;
+				map.serialize_entry("data", data)?;
+				map.end()
+			}
+		}
+	}
+}
+
+/// A deserialized element.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ElementRepr {
+	/// The element’s name.
+	name: String,
+
+	/// The element’s attributes, in document order.
+	#[serde(default)]
+	attributes: AttributesRepr,
+
+	/// The element’s children.
+	#[serde(default)]
+	children: Vec<NodeRepr>,
+}
+
+impl From<ElementRepr> for Element {
+	fn from(repr: ElementRepr) -> Self {
+		let mut element = Element::new(&repr.name);
+		element.attributes.extend(repr.attributes.0);
+		element.children = repr.children.into_iter().map(XMLNode::from).collect();
+		element
+	}
+}
+
+/// The deserialized attributes of an element, in the order they were given.
+#[derive(Default)]
+struct AttributesRepr(Vec<(String, String)>);
+
+impl<'de> Deserialize<'de> for AttributesRepr {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		/// Collects map entries in order.
+		struct AttributesVisitor;
+
+		impl<'de> Visitor<'de> for AttributesVisitor {
+			type Value = AttributesRepr;
+
+			fn expecting(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+				f.write_str("a map of attribute names to values")
+			}
+
+			fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+				let mut ret = Vec::with_capacity(map.size_hint().unwrap_or_default());
+				while let Some(entry) = map.next_entry()? {
+					ret.push(entry);
+				}
+				Ok(AttributesRepr(ret))
+			}
+		}
+
+		deserializer.deserialize_map(AttributesVisitor)
+	}
+}
+
+/// A deserialized child of an element.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NodeRepr {
+	/// A child element.
+	Element(ElementRepr),
+
+	/// Text.
+	Text(String),
+
+	/// A comment.
+	Comment {
+		/// The text of the comment.
+		comment: String,
+	},
+
+	/// A CDATA section.
+	CData {
+		/// The text of the section.
+		cdata: String,
+	},
+
+	/// A processing instruction.
+	ProcessingInstruction {
+		/// The target of the instruction.
+		#[serde(rename = "processing-instruction")]
+		name: String,
+
+		/// The rest of the instruction, if any.
+		data: Option<String>,
+	},
+}
+
+impl From<NodeRepr> for XMLNode {
+	fn from(repr: NodeRepr) -> Self {
+		match repr {
+			NodeRepr::Element(element) => Self::Element(element.into()),
+			NodeRepr::Text(text) => Self::Text(text),
+			NodeRepr::Comment { comment } => Self::Comment(comment),
+			NodeRepr::CData { cdata } => Self::CData(cdata),
+			NodeRepr::ProcessingInstruction { name, data } => {
+				Self::ProcessingInstruction(name, data)
+			}
+		}
+	}
+}
+
+/// Serializes a document given its root element.
+pub fn serialize<S: Serializer>(root: &Element, serializer: S) -> Result<S::Ok, S::Error> {
+	ElementRef(root).serialize(serializer)
+}
+
+/// Deserializes a document, checking that its root element has the expected name.
+pub fn deserialize<'de, D: Deserializer<'de>>(
+	deserializer: D,
+	root_name: &str,
+) -> Result<Element, D::Error> {
+	let root: Element = ElementRepr::deserialize(deserializer)?.into();
+	if root.name != root_name {
+		return Err(D::Error::custom(format!(
+			"Expected {root_name} root element, found {}",
+			root.name
+		)));
+	}
+	Ok(root)
+}
@@ -0,0 +1,122 @@
+//! The errors reported by this crate.
+
+use crate::diagnostic::Diagnostic;
+
+/// Something that went wrong reading, parsing, or writing a save file or submarine.
+///
+/// Errors about the contents of a document carry a [`Diagnostic`] saying where in the document the
+/// problem was found.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+	/// Reading or writing a file failed.
+	#[error(transparent)]
+	Io(std::io::Error),
+
+	/// A save file or submarine is not valid gzip data.
+	#[error("Cannot decompress: {0}")]
+	Gzip(#[source] std::io::Error),
+
+	/// A save file ended part way through a member.
+	#[error("Save file ends within an archive member")]
+	Truncated,
+
+	/// The name of a save file member is not valid UTF-16.
+	#[error("Invalid save file member name: {0}")]
+	MemberName(#[from] std::string::FromUtf16Error),
+
+	/// A member is too large to store in a save file.
+	#[error("{0}")]
+	TooLarge(String),
+
+	/// A file exceeds one of the [`Limits`](crate::limits::Limits) set on what may be read.
+	#[error("{0}")]
+	Limit(String),
+
+	/// A document is not well-formed XML.
+	#[error(transparent)]
+	Xml(Box<Diagnostic>),
+
+	/// A document is well-formed but is not what was expected, such as an attribute whose value
+	/// cannot be understood.
+	#[error(transparent)]
+	Invalid(Box<Diagnostic>),
+}
+
+impl Error {
+	/// Returns the diagnostic saying where the problem was found, if there is one.
+	pub fn diagnostic(&self) -> Option<&Diagnostic> {
+		match self {
+			Self::Xml(diagnostic) | Self::Invalid(diagnostic) => Some(diagnostic),
+			_ => None,
+		}
+	}
+
+	/// Returns the diagnostic saying where the problem was found, mutably, if there is one.
+	pub(crate) fn diagnostic_mut(&mut self) -> Option<&mut Diagnostic> {
+		match self {
+			Self::Xml(diagnostic) | Self::Invalid(diagnostic) => Some(diagnostic),
+			_ => None,
+		}
+	}
+
+	/// Returns the kind of I/O error that this error is reported as.
+	pub fn kind(&self) -> std::io::ErrorKind {
+		match self {
+			Self::Io(e) => e.kind(),
+			Self::Truncated => std::io::ErrorKind::UnexpectedEof,
+			Self::TooLarge(_) => std::io::ErrorKind::Other,
+			Self::Gzip(_)
+			| Self::MemberName(_)
+			| Self::Limit(_)
+			| Self::Xml(_)
+			| Self::Invalid(_) => std::io::ErrorKind::InvalidData,
+		}
+	}
+}
+
+/// Converts an I/O error into an [`Error`].
+///
+/// An I/O error that was itself converted from an [`Error`] is turned back into the original, so
+/// that errors passing through code that only deals in I/O errors keep their class.
+impl From<std::io::Error> for Error {
+	fn from(e: std::io::Error) -> Self {
+		e.downcast().unwrap_or_else(Self::Io)
+	}
+}
+
+/// Converts an [`Error`] into an I/O error of the kind given by [`Error::kind`].
+///
+/// The original error can be recovered by converting back.
+impl From<Error> for std::io::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error::Io(e) => e,
+			e => Self::new(e.kind(), e),
+		}
+	}
+}
+
+/// Classifies an error from reading gzip-compressed data.
+///
+/// The gzip decoder reports corrupt data as [`std::io::ErrorKind::InvalidData`], or, with `flate2`,
+/// as [`std::io::ErrorKind::InvalidInput`], and data that stops part way through the gzip framing
+/// as [`std::io::ErrorKind::UnexpectedEof`]; anything else is a failure of the underlying file.
+///
+/// An I/O error converted from an [`Error`], such as one raised by the decoder for exceeding a
+/// limit, is turned back into the original.
+pub(crate) fn gzip(e: std::io::Error) -> Error {
+	let e = match e.downcast::<Error>() {
+		Ok(e) => return e,
+		Err(e) => e,
+	};
+	match e.kind() {
+		std::io::ErrorKind::InvalidData
+		| std::io::ErrorKind::InvalidInput
+		| std::io::ErrorKind::UnexpectedEof => Error::Gzip(e),
+		_ => e.into(),
+	}
+}
+
+/// A result whose error is an [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
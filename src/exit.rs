@@ -0,0 +1,58 @@
+use std::fmt::{Display, Formatter};
+
+/// The exit status for success.
+pub const SUCCESS: u8 = 0;
+
+/// The exit status when a comparison found differences.
+pub const DIFFERENCES: u8 = 1;
+
+/// The exit status when a save or submarine was read successfully but failed validation.
+pub const INVALID: u8 = 2;
+
+/// The exit status when a file could not be parsed.
+pub const PARSE_ERROR: u8 = 3;
+
+/// The exit status when something named on the command line does not exist.
+pub const NOT_FOUND: u8 = 4;
+
+/// The exit status for other I/O errors.
+pub const IO_ERROR: u8 = 5;
+
+/// The exit status when the command line is invalid.
+pub const USAGE: u8 = 64;
+
+/// An error that calls for a particular exit status.
+#[derive(Debug)]
+pub struct Status {
+	/// The exit status.
+	pub code: u8,
+
+	/// The error message.
+	pub message: String,
+}
+
+impl Display for Status {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.message)
+	}
+}
+
+impl std::error::Error for Status {}
+
+/// Returns an error that causes the program to exit with the given status.
+pub fn error(code: u8, message: String) -> std::io::Error {
+	std::io::Error::other(Status { code, message })
+}
+
+/// Returns the exit status appropriate for an error.
+pub fn code(e: &std::io::Error) -> u8 {
+	if let Some(status) = e.get_ref().and_then(|i| i.downcast_ref::<Status>()) {
+		return status.code;
+	}
+	match e.kind() {
+		std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof => PARSE_ERROR,
+		std::io::ErrorKind::NotFound => NOT_FOUND,
+		std::io::ErrorKind::InvalidInput => USAGE,
+		_ => IO_ERROR,
+	}
+}
@@ -0,0 +1,1354 @@
+//! Reading and modifying the game session, the `gamesession.xml` member of a campaign save.
+
+use crate::character::{self, Character, CharacterEntry, Pet};
+use crate::diagnostic;
+#[cfg(feature = "serde")]
+use crate::element;
+use crate::xml;
+use crate::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use xmltree::{Element, XMLNode};
+
+/// The kind of campaign stored in a save file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum GameMode {
+	/// A single-player campaign.
+	SinglePlayer,
+
+	/// A multiplayer campaign.
+	MultiPlayer,
+
+	/// Some other kind of game mode, identified by the name of its XML element.
+	Other(String),
+}
+
+/// A submarine that the crew owns.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct OwnedSubmarine {
+	/// The name of the submarine, which is also the name of its `.sub` member without extension.
+	pub name: String,
+}
+
+/// An item that has been bought but not yet delivered.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PurchasedItem {
+	/// The item’s prefab identifier.
+	pub identifier: String,
+
+	/// The number of items bought.
+	pub quantity: u32,
+}
+
+/// A single key/value pair from the campaign metadata.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct MetadataEntry {
+	/// The key.
+	pub key: String,
+
+	/// The value, as written in the file.
+	pub value: String,
+
+	/// The name of the .NET type of the value, if recorded.
+	pub kind: Option<String>,
+}
+
+/// A submarine upgrade that has been bought but not yet installed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PendingUpgrade {
+	/// The upgrade prefab identifier, such as `increasehullhealth`.
+	pub prefab: String,
+
+	/// The identifier of the upgrade category the upgrade was bought under.
+	pub category: String,
+
+	/// The level the upgrade will be at once installed.
+	pub level: u32,
+}
+
+/// Counters recording how far a campaign has progressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Progress {
+	/// The total time spent playing the campaign, in seconds.
+	pub play_time: Option<f64>,
+
+	/// The number of levels the crew has passed through.
+	///
+	/// The game uses this, along with the map position, to ramp up the difficulty of the campaign.
+	pub passed_levels: Option<u32>,
+}
+
+/// A single campaign setting, chosen when the campaign was created.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct CampaignSetting {
+	/// The name of the setting, such as `RadiationEnabled` or `MaxMissionCount`.
+	pub name: String,
+
+	/// The value, as written in the file.
+	pub value: String,
+}
+
+/// A location on the campaign map.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Location {
+	/// The index of the location, used to refer to it from elsewhere in the map.
+	pub index: usize,
+
+	/// The location’s name.
+	pub name: String,
+
+	/// The location type (outpost, city, etc.).
+	pub kind: String,
+
+	/// Whether the crew has discovered the location.
+	pub discovered: bool,
+
+	/// The crew’s reputation with the location, if it has one.
+	pub reputation: Option<f32>,
+}
+
+/// A connection between two locations on the campaign map.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Connection {
+	/// The indices of the two locations at the ends of the connection.
+	pub locations: (usize, usize),
+
+	/// Whether the crew has travelled through the connection.
+	pub passed: bool,
+
+	/// The identifier of the biome the connection passes through.
+	pub biome: Option<String>,
+}
+
+/// A faction the crew can gain or lose standing with.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Faction {
+	/// The faction identifier, such as `coalition` or `separatists`.
+	pub identifier: String,
+
+	/// The crew’s reputation with the faction, from −100 to 100.
+	pub reputation: f32,
+}
+
+/// A mission offered at, or accepted from, a location.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Mission {
+	/// The mission prefab identifier.
+	pub identifier: String,
+
+	/// The name of the location offering the mission.
+	pub location: String,
+
+	/// Whether the crew has accepted the mission.
+	pub selected: bool,
+
+	/// The index of the location the mission leads to, if it is not the offering location.
+	pub destination: Option<usize>,
+}
+
+/// The parameters from which a level is generated.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct LevelData {
+	/// The seed for the level generator.
+	pub seed: String,
+
+	/// The difficulty of the level, from 0 to 100.
+	pub difficulty: Option<f32>,
+}
+
+impl LevelData {
+	/// Extracts level data from a `Level` element.
+	fn from_xml(element: &Element) -> Result<Self> {
+		Ok(Self {
+			seed: xml::attribute(element, "seed")
+				.unwrap_or_default()
+				.to_owned(),
+			difficulty: xml::parse_attribute(element, "difficulty")?,
+		})
+	}
+}
+
+/// Identifies which level of the campaign map to operate on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LevelChoice {
+	/// The level at the crew’s current location, such as an outpost.
+	Location,
+
+	/// The level the crew will pass through on the way to its selected destination.
+	Next,
+}
+
+/// The state of the campaign map.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct MapState {
+	/// The seed used to generate the map.
+	pub seed: Option<String>,
+
+	/// The index of the location the crew is currently at.
+	pub current_location: Option<usize>,
+
+	/// The index of the location the crew has chosen to travel to next.
+	pub selected_location: Option<usize>,
+
+	/// The locations on the map.
+	pub locations: Vec<Location>,
+
+	/// The connections between locations.
+	pub connections: Vec<Connection>,
+}
+
+impl MapState {
+	/// Returns the location the crew is currently at.
+	pub fn current(&self) -> Option<&Location> {
+		let index = self.current_location?;
+		self.locations.iter().find(|i| i.index == index)
+	}
+
+	/// Returns the indices of the locations reachable from the current location by passing
+	/// through at most `radius` connections.
+	///
+	/// If there is no current location, the set is empty.
+	pub fn within(&self, radius: usize) -> HashSet<usize> {
+		let mut ret = HashSet::new();
+		let mut frontier: Vec<usize> = self.current_location.into_iter().collect();
+		ret.extend(frontier.iter().copied());
+		for _ in 0..radius {
+			let mut next = Vec::new();
+			for &(a, b) in self.connections.iter().map(|i| &i.locations) {
+				for (from, to) in [(a, b), (b, a)] {
+					if frontier.contains(&from) && ret.insert(to) {
+						next.push(to);
+					}
+				}
+			}
+			frontier = next;
+		}
+		ret
+	}
+}
+
+/// The contents of a campaign save’s `gamesession.xml` member.
+///
+/// The document is held in full so that anything not understood by barotool survives a round trip
+/// unchanged; the accessors pick typed values out of it on demand.
+#[derive(Clone, Debug)]
+pub struct GameSession {
+	/// The root `Gamesession` element.
+	root: Element,
+}
+
+impl GameSession {
+	/// The name of the save file member holding the game session.
+	pub const MEMBER_NAME: &'static str = "gamesession.xml";
+
+	/// Parses a game session document.
+	pub fn parse<R: Read>(source: R) -> Result<Self> {
+		let root = xml::parse(source)?;
+		if !xml::is_named(&root, "Gamesession") {
+			return Err(diagnostic::invalid(format!(
+				"Expected Gamesession root element, found {}",
+				root.name
+			)));
+		}
+		Ok(Self { root })
+	}
+
+	/// Writes the game session document.
+	pub fn write<W: Write>(&self, sink: W) -> Result<()> {
+		xml::write(&self.root, sink)
+	}
+
+	/// Returns the version of the game that wrote the save.
+	pub fn version(&self) -> Option<&str> {
+		xml::attribute(&self.root, "version")
+	}
+
+	/// Returns the name of the submarine the crew is currently using.
+	pub fn submarine(&self) -> Option<&str> {
+		xml::attribute(&self.root, "submarine")
+	}
+
+	/// Sets the name of the submarine the crew is currently using.
+	pub fn set_submarine(&mut self, name: &str) {
+		xml::set_attribute(&mut self.root, "submarine", name.to_owned());
+	}
+
+	/// Returns whether an element is the one holding the campaign state.
+	///
+	/// Its name depends on the game mode, but always ends in “Campaign”.
+	fn is_campaign(element: &Element) -> bool {
+		element.name.to_ascii_lowercase().ends_with("campaign")
+	}
+
+	/// Returns the element holding the campaign state.
+	fn campaign(&self) -> Option<&Element> {
+		xml::elements(&self.root).find(|i| Self::is_campaign(i))
+	}
+
+	/// Returns the element holding the campaign state, mutably.
+	fn campaign_mut(&mut self) -> Result<&mut Element> {
+		xml::elements_mut(&mut self.root)
+			.find(|i| Self::is_campaign(i))
+			.ok_or_else(|| {
+				diagnostic::invalid("Game session does not contain a campaign".to_owned())
+			})
+	}
+
+	/// Returns the game mode of the save.
+	pub fn game_mode(&self) -> Option<GameMode> {
+		self.campaign().map(|c| {
+			if xml::is_named(c, "SinglePlayerCampaign") {
+				GameMode::SinglePlayer
+			} else if xml::is_named(c, "MultiPlayerCampaign") {
+				GameMode::MultiPlayer
+			} else {
+				GameMode::Other(c.name.clone())
+			}
+		})
+	}
+
+	/// Returns the amount of money in the shared campaign bank.
+	///
+	/// Since version 1.0 this is stored in a `Wallet` element; older saves keep it in a `money`
+	/// attribute on the campaign element itself. Both are handled.
+	pub fn money(&self) -> Result<Option<i32>> {
+		let Some(campaign) = self.campaign() else {
+			return Ok(None);
+		};
+		match xml::child(campaign, "Wallet") {
+			Some(wallet) => xml::parse_attribute(wallet, "money"),
+			None => xml::parse_attribute(campaign, "money"),
+		}
+	}
+
+	/// Sets the amount of money in the shared campaign bank.
+	pub fn set_money(&mut self, money: i32) -> Result<()> {
+		let campaign = self.campaign_mut()?;
+		match xml::child_mut(campaign, "Wallet") {
+			Some(wallet) => xml::set_attribute(wallet, "money", money.to_string()),
+			None => xml::set_attribute(campaign, "money", money.to_string()),
+		}
+		Ok(())
+	}
+
+	/// Returns the number of characters in the crew.
+	pub fn crew_count(&self) -> usize {
+		self.campaign()
+			.and_then(|c| xml::child(c, "crew"))
+			.map_or(0, |crew| xml::children(crew, "character").count())
+	}
+
+	/// Returns the characters in the crew.
+	///
+	/// In multiplayer campaigns this contains only the bots; the players’ characters are kept in
+	/// the separate character data member.
+	pub fn crew(&self) -> Result<Vec<Character>> {
+		let crew = self.campaign().and_then(|c| xml::child(c, "crew"));
+		crew.into_iter()
+			.flat_map(|i| xml::children(i, "character"))
+			.map(Character::from_xml)
+			.collect()
+	}
+
+	/// Returns the crew’s pets.
+	///
+	/// Pets are kept separately from the crew, so crew operations never affect them.
+	pub fn pets(&self) -> Result<Vec<Pet>> {
+		let pets = self.campaign().and_then(|c| xml::child(c, "pets"));
+		pets.into_iter()
+			.flat_map(|i| xml::children(i, "pet"))
+			.map(Pet::from_xml)
+			.collect()
+	}
+
+	/// Returns the characters in the crew for modification.
+	pub fn crew_mut(&mut self) -> Vec<CharacterEntry<'_>> {
+		let crew = self
+			.campaign_mut()
+			.ok()
+			.and_then(|c| xml::child_mut(c, "crew"));
+		crew.into_iter()
+			.flat_map(|i| xml::children_mut(i, "character"))
+			.map(CharacterEntry::new)
+			.collect()
+	}
+
+	/// Adds a character to the crew.
+	///
+	/// `character` must be a `Character` element.
+	pub fn add_crew_member(&mut self, character: Element) -> Result<()> {
+		let campaign = self.campaign_mut()?;
+		if xml::child(campaign, "crew").is_none() {
+			campaign
+				.children
+				.push(XMLNode::Element(Element::new("crew")));
+		}
+		let crew = xml::child_mut(campaign, "crew").unwrap();
+		crew.children.push(XMLNode::Element(character));
+		Ok(())
+	}
+
+	/// Removes a character from the crew.
+	///
+	/// Returns `false` if there is no crew member with the given name.
+	pub fn remove_crew_member(&mut self, name: &str) -> bool {
+		let Some(crew) = self
+			.campaign_mut()
+			.ok()
+			.and_then(|c| xml::child_mut(c, "crew"))
+		else {
+			return false;
+		};
+		let before = crew.children.len();
+		crew.children.retain(|i| {
+			!i.as_element().is_some_and(|i| {
+				xml::is_named(i, "character") && xml::attribute(i, "name") == Some(name)
+			})
+		});
+		crew.children.len() != before
+	}
+
+	/// Removes the crew members recorded as dead, returning them.
+	pub fn remove_dead_crew(&mut self) -> Result<Vec<Character>> {
+		let Some(crew) = self
+			.campaign_mut()
+			.ok()
+			.and_then(|c| xml::child_mut(c, "crew"))
+		else {
+			return Ok(Vec::new());
+		};
+		let dead = xml::children(crew, "character")
+			.filter(|i| character::is_dead(i))
+			.map(Character::from_xml)
+			.collect::<Result<Vec<_>>>()?;
+		crew.children.retain(|i| {
+			!i.as_element()
+				.is_some_and(|i| xml::is_named(i, "character") && character::is_dead(i))
+		});
+		Ok(dead)
+	}
+
+	/// Clears the owner of every pet owned by one of the given characters, returning the number
+	/// of pets changed.
+	///
+	/// The pets themselves are kept; the game treats a pet with no owner as belonging to the crew.
+	pub fn disown_pets(&mut self, owners: &[u16]) -> Result<usize> {
+		let Some(pets) = self
+			.campaign_mut()
+			.ok()
+			.and_then(|c| xml::child_mut(c, "pets"))
+		else {
+			return Ok(0);
+		};
+		let mut changed = 0;
+		for pet in xml::children_mut(pets, "pet") {
+			if let Some(owner) = xml::parse_attribute::<u16>(pet, "ownerid")? {
+				if owners.contains(&owner) {
+					xml::remove_attribute(pet, "ownerid");
+					changed += 1;
+				}
+			}
+		}
+		Ok(changed)
+	}
+
+	/// Returns the largest entity ID of any item in the game session.
+	pub fn max_item_id(&self) -> Result<u16> {
+		xml::max_item_id(&self.root)
+	}
+
+	/// Returns the identifiers of the missions the crew has completed.
+	pub fn completed_missions(&self) -> Vec<&str> {
+		self.campaign()
+			.and_then(|c| xml::child(c, "CompletedMissions"))
+			.into_iter()
+			.flat_map(|i| xml::children(i, "mission"))
+			.filter_map(|i| xml::attribute(i, "identifier"))
+			.collect()
+	}
+
+	/// Returns the submarines the crew owns.
+	pub fn owned_submarines(&self) -> Vec<OwnedSubmarine> {
+		xml::child(&self.root, "ownedsubmarines")
+			.into_iter()
+			.flat_map(|i| xml::children(i, "sub"))
+			.filter_map(|i| xml::attribute(i, "name"))
+			.map(|name| OwnedSubmarine {
+				name: name.to_owned(),
+			})
+			.collect()
+	}
+
+	/// Adds a submarine to the list of owned submarines.
+	///
+	/// Returns `false` if the submarine was already owned.
+	pub fn add_owned_submarine(&mut self, name: &str) -> bool {
+		if self.owned_submarines().iter().any(|i| i.name == name) {
+			return false;
+		}
+		if xml::child(&self.root, "ownedsubmarines").is_none() {
+			self.root
+				.children
+				.insert(0, XMLNode::Element(Element::new("ownedsubmarines")));
+		}
+		let list = xml::child_mut(&mut self.root, "ownedsubmarines").unwrap();
+		let mut sub = Element::new("sub");
+		xml::set_attribute(&mut sub, "name", name.to_owned());
+		list.children.push(XMLNode::Element(sub));
+		true
+	}
+
+	/// Removes a submarine from the list of owned submarines.
+	///
+	/// Returns `false` if the submarine was not owned.
+	pub fn remove_owned_submarine(&mut self, name: &str) -> bool {
+		let Some(list) = xml::child_mut(&mut self.root, "ownedsubmarines") else {
+			return false;
+		};
+		let before = list.children.len();
+		list.children.retain(|i| {
+			!i.as_element()
+				.is_some_and(|i| xml::is_named(i, "sub") && xml::attribute(i, "name") == Some(name))
+		});
+		list.children.len() != before
+	}
+
+	/// Renames an owned submarine, updating the active submarine if it is the one renamed.
+	///
+	/// Returns `false` if the submarine was not owned.
+	pub fn rename_owned_submarine(&mut self, old: &str, new: &str) -> bool {
+		let Some(sub) = xml::child_mut(&mut self.root, "ownedsubmarines").and_then(|list| {
+			xml::children_mut(list, "sub").find(|i| xml::attribute(i, "name") == Some(old))
+		}) else {
+			return false;
+		};
+		xml::set_attribute(sub, "name", new.to_owned());
+		if self.submarine() == Some(old) {
+			self.set_submarine(new);
+		}
+		true
+	}
+
+	/// Returns the state of the campaign map.
+	pub fn map(&self) -> Result<Option<MapState>> {
+		let Some(map) = self.campaign().and_then(|c| xml::child(c, "map")) else {
+			return Ok(None);
+		};
+		let mut locations = Vec::new();
+		for (position, location) in xml::children(map, "location").enumerate() {
+			locations.push(Location {
+				index: xml::parse_attribute(location, "i")?.unwrap_or(position),
+				name: xml::attribute(location, "name")
+					.unwrap_or_default()
+					.to_owned(),
+				kind: xml::attribute(location, "type")
+					.unwrap_or_default()
+					.to_owned(),
+				discovered: xml::bool_attribute(location, "discovered")?.unwrap_or(false),
+				reputation: xml::parse_attribute(location, "reputation")?,
+			});
+		}
+		let mut connections = Vec::new();
+		for connection in xml::children(map, "connection") {
+			let ends = xml::attribute(connection, "locations").unwrap_or_default();
+			let ends = match ends.split_once(',') {
+				Some((a, b)) => (a.trim().parse(), b.trim().parse()),
+				None => continue,
+			};
+			let ends = match ends {
+				(Ok(a), Ok(b)) => (a, b),
+				_ => {
+					return Err(diagnostic::invalid(
+						"Invalid locations attribute on map connection".to_owned(),
+					))
+				}
+			};
+			connections.push(Connection {
+				locations: ends,
+				passed: xml::bool_attribute(connection, "passed")?.unwrap_or(false),
+				biome: xml::attribute(connection, "biome").map(str::to_owned),
+			});
+		}
+		Ok(Some(MapState {
+			seed: xml::attribute(map, "seed").map(str::to_owned),
+			current_location: xml::parse_attribute(map, "currentlocation")?,
+			selected_location: xml::parse_attribute(map, "selectedlocation")?,
+			locations,
+			connections,
+		}))
+	}
+
+	/// Returns the missions offered at, or accepted from, every location.
+	pub fn missions(&self) -> Result<Vec<Mission>> {
+		let map = self.campaign().and_then(|c| xml::child(c, "map"));
+		let mut ret = Vec::new();
+		for location in map.into_iter().flat_map(|i| xml::children(i, "location")) {
+			let name = xml::attribute(location, "name").unwrap_or_default();
+			for (list, selected) in [("AvailableMissions", false), ("SelectedMissions", true)] {
+				let list = xml::child(location, list);
+				for mission in list.into_iter().flat_map(|i| xml::children(i, "Mission")) {
+					ret.push(Mission {
+						identifier: xml::attribute(mission, "identifier")
+							.unwrap_or_default()
+							.to_owned(),
+						location: name.to_owned(),
+						selected,
+						destination: xml::parse_attribute(mission, "destinationindex")?,
+					});
+				}
+			}
+		}
+		Ok(ret)
+	}
+
+	/// Removes every mission, offered or accepted, with a given identifier.
+	///
+	/// Returns the number of missions removed.
+	pub fn remove_missions(&mut self, identifier: &str) -> Result<usize> {
+		let mut removed = 0;
+		for location in xml::children_mut(self.map_mut()?, "location") {
+			for list in xml::elements_mut(location) {
+				if xml::is_named(list, "AvailableMissions")
+					|| xml::is_named(list, "SelectedMissions")
+				{
+					let before = list.children.len();
+					list.children.retain(|i| {
+						!i.as_element().is_some_and(|i| {
+							xml::is_named(i, "Mission")
+								&& xml::attribute(i, "identifier") == Some(identifier)
+						})
+					});
+					removed += before - list.children.len();
+				}
+			}
+		}
+		Ok(removed)
+	}
+
+	/// Finds the element holding the chosen level, returning the path to it from the map element
+	/// as a list of child element positions.
+	fn level_path(&self, choice: LevelChoice) -> Result<Option<Vec<usize>>> {
+		let Some(map) = self.campaign().and_then(|c| xml::child(c, "map")) else {
+			return Ok(None);
+		};
+		let Some(current) = xml::parse_attribute::<usize>(map, "currentlocation")? else {
+			return Ok(None);
+		};
+		let selected: Option<usize> = xml::parse_attribute(map, "selectedlocation")?;
+		let mut location_position = 0;
+		for (position, element) in xml::elements(map).enumerate() {
+			let matches = if xml::is_named(element, "location") {
+				let index = xml::parse_attribute(element, "i")?.unwrap_or(location_position);
+				location_position += 1;
+				choice == LevelChoice::Location && index == current
+			} else if xml::is_named(element, "connection") {
+				let ends = xml::attribute(element, "locations").unwrap_or_default();
+				let ends: Vec<usize> = ends
+					.split(',')
+					.filter_map(|i| i.trim().parse().ok())
+					.collect();
+				choice == LevelChoice::Next
+					&& selected.is_some_and(|selected| {
+						ends == [current, selected] || ends == [selected, current]
+					})
+			} else {
+				false
+			};
+			if matches {
+				return Ok(xml::elements(element)
+					.position(|i| xml::is_named(i, "Level"))
+					.map(|level| vec![position, level]));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Returns the parameters of a level on the campaign map.
+	pub fn level(&self, choice: LevelChoice) -> Result<Option<LevelData>> {
+		let Some(path) = self.level_path(choice)? else {
+			return Ok(None);
+		};
+		let mut element = self.campaign().and_then(|c| xml::child(c, "map")).unwrap();
+		for i in path {
+			element = xml::elements(element).nth(i).unwrap();
+		}
+		LevelData::from_xml(element).map(Some)
+	}
+
+	/// Sets the seed of a level on the campaign map.
+	///
+	/// Returns `false` if there is no such level.
+	pub fn set_level_seed(&mut self, choice: LevelChoice, seed: &str) -> Result<bool> {
+		let Some(path) = self.level_path(choice)? else {
+			return Ok(false);
+		};
+		let mut element = self.map_mut()?;
+		for i in path {
+			element = xml::elements_mut(element).nth(i).unwrap();
+		}
+		xml::set_attribute(element, "seed", seed.to_owned());
+		Ok(true)
+	}
+
+	/// Finds the position, among the map’s `location` elements, of the crew’s current location.
+	fn current_location_position(map: &Element) -> Result<Option<usize>> {
+		let Some(current) = xml::parse_attribute::<usize>(map, "currentlocation")? else {
+			return Ok(None);
+		};
+		for (position, location) in xml::children(map, "location").enumerate() {
+			if xml::parse_attribute(location, "i")?.unwrap_or(position) == current {
+				return Ok(Some(position));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Returns the element for the crew’s current location, mutably.
+	fn current_location_mut(&mut self) -> Result<&mut Element> {
+		let map = self.map_mut()?;
+		let position = Self::current_location_position(map)?.ok_or_else(|| {
+			diagnostic::invalid("Campaign map has no current location".to_owned())
+		})?;
+		Ok(xml::children_mut(map, "location").nth(position).unwrap())
+	}
+
+	/// Returns the characters available for hire at the crew’s current location.
+	pub fn hireables(&self) -> Result<Vec<Character>> {
+		let Some(map) = self.campaign().and_then(|c| xml::child(c, "map")) else {
+			return Ok(Vec::new());
+		};
+		let Some(position) = Self::current_location_position(map)? else {
+			return Ok(Vec::new());
+		};
+		let location = xml::children(map, "location").nth(position).unwrap();
+		xml::child(location, "hireables")
+			.into_iter()
+			.flat_map(|i| xml::children(i, "Character"))
+			.map(Character::from_xml)
+			.collect()
+	}
+
+	/// Adds a character to those available for hire at the crew’s current location.
+	pub fn add_hireable(&mut self, character: Element) -> Result<()> {
+		let location = self.current_location_mut()?;
+		if xml::child(location, "hireables").is_none() {
+			location
+				.children
+				.push(XMLNode::Element(Element::new("hireables")));
+		}
+		let hireables = xml::child_mut(location, "hireables").unwrap();
+		hireables.children.push(XMLNode::Element(character));
+		Ok(())
+	}
+
+	/// Removes a character from those available for hire at the crew’s current location.
+	///
+	/// Returns `false` if there is no such character.
+	pub fn remove_hireable(&mut self, name: &str) -> Result<bool> {
+		let Some(hireables) = xml::child_mut(self.current_location_mut()?, "hireables") else {
+			return Ok(false);
+		};
+		let before = hireables.children.len();
+		hireables.children.retain(|i| {
+			!i.as_element().is_some_and(|i| {
+				xml::is_named(i, "Character") && xml::attribute(i, "name") == Some(name)
+			})
+		});
+		Ok(hireables.children.len() != before)
+	}
+
+	/// Returns the campaign map element, mutably.
+	fn map_mut(&mut self) -> Result<&mut Element> {
+		xml::child_mut(self.campaign_mut()?, "map")
+			.ok_or_else(|| diagnostic::invalid("Campaign does not contain a map".to_owned()))
+	}
+
+	/// Marks locations as discovered or undiscovered.
+	///
+	/// Every location for which `filter` returns `true` when called with its index is changed.
+	/// When locations are hidden, connections leading to them are also marked as not passed.
+	/// Returns the number of locations changed.
+	pub fn set_discovered<F: Fn(usize) -> bool>(
+		&mut self,
+		discovered: bool,
+		filter: F,
+	) -> Result<usize> {
+		let map = self.map_mut()?;
+		let mut changed = HashSet::new();
+		for (position, location) in xml::children_mut(map, "location").enumerate() {
+			let index = xml::parse_attribute(location, "i")?.unwrap_or(position);
+			if filter(index) && xml::bool_attribute(location, "discovered")? != Some(discovered) {
+				xml::set_attribute(location, "discovered", discovered.to_string());
+				changed.insert(index);
+			}
+		}
+		if !discovered {
+			for connection in xml::children_mut(map, "connection") {
+				let touches_hidden = xml::attribute(connection, "locations")
+					.unwrap_or_default()
+					.split(',')
+					.filter_map(|i| i.trim().parse().ok())
+					.any(|i: usize| changed.contains(&i));
+				if touches_hidden {
+					xml::set_attribute(connection, "passed", "false".to_owned());
+				}
+			}
+		}
+		Ok(changed.len())
+	}
+
+	/// Sets the crew’s reputation with a location.
+	///
+	/// Returns `false` if there is no location with the given name.
+	pub fn set_location_reputation(&mut self, name: &str, reputation: f32) -> Result<bool> {
+		let Some(location) = xml::children_mut(self.map_mut()?, "location")
+			.find(|i| xml::attribute(i, "name") == Some(name))
+		else {
+			return Ok(false);
+		};
+		xml::set_attribute(location, "reputation", xml::format_f32(reputation));
+		Ok(true)
+	}
+
+	/// Returns the factions and the crew’s reputation with each.
+	pub fn factions(&self) -> Result<Vec<Faction>> {
+		let factions = self.campaign().and_then(|c| xml::child(c, "Factions"));
+		let mut ret = Vec::new();
+		for faction in factions
+			.into_iter()
+			.flat_map(|i| xml::children(i, "Faction"))
+		{
+			ret.push(Faction {
+				identifier: xml::attribute(faction, "identifier")
+					.unwrap_or_default()
+					.to_owned(),
+				reputation: xml::parse_attribute(faction, "reputation")?.unwrap_or(0.0),
+			});
+		}
+		Ok(ret)
+	}
+
+	/// Sets the crew’s reputation with a faction.
+	///
+	/// Returns `false` if there is no faction with the given identifier.
+	pub fn set_faction_reputation(&mut self, identifier: &str, reputation: f32) -> Result<bool> {
+		let Some(faction) = xml::child_mut(self.campaign_mut()?, "Factions").and_then(|i| {
+			xml::children_mut(i, "Faction")
+				.find(|i| xml::attribute(i, "identifier") == Some(identifier))
+		}) else {
+			return Ok(false);
+		};
+		xml::set_attribute(faction, "reputation", xml::format_f32(reputation));
+		Ok(true)
+	}
+
+	/// Returns the items that have been bought but not yet delivered.
+	pub fn purchased_items(&self) -> Result<Vec<PurchasedItem>> {
+		let mut ret = Vec::new();
+		let cargo = self.campaign().and_then(|c| xml::child(c, "cargo"));
+		for item in cargo.into_iter().flat_map(|i| xml::children(i, "item")) {
+			ret.push(PurchasedItem {
+				identifier: xml::attribute(item, "id").unwrap_or_default().to_owned(),
+				quantity: xml::parse_attribute(item, "qty")?.unwrap_or(1),
+			});
+		}
+		Ok(ret)
+	}
+
+	/// Sets the number of a kind of item that has been bought but not yet delivered.
+	///
+	/// If `quantity` is zero, the item is removed from the order; if the item is not already on
+	/// order, it is added.
+	pub fn set_purchased_quantity(&mut self, identifier: &str, quantity: u32) -> Result<()> {
+		let campaign = self.campaign_mut()?;
+		if xml::child(campaign, "cargo").is_none() {
+			campaign
+				.children
+				.push(XMLNode::Element(Element::new("cargo")));
+		}
+		let cargo = xml::child_mut(campaign, "cargo").unwrap();
+		if quantity == 0 {
+			cargo.children.retain(|i| {
+				!i.as_element().is_some_and(|i| {
+					xml::is_named(i, "item") && xml::attribute(i, "id") == Some(identifier)
+				})
+			});
+			return Ok(());
+		}
+		let existing =
+			xml::children_mut(cargo, "item").find(|i| xml::attribute(i, "id") == Some(identifier));
+		if let Some(item) = existing {
+			xml::set_attribute(item, "qty", quantity.to_string());
+		} else {
+			let mut item = Element::new("item");
+			xml::set_attribute(&mut item, "id", identifier.to_owned());
+			xml::set_attribute(&mut item, "qty", quantity.to_string());
+			cargo.children.push(XMLNode::Element(item));
+		}
+		Ok(())
+	}
+
+	/// Cancels the delivery of every item that has been bought but not yet delivered.
+	///
+	/// Returns the number of kinds of item removed.
+	pub fn clear_purchased_items(&mut self) -> Result<usize> {
+		let Some(cargo) = xml::child_mut(self.campaign_mut()?, "cargo") else {
+			return Ok(0);
+		};
+		let before = cargo.children.len();
+		cargo
+			.children
+			.retain(|i| !i.as_element().is_some_and(|i| xml::is_named(i, "item")));
+		Ok(before - cargo.children.len())
+	}
+
+	/// Returns the submarine upgrades that have been bought but not yet installed.
+	pub fn pending_upgrades(&self) -> Result<Vec<PendingUpgrade>> {
+		let pending = self
+			.campaign()
+			.and_then(|c| xml::child(c, "upgrademanager"))
+			.and_then(|i| xml::child(i, "PendingUpgrades"));
+		let mut ret = Vec::new();
+		for upgrade in pending
+			.into_iter()
+			.flat_map(|i| xml::children(i, "PendingUpgrade"))
+		{
+			ret.push(PendingUpgrade {
+				prefab: xml::attribute(upgrade, "upgrade")
+					.unwrap_or_default()
+					.to_owned(),
+				category: xml::attribute(upgrade, "category")
+					.unwrap_or_default()
+					.to_owned(),
+				level: xml::parse_attribute(upgrade, "level")?.unwrap_or(0),
+			});
+		}
+		Ok(ret)
+	}
+
+	/// Cancels the installation of a bought upgrade.
+	///
+	/// Returns the number of pending upgrades removed.
+	pub fn remove_pending_upgrades(&mut self, prefab: &str) -> Result<usize> {
+		let Some(pending) = xml::child_mut(self.campaign_mut()?, "upgrademanager")
+			.and_then(|i| xml::child_mut(i, "PendingUpgrades"))
+		else {
+			return Ok(0);
+		};
+		let before = pending.children.len();
+		pending.children.retain(|i| {
+			!i.as_element().is_some_and(|i| {
+				xml::is_named(i, "PendingUpgrade") && xml::attribute(i, "upgrade") == Some(prefab)
+			})
+		});
+		Ok(before - pending.children.len())
+	}
+
+	/// Returns the campaign’s progress counters.
+	pub fn progress(&self) -> Result<Progress> {
+		let Some(campaign) = self.campaign() else {
+			return Ok(Progress::default());
+		};
+		Ok(Progress {
+			play_time: xml::parse_attribute(campaign, "totalplaytime")?,
+			passed_levels: xml::parse_attribute(campaign, "totalpassedlevels")?,
+		})
+	}
+
+	/// Sets the campaign’s progress counters.
+	///
+	/// Counters that are `None` in `progress` are left unchanged.
+	pub fn set_progress(&mut self, progress: Progress) -> Result<()> {
+		let campaign = self.campaign_mut()?;
+		if let Some(play_time) = progress.play_time {
+			xml::set_attribute(campaign, "totalplaytime", xml::format_f64(play_time));
+		}
+		if let Some(passed_levels) = progress.passed_levels {
+			xml::set_attribute(campaign, "totalpassedlevels", passed_levels.to_string());
+		}
+		Ok(())
+	}
+
+	/// Returns the campaign settings.
+	pub fn settings(&self) -> Vec<CampaignSetting> {
+		self.campaign()
+			.and_then(|c| xml::child(c, "CampaignSettings"))
+			.into_iter()
+			.flat_map(|i| i.attributes.iter())
+			.map(|(name, value)| CampaignSetting {
+				name: name.clone(),
+				value: value.clone(),
+			})
+			.collect()
+	}
+
+	/// Changes a campaign setting.
+	///
+	/// Only settings already present in the save can be changed, so that a misspelled name is not
+	/// silently written into the file. Returns `false` if there is no such setting.
+	pub fn set_setting(&mut self, name: &str, value: &str) -> Result<bool> {
+		let Some(settings) = xml::child_mut(self.campaign_mut()?, "CampaignSettings") else {
+			return Ok(false);
+		};
+		if xml::attribute(settings, name).is_none() {
+			return Ok(false);
+		}
+		xml::set_attribute(settings, name, value.to_owned());
+		Ok(true)
+	}
+
+	/// Returns the campaign metadata.
+	pub fn metadata(&self) -> Vec<MetadataEntry> {
+		self.campaign()
+			.and_then(|c| xml::child(c, "Metadata"))
+			.into_iter()
+			.flat_map(|i| xml::children(i, "Data"))
+			.map(|i| MetadataEntry {
+				key: xml::attribute(i, "key").unwrap_or_default().to_owned(),
+				value: xml::attribute(i, "value").unwrap_or_default().to_owned(),
+				kind: xml::attribute(i, "type").map(str::to_owned),
+			})
+			.collect()
+	}
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for GameSession {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		element::serialize(&self.root, serializer)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for GameSession {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+		element::deserialize(deserializer, "Gamesession").map(|root| Self { root })
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<Gamesession version="1.0.21.0" submarine="Humpback">
+  <ownedsubmarines>
+    <sub name="Humpback" />
+    <sub name="Orca" />
+  </ownedsubmarines>
+  <SinglePlayerCampaign>
+    <Wallet money="8150" />
+    <crew>
+      <character name="Alice" />
+      <character name="Bob" />
+    </crew>
+    <CompletedMissions>
+      <mission identifier="salvagecargo" />
+    </CompletedMissions>
+    <map seed="abcd" currentlocation="1" selectedlocation="0">
+      <location i="0" type="Outpost" name="Alpha" discovered="true" />
+      <location i="1" type="City" name="Beta" discovered="True" />
+      <connection passed="true" locations="0,1" biome="coldcaverns" />
+    </map>
+    <cargo>
+      <item id="oxygentank" qty="3" />
+    </cargo>
+    <Metadata>
+      <Data key="tutorial" value="true" type="bool" />
+    </Metadata>
+  </SinglePlayerCampaign>
+</Gamesession>"#;
+
+	/// A campaign whose map has three locations in a row, with the crew at the middle one.
+	const MAP: &str = r#"<Gamesession>
+  <MultiPlayerCampaign money="100">
+    <map currentlocation="1" selectedlocation="2">
+      <location i="0" name="Alpha" discovered="true">
+        <Level seed="alpha" />
+      </location>
+      <location i="1" name="Beta" discovered="true" reputation="10">
+        <Level seed="beta" difficulty="20" />
+        <AvailableMissions>
+          <Mission identifier="salvagecargo" />
+          <Mission identifier="killmoloch" destinationindex="2" />
+        </AvailableMissions>
+        <SelectedMissions>
+          <Mission identifier="salvagecargo" />
+        </SelectedMissions>
+      </location>
+      <location i="2" name="Gamma" discovered="false" />
+      <connection passed="true" locations="0,1">
+        <Level seed="alphabeta" />
+      </connection>
+      <connection passed="true" locations="1,2">
+        <Level seed="betagamma" />
+      </connection>
+    </map>
+    <Factions>
+      <Faction identifier="coalition" reputation="5" />
+      <Faction identifier="separatists" reputation="-5" />
+    </Factions>
+  </MultiPlayerCampaign>
+</Gamesession>"#;
+
+	#[test]
+	fn test_parse() {
+		let session = GameSession::parse(SAMPLE.as_bytes()).unwrap();
+		assert_eq!(session.version(), Some("1.0.21.0"));
+		assert_eq!(session.submarine(), Some("Humpback"));
+		assert_eq!(session.game_mode(), Some(GameMode::SinglePlayer));
+		assert_eq!(session.money().unwrap(), Some(8150));
+		assert_eq!(session.crew_count(), 2);
+		assert_eq!(session.completed_missions(), vec!["salvagecargo"]);
+		assert_eq!(session.owned_submarines().len(), 2);
+		let map = session.map().unwrap().unwrap();
+		assert_eq!(map.current().unwrap().name, "Beta");
+		assert_eq!(map.connections[0].locations, (0, 1));
+		assert_eq!(
+			session.purchased_items().unwrap(),
+			vec![PurchasedItem {
+				identifier: "oxygentank".to_owned(),
+				quantity: 3
+			}]
+		);
+		assert_eq!(session.metadata()[0].key, "tutorial");
+	}
+
+	#[test]
+	fn test_campaign_name() {
+		// The last eight bytes of this name start part way through a character.
+		let session = GameSession::parse("<Gamesession><X€€€ /></Gamesession>".as_bytes()).unwrap();
+		assert_eq!(session.game_mode(), None);
+		let session =
+			GameSession::parse("<Gamesession><ÉditeurCampaign /></Gamesession>".as_bytes())
+				.unwrap();
+		assert_eq!(
+			session.game_mode(),
+			Some(GameMode::Other("ÉditeurCampaign".to_owned()))
+		);
+	}
+
+	#[test]
+	fn test_set_money() {
+		let mut session = GameSession::parse(SAMPLE.as_bytes()).unwrap();
+		session.set_money(-20).unwrap();
+		assert_eq!(session.money().unwrap(), Some(-20));
+		assert_eq!(xml::attribute(session.campaign().unwrap(), "money"), None);
+
+		// Saves from before version 1.0 keep the money on the campaign element.
+		let mut session = GameSession::parse(MAP.as_bytes()).unwrap();
+		assert_eq!(session.money().unwrap(), Some(100));
+		session.set_money(250).unwrap();
+		assert_eq!(session.money().unwrap(), Some(250));
+		assert!(xml::child(session.campaign().unwrap(), "Wallet").is_none());
+
+		let mut session = GameSession::parse("<Gamesession />".as_bytes()).unwrap();
+		assert_eq!(session.money().unwrap(), None);
+		assert!(session.set_money(1).is_err());
+	}
+
+	#[test]
+	fn test_discovered() {
+		let mut session = GameSession::parse(MAP.as_bytes()).unwrap();
+		let map = session.map().unwrap().unwrap();
+		assert_eq!(map.within(0), HashSet::from([1]));
+		assert_eq!(map.within(1), HashSet::from([0, 1, 2]));
+		assert_eq!(map.within(5), HashSet::from([0, 1, 2]));
+		let mut nowhere = map.clone();
+		nowhere.current_location = None;
+		assert!(nowhere.within(5).is_empty());
+
+		// Only locations whose state changes are counted.
+		assert_eq!(session.set_discovered(true, |_| true).unwrap(), 1);
+		assert_eq!(session.set_discovered(true, |_| true).unwrap(), 0);
+		assert!(session.map().unwrap().unwrap().locations[2].discovered);
+
+		// Hiding a location also unmarks the connections leading to it.
+		assert_eq!(session.set_discovered(false, |i| i == 0).unwrap(), 1);
+		let map = session.map().unwrap().unwrap();
+		assert!(!map.locations[0].discovered);
+		assert!(map.locations[1].discovered);
+		assert!(!map.connections[0].passed);
+		assert!(map.connections[1].passed);
+	}
+
+	#[test]
+	fn test_remove_missions() {
+		let mut session = GameSession::parse(MAP.as_bytes()).unwrap();
+		assert_eq!(session.missions().unwrap().len(), 3);
+		assert_eq!(session.remove_missions("salvagecargo").unwrap(), 2);
+		assert_eq!(session.remove_missions("salvagecargo").unwrap(), 0);
+		let missions = session.missions().unwrap();
+		assert_eq!(
+			missions,
+			[Mission {
+				identifier: "killmoloch".to_owned(),
+				location: "Beta".to_owned(),
+				selected: false,
+				destination: Some(2),
+			}]
+		);
+	}
+
+	#[test]
+	fn test_set_purchased_quantity() {
+		let mut session = GameSession::parse(SAMPLE.as_bytes()).unwrap();
+		session.set_purchased_quantity("oxygentank", 5).unwrap();
+		session.set_purchased_quantity("weldingfuel", 2).unwrap();
+		let quantities = |session: &GameSession| {
+			session
+				.purchased_items()
+				.unwrap()
+				.into_iter()
+				.map(|i| (i.identifier, i.quantity))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(
+			quantities(&session),
+			[("oxygentank".to_owned(), 5), ("weldingfuel".to_owned(), 2)]
+		);
+		session.set_purchased_quantity("oxygentank", 0).unwrap();
+		assert_eq!(quantities(&session), [("weldingfuel".to_owned(), 2)]);
+
+		// A campaign with nothing on order yet gets a cargo element.
+		let mut session = GameSession::parse(MAP.as_bytes()).unwrap();
+		assert!(session.purchased_items().unwrap().is_empty());
+		session.set_purchased_quantity("oxygentank", 1).unwrap();
+		assert_eq!(quantities(&session), [("oxygentank".to_owned(), 1)]);
+		assert_eq!(session.clear_purchased_items().unwrap(), 1);
+		assert!(session.purchased_items().unwrap().is_empty());
+	}
+
+	#[test]
+	fn test_set_reputation() {
+		let mut session = GameSession::parse(MAP.as_bytes()).unwrap();
+		assert!(session.set_faction_reputation("separatists", 42.5).unwrap());
+		assert!(!session.set_faction_reputation("husks", 1.0).unwrap());
+		assert_eq!(
+			session.factions().unwrap(),
+			[
+				Faction {
+					identifier: "coalition".to_owned(),
+					reputation: 5.0,
+				},
+				Faction {
+					identifier: "separatists".to_owned(),
+					reputation: 42.5,
+				},
+			]
+		);
+
+		assert!(session.set_location_reputation("Gamma", -3.0).unwrap());
+		assert!(!session.set_location_reputation("Delta", 1.0).unwrap());
+		let map = session.map().unwrap().unwrap();
+		assert_eq!(map.locations[1].reputation, Some(10.0));
+		assert_eq!(map.locations[2].reputation, Some(-3.0));
+
+		// A campaign without factions has none to change.
+		let mut session = GameSession::parse(SAMPLE.as_bytes()).unwrap();
+		assert!(!session.set_faction_reputation("coalition", 1.0).unwrap());
+	}
+
+	#[test]
+	fn test_set_level_seed() {
+		let mut session = GameSession::parse(MAP.as_bytes()).unwrap();
+		let level = session.level(LevelChoice::Location).unwrap().unwrap();
+		assert_eq!(level.seed, "beta");
+		assert_eq!(level.difficulty, Some(20.0));
+		assert_eq!(
+			session.level(LevelChoice::Next).unwrap().unwrap().seed,
+			"betagamma"
+		);
+
+		assert!(session
+			.set_level_seed(LevelChoice::Location, "newbeta")
+			.unwrap());
+		assert!(session
+			.set_level_seed(LevelChoice::Next, "newnext")
+			.unwrap());
+		let seed = |choice| session.level(choice).unwrap().unwrap().seed;
+		assert_eq!(seed(LevelChoice::Location), "newbeta");
+		assert_eq!(seed(LevelChoice::Next), "newnext");
+		let mut written = Vec::new();
+		session.write(&mut written).unwrap();
+		let written = String::from_utf8(written).unwrap();
+		assert!(written.contains(r#"seed="alphabeta""#));
+		assert!(written.contains(r#"seed="alpha""#));
+
+		// With no destination selected, there is no next level.
+		let mut session = GameSession::parse(SAMPLE.as_bytes()).unwrap();
+		assert!(xml::remove_attribute(
+			session.map_mut().unwrap(),
+			"selectedlocation"
+		));
+		assert!(!session.set_level_seed(LevelChoice::Next, "x").unwrap());
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let session = GameSession::parse(SAMPLE.as_bytes()).unwrap();
+		let mut written = Vec::new();
+		session.write(&mut written).unwrap();
+		let reparsed = GameSession::parse(&written[..]).unwrap();
+		assert_eq!(session.root, reparsed.root);
+	}
+
+	#[test]
+	fn test_remove_dead_crew() {
+		let mut session = GameSession::parse(
+			r#"<Gamesession>
+  <SinglePlayerCampaign>
+    <crew>
+      <character name="Alice" ID="7" causeofdeath="Pressure" />
+      <character name="Bob" ID="8" causeofdeath="" />
+      <character name="Carol" ID="9" dead="True" />
+    </crew>
+    <pets>
+      <pet speciesname="Peanut" ownerid="7" hunger="50" />
+      <pet speciesname="Orangeboy" ownerid="8" />
+    </pets>
+  </SinglePlayerCampaign>
+</Gamesession>"#
+				.as_bytes(),
+		)
+		.unwrap();
+		let dead = session.remove_dead_crew().unwrap();
+		assert_eq!(
+			dead.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(),
+			vec!["Alice", "Carol"]
+		);
+		assert_eq!(dead[0].cause_of_death.as_deref(), Some("Pressure"));
+		assert_eq!(session.crew().unwrap()[0].name, "Bob");
+		assert_eq!(session.disown_pets(&[7, 9]).unwrap(), 1);
+		let pets = session.pets().unwrap();
+		assert_eq!(pets[0].owner, None);
+		assert_eq!(pets[0].hunger, Some(50.0));
+		assert_eq!(pets[1].owner, Some(8));
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_serde_round_trip() {
+		let session = GameSession::parse(SAMPLE.as_bytes()).unwrap();
+		let json = serde_json::to_string(&session).unwrap();
+		let deserialized: GameSession = serde_json::from_str(&json).unwrap();
+		assert_eq!(session.root, deserialized.root);
+		assert!(serde_json::from_str::<GameSession>(r#"{"name":"Submarine"}"#).is_err());
+	}
+}
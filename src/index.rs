@@ -0,0 +1,362 @@
+//! Indexes of where the members of `.save` files start, for reading one member without
+//! decompressing those before it.
+//!
+//! A save file is a gzip stream, so reading one of its members usually means decompressing every
+//! member before it. A [segmented](crate::save::ArchiveWriter::segmented) save file, though, holds
+//! each member in a segment of its own, which can be decompressed alone. An [`Index`] records where
+//! each member’s segment starts, so that the member can be read by seeking straight to it. The
+//! members of any other save file, such as one written by the game, are all in one segment, so
+//! they can still be found through an index, but are read no faster.
+//!
+//! An index can be kept alongside its save file, as [`cached`] does, and is built again whenever
+//! the save file changes.
+//!
+//! ```
+//! use barotool::index::Index;
+//! use barotool::save::ArchiveWriter;
+//! use std::io::Cursor;
+//!
+//! # fn main() -> barotool::Result<()> {
+//! let mut writer = ArchiveWriter::new(Cursor::new(Vec::new()))?.segmented();
+//! writer.write_member("gamesession.xml", b"<Gamesession />")?;
+//! writer.write_member("Orca.sub", b"sub")?;
+//! let data = writer.finish()?.into_inner();
+//! let index = Index::build(Cursor::new(&data))?;
+//! let entry = index.find("Orca.sub").unwrap();
+//! let mut reader = entry.open(Cursor::new(&data))?;
+//! assert_eq!(entry.member(&mut reader)?.read_to_vec()?, b"sub");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::save::{self, ArchiveReader, Member};
+use crate::Result;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::convert::TryFrom;
+use std::ffi::OsStr;
+use std::fs::{File, Metadata};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// The suffix of the name of the file an index is kept in by [`cached`], after the name of its
+/// save file.
+pub const SUFFIX: &str = ".barotool-index";
+
+/// The bytes an index file starts with, including the version of its format.
+const MAGIC: [u8; 8] = *b"BTINDEX1";
+
+/// Where a member of a save file is, as recorded in an [`Index`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+	/// The member’s filename.
+	pub name: String,
+
+	/// The size of the member, in bytes.
+	pub size: usize,
+
+	/// The offset in the save file of the segment holding the member.
+	pub segment: u64,
+
+	/// The number of bytes that decompress from the segment before the member’s header.
+	pub skip: u64,
+}
+
+impl Entry {
+	/// Starts reading a save file at the member, which is the first that the returned reader
+	/// yields, and which [`member`](Self::member) then reads.
+	pub fn open<R: Read + Seek>(&self, mut source: R) -> Result<ArchiveReader<R>> {
+		source.seek(SeekFrom::Start(self.segment))?;
+		let mut reader = ArchiveReader::new(source)?;
+		reader.skip(self.skip)?;
+		Ok(reader)
+	}
+
+	/// Returns the member from a reader started at it by [`open`](Self::open).
+	///
+	/// If the reader holds some other member there, as when the save file has changed since it was
+	/// indexed, an error of kind [`std::io::ErrorKind::InvalidData`] is returned.
+	pub fn member<'file, R: Read>(
+		&self,
+		reader: &'file mut ArchiveReader<R>,
+	) -> Result<Member<'file, R>> {
+		match reader.next()? {
+			Some(member) if member.name() == self.name && member.size() == self.size => Ok(member),
+			_ => Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				format!(
+					"The index does not match the save file at member {}",
+					self.name
+				),
+			)
+			.into()),
+		}
+	}
+}
+
+/// Where each member of a save file is, in order.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Index {
+	/// The members.
+	pub entries: Vec<Entry>,
+}
+
+impl Index {
+	/// Indexes a save file, reading it from its start.
+	///
+	/// Only the header of each segment is read, until one is found that was not written by a
+	/// [segmented](crate::save::ArchiveWriter::segmented) writer, from which the rest of the save
+	/// file is decompressed.
+	pub fn build<R: Read + Seek>(mut source: R) -> Result<Self> {
+		let mut entries = Vec::new();
+		let mut offset = 0;
+		source.rewind()?;
+		loop {
+			let mut head = Vec::with_capacity(save::SEGMENT_HEAD_SIZE);
+			source
+				.by_ref()
+				// Cast is sound because u64 ≥ usize.
+				.take(save::SEGMENT_HEAD_SIZE as u64)
+				.read_to_end(&mut head)?;
+			if head.is_empty() {
+				break;
+			}
+			source.seek(SeekFrom::Start(offset))?;
+			if let Some(length) = save::segment_length(&head)? {
+				let mut reader = ArchiveReader::new(source.by_ref().take(length))?;
+				if let Some(member) = reader.next()? {
+					entries.push(Entry {
+						name: member.name().to_owned(),
+						size: member.size(),
+						segment: offset,
+						skip: 0,
+					});
+				}
+				offset += length;
+				source.seek(SeekFrom::Start(offset))?;
+			} else {
+				// The rest of the file can only be read from start to end.
+				let mut reader = ArchiveReader::new(source.by_ref())?;
+				let mut skip = 0;
+				while let Some(member) = reader.next()? {
+					entries.push(Entry {
+						name: member.name().to_owned(),
+						size: member.size(),
+						segment: offset,
+						skip,
+					});
+					// Casts are sound because u64 ≥ usize.
+					skip += 8 + 2 * member.raw_name().len() as u64 + member.size() as u64;
+				}
+				break;
+			}
+		}
+		Ok(Self { entries })
+	}
+
+	/// Returns the first member with the given name, if there is one.
+	pub fn find(&self, name: &str) -> Option<&Entry> {
+		self.entries.iter().find(|i| i.name == name)
+	}
+}
+
+/// The size and modification time of a save file, which an index kept for it is only used if they
+/// still match.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Stamp {
+	/// The size of the save file, in bytes.
+	size: u64,
+
+	/// The whole seconds of the modification time since the Unix epoch.
+	seconds: u64,
+
+	/// The nanoseconds of the modification time past the whole seconds.
+	nanoseconds: u32,
+}
+
+impl Stamp {
+	/// Returns the stamp of a save file, given its metadata.
+	fn new(metadata: &Metadata) -> Result<Self> {
+		let modified = metadata
+			.modified()?
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default();
+		Ok(Self {
+			size: metadata.len(),
+			seconds: modified.as_secs(),
+			nanoseconds: modified.subsec_nanos(),
+		})
+	}
+}
+
+/// Returns the path of the file an index of a save file is kept in by [`cached`].
+pub fn path(filename: &OsStr) -> PathBuf {
+	let mut path = filename.to_owned();
+	path.push(SUFFIX);
+	path.into()
+}
+
+/// Returns the index of a save file, from the file it is kept in alongside the save file if that is
+/// up to date, or else by building it and keeping it there for next time.
+///
+/// Failing to keep the index, as in a directory that cannot be written to, is only logged.
+pub fn cached(filename: &OsStr) -> Result<Index> {
+	let stamp = Stamp::new(&std::fs::metadata(filename)?)?;
+	let index_path = path(filename);
+	match read_file(&index_path) {
+		Ok((kept, index)) if kept == stamp => {
+			log::debug!("Using index {}", index_path.display());
+			return Ok(index);
+		}
+		Ok(_) => log::info!("Index {} is out of date", index_path.display()),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+		Err(e) => log::warn!("Cannot read index {}: {e}", index_path.display()),
+	}
+	log::info!("Indexing {}", Path::new(filename).display());
+	let index = Index::build(BufReader::new(File::open(filename)?))?;
+	if let Err(e) = write_file(&index_path, stamp, &index) {
+		log::warn!("Cannot write index {}: {e}", index_path.display());
+	}
+	Ok(index)
+}
+
+/// Reads an index file, returning the stamp of the save file it was built from and the index.
+fn read_file(path: &Path) -> Result<(Stamp, Index)> {
+	let invalid = || {
+		Error::from(std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			"Not a barotool index file",
+		))
+	};
+	let mut source = BufReader::new(File::open(path)?);
+	let mut magic = [0_u8; 8];
+	source.read_exact(&mut magic).map_err(|_| invalid())?;
+	if magic != MAGIC {
+		return Err(invalid());
+	}
+	let (stamp, index) = (|| -> std::io::Result<(Stamp, Index)> {
+		let stamp = Stamp {
+			size: source.read_u64::<LittleEndian>()?,
+			seconds: source.read_u64::<LittleEndian>()?,
+			nanoseconds: source.read_u32::<LittleEndian>()?,
+		};
+		let count = source.read_u32::<LittleEndian>()?;
+		let mut entries = Vec::new();
+		for _ in 0..count {
+			let segment = source.read_u64::<LittleEndian>()?;
+			let skip = source.read_u64::<LittleEndian>()?;
+			let name_bytes = u64::from(source.read_u32::<LittleEndian>()?) * 2;
+			let name = String::from_utf16(&save::read_name(&mut source, name_bytes)?)
+				.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+			// Cast is sound because usize ≥ 32.
+			let size = source.read_u32::<LittleEndian>()? as usize;
+			entries.push(Entry {
+				name,
+				size,
+				segment,
+				skip,
+			});
+		}
+		Ok((stamp, Index { entries }))
+	})()
+	.map_err(|_| invalid())?;
+	Ok((stamp, index))
+}
+
+/// Writes an index file for a save file with the given stamp.
+fn write_file(path: &Path, stamp: Stamp, index: &Index) -> Result<()> {
+	let count = u32::try_from(index.entries.len())
+		.map_err(|_| Error::TooLarge("Too many members to index".to_owned()))?;
+	let mut sink = BufWriter::new(File::create(path)?);
+	sink.write_all(&MAGIC)?;
+	sink.write_u64::<LittleEndian>(stamp.size)?;
+	sink.write_u64::<LittleEndian>(stamp.seconds)?;
+	sink.write_u32::<LittleEndian>(stamp.nanoseconds)?;
+	sink.write_u32::<LittleEndian>(count)?;
+	for entry in &index.entries {
+		let size = u32::try_from(entry.size)
+			.map_err(|_| Error::TooLarge(format!("{} is too large", entry.name)))?;
+		sink.write_u64::<LittleEndian>(entry.segment)?;
+		sink.write_u64::<LittleEndian>(entry.skip)?;
+		sink.write_all(&save::member_header(&entry.name, size)?)?;
+	}
+	sink.flush()?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::compression;
+	use crate::save::ArchiveWriter;
+	use std::io::Cursor;
+
+	#[test]
+	fn test_build() {
+		// Members in segments of their own, followed by members in one gzip member.
+		let mut writer = ArchiveWriter::new(Cursor::new(Vec::new()))
+			.unwrap()
+			.segmented();
+		writer.write_member("a", b"1").unwrap();
+		writer.write_member("b", b"22").unwrap();
+		let mut data = writer.finish().unwrap().into_inner();
+		let mut encoder = compression::encoder(Vec::new()).unwrap();
+		encoder
+			.write_all(&save::member_header("c", 3).unwrap())
+			.unwrap();
+		encoder.write_all(b"333").unwrap();
+		encoder
+			.write_all(&save::member_header("dd", 1).unwrap())
+			.unwrap();
+		encoder.write_all(b"4").unwrap();
+		data.extend(encoder.finish().unwrap());
+
+		let index = Index::build(Cursor::new(&data)).unwrap();
+		let names: Vec<&str> = index.entries.iter().map(|i| i.name.as_str()).collect();
+		assert_eq!(names, ["a", "b", "c", "dd"]);
+		assert_eq!(index.entries[0].segment, 0);
+		assert_eq!(index.entries[2].segment, index.entries[3].segment);
+		assert_eq!(index.entries[3].skip, 10 + 3);
+		for (name, contents) in [("a", &b"1"[..]), ("b", b"22"), ("c", b"333"), ("dd", b"4")] {
+			let entry = index.find(name).unwrap();
+			let mut reader = entry.open(Cursor::new(&data)).unwrap();
+			assert_eq!(
+				entry.member(&mut reader).unwrap().read_to_vec().unwrap(),
+				contents
+			);
+		}
+
+		// An index that does not match the save file is noticed.
+		let mut entry = index.find("b").unwrap().clone();
+		entry.name = "a".to_owned();
+		let mut reader = entry.open(Cursor::new(&data)).unwrap();
+		assert!(entry.member(&mut reader).is_err());
+	}
+
+	#[test]
+	fn test_cached() {
+		let dir = std::env::temp_dir().join(format!("barotool-test-index-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let filename = dir.join("test.save");
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		writer.write_member("a", b"1").unwrap();
+		std::fs::write(&filename, writer.finish().unwrap()).unwrap();
+		let index = cached(filename.as_os_str()).unwrap();
+		assert_eq!(index.entries.len(), 1);
+		let (stamp, kept) = read_file(&path(filename.as_os_str())).unwrap();
+		assert_eq!(kept, index);
+		assert_eq!(
+			stamp,
+			Stamp::new(&std::fs::metadata(&filename).unwrap()).unwrap()
+		);
+
+		// A save file that has changed is indexed again.
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		writer.write_member("a", b"1").unwrap();
+		writer.write_member("b", b"2").unwrap();
+		std::fs::write(&filename, writer.finish().unwrap()).unwrap();
+		assert_eq!(cached(filename.as_os_str()).unwrap().entries.len(), 2);
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
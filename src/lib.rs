@@ -0,0 +1,94 @@
+//! Reading and modifying [Barotrauma](https://barotraumagame.com/) save files and submarines.
+//!
+//! This library holds the file formats and game models behind the `barotool` command-line tool,
+//! so that other programs, such as server management tools, can work with saves without running
+//! it.
+//!
+//! A campaign is kept in a `.save` file, an archive read and written by the [`save`] module. Its
+//! `gamesession.xml` member is modelled by [`gamesession::GameSession`], each of its `.sub`
+//! members by [`submarine::Submarine`], and, in multiplayer campaigns, its `CharacterData.xml`
+//! member by [`characterdata::CharacterData`]. Errors are reported as [`Error`]s, which tell apart
+//! failures of the file system, corrupt compression, malformed XML, and documents that are not what
+//! was expected; those about the contents of a file carry a [`diagnostic::Diagnostic`] saying where
+//! the problem is. An [`Error`] converts to and from a [`std::io::Error`] without losing its class.
+//!
+//! Documents are parsed from any [`std::io::Read`] and written to any [`std::io::Write`], and only
+//! the functions that take a file name touch the file system. With default features turned off,
+//! the library depends only on pure-Rust crates, so it builds for `wasm32-unknown-unknown` and can
+//! inspect saves and submarines in a browser.
+//!
+//! With the `serde` feature, which is on by default, the models implement `serde::Serialize` and
+//! `serde::Deserialize`. A whole document, such as a [`submarine::Submarine`], is represented as a
+//! tree: each element is a structure with its `name`, its `attributes` in document order, and its
+//! `children`, which are elements, strings of text, or single-entry maps holding a `comment`,
+//! `cdata`, or `processing-instruction`. Deserializing checks the name of the root element but
+//! nothing else.
+//!
+//! ```no_run
+//! use barotool::gamesession::GameSession;
+//! use barotool::save;
+//! use std::ffi::OsStr;
+//!
+//! # fn main() -> barotool::Result<()> {
+//! let filename = OsStr::new("My Campaign.save");
+//! let data = save::read_member(filename, GameSession::MEMBER_NAME)?.expect("not a campaign");
+//! let mut session = GameSession::parse(&data[..])?;
+//! session.set_money(10_000)?;
+//! let mut data = Vec::new();
+//! session.write(&mut data)?;
+//! save::update(filename, &[(GameSession::MEMBER_NAME, Some(data))])?;
+//! # Ok(())
+//! # }
+//! ```
+#![forbid(unsafe_code)]
+#![warn(
+	// Turn on extra language lints.
+	future_incompatible,
+	missing_abi,
+	missing_docs,
+	nonstandard_style,
+	rust_2018_idioms,
+	// Disabled due to <https://github.com/rust-lang/rust/issues/69952>.
+	// single_use_lifetimes,
+	trivial_casts,
+	trivial_numeric_casts,
+	unused,
+	// Disabled because the dependencies of the command-line tool are not used by the library.
+	// unused_crate_dependencies,
+	unused_import_braces,
+	unused_lifetimes,
+	unused_qualifications,
+
+	// Turn on extra Rustdoc lints.
+	rustdoc::all,
+
+	// Turn on extra Clippy lints.
+	clippy::cargo,
+	clippy::pedantic,
+)]
+// Duplicate transitive dependency versions are outside this crate’s control.
+#![allow(clippy::multiple_crate_versions)]
+// Every fallible function fails with an Error, whose variant and message say why.
+#![allow(clippy::missing_errors_doc)]
+// The only panics are on broken internal invariants, such as an element just added not being found.
+#![allow(clippy::missing_panics_doc)]
+// Nearly every accessor would qualify, which would add noise for little benefit.
+#![allow(clippy::must_use_candidate)]
+
+#[cfg(feature = "async")]
+pub mod asyncsave;
+pub mod character;
+pub mod characterdata;
+pub mod compression;
+pub mod diagnostic;
+#[cfg(feature = "serde")]
+mod element;
+mod error;
+pub mod gamesession;
+pub mod index;
+pub mod limits;
+pub mod save;
+pub mod submarine;
+pub mod xml;
+
+pub use error::{Error, Result};
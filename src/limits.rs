@@ -0,0 +1,146 @@
+//! Limits on the resources spent reading a file, for handling files from untrusted sources.
+//!
+//! A small save file can decompress to gigabytes, declare members far larger than it holds, or
+//! nest elements deeply enough to overflow the stack of the XML parser, which recurses once per
+//! level. By default only the nesting depth is limited, to [`DEFAULT_MAX_DEPTH`], which no file
+//! written by Barotrauma comes near. A program reading files uploaded by strangers should set
+//! tighter limits with [`set_limits`] before reading any; a file that exceeds one fails to read
+//! with [`Error::Limit`], before the memory it asks for is allocated.
+//!
+//! ```
+//! use barotool::limits::{self, Limits};
+//!
+//! limits::set_limits(Limits {
+//!     max_members: Some(64),
+//!     max_member_size: Some(64 << 20),
+//!     max_total_size: Some(256 << 20),
+//!     ..Limits::default()
+//! });
+//! ```
+
+use crate::{Error, Result};
+use std::sync::RwLock;
+
+/// The greatest depth to which elements may be nested unless configured otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// Limits on what a file may contain, each of which is `None` for no limit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Limits {
+	/// The greatest number of members in a save file.
+	pub max_members: Option<usize>,
+
+	/// The greatest size, in bytes, of any one member of a save file, as declared in its header.
+	pub max_member_size: Option<usize>,
+
+	/// The greatest size, in bytes, of the decompressed contents of a save file or submarine.
+	pub max_total_size: Option<u64>,
+
+	/// The greatest depth to which the elements of an XML document may be nested, counting the
+	/// root element as depth 1.
+	pub max_depth: Option<usize>,
+}
+
+impl Limits {
+	/// The limits in effect unless configured otherwise.
+	const DEFAULT: Self = Self {
+		max_members: None,
+		max_member_size: None,
+		max_total_size: None,
+		max_depth: Some(DEFAULT_MAX_DEPTH),
+	};
+}
+
+impl Default for Limits {
+	fn default() -> Self {
+		Self::DEFAULT
+	}
+}
+
+/// The limits in effect.
+static LIMITS: RwLock<Limits> = RwLock::new(Limits::DEFAULT);
+
+/// Sets the limits applied to files read from now on, in every thread.
+pub fn set_limits(limits: Limits) {
+	*LIMITS
+		.write()
+		.unwrap_or_else(std::sync::PoisonError::into_inner) = limits;
+}
+
+/// Returns the limits in effect.
+pub fn limits() -> Limits {
+	*LIMITS
+		.read()
+		.unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Fails if an element is nested deeper than the limit allows.
+pub(crate) fn check_depth(limits: &Limits, depth: usize) -> Result<()> {
+	match limits.max_depth {
+		Some(max) if depth > max => Err(Error::Limit(format!(
+			"Elements are nested more than {max} deep"
+		))),
+		_ => Ok(()),
+	}
+}
+
+/// Fails if a decompressed document is larger than the limit allows.
+pub(crate) fn check_total_size(limits: &Limits, size: u64) -> Result<()> {
+	match limits.max_total_size {
+		Some(max) if size > max => Err(Error::Limit(format!(
+			"Decompressed data is larger than the limit of {max} bytes"
+		))),
+		_ => Ok(()),
+	}
+}
+
+/// What has been read of a save file so far, as counted against its limits.
+#[derive(Debug)]
+pub(crate) struct Usage {
+	/// The limits applied to the save file.
+	limits: Limits,
+
+	/// The number of members found so far.
+	members: usize,
+
+	/// The number of decompressed bytes declared so far, counting member headers.
+	total: u64,
+}
+
+impl Usage {
+	/// Starts counting a save file against some limits.
+	pub fn new(limits: Limits) -> Self {
+		Self {
+			limits,
+			members: 0,
+			total: 0,
+		}
+	}
+
+	/// Counts bytes of member headers toward the total size.
+	pub fn header(&mut self, bytes: u64) -> Result<()> {
+		self.total += bytes;
+		check_total_size(&self.limits, self.total)
+	}
+
+	/// Counts a member, of the size declared in its header.
+	pub fn member(&mut self, name: &str, size: usize) -> Result<()> {
+		self.members += 1;
+		if let Some(max) = self.limits.max_members {
+			if self.members > max {
+				return Err(Error::Limit(format!(
+					"Save file has more than {max} members"
+				)));
+			}
+		}
+		if let Some(max) = self.limits.max_member_size {
+			if size > max {
+				return Err(Error::Limit(format!(
+					"Archive member {name} is {size} bytes, more than the limit of {max}"
+				)));
+			}
+		}
+		// Cast is sound because u64 ≥ usize.
+		self.header(size as u64)
+	}
+}
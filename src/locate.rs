@@ -0,0 +1,159 @@
+use crate::output::{self, outln, Format};
+use crate::time::DateTime;
+use serde::Serialize;
+use std::ffi::{OsStr, OsString};
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+/// A directory in which the game keeps save files.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct SaveDir {
+	/// The path to the directory.
+	path: PathBuf,
+
+	/// Whether the directory holds multiplayer campaigns.
+	multiplayer: bool,
+}
+
+/// Returns the directories in which the game keeps save files.
+///
+/// The game keeps single-player campaigns in `Daedalic Entertainment GmbH/Barotrauma` in the
+/// platform’s local data directory (`%LocalAppData%` on Windows, `~/.local/share` on Linux, and
+/// `~/Library/Application Support` on macOS) and multiplayer campaigns, including those hosted by a
+/// dedicated server, in its `Multiplayer` subdirectory. If `save_dir` is given, it is used, along
+/// with its own `Multiplayer` subdirectory, instead of the local data directory. If `game_dir` is
+/// given, the `Data/Saves` directory in the game installation, used by older versions and by servers
+/// run from the install directory, is also included.
+fn save_dirs(save_dir: Option<&Path>, game_dir: Option<&Path>) -> Vec<SaveDir> {
+	let mut bases = Vec::new();
+	if let Some(save_dir) = save_dir {
+		bases.push(save_dir.to_owned());
+	} else if let Some(data) = dirs::data_local_dir() {
+		bases.push(data.join("Daedalic Entertainment GmbH").join("Barotrauma"));
+	}
+	if let Some(game_dir) = game_dir {
+		bases.push(game_dir.join("Data").join("Saves"));
+	}
+	let mut ret = Vec::new();
+	for base in bases {
+		ret.push(SaveDir {
+			path: base.join("Multiplayer"),
+			multiplayer: true,
+		});
+		ret.push(SaveDir {
+			path: base,
+			multiplayer: false,
+		});
+	}
+	ret
+}
+
+/// A save file found in one of the game’s save directories.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct FoundSave {
+	/// The name of the campaign, which is the save file’s name without its extension.
+	pub campaign: String,
+
+	/// Whether the save is a multiplayer campaign.
+	pub multiplayer: bool,
+
+	/// When the save was last written.
+	pub saved: Option<String>,
+
+	/// The path to the save file.
+	pub path: PathBuf,
+}
+
+/// Finds the save files in the game’s save directories.
+///
+/// Directories that do not exist are skipped. The saves are returned sorted by campaign name.
+pub fn find(save_dir: Option<&Path>, game_dir: Option<&Path>) -> Result<Vec<FoundSave>> {
+	let mut ret = Vec::new();
+	for dir in save_dirs(save_dir, game_dir) {
+		let entries = match std::fs::read_dir(&dir.path) {
+			Ok(entries) => entries,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+			Err(e) => return Err(e),
+		};
+		log::debug!("Searching {} for saves", dir.path.display());
+		for entry in entries {
+			let entry = entry?;
+			let path = entry.path();
+			if path.extension() != Some(OsStr::new("save")) {
+				continue;
+			}
+			let Some(campaign) = path.file_stem().and_then(OsStr::to_str) else {
+				continue;
+			};
+			let saved = entry
+				.metadata()?
+				.modified()
+				.ok()
+				.map(|i| DateTime::from_system_time(i).to_string());
+			ret.push(FoundSave {
+				campaign: campaign.to_owned(),
+				multiplayer: dir.multiplayer,
+				saved,
+				path,
+			});
+		}
+	}
+	ret.sort_by(|a, b| a.campaign.cmp(&b.campaign).then(a.path.cmp(&b.path)));
+	Ok(ret)
+}
+
+/// Lists the save files in the game’s save directories.
+pub fn list(save_dir: Option<&Path>, game_dir: Option<&Path>, format: Format) -> Result<()> {
+	output::emit(format, &find(save_dir, game_dir)?, |saves| {
+		for save in saves {
+			outln!(
+				"{}\t{}\t{}\t{}",
+				save.campaign,
+				if save.multiplayer {
+					"multiplayer"
+				} else {
+					"single-player"
+				},
+				save.saved.as_deref().unwrap_or("?"),
+				save.path.display()
+			);
+		}
+	})
+}
+
+/// Resolves a save named on the command line to a path.
+///
+/// If `name` is `-`, for standard input or output, names an existing file or directory, or contains a
+/// path separator or extension, it is taken as a path. Otherwise it is looked up as a campaign name in the game’s save directories;
+/// if no campaign has that name, `name` is again taken as a path, so that the usual error is
+/// reported when it is opened.
+pub fn resolve(name: &OsStr, save_dir: Option<&Path>, game_dir: Option<&Path>) -> Result<OsString> {
+	let path = Path::new(name);
+	if name == "-" || path.exists() || path.extension().is_some() || path.components().count() != 1
+	{
+		return Ok(name.to_owned());
+	}
+	let matches: Vec<FoundSave> = find(save_dir, game_dir)?
+		.into_iter()
+		.filter(|i| OsStr::new(&i.campaign) == name)
+		.collect();
+	match matches.as_slice() {
+		[] => Ok(name.to_owned()),
+		[found] => {
+			log::info!("Campaign {} is {}", path.display(), found.path.display());
+			Ok(found.path.clone().into_os_string())
+		}
+		_ => Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!(
+				"Several campaigns are named {}; give the path of the save instead: {}",
+				path.display(),
+				matches
+					.iter()
+					.map(|i| i.path.display().to_string())
+					.collect::<Vec<String>>()
+					.join(", ")
+			),
+		)),
+	}
+}
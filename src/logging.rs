@@ -0,0 +1,95 @@
+use crate::color::Style;
+use crate::time::DateTime;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::ffi::OsStr;
+use std::fs::{File, OpenOptions};
+use std::io::{Result, Write};
+use std::sync::{Mutex, OnceLock};
+
+/// A logger that writes messages to standard error, and optionally to a log file.
+struct Logger {
+	/// The most detailed level of message written to standard error.
+	stderr: LevelFilter,
+
+	/// The log file, if any.
+	file: Option<Mutex<File>>,
+}
+
+impl Log for Logger {
+	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+		metadata.level() <= log::max_level()
+	}
+
+	fn log(&self, record: &Record<'_>) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+		if record.level() <= self.stderr {
+			let style = match record.level() {
+				Level::Error => Style::Error,
+				Level::Warn => Style::Warning,
+				Level::Info => Style::Heading,
+				Level::Debug | Level::Trace => Style::Dim,
+			};
+			eprintln!("{}: {}", style.paint_stderr(record.level()), record.args());
+		}
+		if let Some(file) = &self.file {
+			// Each message is written in one piece so that concurrent runs appending to the same
+			// file do not interleave within a line.
+			let line = format!(
+				"{} {}: {}\n",
+				DateTime::now(),
+				record.level(),
+				record.args()
+			);
+			if let Ok(mut file) = file.lock() {
+				let _ = file.write_all(line.as_bytes());
+			}
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+/// The logger instance.
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Returns the log level selected by the `-v` and `-q` command-line options.
+///
+/// By default, warnings and errors are shown. Each `-v` shows one more level of detail; `-q` shows
+/// only errors.
+pub fn level(verbose: u64, quiet: bool) -> LevelFilter {
+	if quiet {
+		return LevelFilter::Error;
+	}
+	match verbose {
+		0 => LevelFilter::Warn,
+		1 => LevelFilter::Info,
+		2 => LevelFilter::Debug,
+		_ => LevelFilter::Trace,
+	}
+}
+
+/// Opens a log file, appending to it if it already exists.
+pub fn open(path: &OsStr) -> Result<File> {
+	OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Installs the logger, showing messages at or above the given level on standard error.
+///
+/// If `file` is given, messages are also written to it with timestamps. The file receives debug
+/// messages even when standard error does not, and trace messages too if `level` includes them.
+pub fn init(level: LevelFilter, file: Option<File>) {
+	let max = if file.is_some() {
+		level.max(LevelFilter::Debug)
+	} else {
+		level
+	};
+	let logger = LOGGER.get_or_init(|| Logger {
+		stderr: level,
+		file: file.map(Mutex::new),
+	});
+	// This can only fail if a logger is already installed, in which case that one is kept.
+	let _ = log::set_logger(logger);
+	log::set_max_level(max);
+}
@@ -22,75 +22,2085 @@
 	clippy::cargo,
 	clippy::pedantic,
 )]
+// Duplicate transitive dependency versions are outside this crate’s control.
+#![allow(clippy::multiple_crate_versions)]
 
-use clap::{Arg, Command};
+use clap::{Arg, ArgGroup, ArgMatches, Command};
 use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
 
-mod save;
+use barotool::character::Appearance;
+use barotool::save::{Existing, MemberFilter, MemberNames, Oversize, PackOptions, UnpackOptions};
+use barotool::{compression, diagnostic, gamesession, limits, submarine};
+// These are only used by the library, which this program links to.
+#[cfg(feature = "async")]
+use async_compression as _;
+use byteorder as _;
+#[cfg(any(feature = "zlib-ng", feature = "zlib-rs"))]
+use flate2 as _;
+use libflate as _;
+use thiserror as _;
+#[cfg(feature = "async")]
+use tokio as _;
+use xml_rs as _;
+use xmlparser as _;
 
+mod archive;
+mod backup;
+mod batch;
+mod campaign;
+mod color;
+mod config;
+mod diff;
+mod dryrun;
+mod editor;
+mod exit;
+mod locate;
+mod logging;
+mod man;
+mod output;
+mod pager;
+mod plugin;
+mod progress;
+mod roundtrip;
+mod selector;
+mod stream;
+mod time;
+mod transform;
+
+/// The nouns under which subcommands are grouped, with their descriptions.
+const NOUNS: [(&str, &str); 4] = [
+	("save", "Works with .save files as a whole."),
+	(
+		"sub",
+		"Works with submarines, on their own or in a campaign.",
+	),
+	("campaign", "Views and modifies the state of a campaign."),
+	("crew", "Views and modifies the crew of a campaign."),
+];
+
+/// The subcommands grouped under nouns, as their flat name, noun, and verb.
+///
+/// For example, `list-save` is also available as `save list`.
+const NESTED: [(&str, &str, &str); 65] = [
+	("locate-saves", "save", "locate"),
+	("list-save", "save", "list"),
+	("pack-save", "save", "pack"),
+	("unpack-save", "save", "unpack"),
+	("add-save", "save", "add"),
+	("remove-save", "save", "remove"),
+	("update-save", "save", "update"),
+	("cat-save", "save", "cat"),
+	("validate-save", "save", "validate"),
+	("verify-save", "save", "verify"),
+	("diff-save", "save", "diff"),
+	("merge-save", "save", "merge"),
+	("edit-save", "save", "edit"),
+	("patch-save", "save", "patch"),
+	("grep-save", "save", "grep"),
+	("convert-save", "save", "convert"),
+	("backup-campaign", "save", "backup"),
+	("list-backups", "save", "list-backups"),
+	("restore-campaign", "save", "restore"),
+	("decompress-sub", "sub", "decompress"),
+	("compress-sub", "sub", "compress"),
+	("transform-sub", "sub", "transform"),
+	("list-subs", "sub", "list"),
+	("add-sub", "sub", "add"),
+	("remove-sub", "sub", "remove"),
+	("switch-active-sub", "sub", "switch"),
+	("upgrade-campaign-sub", "sub", "upgrade"),
+	("list-upgrades", "sub", "list-upgrades"),
+	("set-upgrade", "sub", "set-upgrade"),
+	("show-campaign", "campaign", "show"),
+	("set-money", "campaign", "set-money"),
+	("show-reputation", "campaign", "show-reputation"),
+	("set-reputation", "campaign", "set-reputation"),
+	("list-cargo", "campaign", "list-cargo"),
+	("set-cargo", "campaign", "set-cargo"),
+	("clear-cargo", "campaign", "clear-cargo"),
+	("show-progress", "campaign", "show-progress"),
+	("set-progress", "campaign", "set-progress"),
+	("list-settings", "campaign", "list-settings"),
+	("set-setting", "campaign", "set-setting"),
+	("reveal-map", "campaign", "reveal-map"),
+	("hide-map", "campaign", "hide-map"),
+	("list-missions", "campaign", "list-missions"),
+	("remove-mission", "campaign", "remove-mission"),
+	("show-levels", "campaign", "show-levels"),
+	("set-level-seed", "campaign", "set-level-seed"),
+	("list-crew", "crew", "list"),
+	("set-skill", "crew", "set-skill"),
+	("grant-experience", "crew", "grant-experience"),
+	("unlock-talent", "crew", "unlock-talent"),
+	("heal-crew", "crew", "heal"),
+	("rename-crew", "crew", "rename"),
+	("edit-crew", "crew", "edit"),
+	("add-crew", "crew", "add"),
+	("add-bot", "crew", "add-bot"),
+	("remove-crew", "crew", "remove"),
+	("prune-crew", "crew", "prune"),
+	("list-hires", "crew", "list-hires"),
+	("add-hire", "crew", "add-hire"),
+	("remove-hire", "crew", "remove-hire"),
+	("list-pets", "crew", "list-pets"),
+	("list-inventory", "crew", "list-inventory"),
+	("add-item", "crew", "add-item"),
+	("remove-item", "crew", "remove-item"),
+	("swap-items", "crew", "swap-items"),
+];
+
+// The global options are many, but each is short.
+#[allow(clippy::too_many_lines)]
 fn make_clap_command() -> Command<'static> {
-	Command::new("barotool")
+	let mut command = Command::new("barotool")
 		.author(clap::crate_authors!())
 		.about("Manipulates Barotrauma save files and submarines.")
 		.version(clap::crate_version!())
 		.infer_subcommands(true)
 		.subcommand_required(true)
 		.arg_required_else_help(true)
-		.subcommand(Command::new("list-save")
+		.allow_external_subcommands(true)
+		.allow_invalid_utf8_for_external_subcommands(true)
+		.after_help("Any other COMMAND runs the plugin barotool-COMMAND from PATH, if there is one.")
+		.arg(Arg::new("format")
+			.help("The format in which to print the results of read-only commands (default: text) [env: BAROTOOL_FORMAT]")
+			.long("format")
+			.global(true)
+			.takes_value(true)
+			.possible_values(output::Format::NAMES))
+		.arg(Arg::new("color")
+			.help("When to color output (default: auto, which colors output to a terminal unless NO_COLOR is set) [env: BAROTOOL_COLOR]")
+			.long("color")
+			.global(true)
+			.takes_value(true)
+			.possible_values(color::When::NAMES))
+		.arg(Arg::new("dry-run")
+			.help("Show the changes a command would make, as a diff, without making them")
+			.long("dry-run")
+			.short('n')
+			.global(true))
+		.arg(Arg::new("no-pager")
+			.help("Do not send output to a pager, even when it is longer than the terminal")
+			.long("no-pager")
+			.global(true))
+		.arg(Arg::new("verbose")
+			.help("Show more detail about what is being done (may be repeated)")
+			.long("verbose")
+			.short('v')
+			.global(true)
+			.multiple_occurrences(true))
+		.arg(Arg::new("log-file")
+			.help("Also write all diagnostic messages, with timestamps, to this file")
+			.long("log-file")
+			.global(true)
+			.takes_value(true)
+			.allow_invalid_utf8(true))
+		.arg(Arg::new("quiet")
+			.help("Print nothing but errors; the exit status still reports the outcome")
+			.long("quiet")
+			.short('q')
+			.global(true)
+			.conflicts_with("verbose"))
+		.arg(Arg::new("recursive")
+			.help("Search directories named on the command line recursively for files to process")
+			.long("recursive")
+			.short('r')
+			.global(true))
+		.arg(Arg::new("config")
+			.help("Load defaults from this configuration file instead of the usual one [env: BAROTOOL_CONFIG]")
+			.long("config")
+			.global(true)
+			.takes_value(true)
+			.allow_invalid_utf8(true))
+		.arg(Arg::new("game-dir")
+			.help("The directory in which Barotrauma is installed [env: BAROTOOL_GAME_DIR]")
+			.long("game-dir")
+			.global(true)
+			.takes_value(true)
+			.allow_invalid_utf8(true))
+		.arg(Arg::new("save-dir")
+			.help("Look for campaigns in this directory instead of the game’s usual save directory [env: BAROTOOL_SAVE_DIR]")
+			.long("save-dir")
+			.global(true)
+			.takes_value(true)
+			.allow_invalid_utf8(true))
+		.arg(Arg::new("compression-level")
+			.help("The gzip compression level for files written, from 0 (none) to 9 (best; the default) [env: BAROTOOL_COMPRESSION_LEVEL]")
+			.long("compression-level")
+			.global(true)
+			.takes_value(true)
+			.value_parser(clap::value_parser!(u32).range(0..=i64::from(compression::MAX_LEVEL))))
+		.arg(Arg::new("jobs")
+			.help("The number of files to process at once when given several; 0, the default, uses every CPU [env: BAROTOOL_JOBS]")
+			.long("jobs")
+			.short('j')
+			.global(true)
+			.takes_value(true)
+			.value_parser(clap::value_parser!(usize)));
+	let mut nouns: Vec<Command<'static>> = NOUNS
+		.iter()
+		.map(|(noun, about)| {
+			Command::new(*noun)
+				.about(*about)
+				.infer_subcommands(true)
+				.subcommand_required(true)
+				.arg_required_else_help(true)
+		})
+		.collect();
+	for subcommand in subcommands() {
+		let nested = NESTED
+			.iter()
+			.find(|(flat, _, _)| *flat == subcommand.get_name());
+		if let Some((_, noun, verb)) = nested {
+			let index = NOUNS.iter().position(|(i, _)| i == noun).unwrap();
+			nouns[index] = nouns[index]
+				.clone()
+				.subcommand(subcommand.clone().name(*verb));
+			// The old flat name keeps working, but is no longer advertised.
+			command = command.subcommand(subcommand.hide(true));
+		} else {
+			command = command.subcommand(subcommand);
+		}
+	}
+	command.subcommands(nouns)
+}
+
+/// Returns every subcommand, under its flat name.
+// The subcommand definitions are long, but they are a single flat list.
+#[allow(clippy::too_many_lines)]
+fn subcommands() -> Vec<Command<'static>> {
+	vec![
+		Command::new("show-config")
+			.about("Shows the settings in effect after loading the configuration file."),
+		Command::new("roundtrip-check")
+			.about("Reads and writes back .sub and .save files, reporting any whose content would change.")
+			.long_about("Reads and writes back .sub and .save files, reporting any whose content would change.\n\nEach submarine, and each game session, character data, and submarine member of a save, is parsed and serialized in memory as a command that modifies it would, and the XML before and after is compared; nothing is written to disk. Files are identical if their XML comes back byte for byte, equivalent if only its formatting differs, and changed otherwise. Run this on a copy of your saves to check that barotool handles them faithfully before letting it modify them.")
+			.arg(Arg::new("paths")
+				.help("The .sub and .save files, or directories holding them, to check")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("bytes")
+				.help("Count files whose XML is formatted differently, but is otherwise the same, as having changed")
+				.long("bytes")),
+		Command::new("generate-man")
+			.about("Writes manual pages for barotool and each of its subcommands.")
+			.hide(true)
+			.arg(Arg::new("dir")
+				.help("The directory to write the pages into")
+				.default_value(".")
+				.allow_invalid_utf8(true)),
+		Command::new("locate-saves")
+			.about("Lists the saves in the game’s save directories.")
+			.long_about("Lists the saves in the game’s save directories.\n\nAny command that reads or modifies a .save file also accepts the name of a campaign found here in place of its path."),
+		Command::new("list-save")
 			.about("Lists the files contained within a .save file.")
 			.arg(Arg::new("save")
-				.help("The .save file to read")
+				.help("The .save file(s) to read, or directories to search for them (- for standard input)")
 				.required(true)
-				.allow_invalid_utf8(true)))
-		.subcommand(Command::new("pack-save")
+				.multiple_values(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("filter")
+				.help("List only members matching this pattern, where * matches anything and ? any one character (may be repeated, e.g. --filter '*.sub')")
+				.long("filter")
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.value_name("PATTERN"))
+			.arg(Arg::new("digest")
+				.help("Also print each member’s SHA-256 digest, reading every member in full")
+				.long("digest"))
+			.arg(Arg::new("crc32")
+				.help("Also print each member’s CRC-32, reading every member in full")
+				.long("crc32"))
+			.arg(lossy_names_arg()),
+		Command::new("pack-save")
 			.about("Creates a .save file, packing it with other files.")
 			.arg(Arg::new("save")
-				.help("The .save file to create")
+				.help("The .save file to create (- for standard output)")
 				.required(true)
 				.allow_invalid_utf8(true))
 			.arg(Arg::new("members")
-				.help("The file(s) to pack into the archive.")
-				.required(true)
-				.multiple_values(true)))
-		.subcommand(Command::new("unpack-save")
+				.help("The file(s) to pack into the archive, each stored under its path as given, or as NAME=PATH to store it as NAME")
+				.required_unless_present("from-dir")
+				.multiple_values(true))
+			.arg(strip_prefix_arg())
+			.arg(Arg::new("from-dir")
+				.help("Pack every file within this directory and its subdirectories, each stored under its path relative to the directory")
+				.long("from-dir")
+				.takes_value(true)
+				.value_name("DIR")
+				.allow_invalid_utf8(true)
+				.conflicts_with_all(&["members", "strip-prefix"]))
+			.arg(Arg::new("sort")
+				.help("Store the members sorted by name rather than in the order given, so that the same files always make the same archive")
+				.long("sort"))
+			.arg(Arg::new("split")
+				.help("Split a file too large for one member, of 4 GiB or more, across several members, which unpack-save puts back together; Barotrauma cannot read such a save")
+				.long("split"))
+			.arg(Arg::new("segmented")
+				.help("Compress each member separately, so that later edits and --index reads of the save are faster, at some cost in size; Barotrauma has not been checked to read such a save")
+				.long("segmented"))
+			.arg(progress_arg()),
+		Command::new("unpack-save")
 			.about("Extracts files from a .save file.")
+			.long_about("Extracts files from a .save file.\n\nMember names are made safe before extracting, so that a malicious save cannot write outside the directory: both / and \\ separate directories, leading / and any . or .. are dropped, and characters the file system does not allow are replaced by _. A member extracted under a different name is reported.\n\nA file that already exists is an error, unless --no-clobber, --overwrite, or --keep-newer says what to do with it.")
 			.arg(Arg::new("save")
-				.help("The .save file to read")
+				.help("The .save file to read (- for standard input)")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("members")
+				.help("The file(s) to extract from the archive, which may be patterns such as *.sub, where * matches anything and ? any one character (omit to extract all members).")
+				.multiple_values(true))
+			.arg(Arg::new("output-dir")
+				.help("The directory to extract into, created if need be (default: the current directory)")
+				.long("output-dir")
+				.takes_value(true)
+				.value_name("DIR")
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("unsafe-names")
+				.help("Extract each member at its name exactly as given, even an absolute path or one leading out of the directory with .., rather than made safe; only for saves from a trusted source")
+				.long("unsafe-names"))
+			.arg(lossy_names_arg())
+			.arg(Arg::new("no-clobber")
+				.help("Skip members whose files already exist, leaving the files as they are")
+				.long("no-clobber"))
+			.arg(Arg::new("overwrite")
+				.help("Replace files that already exist")
+				.long("overwrite"))
+			.arg(Arg::new("keep-newer")
+				.help("Skip members whose files already exist and were modified after the .save file, replacing older ones")
+				.long("keep-newer"))
+			.group(ArgGroup::new("existing")
+				.args(&["no-clobber", "overwrite", "keep-newer"]))
+			.arg(index_arg()
+				.conflicts_with("lossy-names"))
+			.arg(progress_arg()),
+		Command::new("add-save")
+			.about("Adds files to an existing .save file.")
+			.long_about("Adds files to an existing .save file.\n\nThe save file is rewritten with its members as they were, followed by the new ones, named as pack-save names them. A file whose name is already a member is refused; nothing is added unless every file can be.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("members")
+				.help("The file(s) to add to the archive, each stored under its path as given, or as NAME=PATH to store it as NAME")
+				.required(true)
+				.multiple_values(true))
+			.arg(strip_prefix_arg())
+			.args(output_args()),
+		Command::new("remove-save")
+			.about("Removes files from a .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("members")
+				.help("The file(s) to remove from the archive.")
+				.required(true)
+				.multiple_values(true))
+			.args(output_args()),
+		Command::new("update-save")
+			.about("Replaces the contents of files in a .save file, keeping the order of its members.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("members")
+				.help("The members to replace, as NAME=PATH, where PATH is the file holding the new contents (e.g. gamesession.xml=edited.xml)")
+				.required(true)
+				.multiple_values(true)
+				.value_parser(parse_replacement))
+			.args(output_args()),
+		Command::new("cat-save")
+			.about("Writes a file contained within a .save file to standard output.")
+			.arg(Arg::new("save")
+				.help("The .save file to read (- for standard input)")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("member")
+				.help("The file to write (e.g. gamesession.xml)")
+				.required(true))
+			.arg(index_arg()),
+		Command::new("decompress-sub")
+			.about("Decompresses a .sub file into plain XML.")
+			.arg(Arg::new("sub")
+				.help("The .sub file to read (- for standard input)")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("xml")
+				.help("The XML file to write (default: standard output)")
+				.default_value("-")
+				.allow_invalid_utf8(true)),
+		Command::new("compress-sub")
+			.about("Compresses a plain XML submarine into a .sub file.")
+			.arg(Arg::new("xml")
+				.help("The XML file to read (- for standard input)")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("sub")
+				.help("The .sub file to write (default: standard output)")
+				.default_value("-")
+				.allow_invalid_utf8(true)),
+		Command::new("transform-sub")
+			.about("Edits items and structures in a .sub file without loading it whole, for submarines too large to handle otherwise.")
+			.arg(Arg::new("sub")
+				.help("The .sub file to read (- for standard input)")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("output-sub")
+				.help("The .sub file to write (default: standard output)")
+				.default_value("-")
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("set-attr")
+				.help("Set an attribute on the selected items and structures (may be repeated)")
+				.long("set-attr")
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.value_name("NAME=VALUE")
+				.value_parser(parse_attribute))
+			.arg(Arg::new("recolor")
+				.help("Set the sprite color of the selected items and structures, as R,G,B[,A] from 0 to 255 or #RRGGBB[AA]")
+				.long("recolor")
+				.takes_value(true)
+				.value_name("COLOR")
+				.value_parser(parse_color))
+			.arg(Arg::new("strip")
+				.help("Remove the selected items and structures, such as with --include tag:decorative")
+				.long("strip")
+				.conflicts_with_all(&["set-attr", "recolor"])
+				.requires("include"))
+			.args(selector::Selection::args())
+			.group(ArgGroup::new("edits")
+				.args(&["set-attr", "recolor", "strip"])
+				.multiple(true)
+				.required(true)),
+		Command::new("backup-campaign")
+			.about("Takes a timestamped snapshot of a .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to back up, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("dir")
+				.help("The directory to keep snapshots in (default: backup.dir from the configuration file, or the save’s name with .backups appended) [env: BAROTOOL_BACKUP_DIR]")
+				.long("dir")
+				.takes_value(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("keep")
+				.help("Delete the oldest snapshots so that at most this many remain (default: backup.keep from the configuration file) [env: BAROTOOL_BACKUP_KEEP]")
+				.long("keep")
+				.takes_value(true)
+				.value_parser(clap::value_parser!(usize))),
+		Command::new("list-backups")
+			.about("Lists the snapshots of a .save file, oldest first.")
+			.arg(Arg::new("save")
+				.help("The .save file whose snapshots to list")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("dir")
+				.help("The directory snapshots are kept in (default: backup.dir from the configuration file, or the save’s name with .backups appended) [env: BAROTOOL_BACKUP_DIR]")
+				.long("dir")
+				.takes_value(true)
+				.allow_invalid_utf8(true)),
+		Command::new("restore-campaign")
+			.about("Restores a .save file from a snapshot.")
+			.arg(Arg::new("save")
+				.help("The .save file to restore")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("snapshot")
+				.help("The snapshot to restore (default: the most recent)")
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("dir")
+				.help("The directory snapshots are kept in (default: backup.dir from the configuration file, or the save’s name with .backups appended) [env: BAROTOOL_BACKUP_DIR]")
+				.long("dir")
+				.takes_value(true)
+				.allow_invalid_utf8(true)),
+		Command::new("show-campaign")
+			.about("Shows a summary of the campaign in a .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("validate-save")
+			.about("Checks a campaign .save file for missing or orphaned members.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to check, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("verify-save")
+			.about("Checks that a .save file is intact, reading every member in full.")
+			.long_about("Checks that a .save file is intact, reading every member in full.\n\nEvery member header must be complete with a valid name, every member must hold as many bytes as its header declares, and every gzip checksum must match. The first damage found is reported with its offset in the decompressed archive and the member it is in or follows. Unlike validate-save, this does not look at what the members contain.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to check, or directories to search for them (- for standard input)")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true))
+			.arg(progress_arg()),
+		Command::new("diff-save")
+			.about("Compares the files contained within two .save files.")
+			.long_about("Compares the files contained within two .save files.\n\nMembers only in one file, and members in both whose contents differ, are listed with their sizes; members are matched by name and compared by SHA-256 digest. The exit status is 1 if any differ.")
+			.arg(Arg::new("old")
+				.help("The .save file to compare from")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("new")
+				.help("The .save file to compare to")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("text")
+				.help("Also print a unified diff of each differing member that is text, with XML written the way barotool writes it, so that only real changes show")
+				.long("text")),
+		Command::new("merge-save")
+			.about("Writes a .save file holding the files of one .save file, with those of another replacing them.")
+			.long_about("Writes a .save file holding the files of one .save file, with those of another replacing them.\n\nEvery member of the base save is kept, in order, except those the overlay save also has, whose contents are taken from the overlay; members only in the overlay are added at the end. For example, merging an overlay holding only an edited CharacterData.xml grafts it onto the base.")
+			.arg(Arg::new("out")
+				.help("The .save file to write, which may be the base save to modify it in place")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("base")
+				.help("The .save file whose members to keep")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("overlay")
+				.help("The .save file whose members replace or add to those of the base")
+				.required(true)
+				.allow_invalid_utf8(true)),
+		Command::new("edit-save")
+			.about("Opens a file contained within a .save file in an editor, writing it back afterwards.")
+			.long_about("Opens a file contained within a .save file in an editor, writing it back afterwards.\n\nThe member is extracted to a temporary file and the editor is run on it; once the editor exits, the save file is rewritten with the edited contents, unless they are unchanged or the editor failed. A submarine member is edited as plain XML. The editor is the one given by --editor, or else the command in the VISUAL or EDITOR environment variable.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("member")
+				.help("The file to edit (e.g. gamesession.xml)")
+				.required(true))
+			.arg(Arg::new("editor")
+				.help("The editor to run, which is given the file to edit as its last argument")
+				.long("editor")
+				.takes_value(true)
+				.value_name("COMMAND")
+				.allow_invalid_utf8(true))
+			.args(output_args()),
+		Command::new("patch-save")
+			.about("Applies a unified diff to a file contained within a .save file.")
+			.long_about("Applies a unified diff to a file contained within a .save file.\n\nThe diff is one made by diff -u or git diff of the member, such as of a file extracted with unpack-save, or printed by diff-save --text. A submarine member is patched as plain XML. Each hunk must match exactly, but may be found above or below where the diff says it is. Only text members can be patched.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("member")
+				.help("The file to patch (e.g. gamesession.xml)")
+				.required(true))
+			.arg(Arg::new("patch")
+				.help("The file holding the unified diff (- for standard input)")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.args(output_args()),
+		Command::new("grep-save")
+			.about("Searches the files contained within a .save file for lines matching a regular expression.")
+			.long_about("Searches the files contained within a .save file for lines matching a regular expression.\n\nEach matching line is printed as MEMBER:LINE:TEXT. Submarine members are decompressed, so that their XML is searched.")
+			.arg(Arg::new("save")
+				.help("The .save file to search (- for standard input)")
 				.required(true)
 				.allow_invalid_utf8(true))
+			.arg(Arg::new("pattern")
+				.help("The regular expression to search for (e.g. 'identifier=\"fuelrod\"')")
+				.required(true))
 			.arg(Arg::new("members")
-				.help("The file(s) to extract from the archive (omit to extract all members).")
-				.multiple_values(true)))
-}
-
-fn main() -> std::io::Result<()> {
-	let matches = make_clap_command().get_matches();
-	if let Some(matches) = matches.subcommand_matches("list-save") {
-		let filename = matches.value_of_os("save").unwrap();
-		save::list(filename)?;
-	}
-	if let Some(matches) = matches.subcommand_matches("pack-save") {
-		let filename = matches.value_of_os("save").unwrap();
-		let members = matches.values_of("members").unwrap().collect::<Vec<&str>>();
-		save::pack(filename, &members)?;
-	}
-	if let Some(matches) = matches.subcommand_matches("unpack-save") {
-		let filename = matches.value_of_os("save").unwrap();
-		let mut members = matches
-			.values_of("members")
-			.map_or(HashSet::<&str>::new(), Iterator::collect::<HashSet<&str>>);
-		save::unpack(filename, &mut members)?;
-		if !members.is_empty() {
-			eprintln!("Some members were not found:");
-			for i in members {
-				eprintln!("{}", i);
+				.help("The file(s) to search, which may be patterns such as *.sub, where * matches anything and ? any one character (omit to search all members).")
+				.multiple_values(true))
+			.arg(Arg::new("ignore-case")
+				.help("Match letters regardless of case")
+				.long("ignore-case")
+				.short('i')),
+		Command::new("convert-save")
+			.about("Converts a .save file to or from a zip or tar archive.")
+			.long_about("Converts a .save file to or from a zip or tar archive.\n\nEach member of the save becomes a file in the archive of the same name and size, in the same order, and back again, so that a save can be inspected and edited with ordinary archive tools and then converted back without loss. Directories in the archive are skipped.")
+			.arg(Arg::new("from")
+				.help("The file to convert: the .save file with --to-zip or --to-tar, or the archive with --from-zip or --from-tar (- for standard input, except for a zip archive)")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("to")
+				.help("The file to create: the archive with --to-zip or --to-tar, or the .save file with --from-zip or --from-tar (- for standard output, except for a zip archive)")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("to-zip")
+				.help("Convert a .save file to a zip archive")
+				.long("to-zip"))
+			.arg(Arg::new("from-zip")
+				.help("Convert a zip archive to a .save file")
+				.long("from-zip"))
+			.arg(Arg::new("to-tar")
+				.help("Convert a .save file to a tar archive")
+				.long("to-tar"))
+			.arg(Arg::new("from-tar")
+				.help("Convert a tar archive to a .save file")
+				.long("from-tar"))
+			.group(ArgGroup::new("direction")
+				.args(&["to-zip", "from-zip", "to-tar", "from-tar"])
+				.required(true)),
+		Command::new("set-money")
+			.about("Sets the amount of money in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("amount")
+				.help("The new amount of money")
+				.required(true)
+				.value_parser(clap::value_parser!(i32).range(0..)))
+			.arg(Arg::new("character")
+				.help("Modify the named player’s wallet in a multiplayer campaign instead of the shared bank")
+				.long("character")
+				.takes_value(true))
+			.args(output_args()),
+		Command::new("list-subs")
+			.about("Lists the submarines owned in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("add-sub")
+			.about("Adds a submarine to those owned in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("sub")
+				.help("The .sub file to add")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.args(output_args()),
+		Command::new("remove-sub")
+			.about("Removes a submarine from those owned in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("name")
+				.help("The name of the submarine to remove")
+				.required(true))
+			.args(output_args()),
+		Command::new("switch-active-sub")
+			.about("Changes which owned submarine is in use in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("name")
+				.help("The name of the submarine to switch to")
+				.required(true))
+			.args(output_args()),
+		Command::new("upgrade-campaign-sub")
+			.about("Replaces a submarine in a campaign .save file with a newer revision, keeping its upgrades and item state.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("sub")
+				.help("The .sub file containing the new revision")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("replace")
+				.help("The name of the owned submarine to replace (defaults to the new revision’s name)")
+				.long("replace")
+				.takes_value(true))
+			.args(output_args()),
+		Command::new("list-upgrades")
+			.about("Lists the upgrades installed and pending on the active submarine in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("set-upgrade")
+			.about("Sets the level of an upgrade on the active submarine in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("identifier")
+				.help("The identifier of the upgrade")
+				.required(true))
+			.arg(Arg::new("level")
+				.help("The new level (0 removes the upgrade)")
+				.required(true)
+				.value_parser(clap::value_parser!(u32)))
+			.args(output_args()),
+		Command::new("list-crew")
+			.about("Lists the crew in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("set-skill")
+			.about("Sets skill levels of a crew member in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("The name of the crew member")
+				.required_unless_present("character-name"))
+			.arg(Arg::new("skills")
+				.help("The skills to set, as IDENTIFIER=LEVEL (e.g. helm=100)")
+				.required_unless_present("skill")
+				.multiple_values(true)
+				.value_parser(parse_skill))
+			.arg(Arg::new("character-name")
+				.help("The name of the crew member, instead of giving it as CHARACTER")
+				.long("character")
+				.takes_value(true)
+				.value_name("NAME")
+				.conflicts_with("character"))
+			.arg(Arg::new("skill")
+				.help("A skill to set, as IDENTIFIER=LEVEL, as well as any given as SKILLS (may be repeated)")
+				.long("skill")
+				.takes_value(true)
+				.value_name("IDENTIFIER=LEVEL")
+				.multiple_occurrences(true)
+				.value_parser(parse_skill))
+			.args(output_args()),
+		Command::new("heal-crew")
+			.about("Removes afflictions from crew members in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to modify, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("Heal only the named crew member (default: all crew members)")
+				.long("character")
+				.takes_value(true))
+			.arg(Arg::new("affliction")
+				.help("Remove only afflictions with this identifier (may be repeated; default: all afflictions)")
+				.long("affliction")
+				.takes_value(true)
+				.multiple_occurrences(true))
+			.args(output_args()),
+		Command::new("grant-experience")
+			.about("Adds experience points to a crew member in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("The name of the crew member")
+				.required(true))
+			.arg(Arg::new("amount")
+				.help("The number of experience points to add")
+				.required(true)
+				.value_parser(clap::value_parser!(u32)))
+			.args(output_args()),
+		Command::new("unlock-talent")
+			.about("Unlocks talents for a crew member in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("The name of the crew member")
+				.required(true))
+			.arg(Arg::new("talents")
+				.help("The identifiers of the talents to unlock")
+				.required(true)
+				.multiple_values(true))
+			.args(output_args()),
+		Command::new("rename-crew")
+			.about("Renames a crew member in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("old")
+				.help("The crew member’s current name")
+				.required(true))
+			.arg(Arg::new("new")
+				.help("The crew member’s new name")
+				.required(true))
+			.args(output_args()),
+		Command::new("edit-crew")
+			.about("Changes a crew member’s name, job, or appearance in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("The name of the crew member")
+				.required(true))
+			.arg(Arg::new("name")
+				.help("The crew member’s new name")
+				.long("name")
+				.takes_value(true))
+			.arg(Arg::new("job")
+				.help("The identifier of the crew member’s new job (e.g. mechanic)")
+				.long("job")
+				.takes_value(true))
+			.arg(Arg::new("gender")
+				.help("The crew member’s gender (e.g. female)")
+				.long("gender")
+				.takes_value(true))
+			.arg(Arg::new("head-index")
+				.help("The index of the crew member’s head sprite")
+				.long("head-index")
+				.takes_value(true)
+				.value_parser(clap::value_parser!(u32)))
+			.arg(Arg::new("hair-index")
+				.help("The index of the crew member’s hair style, or -1 for none")
+				.long("hair-index")
+				.takes_value(true)
+				.allow_hyphen_values(true)
+				.value_parser(clap::value_parser!(i32).range(-1..)))
+			.arg(Arg::new("tags")
+				.help("The crew member’s comma-separated tags, which recent game versions use to choose the head")
+				.long("tags")
+				.takes_value(true))
+			.group(ArgGroup::new("changes")
+				.args(&["name", "job", "gender", "head-index", "hair-index", "tags"])
+				.multiple(true)
+				.required(true))
+			.args(output_args()),
+		Command::new("add-crew")
+			.about("Adds a crew member to a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("template")
+				.help("An XML file containing the Character element to add")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.args(output_args()),
+		Command::new("add-bot")
+			.about("Adds a new bot to the crew in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("description")
+				.help("A TOML file (or JSON, if named *.json) giving the bot’s name, job, and optionally a skill preset (novice, experienced, or veteran) and skill levels")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.args(output_args()),
+		Command::new("remove-crew")
+			.about("Removes a crew member from a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("name")
+				.help("The name of the crew member to remove")
+				.required(true))
+			.args(output_args()),
+		Command::new("prune-crew")
+			.about("Removes dead crew members from a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.args(output_args()),
+		Command::new("list-hires")
+			.about("Lists the characters available for hire at the current location in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("add-hire")
+			.about("Makes a character available for hire at the current location in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("template")
+				.help("An XML file containing the Character element to add")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.args(output_args()),
+		Command::new("remove-hire")
+			.about("Removes a character from those available for hire at the current location in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("The name of the character to remove")
+				.required(true))
+			.args(output_args()),
+		Command::new("list-pets")
+			.about("Lists the pets in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("show-reputation")
+			.about("Shows the crew’s reputation with factions and locations in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("set-reputation")
+			.about("Sets the crew’s reputation with a faction or location in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("target")
+				.help("The identifier of the faction (or, with --location, the name of the location)")
+				.required(true))
+			.arg(Arg::new("value")
+				.help("The new reputation, from -100 to 100")
+				.required(true)
+				.allow_hyphen_values(true)
+				.value_parser(parse_reputation))
+			.arg(Arg::new("location")
+				.help("Set the reputation of a location rather than a faction")
+				.long("location"))
+			.args(output_args()),
+		Command::new("list-cargo")
+			.about("Lists the items bought but not yet delivered in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("set-cargo")
+			.about("Changes the quantity of an item bought but not yet delivered in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("identifier")
+				.help("The identifier of the item")
+				.required(true))
+			.arg(Arg::new("quantity")
+				.help("The number of items to deliver (0 cancels the order)")
+				.required(true)
+				.value_parser(clap::value_parser!(u32)))
+			.args(output_args()),
+		Command::new("clear-cargo")
+			.about("Cancels delivery of all items bought but not yet delivered in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to modify, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true))
+			.args(output_args()),
+		Command::new("show-progress")
+			.about("Shows the progress counters of a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("set-progress")
+			.about("Changes the progress counters of a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("play-time")
+				.help("The total time spent playing, in seconds")
+				.long("play-time")
+				.takes_value(true)
+				.value_parser(clap::value_parser!(u32)))
+			.arg(Arg::new("passed-levels")
+				.help("The number of levels passed, which drives the difficulty ramp")
+				.long("passed-levels")
+				.takes_value(true)
+				.value_parser(clap::value_parser!(u32)))
+			.group(ArgGroup::new("counters")
+				.args(&["play-time", "passed-levels"])
+				.required(true)
+				.multiple(true))
+			.args(output_args()),
+		Command::new("list-settings")
+			.about("Lists the settings chosen when a campaign was created.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("set-setting")
+			.about("Changes settings chosen when a campaign was created.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("settings")
+				.help("The settings to change, as NAME=VALUE (e.g. RadiationEnabled=false)")
+				.required(true)
+				.multiple_values(true)
+				.value_parser(parse_setting))
+			.args(output_args()),
+		Command::new("reveal-map")
+			.about("Marks locations on the map in a campaign .save file as discovered.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to modify, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("radius")
+				.help("Reveal only locations within this many connections of the current location (default: reveal the whole map)")
+				.long("radius")
+				.takes_value(true)
+				.value_parser(clap::value_parser!(usize)))
+			.args(output_args()),
+		Command::new("hide-map")
+			.about("Marks locations on the map in a campaign .save file as undiscovered.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("keep")
+				.help("Keep locations within this many connections of the current location discovered")
+				.long("keep-radius")
+				.takes_value(true)
+				.default_value("0")
+				.value_parser(clap::value_parser!(usize)))
+			.args(output_args()),
+		Command::new("list-missions")
+			.about("Lists the missions offered and accepted in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("remove-mission")
+			.about("Removes a mission from a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("identifier")
+				.help("The identifier of the mission to remove")
+				.required(true))
+			.args(output_args()),
+		Command::new("show-levels")
+			.about("Shows the seeds of the current and next levels in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file(s) to read, or directories to search for them")
+				.required(true)
+				.multiple_values(true)
+				.allow_invalid_utf8(true)),
+		Command::new("set-level-seed")
+			.about("Changes the seed of the next level in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("seed")
+				.help("The new level seed")
+				.required(true))
+			.arg(Arg::new("location")
+				.help("Change the level at the current location instead of the next level")
+				.long("location"))
+			.args(output_args()),
+		Command::new("list-inventory")
+			.about("Lists the items carried by a crew member in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to read")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("The name of the crew member")
+				.required(true))
+			.args(selector::Selection::args()),
+		Command::new("add-item")
+			.about("Adds an item to a crew member’s inventory in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("The name of the crew member")
+				.required(true))
+			.arg(Arg::new("identifier")
+				.help("The identifier of the item to add")
+				.required(true))
+			.arg(Arg::new("slot")
+				.help("The inventory slot to put the item in (default: the first free slot)")
+				.long("slot")
+				.takes_value(true)
+				.value_parser(clap::value_parser!(usize)))
+			.args(output_args()),
+		Command::new("remove-item")
+			.about("Removes items from a crew member’s inventory in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("The name of the crew member")
+				.required(true))
+			.arg(Arg::new("identifier")
+				.help("The identifier of the items to remove; shorthand for --include identifier:IDENTIFIER"))
+			.args(selector::Selection::args())
+			.group(ArgGroup::new("items")
+				.args(&["identifier", "include"])
+				.multiple(true)
+				.required(true))
+			.args(output_args()),
+		Command::new("swap-items")
+			.about("Swaps the contents of two slots in a crew member’s inventory in a campaign .save file.")
+			.arg(Arg::new("save")
+				.help("The .save file to modify")
+				.required(true)
+				.allow_invalid_utf8(true))
+			.arg(Arg::new("character")
+				.help("The name of the crew member")
+				.required(true))
+			.arg(Arg::new("a")
+				.help("The first inventory slot")
+				.required(true)
+				.value_parser(clap::value_parser!(usize)))
+			.arg(Arg::new("b")
+				.help("The second inventory slot")
+				.required(true)
+				.value_parser(clap::value_parser!(usize)))
+			.args(output_args()),
+	]
+}
+
+/// Returns the save files named by a subcommand’s `save` argument.
+///
+/// Directories are expanded to the `.save` files within them, searching subdirectories too if
+/// `--recursive` was given.
+fn saves(matches: &ArgMatches, config: &config::Config) -> std::io::Result<Vec<PathBuf>> {
+	let paths = matches
+		.values_of_os("save")
+		.unwrap()
+		.map(|i| locate::resolve(i, config.save_dir.as_deref(), config.game_dir.as_deref()))
+		.collect::<std::io::Result<Vec<OsString>>>()?;
+	let saves = batch::expand(
+		paths.iter().map(OsString::as_os_str),
+		&["save"],
+		matches.is_present("recursive"),
+	)?;
+	// Only the subcommands that modify a save have `--output`.
+	let output = matches.try_contains_id("output").unwrap_or(false);
+	if saves.len() > 1 && output {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidInput,
+			"--output cannot be used with more than one save file",
+		));
+	}
+	Ok(saves)
+}
+
+/// Returns the save file that a modifying subcommand should write to.
+fn target_save(matches: &ArgMatches, config: &config::Config) -> std::io::Result<OsString> {
+	let input = source_save(matches, config)?;
+	target_save_of(matches, &input).map(OsStr::to_owned)
+}
+
+/// Returns the save file named on the command line, resolving a campaign name to its path.
+fn source_save(matches: &ArgMatches, config: &config::Config) -> std::io::Result<OsString> {
+	source_save_named(matches, config, "save")
+}
+
+/// Returns the save file named on the command line by the argument `id`, resolving a campaign name
+/// to its path.
+fn source_save_named(
+	matches: &ArgMatches,
+	config: &config::Config,
+	id: &str,
+) -> std::io::Result<OsString> {
+	locate::resolve(
+		matches.value_of_os(id).unwrap(),
+		config.save_dir.as_deref(),
+		config.game_dir.as_deref(),
+	)
+}
+
+/// Returns the save file that a modifying subcommand should write to when modifying `input`.
+///
+/// Normally this is the input save itself. If `--output` was given, the input save is first copied
+/// to the output path, which is then modified instead, leaving the input untouched.
+fn target_save_of<'a>(matches: &'a ArgMatches, input: &'a OsStr) -> std::io::Result<&'a OsStr> {
+	let Some(output) = matches.value_of_os("output") else {
+		return Ok(input);
+	};
+	if dryrun::is_enabled() {
+		// The changes are previewed against the input, which the output would start as a copy of.
+		dryrun::would(format!(
+			"copy {} to {}",
+			Path::new(input).display(),
+			Path::new(output).display()
+		));
+		return Ok(input);
+	}
+	if output != input {
+		std::fs::copy(input, output)?;
+	}
+	Ok(output)
+}
+
+/// Returns the `--output` and `--in-place` options of the subcommands that modify a save.
+fn output_args() -> [Arg<'static>; 2] {
+	[
+		Arg::new("output")
+			.help("Write the modified save to this file instead of modifying the input save")
+			.long("output")
+			.short('o')
+			.takes_value(true)
+			.allow_invalid_utf8(true),
+		Arg::new("in-place")
+			.help("Modify the input save in place (the default)")
+			.long("in-place")
+			.conflicts_with("output"),
+	]
+}
+
+/// Returns the `--strip-prefix` option of the subcommands that pack files into a save.
+fn strip_prefix_arg() -> Arg<'static> {
+	Arg::new("strip-prefix")
+		.help("Remove this leading directory from the paths of files not given as NAME=PATH to name their members (e.g. --strip-prefix tmp stores tmp/gamesession.xml as gamesession.xml)")
+		.long("strip-prefix")
+		.takes_value(true)
+		.value_name("DIR")
+}
+
+/// Starts showing a progress bar if `--progress` was given, until the returned bar is dropped.
+fn progress(matches: &ArgMatches) -> Option<progress::Bar> {
+	if matches.is_present("progress") {
+		progress::Bar::start()
+	} else {
+		None
+	}
+}
+
+/// Returns the `--progress` option of the subcommands that read or write whole saves.
+fn progress_arg() -> Arg<'static> {
+	Arg::new("progress")
+		.help("Show a progress bar, with the bytes processed so far and the file being processed, if standard error is a terminal")
+		.long("progress")
+}
+
+/// Returns the `--index` option of the subcommands that read single members of a save.
+fn index_arg() -> Arg<'static> {
+	Arg::new("index")
+		.help("Find members through an index kept alongside the save, as SAVE.barotool-index, built on first use and again whenever the save changes; members of a save written by barotool are then read without decompressing those before them")
+		.long("index")
+}
+
+/// Returns the `--lossy-names` option of the subcommands that read the members of a save.
+fn lossy_names_arg() -> Arg<'static> {
+	Arg::new("lossy-names")
+		.help("Read member names that are not valid UTF-16, as in some damaged saves, with � in place of what cannot be decoded, rather than failing")
+		.long("lossy-names")
+}
+
+/// Returns the files to pack into a save, as pairs of member name and path, from the `members`
+/// and `strip-prefix` arguments.
+///
+/// A member given as `NAME=PATH` is stored as `NAME`; any other is stored under its path, less the
+/// prefix if one was given.
+fn packed_members(matches: &ArgMatches) -> std::io::Result<Vec<(String, PathBuf)>> {
+	// `./` is dropped, so that ./tmp/a and tmp/a are alike.
+	let normalize = |path: &str| -> PathBuf {
+		Path::new(path)
+			.components()
+			.filter(|i| *i != Component::CurDir)
+			.collect()
+	};
+	let prefix = matches.value_of("strip-prefix").map(normalize);
+	matches
+		.values_of("members")
+		.unwrap()
+		.map(|member| {
+			if let Some((name, path)) = member.split_once('=') {
+				if name.is_empty() {
+					return Err(exit::error(
+						exit::USAGE,
+						format!("{member} has no member name before ="),
+					));
+				}
+				return Ok((name.to_owned(), path.into()));
 			}
+			let name = match &prefix {
+				Some(prefix) => normalize(member)
+					.strip_prefix(prefix)
+					.map_err(|_| {
+						exit::error(
+							exit::USAGE,
+							format!("{member} does not start with {}", prefix.display()),
+						)
+					})?
+					.to_owned(),
+				None => PathBuf::from(member),
+			};
+			Ok((name.to_string_lossy().into_owned(), member.into()))
+		})
+		.collect()
+}
+
+/// Borrows the member names and paths returned by [`packed_members`].
+fn member_refs(members: &[(String, PathBuf)]) -> Vec<(&str, &Path)> {
+	members
+		.iter()
+		.map(|(name, path)| (name.as_str(), path.as_path()))
+		.collect()
+}
+
+/// Loads the configuration file, then applies the overrides given by environment variables and
+/// then those given on the command line.
+fn load_config(matches: &ArgMatches) -> std::io::Result<config::Config> {
+	let mut config = config::Config::load(matches.value_of_os("config"))?;
+	config.apply_environment()?;
+	if let Some(dir) = matches.value_of_os("game-dir") {
+		config.game_dir = Some(dir.into());
+	}
+	if let Some(dir) = matches.value_of_os("save-dir") {
+		config.save_dir = Some(dir.into());
+	}
+	if let Some(level) = matches.get_one::<u32>("compression-level") {
+		config.compression_level = Some(*level);
+	}
+	if let Some(jobs) = matches.get_one::<usize>("jobs") {
+		config.jobs = Some(*jobs);
+	}
+	if let Some(format) = matches.value_of("format") {
+		config.format = output::Format::from_name(format);
+	}
+	if let Some(when) = matches.value_of("color") {
+		config.color = color::When::from_name(when);
+	}
+	Ok(config)
+}
+
+/// Parses a skill assignment of the form `identifier=level`.
+fn parse_skill(value: &str) -> Result<(String, f32), String> {
+	let (identifier, level) = value
+		.split_once('=')
+		.ok_or_else(|| "expected IDENTIFIER=LEVEL".to_owned())?;
+	let level: f32 = level.parse().map_err(|e| format!("{e}"))?;
+	if !(0.0..=100.0).contains(&level) {
+		return Err("skill level must be between 0 and 100".to_owned());
+	}
+	Ok((identifier.to_owned(), level))
+}
+
+/// Parses a campaign setting assignment of the form `name=value`.
+fn parse_setting(value: &str) -> Result<(String, String), String> {
+	let (name, value) = value
+		.split_once('=')
+		.ok_or_else(|| "expected NAME=VALUE".to_owned())?;
+	Ok((name.to_owned(), value.to_owned()))
+}
+
+/// Parses a member replacement of the form `name=path`.
+fn parse_replacement(value: &str) -> Result<(String, PathBuf), String> {
+	let (name, path) = value
+		.split_once('=')
+		.ok_or_else(|| "expected NAME=PATH".to_owned())?;
+	if name.is_empty() || path.is_empty() {
+		return Err("expected a member name and a path either side of =".to_owned());
+	}
+	Ok((name.to_owned(), path.into()))
+}
+
+/// Parses an attribute assignment of the form `name=value`.
+fn parse_attribute(value: &str) -> Result<(String, String), String> {
+	let (name, value) = value
+		.split_once('=')
+		.ok_or_else(|| "expected NAME=VALUE".to_owned())?;
+	if name.is_empty() {
+		return Err("expected an attribute name before =".to_owned());
+	}
+	Ok((name.to_owned(), value.to_owned()))
+}
+
+/// Parses a color given as `R,G,B`, `R,G,B,A`, `#RRGGBB`, or `#RRGGBBAA`, returning it in the form
+/// Barotrauma saves colors in.
+fn parse_color(value: &str) -> Result<String, String> {
+	let mut channels: Vec<u8> = if let Some(hex) = value.strip_prefix('#') {
+		if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+			return Err("expected #RRGGBB or #RRGGBBAA".to_owned());
+		}
+		(0..hex.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| format!("{e}")))
+			.collect::<Result<_, _>>()?
+	} else {
+		value
+			.split(',')
+			.map(|i| i.trim().parse::<u8>().map_err(|e| format!("{i}: {e}")))
+			.collect::<Result<_, _>>()?
+	};
+	match channels.len() {
+		3 => channels.push(u8::MAX),
+		4 => (),
+		_ => return Err("expected three or four channels".to_owned()),
+	}
+	Ok(channels
+		.iter()
+		.map(u8::to_string)
+		.collect::<Vec<String>>()
+		.join(","))
+}
+
+/// Parses a reputation value.
+fn parse_reputation(value: &str) -> Result<f32, String> {
+	let value: f32 = value.parse().map_err(|e| format!("{e}"))?;
+	if !(-100.0..=100.0).contains(&value) {
+		return Err("reputation must be between -100 and 100".to_owned());
+	}
+	Ok(value)
+}
+
+fn main() -> ExitCode {
+	let matches = match make_clap_command().try_get_matches() {
+		Ok(matches) => matches,
+		Err(e) => {
+			let _ = e.print();
+			return ExitCode::from(if e.use_stderr() {
+				exit::USAGE
+			} else {
+				exit::SUCCESS
+			});
+		}
+	};
+	let level = logging::level(
+		matches.occurrences_of("verbose"),
+		matches.is_present("quiet"),
+	);
+	let log_file = match matches
+		.value_of_os("log-file")
+		.map(logging::open)
+		.transpose()
+	{
+		Ok(file) => file,
+		Err(e) => {
+			logging::init(level, None);
+			log::error!(
+				"{}: {e}",
+				Path::new(matches.value_of_os("log-file").unwrap()).display()
+			);
+			return ExitCode::from(exit::code(&e));
+		}
+	};
+	logging::init(level, log_file);
+	log::debug!(
+		"Running {}",
+		std::env::args_os()
+			.map(|i| i.to_string_lossy().into_owned())
+			.collect::<Vec<String>>()
+			.join(" ")
+	);
+	output::set_quiet(matches.is_present("quiet"));
+	let code = match run(&matches) {
+		Ok(()) => exit::SUCCESS,
+		Err(e) => {
+			// Commands that act on a single save have not yet said which file a problem was in.
+			let save = selected(&matches)
+				.1
+				.try_get_raw("save")
+				.ok()
+				.flatten()
+				.and_then(|mut i| i.next());
+			let e = match save {
+				Some(save) if Path::new(save).is_file() => {
+					diagnostic::in_save(e.into(), save).into()
+				}
+				_ => e,
+			};
+			log::error!("{e}");
+			exit::code(&e)
+		}
+	};
+	log::debug!("Exiting with status {code}");
+	ExitCode::from(code)
+}
+
+/// Returns the flat name of the subcommand selected on the command line, and its arguments.
+///
+/// A subcommand given as a noun and verb, such as `save list`, is reported under its flat name,
+/// such as `list-save`.
+fn selected(matches: &ArgMatches) -> (&str, &ArgMatches) {
+	let (name, matches) = matches.subcommand().unwrap();
+	if let Some((verb, sub)) = matches.subcommand() {
+		let nested = NESTED
+			.iter()
+			.find(|(_, noun, v)| *noun == name && *v == verb);
+		if let Some((flat, _, _)) = nested {
+			return (flat, sub);
+		}
+	}
+	(name, matches)
+}
+
+/// Returns whether a subcommand must not be paged.
+///
+/// Only human-readable text is paged. Subcommands that write a file, such as a save or submarine,
+/// to standard output are not, nor is `edit-save`, whose editor needs the terminal.
+fn unpaged(name: &str, matches: &ArgMatches) -> bool {
+	match name {
+		"cat-save" | "compress-sub" | "decompress-sub" | "transform-sub" | "edit-save" => true,
+		"pack-save" => matches.value_of_os("save") == Some(OsStr::new("-")),
+		"convert-save" => matches.value_of_os("to") == Some(OsStr::new("-")),
+		_ => false,
+	}
+}
+
+/// Runs the subcommand selected on the command line.
+// Dispatch is long, but each subcommand’s handling is short and independent.
+#[allow(clippy::too_many_lines)]
+fn run(matches: &ArgMatches) -> std::io::Result<()> {
+	if let Some((name, args)) = matches.subcommand() {
+		let command = make_clap_command();
+		if command.find_subcommand(name).is_none() {
+			return plugin::run(&command, matches, name, args);
+		}
+	}
+	let config = load_config(matches)?;
+	color::init(config.color.unwrap_or(color::When::Auto));
+	if let Some(level) = config.compression_level {
+		compression::set_level(level);
+	}
+	if let Some(jobs) = config.jobs {
+		batch::set_jobs(jobs);
+	}
+	limits::set_limits(config.limits.resolve());
+	dryrun::set_enabled(matches.is_present("dry-run"));
+	let format = config.format.unwrap_or(output::Format::Text);
+	let backup_dir = config.backup.dir.as_deref().map(Path::as_os_str);
+	// Per-file headers would corrupt structured output.
+	let headers = format == output::Format::Text && !output::is_quiet();
+	let (name, matches) = selected(matches);
+	// Colors are decided first, while standard output is still the terminal.
+	let _pager = if matches.is_present("no-pager") || unpaged(name, matches) {
+		None
+	} else {
+		pager::start()
+	};
+	match name {
+		"generate-man" => {
+			let dir = matches.value_of_os("dir").unwrap();
+			man::generate(make_clap_command(), Path::new(dir))?;
+		}
+		"locate-saves" => {
+			locate::list(
+				config.save_dir.as_deref(),
+				config.game_dir.as_deref(),
+				format,
+			)?;
+		}
+		"show-config" => {
+			config::show(&config, format)?;
+		}
+		"roundtrip-check" => {
+			let paths = matches.values_of_os("paths").unwrap().collect::<Vec<_>>();
+			roundtrip::check_all(
+				&paths,
+				matches.is_present("recursive"),
+				matches.is_present("bytes"),
+				format,
+			)?;
+		}
+		"list-save" => {
+			let filter = MemberFilter::new(matches.values_of("filter").into_iter().flatten());
+			let digests = archive::Digests {
+				sha256: matches.is_present("digest"),
+				crc32: matches.is_present("crc32"),
+			};
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				archive::list(
+					filename,
+					&mut filter.clone(),
+					digests,
+					matches.is_present("lossy-names"),
+					format,
+				)
+			})?;
+		}
+		"pack-save" => {
+			let _progress = progress(matches);
+			let filename = matches.value_of_os("save").unwrap();
+			let mut members = match matches.value_of_os("from-dir") {
+				Some(dir) => archive::dir_members(Path::new(dir))?,
+				None => packed_members(matches)?,
+			};
+			if matches.is_present("sort") {
+				// Sorting the list rather than the archive spares holding the archive in memory.
+				members.sort_by(|a, b| a.0.cmp(&b.0));
+			}
+			let options = PackOptions {
+				oversize: if matches.is_present("split") {
+					Oversize::Split
+				} else {
+					Oversize::Refuse
+				},
+				segmented: matches.is_present("segmented"),
+			};
+			archive::pack(filename, &member_refs(&members), options)?;
+		}
+		"unpack-save" => {
+			let _progress = progress(matches);
+			let filename = source_save(matches, &config)?;
+			let mut members = MemberFilter::new(matches.values_of("members").into_iter().flatten());
+			let dir = matches.value_of_os("output-dir").map(Path::new);
+			let names = if matches.is_present("unsafe-names") {
+				MemberNames::Unsafe
+			} else {
+				MemberNames::Sanitize
+			};
+			let existing = if matches.is_present("no-clobber") {
+				Existing::Skip
+			} else if matches.is_present("overwrite") {
+				Existing::Overwrite
+			} else if matches.is_present("keep-newer") {
+				// A save read from standard input is taken to be as new as it gets.
+				let modified = std::fs::metadata(&filename).and_then(|i| i.modified());
+				Existing::KeepNewer(modified.unwrap_or_else(|_| std::time::SystemTime::now()))
+			} else {
+				Existing::Refuse
+			};
+			let options = UnpackOptions { names, existing };
+			archive::unpack(
+				&filename,
+				dir,
+				&mut members,
+				options,
+				matches.is_present("lossy-names"),
+				matches.is_present("index"),
+			)?;
+			if members.unmatched().next().is_some() {
+				log::warn!("Some members were not found:");
+				for i in members.unmatched() {
+					log::warn!("{i}");
+				}
+			}
+		}
+		"add-save" => {
+			let filename = target_save(matches, &config)?;
+			let members = packed_members(matches)?;
+			archive::add(&filename, &member_refs(&members))?;
+		}
+		"remove-save" => {
+			let filename = target_save(matches, &config)?;
+			let mut members = matches
+				.values_of("members")
+				.unwrap()
+				.collect::<HashSet<&str>>();
+			archive::remove(&filename, &mut members)?;
+			if !members.is_empty() {
+				log::warn!("Some members were not found:");
+				for i in members {
+					log::warn!("{i}");
+				}
+			}
+		}
+		"update-save" => {
+			let filename = target_save(matches, &config)?;
+			let members = matches
+				.get_many::<(String, PathBuf)>("members")
+				.unwrap()
+				.map(|(name, path)| (name.as_str(), path.as_path()))
+				.collect::<Vec<(&str, &Path)>>();
+			archive::replace(&filename, &members)?;
+		}
+		"cat-save" => {
+			let filename = source_save(matches, &config)?;
+			archive::cat(
+				&filename,
+				matches.value_of("member").unwrap(),
+				matches.is_present("index"),
+			)?;
+		}
+		"decompress-sub" => {
+			let input = matches.value_of_os("sub").unwrap();
+			let output = matches.value_of_os("xml").unwrap();
+			submarine::decompress(stream::open_input(input)?, stream::create_output(output)?)?;
+		}
+		"compress-sub" => {
+			let input = matches.value_of_os("xml").unwrap();
+			let output = matches.value_of_os("sub").unwrap();
+			submarine::compress(stream::open_input(input)?, stream::create_output(output)?)?;
+		}
+		"transform-sub" => {
+			let input = matches.value_of_os("sub").unwrap();
+			let output = matches.value_of_os("output-sub").unwrap();
+			let mut attributes: Vec<(String, String)> = matches
+				.get_many::<(String, String)>("set-attr")
+				.map_or_else(Vec::new, |i| i.cloned().collect());
+			if let Some(color) = matches.get_one::<String>("recolor") {
+				attributes.push(("spritecolor".to_owned(), color.clone()));
+			}
+			let edits = transform::Edits {
+				attributes,
+				strip: matches.is_present("strip"),
+			};
+			let selection = selector::Selection::from_matches(matches);
+			transform::transform(input, output, &edits, &selection)?;
+		}
+		"backup-campaign" => {
+			let dir = matches.value_of_os("dir").or(backup_dir);
+			let keep = matches
+				.get_one::<usize>("keep")
+				.copied()
+				.or(config.backup.keep);
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				backup::backup(filename, dir, keep)
+			})?;
+		}
+		"list-backups" => {
+			let filename = source_save(matches, &config)?;
+			let dir = matches.value_of_os("dir").or(backup_dir);
+			backup::list_backups(&filename, dir, format)?;
+		}
+		"restore-campaign" => {
+			let filename = source_save(matches, &config)?;
+			let dir = matches.value_of_os("dir").or(backup_dir);
+			let snapshot = matches.value_of_os("snapshot");
+			backup::restore(&filename, dir, snapshot)?;
+		}
+		"show-campaign" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::show(filename, format)
+			})?;
+		}
+		"validate-save" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::validate(filename, format)
+			})?;
+		}
+		"verify-save" => {
+			let _progress = progress(matches);
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				archive::verify(filename, format)
+			})?;
+		}
+		"diff-save" => {
+			let old = source_save_named(matches, &config, "old")?;
+			let new = source_save_named(matches, &config, "new")?;
+			archive::diff(&old, &new, matches.is_present("text"), format)?;
+		}
+		"merge-save" => {
+			let out = matches.value_of_os("out").unwrap();
+			let base = source_save_named(matches, &config, "base")?;
+			let overlay = source_save_named(matches, &config, "overlay")?;
+			archive::merge(out, &base, &overlay)?;
+		}
+		"edit-save" => {
+			let filename = target_save(matches, &config)?;
+			archive::edit(
+				&filename,
+				matches.value_of("member").unwrap(),
+				matches.value_of_os("editor"),
+			)?;
+		}
+		"patch-save" => {
+			let filename = target_save(matches, &config)?;
+			archive::patch(
+				&filename,
+				matches.value_of("member").unwrap(),
+				matches.value_of_os("patch").unwrap(),
+			)?;
+		}
+		"grep-save" => {
+			let filename = source_save(matches, &config)?;
+			let pattern = matches.get_one::<String>("pattern").unwrap();
+			let pattern = regex::RegexBuilder::new(pattern)
+				.case_insensitive(matches.is_present("ignore-case"))
+				.build()
+				.map_err(|e| exit::error(exit::USAGE, e.to_string()))?;
+			let mut members = MemberFilter::new(matches.values_of("members").into_iter().flatten());
+			archive::grep(&filename, &pattern, &mut members, format)?;
+		}
+		"convert-save" => {
+			let from = matches.value_of_os("from").unwrap();
+			let to = matches.value_of_os("to").unwrap();
+			if matches.is_present("to-zip") {
+				archive::to_zip(&source_save_named(matches, &config, "from")?, to)?;
+			} else if matches.is_present("to-tar") {
+				archive::to_tar(&source_save_named(matches, &config, "from")?, to)?;
+			} else if matches.is_present("from-zip") {
+				archive::from_zip(from, to)?;
+			} else {
+				archive::from_tar(from, to)?;
+			}
+		}
+		"set-money" => {
+			let filename = target_save(matches, &config)?;
+			let amount = *matches.get_one::<i32>("amount").unwrap();
+			campaign::set_money(&filename, amount, matches.value_of("character"))?;
+		}
+		"list-subs" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::list_submarines(filename, format)
+			})?;
+		}
+		"add-sub" => {
+			let filename = target_save(matches, &config)?;
+			let sub = matches.value_of_os("sub").unwrap();
+			campaign::add_submarine(&filename, sub)?;
+		}
+		"remove-sub" => {
+			let filename = target_save(matches, &config)?;
+			let name = matches.value_of("name").unwrap();
+			campaign::remove_submarine(&filename, name)?;
+		}
+		"switch-active-sub" => {
+			let filename = target_save(matches, &config)?;
+			let name = matches.value_of("name").unwrap();
+			campaign::switch_submarine(&filename, name)?;
+		}
+		"upgrade-campaign-sub" => {
+			let filename = target_save(matches, &config)?;
+			let sub = matches.value_of_os("sub").unwrap();
+			campaign::upgrade_submarine(&filename, sub, matches.value_of("replace"))?;
+		}
+		"list-upgrades" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::list_upgrades(filename, format)
+			})?;
+		}
+		"set-upgrade" => {
+			let filename = target_save(matches, &config)?;
+			let identifier = matches.value_of("identifier").unwrap();
+			let level = *matches.get_one::<u32>("level").unwrap();
+			campaign::set_upgrade(&filename, identifier, level)?;
+		}
+		"list-crew" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::list_crew(filename, format)
+			})?;
+		}
+		"set-skill" => {
+			let filename = target_save(matches, &config)?;
+			let character = matches
+				.value_of("character-name")
+				.or_else(|| matches.value_of("character"))
+				.unwrap();
+			let skills = ["skills", "skill"]
+				.iter()
+				.filter_map(|i| matches.get_many::<(String, f32)>(i))
+				.flatten()
+				.map(|(identifier, level)| (identifier.as_str(), *level))
+				.collect::<Vec<(&str, f32)>>();
+			campaign::set_skills(&filename, character, &skills)?;
+		}
+		"grant-experience" => {
+			let filename = target_save(matches, &config)?;
+			let character = matches.value_of("character").unwrap();
+			let amount = *matches.get_one::<u32>("amount").unwrap();
+			campaign::grant_experience(&filename, character, amount)?;
+		}
+		"unlock-talent" => {
+			let filename = target_save(matches, &config)?;
+			let character = matches.value_of("character").unwrap();
+			let talents = matches.values_of("talents").unwrap().collect::<Vec<&str>>();
+			campaign::unlock_talents(&filename, character, &talents)?;
+		}
+		"heal-crew" => {
+			let afflictions = matches
+				.values_of("affliction")
+				.map_or_else(Vec::new, Iterator::collect::<Vec<&str>>);
+			let character = matches.value_of("character");
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::heal_crew(target_save_of(matches, filename)?, character, &afflictions)
+			})?;
+		}
+		"rename-crew" => {
+			let filename = target_save(matches, &config)?;
+			let old = matches.value_of("old").unwrap();
+			let new = matches.value_of("new").unwrap();
+			campaign::rename_crew(&filename, old, new)?;
+		}
+		"edit-crew" => {
+			let filename = target_save(matches, &config)?;
+			let character = matches.value_of("character").unwrap();
+			let appearance = Appearance {
+				gender: matches.value_of("gender").map(str::to_owned),
+				head_index: matches.get_one::<u32>("head-index").copied(),
+				hair_index: matches.get_one::<i32>("hair-index").copied(),
+				tags: matches.value_of("tags").map(str::to_owned),
+			};
+			campaign::edit_crew(
+				&filename,
+				character,
+				matches.value_of("name"),
+				matches.value_of("job"),
+				&appearance,
+			)?;
+		}
+		"add-crew" => {
+			let filename = target_save(matches, &config)?;
+			let template = matches.value_of_os("template").unwrap();
+			campaign::add_crew(&filename, template)?;
+		}
+		"add-bot" => {
+			let filename = target_save(matches, &config)?;
+			let description = matches.value_of_os("description").unwrap();
+			campaign::add_bot(&filename, description)?;
+		}
+		"remove-crew" => {
+			let filename = target_save(matches, &config)?;
+			let name = matches.value_of("name").unwrap();
+			campaign::remove_crew(&filename, name)?;
+		}
+		"prune-crew" => {
+			let filename = target_save(matches, &config)?;
+			campaign::prune_crew(&filename)?;
+		}
+		"list-hires" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::list_hires(filename, format)
+			})?;
+		}
+		"add-hire" => {
+			let filename = target_save(matches, &config)?;
+			let template = matches.value_of_os("template").unwrap();
+			campaign::add_hire(&filename, template)?;
+		}
+		"remove-hire" => {
+			let filename = target_save(matches, &config)?;
+			let name = matches.value_of("character").unwrap();
+			campaign::remove_hire(&filename, name)?;
+		}
+		"list-pets" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::list_pets(filename, format)
+			})?;
+		}
+		"show-reputation" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::show_reputation(filename, format)
+			})?;
+		}
+		"set-reputation" => {
+			let filename = target_save(matches, &config)?;
+			let target = matches.value_of("target").unwrap();
+			let value = *matches.get_one::<f32>("value").unwrap();
+			campaign::set_reputation(&filename, target, value, matches.is_present("location"))?;
+		}
+		"list-cargo" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::list_cargo(filename, format)
+			})?;
+		}
+		"set-cargo" => {
+			let filename = target_save(matches, &config)?;
+			let identifier = matches.value_of("identifier").unwrap();
+			let quantity = *matches.get_one::<u32>("quantity").unwrap();
+			campaign::set_cargo(&filename, identifier, quantity)?;
+		}
+		"clear-cargo" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::clear_cargo(target_save_of(matches, filename)?)
+			})?;
+		}
+		"show-progress" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::show_progress(filename, format)
+			})?;
+		}
+		"set-progress" => {
+			let filename = target_save(matches, &config)?;
+			let progress = gamesession::Progress {
+				play_time: matches.get_one::<u32>("play-time").map(|i| f64::from(*i)),
+				passed_levels: matches.get_one::<u32>("passed-levels").copied(),
+			};
+			campaign::set_progress(&filename, progress)?;
+		}
+		"list-settings" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::list_settings(filename, format)
+			})?;
+		}
+		"set-setting" => {
+			let filename = target_save(matches, &config)?;
+			let settings = matches
+				.get_many::<(String, String)>("settings")
+				.unwrap()
+				.map(|(name, value)| (name.as_str(), value.as_str()))
+				.collect::<Vec<(&str, &str)>>();
+			campaign::set_settings(&filename, &settings)?;
+		}
+		"reveal-map" => {
+			let radius = matches.get_one::<usize>("radius").copied();
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::reveal_map(target_save_of(matches, filename)?, radius)
+			})?;
+		}
+		"hide-map" => {
+			let filename = target_save(matches, &config)?;
+			campaign::hide_map(&filename, *matches.get_one::<usize>("keep").unwrap())?;
+		}
+		"list-missions" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::list_missions(filename, format)
+			})?;
+		}
+		"remove-mission" => {
+			let filename = target_save(matches, &config)?;
+			let identifier = matches.value_of("identifier").unwrap();
+			campaign::remove_mission(&filename, identifier)?;
+		}
+		"show-levels" => {
+			batch::run(&saves(matches, &config)?, headers, |filename| {
+				campaign::show_levels(filename, format)
+			})?;
+		}
+		"set-level-seed" => {
+			let filename = target_save(matches, &config)?;
+			let seed = matches.value_of("seed").unwrap();
+			let choice = if matches.is_present("location") {
+				gamesession::LevelChoice::Location
+			} else {
+				gamesession::LevelChoice::Next
+			};
+			campaign::set_level_seed(&filename, choice, seed)?;
+		}
+		"list-inventory" => {
+			let filename = source_save(matches, &config)?;
+			let character = matches.value_of("character").unwrap();
+			let selection = selector::Selection::from_matches(matches);
+			campaign::list_inventory(&filename, character, &selection, format)?;
+		}
+		"add-item" => {
+			let filename = target_save(matches, &config)?;
+			let character = matches.value_of("character").unwrap();
+			let identifier = matches.value_of("identifier").unwrap();
+			let slot = matches.get_one::<usize>("slot").copied();
+			campaign::add_item(&filename, character, identifier, slot)?;
+		}
+		"remove-item" => {
+			let filename = target_save(matches, &config)?;
+			let character = matches.value_of("character").unwrap();
+			let mut selection = selector::Selection::from_matches(matches);
+			if let Some(identifier) = matches.value_of("identifier") {
+				selection
+					.include
+					.push(selector::Selector::Identifier(identifier.to_owned()));
+			}
+			campaign::remove_item(&filename, character, &selection)?;
+		}
+		"swap-items" => {
+			let filename = target_save(matches, &config)?;
+			let character = matches.value_of("character").unwrap();
+			let a = *matches.get_one::<usize>("a").unwrap();
+			let b = *matches.get_one::<usize>("b").unwrap();
+			campaign::swap_items(&filename, character, a, b)?;
 		}
+		_ => unreachable!("unhandled subcommand {}", name),
 	}
 	Ok(())
 }
 
 #[test]
 fn test_make_clap_command() {
-	make_clap_command().debug_assert()
+	make_clap_command().debug_assert();
 }
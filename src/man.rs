@@ -0,0 +1,176 @@
+use crate::dryrun;
+use crate::output;
+use clap::{Arg, Command};
+use roff::{bold, italic, roman, Inline, Roff};
+use std::fmt::Write;
+use std::io::Result;
+use std::path::Path;
+
+/// Returns the name of the manual page for a command, such as `barotool-save-list`.
+fn page_name(command: &Command<'_>) -> String {
+	command
+		.get_bin_name()
+		.unwrap_or_else(|| command.get_name())
+		.replace(' ', "-")
+}
+
+/// Returns the usage line for a command, without the `USAGE:` heading.
+fn usage(command: &Command<'_>) -> String {
+	let usage = command.clone().render_usage();
+	let usage = usage.strip_prefix("USAGE:").unwrap_or(&usage);
+	usage.trim().to_owned()
+}
+
+/// Appends the description of an argument to a page, as a tagged paragraph.
+fn describe_arg(page: &mut Roff, arg: &Arg<'_>) {
+	let value_name = arg
+		.get_value_names()
+		.map_or_else(|| arg.get_id().to_uppercase(), |i| i.join(" "));
+	let mut tag: Vec<Inline> = Vec::new();
+	if arg.is_positional() {
+		tag.push(italic(value_name));
+	} else {
+		if let Some(short) = arg.get_short() {
+			tag.push(bold(format!("-{short}")));
+		}
+		if let Some(long) = arg.get_long() {
+			if !tag.is_empty() {
+				tag.push(roman(", "));
+			}
+			tag.push(bold(format!("--{long}")));
+		}
+		if arg.is_takes_value_set() {
+			tag.push(roman("="));
+			tag.push(italic(value_name));
+		}
+	}
+	page.control("TP", []).text(tag);
+
+	let mut help = arg
+		.get_long_help()
+		.or_else(|| arg.get_help())
+		.unwrap_or_default()
+		.to_owned();
+	if let Some(values) = arg.get_possible_values() {
+		let values: Vec<&str> = values.iter().map(clap::PossibleValue::get_name).collect();
+		let _ = write!(help, " [possible values: {}]", values.join(", "));
+	}
+	let defaults: Vec<String> = arg
+		.get_default_values()
+		.iter()
+		.map(|i| i.to_string_lossy().into_owned())
+		.collect();
+	if !defaults.is_empty() {
+		let _ = write!(help, " [default: {}]", defaults.join(", "));
+	}
+	page.text([roman(help)]);
+}
+
+/// Renders the manual page for one command.
+///
+/// `top` is the top-level command. Its page documents the global options; each subcommand’s page
+/// refers back to it for them. Pages for commands with subcommands list them.
+fn render<'help>(command: &Command<'help>, top: &Command<'help>) -> String {
+	let is_top = std::ptr::eq(command, top);
+	let name = page_name(command);
+	let version = top.get_version().unwrap_or_default();
+	let mut page = Roff::new();
+	page.control(
+		"TH",
+		[
+			name.to_uppercase().as_str(),
+			"1",
+			"",
+			&format!("{} {version}", top.get_name()),
+		],
+	);
+
+	page.control("SH", ["NAME"]);
+	let about = command.get_about().unwrap_or_default();
+	page.text([roman(format!(
+		"{name} - {}",
+		about.strip_suffix('.').unwrap_or(about)
+	))]);
+
+	page.control("SH", ["SYNOPSIS"]);
+	page.text([bold(usage(command))]);
+
+	page.control("SH", ["DESCRIPTION"]);
+	page.text([roman(command.get_long_about().unwrap_or(about))]);
+
+	let args: Vec<&Arg<'_>> = command
+		.get_arguments()
+		.filter(|i| !i.is_hide_set() && (is_top || !i.is_global_set()))
+		.collect();
+	let positionals: Vec<&Arg<'_>> = args.iter().copied().filter(|i| i.is_positional()).collect();
+	let options: Vec<&Arg<'_>> = args
+		.iter()
+		.copied()
+		.filter(|i| !i.is_positional())
+		.collect();
+	if !positionals.is_empty() {
+		page.control("SH", ["ARGUMENTS"]);
+		for arg in positionals {
+			describe_arg(&mut page, arg);
+		}
+	}
+	if !options.is_empty() {
+		page.control("SH", ["OPTIONS"]);
+		for arg in options {
+			describe_arg(&mut page, arg);
+		}
+	}
+
+	if command.has_subcommands() {
+		page.control("SH", ["SUBCOMMANDS"]);
+		for sub in subcommands(command) {
+			page.control("TP", [])
+				.text([bold(page_name(sub)), roman("(1)")]);
+			page.text([roman(sub.get_about().unwrap_or_default())]);
+		}
+	}
+	if !is_top {
+		page.control("SH", ["SEE ALSO"]);
+		page.text([bold(page_name(top)), roman("(1)")]);
+	}
+	page.render()
+}
+
+/// Returns the documented subcommands of a command.
+fn subcommands<'a, 'help>(command: &'a Command<'help>) -> impl Iterator<Item = &'a Command<'help>> {
+	command
+		.get_subcommands()
+		.filter(|i| !i.is_hide_set() && i.get_name() != "help")
+}
+
+/// Renders the pages for a command’s subcommands, and theirs in turn, appending them to `pages`.
+fn render_subcommands<'help>(
+	command: &Command<'help>,
+	top: &Command<'help>,
+	pages: &mut Vec<(String, String)>,
+) {
+	for sub in subcommands(command) {
+		pages.push((page_name(sub), render(sub, top)));
+		render_subcommands(sub, top, pages);
+	}
+}
+
+/// Writes manual pages for a command and each of its subcommands into a directory.
+pub fn generate(mut command: Command<'_>, dir: &Path) -> Result<()> {
+	command.build();
+	let mut pages = vec![(page_name(&command), render(&command, &command))];
+	render_subcommands(&command, &command, &mut pages);
+	if !dryrun::is_enabled() {
+		std::fs::create_dir_all(dir)?;
+	}
+	for (name, text) in pages {
+		let path = dir.join(format!("{name}.1"));
+		if dryrun::is_enabled() {
+			dryrun::would(format!("write {}", path.display()));
+			continue;
+		}
+		std::fs::write(&path, text)?;
+		output::note(format!("Wrote {}", path.display()));
+	}
+	Ok(())
+}
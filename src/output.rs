@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::io::{Result, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether normal output is suppressed.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether normal output is suppressed.
+///
+/// Errors are still reported, and the exit status still reflects the outcome of the command.
+pub fn set_quiet(quiet: bool) {
+	QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Returns whether normal output is suppressed.
+pub fn is_quiet() -> bool {
+	QUIET.load(Ordering::Relaxed)
+}
+
+thread_local! {
+	/// The output collected on this thread by [`capture`], if it is collecting.
+	static CAPTURED: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+}
+
+/// Standard output, or the buffer collecting output on this thread while [`capture`] runs.
+///
+/// All normal output goes through this, by way of [`outln!`], so that files processed in parallel
+/// can have their output printed in order afterwards.
+pub struct Stdout;
+
+impl Write for Stdout {
+	fn write(&mut self, buf: &[u8]) -> Result<usize> {
+		let captured = CAPTURED.with(|i| {
+			i.borrow_mut()
+				.as_mut()
+				.map(|captured| captured.extend_from_slice(buf))
+				.is_some()
+		});
+		if captured {
+			Ok(buf.len())
+		} else {
+			std::io::stdout().write(buf)
+		}
+	}
+
+	fn flush(&mut self) -> Result<()> {
+		std::io::stdout().flush()
+	}
+}
+
+/// Prints a line to [`Stdout`], as `println!` does to standard output.
+macro_rules! outln {
+	($($arg:tt)*) => {{
+		use std::io::Write as _;
+		// As with println!, there is nowhere to report a failure to print.
+		let _ = writeln!($crate::output::Stdout, $($arg)*);
+	}};
+}
+pub(crate) use outln;
+
+/// Runs `f`, collecting what it prints instead of printing it.
+///
+/// Returns the result of `f` along with what it printed.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<u8>) {
+	let outer = CAPTURED.with(|i| i.replace(Some(Vec::new())));
+	let ret = f();
+	let captured = CAPTURED.with(|i| i.replace(outer)).unwrap_or_default();
+	(ret, captured)
+}
+
+/// Prints a message reporting what a command did, unless output is suppressed.
+pub fn note<T: Display>(message: T) {
+	if !is_quiet() {
+		outln!("{message}");
+	}
+}
+
+/// The format in which read-only commands print their results.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+	/// Human-readable, tab-separated text.
+	Text,
+
+	/// A JSON document.
+	Json,
+
+	/// A YAML document.
+	Yaml,
+}
+
+impl Format {
+	/// The names of the formats, as accepted on the command line.
+	pub const NAMES: [&'static str; 3] = ["text", "json", "yaml"];
+
+	/// Returns the format with the given command-line name.
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"text" => Some(Self::Text),
+			"json" => Some(Self::Json),
+			"yaml" => Some(Self::Yaml),
+			_ => None,
+		}
+	}
+
+	/// Returns the format’s command-line name.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Text => "text",
+			Self::Json => "json",
+			Self::Yaml => "yaml",
+		}
+	}
+}
+
+/// Prints the result of a command.
+///
+/// In the structured formats, `value` is serialized to standard output. In text format, `text` is
+/// called to print it instead. Nothing is printed if output is suppressed.
+pub fn emit<T: Serialize + ?Sized, F: FnOnce(&T)>(
+	format: Format,
+	value: &T,
+	text: F,
+) -> Result<()> {
+	if is_quiet() {
+		return Ok(());
+	}
+	match format {
+		Format::Text => text(value),
+		Format::Json => {
+			serde_json::to_writer_pretty(Stdout, value)?;
+			outln!();
+		}
+		Format::Yaml => serde_yaml::to_writer(Stdout, value)
+			.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+	}
+	Ok(())
+}
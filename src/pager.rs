@@ -0,0 +1,102 @@
+use std::io::{IsTerminal, Write};
+use std::process::Child;
+
+/// A pager to which standard output has been redirected.
+///
+/// When dropped, standard output is flushed and restored, and the pager is waited for.
+pub struct Pager {
+	/// The pager process.
+	child: Child,
+
+	/// A duplicate of the original standard output, to restore afterwards.
+	#[cfg(unix)]
+	original: std::os::unix::io::OwnedFd,
+}
+
+/// Starts a pager and redirects standard output into it, if standard output is a terminal.
+///
+/// The pager is the command in the `PAGER` environment variable, run by the shell, or `less` if it
+/// is unset. As Git does, `less` is told by setting `LESS=FRX` (unless `LESS` is already set) to
+/// quit at once if the output fits on one screen and to pass colors through. An empty `PAGER`, or
+/// `cat`, disables paging.
+///
+/// Paging is only supported on Unix; elsewhere this does nothing.
+pub fn start() -> Option<Pager> {
+	if !std::io::stdout().is_terminal() {
+		return None;
+	}
+	let command = std::env::var_os("PAGER").unwrap_or_else(|| "less".into());
+	if command.is_empty() || command == "cat" {
+		return None;
+	}
+	spawn(&command)
+}
+
+/// Starts a pager and redirects standard output into it.
+#[cfg(unix)]
+fn spawn(command: &std::ffi::OsStr) -> Option<Pager> {
+	use std::process::{Command, Stdio};
+
+	let mut process = Command::new("sh");
+	process.arg("-c").arg(command).stdin(Stdio::piped());
+	if std::env::var_os("LESS").is_none() {
+		process.env("LESS", "FRX");
+	}
+	let mut child = match process.spawn() {
+		Ok(child) => child,
+		Err(e) => {
+			log::warn!("Cannot run pager {}: {e}", command.to_string_lossy());
+			return None;
+		}
+	};
+	let pipe = child.stdin.take().unwrap();
+	let _ = std::io::stdout().flush();
+	let redirected = rustix::io::dup(std::io::stdout())
+		.and_then(|original| rustix::stdio::dup2_stdout(&pipe).map(|()| original));
+	let original = match redirected {
+		Ok(original) => original,
+		Err(e) => {
+			log::warn!("Cannot redirect output to pager: {e}");
+			drop(pipe);
+			let _ = child.wait();
+			return None;
+		}
+	};
+	quit_on_broken_pipe();
+	Some(Pager { child, original })
+}
+
+/// Does nothing, as paging is not supported on this platform.
+#[cfg(not(unix))]
+fn spawn(_command: &std::ffi::OsStr) -> Option<Pager> {
+	None
+}
+
+/// Arranges for the program to exit quietly if the user quits the pager before reading everything.
+///
+/// Printing to a closed pipe panics; without this, the panic message would be shown.
+#[cfg(unix)]
+fn quit_on_broken_pipe() {
+	let previous = std::panic::take_hook();
+	std::panic::set_hook(Box::new(move |info| {
+		let message = info
+			.payload()
+			.downcast_ref::<String>()
+			.map_or("", String::as_str);
+		if message.starts_with("failed printing to stdout") {
+			std::process::exit(0);
+		}
+		previous(info);
+	}));
+}
+
+impl Drop for Pager {
+	fn drop(&mut self) {
+		let _ = std::io::stdout().flush();
+		// Restoring standard output closes this process’s end of the pipe, so the pager sees the end
+		// of the output.
+		#[cfg(unix)]
+		let _ = rustix::stdio::dup2_stdout(&self.original);
+		let _ = self.child.wait();
+	}
+}
@@ -0,0 +1,90 @@
+use crate::config::Config;
+use crate::exit;
+use clap::{ArgMatches, Command};
+use std::ffi::{OsStr, OsString};
+use std::io::Result;
+
+/// The prefix of the names of plugin executables.
+const PREFIX: &str = "barotool-";
+
+/// Returns the command-line arguments that repeat the global options given in `matches`, in long
+/// form.
+fn global_arguments(command: &Command<'_>, matches: &ArgMatches) -> Vec<OsString> {
+	let mut ret = Vec::new();
+	for arg in command.get_arguments().filter(|i| i.is_global_set()) {
+		let id = arg.get_id();
+		let Some(long) = arg.get_long() else {
+			continue;
+		};
+		let flag = OsString::from(format!("--{long}"));
+		if arg.is_takes_value_set() {
+			for value in matches.try_get_raw(id).ok().flatten().into_iter().flatten() {
+				ret.push(flag.clone());
+				ret.push(value.to_owned());
+			}
+		} else {
+			for _ in 0..matches.occurrences_of(id) {
+				ret.push(flag.clone());
+			}
+		}
+	}
+	ret
+}
+
+/// Runs the plugin that provides a subcommand that barotool does not have itself.
+///
+/// `barotool foo ARGS` runs the executable `barotool-foo`, found on `PATH`, with the global
+/// options given before `foo`, in long form, followed by `ARGS`. The plugin is told where to find
+/// the `barotool` executable by the `BAROTOOL` environment variable and the configuration file in
+/// use by `BAROTOOL_CONFIG`, so that it can read the same settings or run barotool itself. On Unix
+/// the plugin replaces this process; elsewhere, barotool waits for it and exits with its status.
+pub fn run(
+	command: &Command<'_>,
+	matches: &ArgMatches,
+	name: &str,
+	args: &ArgMatches,
+) -> Result<()> {
+	let program = format!("{PREFIX}{name}");
+	let mut plugin = std::process::Command::new(&program);
+	plugin
+		.args(global_arguments(command, matches))
+		.args(args.values_of_os("").into_iter().flatten());
+	if let Ok(exe) = std::env::current_exe() {
+		plugin.env("BAROTOOL", exe);
+	}
+	let config = matches
+		.value_of_os("config")
+		.map(OsStr::to_owned)
+		.or_else(|| std::env::var_os("BAROTOOL_CONFIG").filter(|i| !i.is_empty()))
+		.or_else(|| {
+			Config::default_path()
+				.filter(|i| i.is_file())
+				.map(OsString::from)
+		});
+	if let Some(config) = config {
+		plugin.env("BAROTOOL_CONFIG", config);
+	}
+	log::debug!("Running plugin {program}");
+	let not_found = |e: std::io::Error| {
+		if e.kind() == std::io::ErrorKind::NotFound {
+			exit::error(
+				exit::USAGE,
+				format!("{name} is not a barotool command, and there is no {program} on PATH"),
+			)
+		} else {
+			std::io::Error::new(e.kind(), format!("{program}: {e}"))
+		}
+	};
+	#[cfg(unix)]
+	{
+		use std::os::unix::process::CommandExt as _;
+		// Only returns if the plugin could not be run.
+		Err(not_found(plugin.exec()))
+	}
+	#[cfg(not(unix))]
+	{
+		let status = plugin.status().map_err(not_found)?;
+		log::logger().flush();
+		std::process::exit(status.code().unwrap_or(i32::from(exit::IO_ERROR)));
+	}
+}
@@ -0,0 +1,88 @@
+use barotool::save::{self, Progress};
+use std::io::IsTerminal;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often the progress bar is redrawn, at most.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// The width of the bar itself, in characters.
+const WIDTH: u64 = 30;
+
+/// The most characters of a member’s name shown.
+const NAME_WIDTH: usize = 40;
+
+/// A progress bar on standard error, showing the progress of reading and writing save files for as
+/// long as it is alive.
+pub struct Bar(());
+
+impl Bar {
+	/// Starts showing a progress bar, unless standard error is not a terminal.
+	pub fn start() -> Option<Self> {
+		if !std::io::stderr().is_terminal() {
+			return None;
+		}
+		let drawn: Mutex<Option<Instant>> = Mutex::new(None);
+		save::set_progress(Some(Arc::new(move |progress: &Progress<'_>| {
+			let mut drawn = drawn
+				.lock()
+				.unwrap_or_else(std::sync::PoisonError::into_inner);
+			if progress.member_done == progress.member_size {
+				// The bar is cleared between members so that it never runs into a command’s output,
+				// which may come as soon as the last member is done.
+				eprint!("\r\x1b[K");
+				*drawn = None;
+			} else if drawn.is_none_or(|i| i.elapsed() >= INTERVAL) {
+				*drawn = Some(Instant::now());
+				draw(progress);
+			}
+		})));
+		Some(Self(()))
+	}
+}
+
+impl Drop for Bar {
+	fn drop(&mut self) {
+		save::set_progress(None);
+		eprint!("\r\x1b[K");
+	}
+}
+
+/// Formats a number of bytes in binary units.
+fn bytes(n: u64) -> String {
+	// Casts lose precision only past 2⁵³ bytes, far beyond what is shown to a tenth.
+	#[allow(clippy::cast_precision_loss)]
+	let n = n as f64;
+	if n < 1024.0 {
+		format!("{n} B")
+	} else if n < 1024.0 * 1024.0 {
+		format!("{:.1} KiB", n / 1024.0)
+	} else if n < 1024.0 * 1024.0 * 1024.0 {
+		format!("{:.1} MiB", n / (1024.0 * 1024.0))
+	} else {
+		format!("{:.1} GiB", n / (1024.0 * 1024.0 * 1024.0))
+	}
+}
+
+/// Redraws the progress bar.
+fn draw(progress: &Progress<'_>) {
+	let name: String = progress.member.chars().take(NAME_WIDTH).collect();
+	match progress.total {
+		Some(total) if total != 0 => {
+			let done = progress.done.min(total);
+			let filled = done * WIDTH / total;
+			// Casts are sound because filled ≤ WIDTH.
+			#[allow(clippy::cast_possible_truncation)]
+			let (filled, empty) = (filled as usize, (WIDTH - filled) as usize);
+			eprint!(
+				"\r\x1b[K[{}{}] {:3}% {} of {}  {name}",
+				"#".repeat(filled),
+				" ".repeat(empty),
+				done * 100 / total,
+				bytes(progress.done),
+				bytes(total)
+			);
+		}
+		_ => eprint!("\r\x1b[K{}  {name}", bytes(progress.done)),
+	}
+}
@@ -0,0 +1,380 @@
+use crate::batch;
+use crate::color::Style;
+use crate::exit;
+use crate::output::{self, outln, Format};
+use barotool::characterdata::CharacterData;
+use barotool::gamesession::GameSession;
+use barotool::save::{ArchiveReader, ArchiveWriter};
+use barotool::submarine::{self, Submarine};
+use barotool::xml;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use xmltree::{Element, XMLNode};
+
+/// How a file fared when read and written back.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+	/// The file was written back byte for byte as it was.
+	Identical,
+
+	/// The file was written back with the same content, but not the same bytes.
+	Equivalent,
+
+	/// The file was written back with different content.
+	Changed,
+
+	/// The file could not be read.
+	Failed,
+}
+
+impl Status {
+	/// Returns the status’s name, as printed.
+	fn name(self) -> &'static str {
+		match self {
+			Self::Identical => "identical",
+			Self::Equivalent => "equivalent",
+			Self::Changed => "changed",
+			Self::Failed => "failed",
+		}
+	}
+
+	/// Returns the style to print the status in, given whether byte differences count as failures.
+	fn style(self, bytes: bool) -> Style {
+		match self {
+			Self::Identical => Style::Good,
+			Self::Equivalent if !bytes => Style::Good,
+			Self::Equivalent | Self::Changed => Style::Warning,
+			Self::Failed => Style::Error,
+		}
+	}
+}
+
+/// The outcome of round-tripping one file.
+#[derive(Debug, Serialize)]
+struct Outcome {
+	/// The file.
+	file: PathBuf,
+
+	/// How the file fared.
+	status: Status,
+
+	/// What changed, or why the file could not be read.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	details: Vec<String>,
+
+	/// The exit status called for by a failure to read the file.
+	#[serde(skip)]
+	code: u8,
+}
+
+/// The outcomes of round-tripping a corpus of files, as printed.
+#[derive(Debug, Serialize)]
+struct Report {
+	/// The outcome for each file.
+	files: Vec<Outcome>,
+
+	/// The number of files with each status.
+	summary: BTreeMap<Status, usize>,
+}
+
+/// What has been found so far while round-tripping one file.
+#[derive(Debug, Default)]
+struct Findings {
+	/// Whether every document was written back byte for byte as it was.
+	bytes_differ: bool,
+
+	/// What changed.
+	differences: Vec<String>,
+}
+
+impl Findings {
+	/// Compares the XML of a document before and after being written back.
+	///
+	/// `label` names the document in any difference found.
+	fn compare(
+		&mut self,
+		label: Option<&str>,
+		before: &[u8],
+		after: &[u8],
+	) -> barotool::Result<()> {
+		if before == after {
+			return Ok(());
+		}
+		self.bytes_differ = true;
+		let old = xml::parse(before)?;
+		let new = xml::parse(after)?;
+		if let Some(difference) = first_difference(&old, &new, &format!("/{}", old.name)) {
+			self.differences.push(match label {
+				Some(label) => format!("{label}: {difference}"),
+				None => difference,
+			});
+		}
+		Ok(())
+	}
+
+	/// Returns the status these findings amount to.
+	fn status(&self) -> Status {
+		if !self.differences.is_empty() {
+			Status::Changed
+		} else if self.bytes_differ {
+			Status::Equivalent
+		} else {
+			Status::Identical
+		}
+	}
+}
+
+/// Describes the first difference between two elements, found depth first, or returns `None` if
+/// they are the same.
+///
+/// Attribute order is not significant, as Barotrauma does not care about it.
+fn first_difference(old: &Element, new: &Element, path: &str) -> Option<String> {
+	if old.name != new.name {
+		return Some(format!("{path}: renamed to {}", new.name));
+	}
+	for (name, value) in &old.attributes {
+		match new.attributes.get(name) {
+			None => return Some(format!("{path}: attribute {name} removed")),
+			Some(new_value) if new_value != value => {
+				return Some(format!(
+					"{path}: attribute {name} changed from “{value}” to “{new_value}”"
+				))
+			}
+			Some(_) => (),
+		}
+	}
+	if let Some(name) = new
+		.attributes
+		.keys()
+		.find(|i| !old.attributes.contains_key(*i))
+	{
+		return Some(format!("{path}: attribute {name} added"));
+	}
+	if old.children.len() != new.children.len() {
+		return Some(format!(
+			"{path}: {} children became {}",
+			old.children.len(),
+			new.children.len()
+		));
+	}
+	let mut counts: HashMap<&str, usize> = HashMap::new();
+	for (old_child, new_child) in old.children.iter().zip(&new.children) {
+		match (old_child, new_child) {
+			(XMLNode::Element(old_child), XMLNode::Element(new_child)) => {
+				let count = counts.entry(&old_child.name).or_default();
+				*count += 1;
+				let path = format!("{path}/{}[{count}]", old_child.name);
+				if let Some(difference) = first_difference(old_child, new_child, &path) {
+					return Some(difference);
+				}
+			}
+			(old_child, new_child) if old_child != new_child => {
+				return Some(format!("{path}: text or comment changed"));
+			}
+			_ => (),
+		}
+	}
+	None
+}
+
+/// A document that has been parsed and written back.
+struct Rewritten {
+	/// The document’s XML as it was.
+	before: Vec<u8>,
+
+	/// The document’s XML as written back.
+	after: Vec<u8>,
+
+	/// The document as written back, compressed if it is stored that way.
+	written: Vec<u8>,
+}
+
+/// Parses a save file member with the model that commands use for it and writes it back.
+///
+/// Members that no command parses are not checked beyond the archive itself, and `None` is
+/// returned for them.
+fn rewrite_member(name: &str, data: &[u8]) -> barotool::Result<Option<Rewritten>> {
+	let mut after = Vec::new();
+	if name == GameSession::MEMBER_NAME {
+		GameSession::parse(data)?.write(&mut after)?;
+	} else if name == CharacterData::MEMBER_NAME {
+		CharacterData::parse(data)?.write(&mut after)?;
+	} else if Path::new(name).extension() == Some(OsStr::new("sub")) {
+		return rewrite_submarine(data).map(Some);
+	} else {
+		return Ok(None);
+	}
+	Ok(Some(Rewritten {
+		before: data.to_vec(),
+		written: after.clone(),
+		after,
+	}))
+}
+
+/// Parses a gzip-compressed submarine and writes it back.
+fn rewrite_submarine(data: &[u8]) -> barotool::Result<Rewritten> {
+	let mut written = Vec::new();
+	Submarine::parse(data)?.write(&mut written)?;
+	let mut before = Vec::new();
+	submarine::decompress(data, &mut before)?;
+	let mut after = Vec::new();
+	submarine::decompress(&written[..], &mut after)?;
+	Ok(Rewritten {
+		before,
+		after,
+		written,
+	})
+}
+
+/// Round-trips a save file, both its modelled members and the archive holding them.
+fn check_save(data: &[u8], findings: &mut Findings) -> barotool::Result<()> {
+	let mut reader = ArchiveReader::new(data)?;
+	let mut writer = ArchiveWriter::new(Vec::new())?;
+	let mut written = Vec::new();
+	while let Some(mut member) = reader.next()? {
+		let name = member.name().to_owned();
+		let data = member.read_to_vec()?;
+		let data = match rewrite_member(&name, &data)? {
+			Some(rewritten) => {
+				findings.compare(Some(&name), &rewritten.before, &rewritten.after)?;
+				rewritten.written
+			}
+			None => data,
+		};
+		writer.write_member(&name, &data)?;
+		written.push((name, data));
+	}
+	let archive = writer.finish()?;
+	let mut reader = ArchiveReader::new(&archive[..])?;
+	let mut read = Vec::new();
+	while let Some(mut member) = reader.next()? {
+		read.push((member.name().to_owned(), member.read_to_vec()?));
+	}
+	if read != written {
+		findings
+			.differences
+			.push("archive: members did not read back as written".to_owned());
+	}
+	Ok(())
+}
+
+/// Round-trips one file.
+fn check(file: &Path) -> Outcome {
+	let mut findings = Findings::default();
+	let result = std::fs::read(file)
+		.map_err(barotool::Error::from)
+		.and_then(|data| {
+			if file.extension() == Some(OsStr::new("sub")) {
+				let rewritten = rewrite_submarine(&data)?;
+				findings.compare(None, &rewritten.before, &rewritten.after)
+			} else {
+				check_save(&data, &mut findings)
+			}
+		});
+	match result {
+		Ok(()) => Outcome {
+			file: file.to_owned(),
+			status: findings.status(),
+			details: findings.differences,
+			code: exit::SUCCESS,
+		},
+		Err(e) => {
+			let e: std::io::Error = e.into();
+			Outcome {
+				file: file.to_owned(),
+				status: Status::Failed,
+				details: vec![e.to_string()],
+				code: exit::code(&e),
+			}
+		}
+	}
+}
+
+/// Reads and writes back every `.sub` and `.save` file among `paths`, reporting any whose content
+/// changes.
+///
+/// Each file, or each member of a save that barotool parses, is parsed and serialized as it would
+/// be by a command that modifies it. A file whose XML is not the same afterwards, ignoring
+/// formatting, has changed; with `bytes`, so has one whose XML is merely formatted differently.
+pub fn check_all(paths: &[&OsStr], recursive: bool, bytes: bool, format: Format) -> Result<()> {
+	let files = batch::expand(paths.iter().copied(), &["sub", "save"], recursive)?;
+	let outcomes = batch::map(&files, check)?;
+	let mut summary = BTreeMap::new();
+	for outcome in &outcomes {
+		*summary.entry(outcome.status).or_default() += 1;
+	}
+	let report = Report {
+		files: outcomes,
+		summary,
+	};
+	output::emit(format, &report, |report| {
+		for outcome in &report.files {
+			let status = outcome.status;
+			outln!(
+				"{}\t{}",
+				status.style(bytes).paint(status.name()),
+				outcome.file.display()
+			);
+			for detail in &outcome.details {
+				outln!("\t{detail}");
+			}
+		}
+		outln!();
+		for status in [
+			Status::Identical,
+			Status::Equivalent,
+			Status::Changed,
+			Status::Failed,
+		] {
+			outln!(
+				"{}\t{}",
+				status.name(),
+				report.summary.get(&status).copied().unwrap_or(0)
+			);
+		}
+	})?;
+	let failed = report
+		.files
+		.iter()
+		.filter(|i| i.status >= Status::Changed || (bytes && i.status == Status::Equivalent));
+	let code = failed
+		.clone()
+		.map(|i| i.code.max(exit::DIFFERENCES))
+		.max()
+		.unwrap_or(exit::SUCCESS);
+	if code == exit::SUCCESS {
+		return Ok(());
+	}
+	Err(exit::error(
+		code,
+		format!(
+			"{} of {} files did not round-trip",
+			failed.count(),
+			files.len()
+		),
+	))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_first_difference() {
+		let old = xml::parse(&br#"<Submarine a="1" b="2"><Item /><Item x="1" /></Submarine>"#[..])
+			.unwrap();
+		let same =
+			xml::parse(&br#"<Submarine b="2" a="1"><Item/><Item x="1"/></Submarine>"#[..]).unwrap();
+		assert_eq!(first_difference(&old, &same, "/Submarine"), None);
+		let new = xml::parse(&br#"<Submarine a="1" b="2"><Item /><Item x="2" /></Submarine>"#[..])
+			.unwrap();
+		assert_eq!(
+			first_difference(&old, &new, "/Submarine").as_deref(),
+			Some("/Submarine/Item[2]: attribute x changed from “1” to “2”")
+		);
+	}
+}
@@ -1,26 +1,483 @@
+//! Reading and writing `.save` files.
+//!
+//! A save file is a gzip-compressed archive of members, each of which is a named file. A campaign’s
+//! save holds its game session, its submarines, and, for multiplayer campaigns, its players’
+//! character data.
+//!
+//! Apart from the functions that take a file name, which are conveniences over the file system,
+//! everything here works on any [`Read`] or [`Write`] stream, including byte slices and vectors, so
+//! saves can be handled where there is no file system at all:
+//!
+//! ```
+//! use barotool::save::{self, ArchiveWriter};
+//!
+//! # fn main() -> barotool::Result<()> {
+//! let mut writer = ArchiveWriter::new(Vec::new())?;
+//! writer.write_member("gamesession.xml", b"<Gamesession />")?;
+//! let data = writer.finish()?;
+//! let member = save::find_member(&data[..], "gamesession.xml")?;
+//! assert_eq!(member.as_deref(), Some(&b"<Gamesession />"[..]));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::compression;
+use crate::error::{self, Error};
+use crate::index::Index;
+use crate::limits::{self, Limits, Usage};
+use crate::Result;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use libflate::gzip::{Decoder, Encoder};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::cmp::min;
-use std::collections::HashSet;
-use std::convert::TryInto;
+use std::collections::{HashSet, VecDeque};
+use std::convert::{TryFrom, TryInto};
 use std::ffi::OsStr;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Result};
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::iter::FusedIterator;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+/// The name and size of an archive member, as listed by [`members`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct MemberInfo {
+	/// The member’s filename.
+	pub name: String,
+
+	/// The size of the member, in bytes.
+	pub size: usize,
+}
+
+/// A choice of archive members, by name or by wildcard pattern, as taken by [`unpack_to`].
+///
+/// In a pattern, `*` matches any run of characters, including none, and `?` matches any one
+/// character; every other character matches only itself. A filter with no patterns matches every
+/// member.
+///
+/// ```
+/// use barotool::save::MemberFilter;
+///
+/// let mut filter = MemberFilter::new(["*.sub", "CharacterData.xml"]);
+/// assert!(filter.matches("Orca.sub"));
+/// assert!(!filter.matches("gamesession.xml"));
+/// assert_eq!(filter.unmatched().collect::<Vec<_>>(), ["CharacterData.xml"]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemberFilter {
+	/// The patterns, each with whether it has matched a member yet.
+	patterns: Vec<(String, bool)>,
+}
+
+impl MemberFilter {
+	/// Creates a filter matching members that match any of the given patterns, or every member if
+	/// there are none.
+	pub fn new<I: IntoIterator>(patterns: I) -> Self
+	where
+		I::Item: Into<String>,
+	{
+		Self {
+			patterns: patterns.into_iter().map(|i| (i.into(), false)).collect(),
+		}
+	}
+
+	/// Returns whether the filter has no patterns, and so matches every member.
+	pub fn is_empty(&self) -> bool {
+		self.patterns.is_empty()
+	}
+
+	/// Returns whether a member name matches the filter, noting which patterns it matched.
+	pub fn matches(&mut self, name: &str) -> bool {
+		if self.patterns.is_empty() {
+			return true;
+		}
+		let name: Vec<char> = name.chars().collect();
+		let mut ret = false;
+		for (pattern, matched) in &mut self.patterns {
+			if wildcard_match(&pattern.chars().collect::<Vec<_>>(), &name) {
+				*matched = true;
+				ret = true;
+			}
+		}
+		ret
+	}
+
+	/// Returns the patterns that have not matched any member so far.
+	pub fn unmatched(&self) -> impl Iterator<Item = &str> {
+		self.patterns
+			.iter()
+			.filter(|(_, matched)| !matched)
+			.map(|(pattern, _)| pattern.as_str())
+	}
+}
+
+/// Returns whether a name matches a wildcard pattern, as described at [`MemberFilter`].
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+	let (mut p, mut n) = (0, 0);
+	// Where to resume after the last `*`, should what follows it fail to match: the position in the
+	// pattern just past the `*`, and the position in the name that the `*` matched up to.
+	let mut resume = None;
+	while n < name.len() {
+		match pattern.get(p) {
+			Some('*') => {
+				p += 1;
+				resume = Some((p, n));
+			}
+			Some(c) if *c == '?' || *c == name[n] => {
+				p += 1;
+				n += 1;
+			}
+			_ => match resume {
+				Some((star_p, star_n)) => {
+					p = star_p;
+					n = star_n + 1;
+					resume = Some((star_p, n));
+				}
+				None => return false,
+			},
+		}
+	}
+	pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// How the names of members are turned into paths when unpacking a save file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MemberNames {
+	#[default]
+	/// Each name is made safe with [`member_path`], so that nothing is written outside the
+	/// directory being unpacked into.
+	Sanitize,
+
+	/// Each name is used as a path as it is, even if it is absolute or climbs out of the directory
+	/// with `..`. Only for save files from a trusted source.
+	Unsafe,
+}
+
+impl MemberNames {
+	/// Returns the relative path at which to unpack a member, or `None` if its name has nothing
+	/// left once made safe.
+	pub fn path(self, name: &str) -> Option<PathBuf> {
+		match self {
+			Self::Sanitize => member_path(name),
+			Self::Unsafe => Some(PathBuf::from(name)),
+		}
+	}
+}
+
+/// What to do when unpacking a member to where a file already exists.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Existing {
+	/// Fail with an error of kind [`std::io::ErrorKind::AlreadyExists`].
+	#[default]
+	Refuse,
+
+	/// Leave the existing file as it is and skip the member.
+	Skip,
+
+	/// Replace the existing file.
+	Overwrite,
+
+	/// Leave the existing file as it is if it was modified after the given time, usually that at
+	/// which the save file was, and otherwise replace it.
+	KeepNewer(SystemTime),
+}
+
+impl Existing {
+	/// Returns whether a member should be extracted to a path, given whatever is there already, or
+	/// fails if it should not be and that is an error.
+	pub fn should_extract(self, path: &Path) -> Result<bool> {
+		let Ok(metadata) = std::fs::symlink_metadata(path) else {
+			return Ok(true);
+		};
+		match self {
+			Self::Refuse => Err(std::io::Error::new(
+				std::io::ErrorKind::AlreadyExists,
+				format!("{} already exists", path.display()),
+			)
+			.into()),
+			Self::Skip => Ok(false),
+			Self::Overwrite => Ok(true),
+			Self::KeepNewer(time) => Ok(metadata.modified()? <= time),
+		}
+	}
+}
+
+/// How to unpack the members of a save file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UnpackOptions {
+	/// How member names are turned into paths.
+	pub names: MemberNames,
+
+	/// What to do about files that already exist.
+	pub existing: Existing,
+}
+
+/// The names that Windows reserves for devices, in any directory and with any extension.
+const RESERVED_NAMES: [&str; 22] = [
+	"CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+	"COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Returns a component of a member name with the characters that the local file system does not
+/// allow in a filename replaced by `_`.
+fn sanitize_component(component: &str) -> String {
+	let illegal =
+		|c: char| c == '\0' || (cfg!(windows) && (c.is_control() || "<>:\"|?*".contains(c)));
+	let mut ret: String = component
+		.chars()
+		.map(|c| if illegal(c) { '_' } else { c })
+		.collect();
+	if cfg!(windows) {
+		// Windows drops trailing dots and spaces, and takes reserved names to mean devices.
+		if ret.ends_with(['.', ' ']) {
+			ret.push('_');
+		}
+		let stem = ret.split('.').next().unwrap_or_default();
+		if RESERVED_NAMES.iter().any(|i| i.eq_ignore_ascii_case(stem)) {
+			ret.insert(0, '_');
+		}
+	}
+	ret
+}
+
+/// Returns the relative path at which to unpack a member, or `None` if its name has nothing left
+/// once made safe.
+///
+/// Both `/` and `\` separate components, whichever platform the save file was written on. Empty
+/// components, `.`, and `..` are dropped, so the path never leads outside the directory being
+/// unpacked into, and characters that the local file system does not allow are replaced by `_`.
+///
+/// ```
+/// use barotool::save;
+/// use std::path::Path;
+///
+/// let path = save::member_path("../../etc/passwd");
+/// assert_eq!(path.as_deref(), Some(Path::new("etc/passwd")));
+/// let path = save::member_path("/Submarines\\Orca.sub");
+/// assert_eq!(path.as_deref(), Some(Path::new("Submarines/Orca.sub")));
+/// assert_eq!(save::member_path(".."), None);
+/// ```
+pub fn member_path(name: &str) -> Option<PathBuf> {
+	let path: PathBuf = name
+		.split(['/', '\\'])
+		.filter(|i| !matches!(*i, "" | "." | ".."))
+		.map(sanitize_component)
+		.collect();
+	if path.as_os_str().is_empty() {
+		None
+	} else {
+		Some(path)
+	}
+}
+
+/// How far an operation on a save file has got, as reported to the
+/// [progress callback](set_progress).
+#[derive(Clone, Copy, Debug)]
+pub struct Progress<'a> {
+	/// The member being read or written.
+	pub member: &'a str,
+
+	/// The size of the member, in bytes.
+	pub member_size: u64,
+
+	/// The number of bytes of the member read or written so far.
+	pub member_done: u64,
+
+	/// The number of bytes of member content read or written so far, across every member.
+	pub done: u64,
+
+	/// The number of bytes of member content to be read or written in all, if known, as it is when
+	/// [packing](pack_named) but not when reading.
+	pub total: Option<u64>,
+}
+
+/// A function called with the progress of reading and writing save files.
+pub type ProgressCallback = dyn Fn(&Progress<'_>) + Send + Sync;
+
+/// The progress callback in effect.
+static PROGRESS: RwLock<Option<Arc<ProgressCallback>>> = RwLock::new(None);
+
+/// Sets the function to call, from whichever thread is doing the work, each time member content
+/// is read from or written to a save file, or removes it.
+///
+/// Readers and writers take the callback in effect when they are created, so that one can be set
+/// around a particular operation. The callback is called often, once per buffer, and should be
+/// quick.
+///
+/// ```
+/// use barotool::save::{self, ArchiveReader, ArchiveWriter};
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::sync::Arc;
+///
+/// # fn main() -> barotool::Result<()> {
+/// let mut writer = ArchiveWriter::new(Vec::new())?;
+/// writer.write_member("gamesession.xml", b"<Gamesession />")?;
+/// let data = writer.finish()?;
+///
+/// let done = Arc::new(AtomicU64::new(0));
+/// let seen = Arc::clone(&done);
+/// save::set_progress(Some(Arc::new(move |progress| {
+///     seen.store(progress.done, Ordering::Relaxed)
+/// })));
+/// save::find_member(&data[..], "gamesession.xml")?;
+/// save::set_progress(None);
+/// assert_eq!(done.load(Ordering::Relaxed), 15);
+/// # Ok(())
+/// # }
+/// ```
+pub fn set_progress(callback: Option<Arc<ProgressCallback>>) {
+	*PROGRESS
+		.write()
+		.unwrap_or_else(std::sync::PoisonError::into_inner) = callback;
+}
+
+/// The progress of reading or writing a save file, and where to report it.
+#[derive(Clone, Default)]
+struct Tracker {
+	/// The progress callback in effect when reading or writing started.
+	callback: Option<Arc<ProgressCallback>>,
+
+	/// The number of bytes of member content read or written so far.
+	done: u64,
+
+	/// The number of bytes of member content to be read or written in all, if known.
+	total: Option<u64>,
+}
+
+impl Tracker {
+	/// Starts tracking progress, to be reported to the callback in effect.
+	fn new() -> Self {
+		Self {
+			callback: PROGRESS
+				.read()
+				.unwrap_or_else(std::sync::PoisonError::into_inner)
+				.clone(),
+			done: 0,
+			total: None,
+		}
+	}
+
+	/// Counts bytes read or written of a member, of which `left` remain, and reports progress.
+	fn advance(&mut self, member: &str, member_size: u64, left: u64, bytes: u64) {
+		self.done += bytes;
+		if let Some(callback) = &self.callback {
+			callback(&Progress {
+				member,
+				member_size,
+				member_done: member_size - left,
+				done: self.done,
+				total: self.total,
+			});
+		}
+	}
+}
+
+impl std::fmt::Debug for Tracker {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Tracker")
+			.field("callback", &self.callback.as_ref().map(|_| "Fn"))
+			.field("done", &self.done)
+			.field("total", &self.total)
+			.finish()
+	}
+}
 
 /// A save file.
 #[derive(Debug)]
 pub struct ArchiveReader<R: Read> {
-	/// The GZip decoder decoding the file.
-	decoder: Decoder<R>,
+	/// The gzip decoder decoding the file.
+	decoder: compression::Decoder<R>,
 
 	/// The number of bytes remaining in the current member’s file content.
 	member_bytes_left: usize,
+
+	/// The buffer that skipped member content is read into, allocated on first use and kept for
+	/// later members.
+	skip_buffer: Vec<u8>,
+
+	/// What has been read so far, as counted against the limits.
+	usage: Usage,
+
+	/// Whether member names that are not valid UTF-16 are decoded lossily rather than refused.
+	lossy_names: bool,
+
+	/// The progress of reading member content.
+	progress: Tracker,
 }
 
+/// The most that [`Member::read_to_end`] sizes its buffer for ahead of the data read.
+const PRESIZE_LIMIT: usize = 64 << 20;
+
+/// The size of the buffer that skipped member content is read into.
+///
+/// Reading in large pieces keeps the per-call overhead of the decoder out of the way, so that
+/// listing a save runs at the speed of decompression.
+const SKIP_BUFFER_SIZE: usize = 256 * 1024;
+
 impl<R: Read> ArchiveReader<R> {
+	/// Starts reading a save file from a stream.
+	///
+	/// The save file is held to the [`Limits`] in effect now; each member’s
+	/// header is checked against them before any of its content is read.
+	pub fn new(source: R) -> Result<Self> {
+		Self::with_limits(source, limits::limits())
+	}
+
+	/// Starts reading a save file from a stream, holding it to particular limits rather than those
+	/// in effect.
+	pub fn with_limits(source: R, limits: Limits) -> Result<Self> {
+		Ok(Self {
+			decoder: compression::Decoder::with_limits(source, limits)?,
+			member_bytes_left: 0,
+			skip_buffer: Vec::new(),
+			usage: Usage::new(limits),
+			lossy_names: false,
+			progress: Tracker::new(),
+		})
+	}
+
+	/// Makes the reader decode member names that are not valid UTF-16 lossily, rather than
+	/// failing with [`Error::MemberName`].
+	///
+	/// Such a name, as some saves shared by players have, with unpaired surrogates, would otherwise
+	/// make every member after it unreadable. Each invalid code unit is replaced by U+FFFD; the
+	/// name as stored is still available from [`Member::raw_name`].
+	///
+	/// ```
+	/// use barotool::compression;
+	/// use barotool::save::ArchiveReader;
+	/// use std::io::Write;
+	///
+	/// # fn main() -> barotool::Result<()> {
+	/// // An empty member named with an unpaired surrogate, followed by one named a.
+	/// let mut encoder = compression::encoder(Vec::new())?;
+	/// encoder.write_all(&[1, 0, 0, 0, 0x00, 0xD8, 0, 0, 0, 0])?;
+	/// encoder.write_all(&[1, 0, 0, 0, b'a', 0, 0, 0, 0, 0])?;
+	/// let data = encoder.finish()?;
+	/// assert!(ArchiveReader::new(&data[..])?.next().is_err());
+	/// let mut reader = ArchiveReader::new(&data[..])?.lossy_names();
+	/// let member = reader.next()?.unwrap();
+	/// assert_eq!(member.name(), "\u{FFFD}");
+	/// assert_eq!(member.raw_name(), [0xD800]);
+	/// assert_eq!(reader.next()?.unwrap().name(), "a");
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[must_use]
+	pub fn lossy_names(mut self) -> Self {
+		self.lossy_names = true;
+		self
+	}
+
 	/// Returns the next member in the file.
 	///
-	/// If there are no more members in the archive, `None` is returned.
+	/// If there are no more members in the archive, `None` is returned. The member borrows the
+	/// reader and can be read as a stream, so even large members need not be held in memory; any of
+	/// it left unread is skipped when the next member is requested.
+	// Each member borrows the reader, so this cannot be an Iterator.
+	#[allow(clippy::should_implement_trait)]
 	pub fn next(&mut self) -> Result<Option<Member<'_, R>>> {
 		// If the current member isn’t finished, finish it.
 		if self.member_bytes_left != 0 {
@@ -29,37 +486,72 @@ impl<R: Read> ArchiveReader<R> {
 
 		// Read the name, which comprises a little-endian 32-bit length followed by that many
 		// little-endian UTF-16 code units.
-		let name_length = match self.read_u32()? {
-			// Cast is sound because usize ≥ 32.
-			Some(n) => n as usize,
+		let name_bytes = match self.read_u32()? {
+			Some(n) => u64::from(n) * 2,
 			None => return Ok(None),
 		};
-		let mut name_buf: Vec<u16> = Vec::new();
-		name_buf.resize(name_length, 0_u16);
-		self.decoder.read_u16_into::<LittleEndian>(&mut name_buf)?;
-		let name = match String::from_utf16(&name_buf) {
-			Ok(n) => n,
-			Err(e) => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+		self.usage.header(8 + name_bytes)?;
+		let raw_name = read_name(&mut self.decoder, name_bytes).map_err(read_error)?;
+		let name = match String::from_utf16(&raw_name) {
+			Ok(name) => name,
+			Err(_) if self.lossy_names => {
+				let name = String::from_utf16_lossy(&raw_name);
+				log::warn!("Archive member name {name} is not valid UTF-16");
+				name
+			}
+			Err(e) => return Err(e.into()),
 		};
-		drop(name_buf);
 
 		// Read the file length, a little-endian 32-bit length. Cast is sound because usize ≥ 32.
-		let size = self.decoder.read_u32::<LittleEndian>()? as usize;
+		let size = self
+			.decoder
+			.read_u32::<LittleEndian>()
+			.map_err(read_error)? as usize;
+		self.usage.member(&name, size)?;
 		self.member_bytes_left = size;
+		log::trace!("Archive member {name}, {size} bytes");
 
 		Ok(Some(Member {
 			name,
+			raw_name,
 			size,
 			container: self,
 		}))
 	}
 
+	/// Returns an iterator over the names and sizes of the remaining members, skipping their
+	/// contents.
+	pub fn entries(self) -> Entries<R> {
+		Entries {
+			reader: self,
+			done: false,
+		}
+	}
+
+	/// Skips some of the decompressed data, as if it were the content of a member, so that reading
+	/// can start at a member header part way through a segment.
+	pub(crate) fn skip(&mut self, bytes: u64) -> Result<()> {
+		self.member_bytes_left = usize::try_from(bytes)
+			.map_err(|_| Error::TooLarge(format!("Cannot skip {bytes} bytes")))?;
+		self.finish_current_member()
+	}
+
 	/// Reads to the end of the current member’s file content.
+	///
+	/// If the save file ends before the member does, [`Error::Truncated`] is returned.
 	fn finish_current_member(&mut self) -> Result<()> {
-		let mut buffer = [0_u8; 4096];
+		if self.skip_buffer.is_empty() {
+			self.skip_buffer = vec![0_u8; SKIP_BUFFER_SIZE];
+		}
 		while self.member_bytes_left != 0 {
-			let to_read = min(buffer.len(), self.member_bytes_left);
-			let bytes_read = self.decoder.read(&mut buffer[..to_read])?;
+			let to_read = min(self.skip_buffer.len(), self.member_bytes_left);
+			let bytes_read = self
+				.decoder
+				.read(&mut self.skip_buffer[..to_read])
+				.map_err(error::gzip)?;
+			if bytes_read == 0 {
+				return Err(Error::Truncated);
+			}
 			self.member_bytes_left -= bytes_read;
 		}
 		Ok(())
@@ -69,21 +561,21 @@ impl<R: Read> ArchiveReader<R> {
 	///
 	/// If the file is at EOF, `None` is returned. If four bytes can be read successfully, their
 	/// value as a little-endian `u32` is returned. If at least one byte, but nor four, can be
-	/// read, `std::io::ErrorKind::UnexpectedEof` is returned.
+	/// read, [`Error::Truncated`] is returned.
 	fn read_u32(&mut self) -> Result<Option<u32>> {
 		let mut buffer = [0_u8; 4];
 		let mut buffer_filled = 0;
 		while buffer_filled != 4 {
-			let bytes_read = self.decoder.read(&mut buffer[buffer_filled..])?;
+			let bytes_read = self
+				.decoder
+				.read(&mut buffer[buffer_filled..])
+				.map_err(error::gzip)?;
 			if bytes_read == 0 {
 				// EOF
 				return if buffer_filled == 0 {
 					Ok(None)
 				} else {
-					Err(std::io::Error::new(
-						std::io::ErrorKind::UnexpectedEof,
-						"EOF within archive member",
-					))
+					Err(Error::Truncated)
 				};
 			}
 			buffer_filled += bytes_read;
@@ -92,6 +584,42 @@ impl<R: Read> ArchiveReader<R> {
 	}
 }
 
+/// Reads a member name of `bytes` bytes of little-endian UTF-16, as code units.
+///
+/// The name is read into a buffer that grows as it is filled, so that a corrupt length costs no
+/// more memory than the data actually there.
+pub(crate) fn read_name<R: Read>(source: R, bytes: u64) -> std::io::Result<Vec<u16>> {
+	let mut data = Vec::new();
+	source.take(bytes).read_to_end(&mut data)?;
+	// Cast is sound because u64 ≥ usize.
+	if data.len() as u64 != bytes {
+		return Err(std::io::ErrorKind::UnexpectedEof.into());
+	}
+	Ok(name_units(&data))
+}
+
+/// Splits a member name’s little-endian UTF-16 bytes into code units.
+fn name_units(data: &[u8]) -> Vec<u16> {
+	data.chunks_exact(2)
+		.map(|i| u16::from_le_bytes([i[0], i[1]]))
+		.collect()
+}
+
+/// Decodes a member name from its little-endian UTF-16 bytes.
+#[cfg(feature = "async")]
+pub(crate) fn decode_name(data: &[u8]) -> Result<String> {
+	Ok(String::from_utf16(&name_units(data))?)
+}
+
+/// Classifies an error from reading a member header.
+pub(crate) fn read_error(e: std::io::Error) -> Error {
+	if e.kind() == std::io::ErrorKind::UnexpectedEof {
+		Error::Truncated
+	} else {
+		error::gzip(e)
+	}
+}
+
 /// A single member of a save file.
 ///
 /// The `R` generic parameter indicates the type of the reader being used to read the save file.
@@ -101,6 +629,9 @@ pub struct Member<'file, R: Read> {
 	/// The member’s filename.
 	name: String,
 
+	/// The member’s filename as stored, in UTF-16 code units.
+	raw_name: Vec<u16>,
+
 	/// The size of the member, in bytes.
 	size: usize,
 
@@ -108,93 +639,1765 @@ pub struct Member<'file, R: Read> {
 	container: &'file mut ArchiveReader<R>,
 }
 
-impl<'member, R: Read> Member<'member, R> {
+impl<R: Read> Member<'_, R> {
 	/// Returns the member’s filename.
 	pub fn name(&self) -> &str {
 		&self.name
 	}
 
+	/// Returns the member’s filename as stored, in UTF-16 code units.
+	///
+	/// This differs from [`name`](Self::name) only for a name that is not valid UTF-16, read with
+	/// [`ArchiveReader::lossy_names`].
+	pub fn raw_name(&self) -> &[u16] {
+		&self.raw_name
+	}
+
 	/// Returns the size of the member, in bytes.
 	pub fn size(&self) -> usize {
 		self.size
 	}
+
+	/// Returns the member’s name and size.
+	pub fn info(&self) -> MemberInfo {
+		MemberInfo {
+			name: self.name.clone(),
+			size: self.size,
+		}
+	}
+
+	/// Reads the rest of the member into memory.
+	///
+	/// If the save file ends before the member does, [`Error::Truncated`] is returned.
+	pub fn read_to_vec(&mut self) -> Result<Vec<u8>> {
+		let mut data = Vec::new();
+		self.read_to_end(&mut data).map_err(error::gzip)?;
+		if self.container.member_bytes_left != 0 {
+			return Err(Error::Truncated);
+		}
+		Ok(data)
+	}
 }
 
-impl<'member, R: Read> Read for Member<'member, R> {
-	fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+impl<R: Read> Read for Member<'_, R> {
+	fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
 		let to_read = min(buffer.len(), self.container.member_bytes_left);
-		// libflate::gzip::Decoder does not like being called with a zero-length buffer (it
+		// libflate’s gzip decoder does not like being called with a zero-length buffer (it
 		// interprets the zero return value from the underlying stream as an EOF).
 		// <https://github.com/sile/libflate/issues/61>
 		let bytes_read = self.container.decoder.read(&mut buffer[..to_read])?;
 		self.container.member_bytes_left -= bytes_read;
+		// Casts are sound because u64 ≥ usize.
+		self.container.progress.advance(
+			&self.name,
+			self.size as u64,
+			self.container.member_bytes_left as u64,
+			bytes_read as u64,
+		);
 		Ok(bytes_read)
 	}
+
+	/// Reads the rest of the member, sizing `buffer` for it up front instead of growing it a piece at
+	/// a time.
+	///
+	/// The buffer grows by no more than 64 MiB ahead of the data actually read, so that a corrupt
+	/// member size costs no more memory than the data there.
+	fn read_to_end(&mut self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+		let start = buffer.len();
+		let mut filled = start;
+		while self.container.member_bytes_left != 0 {
+			if filled == buffer.len() {
+				let grow = min(self.container.member_bytes_left, PRESIZE_LIMIT);
+				buffer.resize(filled + grow, 0);
+			}
+			match self.read(&mut buffer[filled..]) {
+				Ok(0) => break,
+				Ok(n) => filled += n,
+				Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+				Err(e) => {
+					buffer.truncate(filled);
+					return Err(e);
+				}
+			}
+		}
+		buffer.truncate(filled);
+		Ok(filled - start)
+	}
+
+	/// Fills `buffer`, failing without reading anything if the member has too little left to do so.
+	fn read_exact(&mut self, buffer: &mut [u8]) -> std::io::Result<()> {
+		if buffer.len() > self.container.member_bytes_left {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::UnexpectedEof,
+				"member is shorter than the data requested",
+			));
+		}
+		let mut filled = 0;
+		while filled != buffer.len() {
+			match self.read(&mut buffer[filled..]) {
+				Ok(0) => {
+					return Err(std::io::Error::new(
+						std::io::ErrorKind::UnexpectedEof,
+						"save file ends within a member",
+					))
+				}
+				Ok(n) => filled += n,
+				Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+				Err(e) => return Err(e),
+			}
+		}
+		Ok(())
+	}
 }
 
-/// Opens a save file for reading.
-fn open_read(filename: &OsStr) -> Result<ArchiveReader<BufReader<File>>> {
-	Ok(ArchiveReader {
-		decoder: Decoder::new(BufReader::new(File::open(filename)?))?,
-		member_bytes_left: 0,
-	})
+/// An iterator over the names and sizes of the members of a save file.
+///
+/// This is returned by [`ArchiveReader::entries`]. Iteration stops after the first error.
+#[derive(Debug)]
+pub struct Entries<R: Read> {
+	/// The save file.
+	reader: ArchiveReader<R>,
+
+	/// Whether the end of the archive, or an error, has been reached.
+	done: bool,
 }
 
-/// Lists the contents of a save file.
-pub fn list(filename: &OsStr) -> Result<()> {
-	let mut reader = open_read(filename)?;
-	while let Some(member) = reader.next()? {
-		println!("{}\t{}", member.name(), member.size());
+impl<R: Read> Iterator for Entries<R> {
+	type Item = Result<MemberInfo>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+		match self.reader.next() {
+			Ok(Some(member)) => Some(Ok(member.info())),
+			Ok(None) => {
+				self.done = true;
+				None
+			}
+			Err(e) => {
+				self.done = true;
+				Some(Err(e))
+			}
+		}
 	}
+}
+
+impl<R: Read> FusedIterator for Entries<R> {}
+
+/// Opens a save file for reading.
+pub fn open(filename: &OsStr) -> Result<ArchiveReader<BufReader<File>>> {
+	ArchiveReader::new(BufReader::new(File::open(filename)?))
+}
+
+/// Returns the names of the members of a save file, in order.
+pub fn member_names(filename: &OsStr) -> Result<Vec<String>> {
+	open(filename)?
+		.entries()
+		.map(|i| i.map(|i| i.name))
+		.collect()
+}
+
+/// Returns the names and sizes of the members of a save file, in order.
+pub fn members(filename: &OsStr) -> Result<Vec<MemberInfo>> {
+	open(filename)?.entries().collect()
+}
+
+/// Encodes the header of an archive member.
+pub(crate) fn member_header(name: &str, size: u32) -> Result<Vec<u8>> {
+	// The name is in little-endian UTF-16, preceded by its length in code units as a little-endian
+	// u32.
+	let name: Vec<u16> = name.encode_utf16().collect();
+	let name_len: u32 = name
+		.len()
+		.try_into()
+		.map_err(|_| Error::TooLarge("Member name too long".to_owned()))?;
+	let mut header = Vec::with_capacity(8 + 2 * name.len());
+	header.write_u32::<LittleEndian>(name_len)?;
+	name.iter()
+		.try_for_each(|i| header.write_u16::<LittleEndian>(*i))?;
+
+	// The length of the file body in bytes follows as a little-endian u32.
+	header.write_u32::<LittleEndian>(size)?;
+	Ok(header)
+}
+
+/// The ID of the gzip extra subfield recording the length of a segment of a save file.
+const SEGMENT_SUBFIELD: [u8; 2] = *b"BT";
+
+/// The offset of a segment’s length within it: the gzip header, `XLEN`, and the subfield’s ID and
+/// length come first.
+const SEGMENT_LENGTH_OFFSET: usize = 16;
+
+/// The size of the start of a segment, up to the end of its length.
+pub(crate) const SEGMENT_HEAD_SIZE: usize = SEGMENT_LENGTH_OFFSET + 8;
+
+/// A save file being written.
+///
+/// Members are written in order with [`write_member`](Self::write_member) or
+/// [`copy_member`](Self::copy_member), unless the writer is [`sorted`](Self::sorted), and the file
+/// is completed by [`finish`](Self::finish). Each member’s contents are compressed straight into
+/// the stream as they are written, so little of even the largest member is held in memory.
+///
+/// By default the save file is a single gzip member, as the game writes it. A
+/// [`segmented`](Self::segmented) writer instead compresses each archive member as a gzip member,
+/// or *segment*, of its own, whose header records its compressed length, so that [`update`] and
+/// [`Index`] can find members without decompressing the others.
+pub struct ArchiveWriter<W: Write> {
+	/// The stream the save file is written to, as far as writing it has got.
+	sink: Sink<W>,
+
+	/// If the save file is segmented, how to find and patch positions in the stream.
+	seeker: Option<Seeker<W>>,
+
+	/// Whether no member has been written yet.
+	empty: bool,
+
+	/// If members are being sorted, the contents of those completed so far, each with its member’s
+	/// name.
+	pending: Option<Vec<(String, Vec<u8>)>>,
+
+	/// The progress of writing member content.
+	progress: Tracker,
+}
+
+/// The stream an [`ArchiveWriter`] writes to, in the state writing has left it.
+enum Sink<W: Write> {
+	/// No gzip member is open: nothing has been written yet or, in a segmented save file, the last
+	/// segment is complete.
+	Idle(W),
+
+	/// The gzip member holding every archive member of an unsegmented save file is open.
+	Stream(compression::Encoder<W>),
+
+	/// The segment of an archive member is open, having started at the given offset in the stream.
+	Segment(compression::Encoder<W>, u64),
+
+	/// Writing failed, or a member was left incomplete, so the save file cannot be completed.
+	Failed,
+}
+
+/// Finds and patches positions in the stream of a segmented save file, which must be able to seek.
+struct Seeker<W> {
+	/// Returns the position of the stream.
+	position: fn(&mut W) -> std::io::Result<u64>,
+
+	/// Overwrites bytes at an earlier position, leaving the stream where it was.
+	patch: fn(&mut W, u64, &[u8]) -> std::io::Result<()>,
+}
+
+/// Overwrites bytes at an earlier position in a stream, leaving the stream where it was.
+fn patch<W: Write + Seek>(sink: &mut W, offset: u64, data: &[u8]) -> std::io::Result<()> {
+	let end = sink.stream_position()?;
+	sink.seek(SeekFrom::Start(offset))?;
+	sink.write_all(data)?;
+	sink.seek(SeekFrom::Start(end))?;
 	Ok(())
 }
 
-/// Packs a save file.
-pub fn pack(filename: &OsStr, members: &[&str]) -> Result<()> {
-	let mut writer = Encoder::new(BufWriter::new(File::create(filename)?))?;
-	for member in members {
-		// Write the name, in little-endian UTF-16, preceded by its length in code units as a
-		// little-endian u32.
-		let name: Vec<u16> = member.encode_utf16().collect();
-		let name_len: u32 = name
+/// Returns the error reported once a save file being written cannot be completed.
+fn incomplete() -> Error {
+	std::io::Error::other("The save file cannot be completed, as a member of it was not").into()
+}
+
+impl<W: Write> ArchiveWriter<W> {
+	/// Starts writing a save file to a stream, at the configured compression level.
+	pub fn new(sink: W) -> Result<Self> {
+		Ok(Self {
+			sink: Sink::Idle(sink),
+			seeker: None,
+			empty: true,
+			pending: None,
+			progress: Tracker::new(),
+		})
+	}
+
+	/// Makes the save file hold its members sorted by name, rather than in the order they are
+	/// written, so that the same members always make the same file however they were gathered.
+	///
+	/// Members with the same name keep the order they were written in. Every member is held in
+	/// memory, uncompressed, until [`finish`](Self::finish) is called.
+	///
+	/// ```
+	/// use barotool::save::{ArchiveReader, ArchiveWriter};
+	///
+	/// # fn main() -> barotool::Result<()> {
+	/// let mut writer = ArchiveWriter::new(Vec::new())?.sorted();
+	/// writer.write_member("b", b"2")?;
+	/// writer.write_member("a", b"1")?;
+	/// let data = writer.finish()?;
+	/// let names = ArchiveReader::new(&data[..])?
+	///     .entries()
+	///     .map(|i| i.map(|i| i.name))
+	///     .collect::<barotool::Result<Vec<_>>>()?;
+	/// assert_eq!(names, ["a", "b"]);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[must_use]
+	pub fn sorted(mut self) -> Self {
+		self.pending.get_or_insert_with(Vec::new);
+		self
+	}
+
+	/// Opens the gzip member that the next archive member is compressed into, and writes the
+	/// archive member’s header to it.
+	fn begin(&mut self, name: &str, size: u32) -> Result<()> {
+		let header = member_header(name, size)?;
+		let encoder = match (
+			std::mem::replace(&mut self.sink, Sink::Failed),
+			&self.seeker,
+		) {
+			(Sink::Idle(mut sink), Some(seeker)) => {
+				let start = (seeker.position)(&mut sink)?;
+				let length = vec![0; SEGMENT_HEAD_SIZE - SEGMENT_LENGTH_OFFSET];
+				let encoder =
+					compression::encoder_with_subfield(sink, Some((SEGMENT_SUBFIELD, length)))?;
+				self.sink = Sink::Segment(encoder, start);
+				self.encoder()?
+			}
+			(Sink::Idle(sink), None) => {
+				self.sink = Sink::Stream(compression::encoder(sink)?);
+				self.encoder()?
+			}
+			(Sink::Stream(encoder), _) => {
+				self.sink = Sink::Stream(encoder);
+				self.encoder()?
+			}
+			(Sink::Segment(..) | Sink::Failed, _) => return Err(incomplete()),
+		};
+		encoder.write_all(&header)?;
+		self.empty = false;
+		Ok(())
+	}
+
+	/// Completes the archive member being written, which, in a segmented save file, completes its
+	/// segment and records the segment’s length.
+	fn end(&mut self) -> Result<()> {
+		self.sink = match std::mem::replace(&mut self.sink, Sink::Failed) {
+			Sink::Segment(encoder, start) => {
+				let mut sink = encoder.finish()?;
+				// A segment is only opened in a segmented save file.
+				let seeker = self.seeker.as_ref().unwrap();
+				let length = (seeker.position)(&mut sink)? - start;
+				// Cast is sound because u64 ≥ usize.
+				let offset = start + SEGMENT_LENGTH_OFFSET as u64;
+				(seeker.patch)(&mut sink, offset, &length.to_le_bytes())?;
+				Sink::Idle(sink)
+			}
+			Sink::Stream(encoder) => Sink::Stream(encoder),
+			Sink::Idle(_) | Sink::Failed => return Err(incomplete()),
+		};
+		Ok(())
+	}
+
+	/// Returns the encoder that the archive member being written is compressed by.
+	fn encoder(&mut self) -> std::io::Result<&mut compression::Encoder<W>> {
+		match &mut self.sink {
+			Sink::Stream(encoder) | Sink::Segment(encoder, _) => Ok(encoder),
+			Sink::Idle(_) | Sink::Failed => Err(incomplete().into()),
+		}
+	}
+
+	/// Returns whether the save file is segmented.
+	fn is_segmented(&self) -> bool {
+		self.seeker.is_some()
+	}
+
+	/// Writes a complete segment of the given length, of which `start` has already been read and
+	/// the rest is streamed from `rest`.
+	///
+	/// This bypasses sorting, so is only for segmented writers that do not sort their members.
+	fn copy_segment<S: Read>(&mut self, start: &[u8], rest: S, length: u64) -> Result<()> {
+		debug_assert!(self.is_segmented() && self.pending.is_none());
+		let Sink::Idle(sink) = &mut self.sink else {
+			return Err(incomplete());
+		};
+		sink.write_all(start)?;
+		self.empty = false;
+		// Cast is sound because u64 ≥ usize.
+		let rest_length = length - start.len() as u64;
+		if std::io::copy(&mut rest.take(rest_length), sink)? != rest_length {
+			self.sink = Sink::Failed;
+			return Err(Error::Truncated);
+		}
+		Ok(())
+	}
+
+	/// Starts a member of the given size, whose contents are then written to the returned
+	/// [`MemberWriter`].
+	///
+	/// The size is recorded before the contents, so it must be known up front. The contents are
+	/// compressed into the save file as they are written, so the member must be completed with
+	/// [`MemberWriter::finish`] before anything else is written; if the member writer is dropped
+	/// instead, the save file cannot be completed, and this and [`finish`](Self::finish) report
+	/// as much. If the writer is [`sorted`](Self::sorted), the member is held in memory instead,
+	/// and one that is dropped is simply left out.
+	///
+	/// ```
+	/// use barotool::save::{self, ArchiveWriter};
+	/// use std::io::Write;
+	///
+	/// # fn main() -> barotool::Result<()> {
+	/// let mut writer = ArchiveWriter::new(Vec::new())?;
+	/// let mut member = writer.start_member("gamesession.xml", 15)?;
+	/// member.write_all(b"<Gamesession")?;
+	/// member.write_all(b" />")?;
+	/// member.finish()?;
+	/// let data = writer.finish()?;
+	/// let member = save::find_member(&data[..], "gamesession.xml")?;
+	/// assert_eq!(member.as_deref(), Some(&b"<Gamesession />"[..]));
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn start_member(&mut self, name: &str, size: u32) -> Result<MemberWriter<'_, W>> {
+		let buffer = if self.pending.is_some() {
+			Some(Vec::new())
+		} else {
+			self.begin(name, size)?;
+			None
+		};
+		Ok(MemberWriter {
+			archive: self,
+			buffer,
+			name: name.to_owned(),
+			size,
+			left: size,
+			finished: false,
+		})
+	}
+
+	/// Writes a complete member from memory.
+	pub fn write_member(&mut self, name: &str, data: &[u8]) -> Result<()> {
+		let size: u32 = data
 			.len()
 			.try_into()
-			.map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Member name too long"))?;
-		writer.write_u32::<LittleEndian>(name_len)?;
-		name.iter()
-			.try_for_each(|i| writer.write_u16::<LittleEndian>(*i))?;
-		drop(name);
-
-		// Write the file body, preceded by its length in bytes as a little-endian u32.
-		let reader = File::open(member)?;
-		let file_size = reader.metadata()?.len();
-		let file_size: u32 = file_size
-			.try_into()
-			.map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Member too large"))?;
-		let mut reader = BufReader::new(reader.take(file_size.into()));
-		writer.write_u32::<LittleEndian>(file_size)?;
-		std::io::copy(&mut reader, &mut writer)?;
-	}
-	let writer = writer.finish().into_result()?;
-	let writer = writer.into_inner()?;
-	writer.sync_all()?;
+			.map_err(|_| Error::TooLarge("Member too large".to_owned()))?;
+		let mut member = self.start_member(name, size)?;
+		member.write_all(data)?;
+		member.finish()
+	}
+
+	/// Writes a member of the given size, streaming its contents from `source`.
+	///
+	/// Exactly `size` bytes are read from `source`; if it ends sooner, an error of kind
+	/// [`std::io::ErrorKind::UnexpectedEof`] is returned, and the save file cannot be completed, as
+	/// described at [`start_member`](Self::start_member). To copy a member of another save file,
+	/// see [`copy_member`](crate::save::copy_member).
+	pub fn copy_member<S: Read>(&mut self, name: &str, size: u32, source: S) -> Result<()> {
+		let mut member = self.start_member(name, size)?;
+		std::io::copy(&mut source.take(size.into()), &mut member)?;
+		member.finish()
+	}
+
+	/// Completes the save file and returns the stream it was written to.
+	pub fn finish(mut self) -> Result<W> {
+		if let Some(mut pending) = self.pending.take() {
+			pending.sort_by(|a, b| a.0.cmp(&b.0));
+			for (name, data) in pending {
+				self.write_member(&name, &data)?;
+			}
+		}
+		match std::mem::replace(&mut self.sink, Sink::Failed) {
+			Sink::Idle(mut sink) => {
+				// A save file with no members is still a gzip stream.
+				if self.empty {
+					compression::encoder(&mut sink)?.finish()?;
+				}
+				Ok(sink)
+			}
+			Sink::Stream(encoder) => encoder.finish(),
+			Sink::Segment(..) | Sink::Failed => Err(incomplete()),
+		}
+	}
+}
+
+impl<W: Write + Seek> ArchiveWriter<W> {
+	/// Makes the save file hold each member in a segment of its own, a separate gzip member whose
+	/// header records its compressed length.
+	///
+	/// This must be called before any member is written. The length of each segment is written
+	/// into its header once the segment is complete, so the stream must be able to seek.
+	///
+	/// A segmented save file is still a valid gzip stream, as RFC 1952 allows one to hold several
+	/// members one after another, and barotool reads it as it does any other. Barotrauma’s own
+	/// loader has not been checked to read one, though, which is why saves are only segmented on
+	/// request. Members no longer share a compression dictionary, so a segmented save file is
+	/// also somewhat larger.
+	///
+	/// ```
+	/// use barotool::save::{self, ArchiveWriter};
+	/// use std::io::Cursor;
+	///
+	/// # fn main() -> barotool::Result<()> {
+	/// let mut writer = ArchiveWriter::new(Cursor::new(Vec::new()))?.segmented();
+	/// writer.write_member("gamesession.xml", b"<Gamesession />")?;
+	/// let data = writer.finish()?.into_inner();
+	/// let member = save::find_member(&data[..], "gamesession.xml")?;
+	/// assert_eq!(member.as_deref(), Some(&b"<Gamesession />"[..]));
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[must_use]
+	pub fn segmented(mut self) -> Self {
+		self.seeker = Some(Seeker {
+			position: W::stream_position,
+			patch: patch::<W>,
+		});
+		self
+	}
+}
+
+/// A member of a save file being written, as started by [`ArchiveWriter::start_member`].
+///
+/// Writing more than the size the member was started with fails with an error of kind
+/// [`std::io::ErrorKind::InvalidInput`], leaving the member unchanged.
+pub struct MemberWriter<'archive, W: Write> {
+	/// The save file the member belongs to.
+	archive: &'archive mut ArchiveWriter<W>,
+
+	/// If the save file’s members are being sorted, the member’s contents, held until the save
+	/// file is finished.
+	buffer: Option<Vec<u8>>,
+
+	/// The member’s filename.
+	name: String,
+
+	/// The size of the member, in bytes.
+	size: u32,
+
+	/// The number of bytes still to be written.
+	left: u32,
+
+	/// Whether the member was completed.
+	finished: bool,
+}
+
+impl<W: Write> MemberWriter<'_, W> {
+	/// Completes the member.
+	///
+	/// If fewer bytes were written than the member was started with, an error of kind
+	/// [`std::io::ErrorKind::UnexpectedEof`] is returned, and the save file cannot be completed, as
+	/// described at [`ArchiveWriter::start_member`].
+	pub fn finish(mut self) -> Result<()> {
+		if self.left != 0 {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::UnexpectedEof,
+				format!(
+					"Member {} ended after {} of {} bytes",
+					self.name,
+					self.size - self.left,
+					self.size
+				),
+			)
+			.into());
+		}
+		self.finished = true;
+		match self.buffer.take() {
+			Some(data) => {
+				let name = std::mem::take(&mut self.name);
+				// A member is only buffered if members are being sorted.
+				self.archive.pending.as_mut().unwrap().push((name, data));
+				Ok(())
+			}
+			None => self.archive.end(),
+		}
+	}
+}
+
+impl<W: Write> Write for MemberWriter<'_, W> {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		if buf.len() > self.left as usize {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"Member {} is longer than its size of {} bytes",
+					self.name, self.size
+				),
+			));
+		}
+		let written = match &mut self.buffer {
+			Some(buffer) => buffer.write(buf)?,
+			None => self.archive.encoder()?.write(buf)?,
+		};
+		// Cast is sound because no more than `left` bytes were written.
+		#[allow(clippy::cast_possible_truncation)]
+		let written_u32 = written as u32;
+		self.left -= written_u32;
+		if self.buffer.is_none() {
+			self.archive.progress.advance(
+				&self.name,
+				self.size.into(),
+				self.left.into(),
+				// Cast is sound because u64 ≥ usize.
+				written as u64,
+			);
+		}
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		// The compressed data reaches the stream as the encoder sees fit, and all of it by the time
+		// the save file is finished.
+		Ok(())
+	}
+}
+
+impl<W: Write> Drop for MemberWriter<'_, W> {
+	fn drop(&mut self) {
+		// What was written of an incomplete member cannot be taken back out of the stream.
+		if !self.finished && self.buffer.is_none() {
+			self.archive.sink = Sink::Failed;
+		}
+	}
+}
+
+/// The greatest size of a save file member, whose size is stored in 32 bits.
+pub const MAX_MEMBER_SIZE: u64 = 0xFFFF_FFFF;
+
+/// The suffix of the name of the manifest member listing the parts that a file was split into by
+/// [`Oversize::Split`].
+///
+/// A file `NAME` is stored as a manifest member `NAME.barotool-split`, holding the file’s size and
+/// then the names of its parts, one per line, followed by the parts themselves, `NAME.part000`,
+/// `NAME.part001`, and so on, each of at most [`MAX_MEMBER_SIZE`] bytes.
+pub const SPLIT_SUFFIX: &str = ".barotool-split";
+
+/// What to do with a file too large to be a single member of a save file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Oversize {
+	/// Refuse to pack it, failing with [`Error::TooLarge`] before anything is written.
+	#[default]
+	Refuse,
+
+	/// Split it across several members, as described at [`SPLIT_SUFFIX`], from which
+	/// [`unpack_to`] puts it back together. Barotrauma cannot read such a save file.
+	Split,
+}
+
+/// How to pack a save file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PackOptions {
+	/// What to do with a file too large to be a single member.
+	pub oversize: Oversize,
+
+	/// Whether to hold each member in a segment of its own, as described at
+	/// [`ArchiveWriter::segmented`].
+	pub segmented: bool,
+}
+
+/// Packs a save file, storing each file under its path as given.
+pub fn pack(filename: &OsStr, members: &[&str]) -> Result<()> {
+	let members: Vec<(&str, &Path)> = members.iter().map(|i| (*i, Path::new(i))).collect();
+	pack_named(filename, &members, PackOptions::default())
+}
+
+/// Packs a save file from files stored under names of their own, given as pairs of member name and
+/// path.
+///
+/// Every file’s size is checked before the save file is created, so a file too large for one
+/// member is refused up front unless it may be split.
+pub fn pack_named(filename: &OsStr, members: &[(&str, &Path)], options: PackOptions) -> Result<()> {
+	let sizes = packed_sizes(members, options.oversize)?;
+	let writer = ArchiveWriter::new(BufWriter::new(File::create(filename)?))?;
+	let writer = if options.segmented {
+		writer.segmented()
+	} else {
+		writer
+	};
+	pack_parts(writer, members, &sizes, MAX_MEMBER_SIZE)?
+		.into_inner()
+		.map_err(std::io::IntoInnerError::into_error)?
+		.sync_all()?;
 	Ok(())
 }
 
-/// Unpacks a save file.
+/// Packs a save file into a stream, as [`pack_named`] does, and returns the stream.
+///
+/// The save file is a single gzip member, as the stream need not be able to seek.
+pub fn pack_into<W: Write>(sink: W, members: &[(&str, &Path)], oversize: Oversize) -> Result<W> {
+	let sizes = packed_sizes(members, oversize)?;
+	pack_parts(ArchiveWriter::new(sink)?, members, &sizes, MAX_MEMBER_SIZE)
+}
+
+/// Returns the sizes of the files to be packed, failing if any is too large for one member and may
+/// not be split.
+fn packed_sizes(members: &[(&str, &Path)], oversize: Oversize) -> Result<Vec<u64>> {
+	members
+		.iter()
+		.map(|(_, path)| {
+			let size = std::fs::metadata(path)?.len();
+			if size > MAX_MEMBER_SIZE && oversize == Oversize::Refuse {
+				return Err(Error::TooLarge(format!(
+					"{} is {size} bytes, more than the {MAX_MEMBER_SIZE} bytes a save file member can hold",
+					path.display()
+				)));
+			}
+			Ok(size)
+		})
+		.collect()
+}
+
+/// Packs files of known sizes into a save file being written, splitting any larger than
+/// `part_size` into parts, and completes it.
+fn pack_parts<W: Write>(
+	mut writer: ArchiveWriter<W>,
+	members: &[(&str, &Path)],
+	sizes: &[u64],
+	part_size: u64,
+) -> Result<W> {
+	writer.progress.total = Some(sizes.iter().sum());
+	for ((name, path), size) in members.iter().zip(sizes.iter().copied()) {
+		let mut reader = BufReader::new(File::open(path)?);
+		if size <= part_size {
+			copy_part(&mut writer, name, size, &mut reader)?;
+		} else {
+			let parts: Vec<String> = (0..size.div_ceil(part_size))
+				.map(|i| format!("{name}.part{i:03}"))
+				.collect();
+			let manifest = format!("{size}\n{}\n", parts.join("\n"));
+			writer.write_member(&format!("{name}{SPLIT_SUFFIX}"), manifest.as_bytes())?;
+			let mut left = size;
+			for part in &parts {
+				let part_len = min(left, part_size);
+				copy_part(&mut writer, part, part_len, &mut reader)?;
+				left -= part_len;
+			}
+		}
+		// The file may have grown since its size was taken.
+		if reader.read(&mut [0])? != 0 {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				format!("{} grew while it was being packed", path.display()),
+			)
+			.into());
+		}
+	}
+	writer.finish()
+}
+
+/// Writes a member holding the next `size` bytes of a file.
+fn copy_part<W: Write, R: Read>(
+	writer: &mut ArchiveWriter<W>,
+	name: &str,
+	size: u64,
+	reader: &mut R,
+) -> Result<()> {
+	let size = u32::try_from(size).map_err(|_| Error::TooLarge(format!("{name} is too large")))?;
+	writer.copy_member(name, size, reader)
+}
+
+/// Copies a member being read from one save file into another being written, under the same name,
+/// streaming its contents.
+///
+/// The member must not have been read from yet. Its contents are decompressed and compressed
+/// again, a buffer at a time, so little of it is held in memory.
+///
+/// ```
+/// use barotool::save::{self, ArchiveReader, ArchiveWriter};
+///
+/// # fn main() -> barotool::Result<()> {
+/// let mut writer = ArchiveWriter::new(Vec::new())?;
+/// writer.write_member("gamesession.xml", b"<Gamesession />")?;
+/// writer.write_member("Orca.sub", b"sub")?;
+/// let data = writer.finish()?;
+///
+/// let mut reader = ArchiveReader::new(&data[..])?;
+/// let mut writer = ArchiveWriter::new(Vec::new())?;
+/// while let Some(mut member) = reader.next()? {
+///     if member.name().ends_with(".sub") {
+///         save::copy_member(&mut member, &mut writer)?;
+///     }
+/// }
+/// let data = writer.finish()?;
+/// assert_eq!(save::find_member(&data[..], "Orca.sub")?.as_deref(), Some(&b"sub"[..]));
+/// # Ok(())
+/// # }
+/// ```
+pub fn copy_member<R: Read, W: Write>(
+	member: &mut Member<'_, R>,
+	writer: &mut ArchiveWriter<W>,
+) -> Result<()> {
+	if member.container.member_bytes_left != member.size {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!("Member {} has already been read from", member.name),
+		)
+		.into());
+	}
+	let size = u32::try_from(member.size)
+		.map_err(|_| Error::TooLarge(format!("Member {} is too large", member.name)))?;
+	let name = member.name.clone();
+	let mut copy = writer.start_member(&name, size)?;
+	std::io::copy(member, &mut copy)?;
+	if member.container.member_bytes_left != 0 {
+		return Err(Error::Truncated);
+	}
+	copy.finish()
+}
+
+/// A piece of a save file, as read by [`rewrite`].
+enum Segment {
+	/// The end of the file.
+	End,
+
+	/// The start of a segment written by a [segmented](ArchiveWriter::segmented) writer, up to the
+	/// end of its length, and that length; the rest of the segment is still to be read.
+	Member(Vec<u8>, u64),
+
+	/// The start of a gzip member not written by a segmented writer, whose length is unknown.
+	Other(Vec<u8>),
+}
+
+/// Returns the length of a segment written by a [segmented](ArchiveWriter::segmented) writer, given
+/// the first [`SEGMENT_HEAD_SIZE`] bytes of it, or `None` if they are not the start of such a
+/// segment.
+pub(crate) fn segment_length(head: &[u8]) -> Result<Option<u64>> {
+	// The header must start with the gzip magic number, have the FEXTRA flag, and hold the length
+	// subfield first.
+	let is_segment = head.len() == SEGMENT_HEAD_SIZE
+		&& head[0..2] == [0x1F, 0x8B]
+		&& head[3] & 0x04 != 0
+		&& head[12..14] == SEGMENT_SUBFIELD
+		&& head[14..16] == [8, 0];
+	if !is_segment {
+		return Ok(None);
+	}
+	let length = u64::from_le_bytes(head[SEGMENT_LENGTH_OFFSET..].try_into().unwrap());
+	// Cast is sound because u64 ≥ usize.
+	if length < SEGMENT_HEAD_SIZE as u64 {
+		return Err(Error::Gzip(std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			format!("Invalid save file segment length {length}"),
+		)));
+	}
+	Ok(Some(length))
+}
+
+/// Reads the start of the next piece of a save file.
+fn read_segment<R: Read>(source: &mut R) -> Result<Segment> {
+	let mut head = Vec::with_capacity(SEGMENT_HEAD_SIZE);
+	source
+		.by_ref()
+		.take(SEGMENT_HEAD_SIZE as u64)
+		.read_to_end(&mut head)?;
+	if head.is_empty() {
+		return Ok(Segment::End);
+	}
+	Ok(match segment_length(&head)? {
+		Some(length) => Segment::Member(head, length),
+		None => Segment::Other(head),
+	})
+}
+
+/// A reader that keeps a copy of everything read through it.
+struct Recorder<R: Read> {
+	/// The reader being read through.
+	inner: R,
+
+	/// What has been read so far.
+	record: Vec<u8>,
+}
+
+impl<R: Read> Read for Recorder<R> {
+	fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+		let bytes_read = self.inner.read(buffer)?;
+		self.record.extend_from_slice(&buffer[..bytes_read]);
+		Ok(bytes_read)
+	}
+}
+
+/// Copies a save file from `source` to `sink`, modifying some of its members, and returns the sink.
+///
+/// Each entry in `changes` names a member. If the corresponding data is `Some`, the member’s
+/// contents are replaced, or, if no such member exists, a new member is appended to the end of the
+/// archive. If the data is `None`, the member is removed. All other members are copied unchanged
+/// with [`copy_member`], and the order of members is preserved.
+///
+/// The new save file is a single gzip member, as the game writes. Every member is decompressed and
+/// compressed again, however the source was written; see [`rewrite_segmented`] for a faster way
+/// to rewrite a segmented one.
+pub fn rewrite<R: Read, W: Write>(
+	source: R,
+	sink: W,
+	changes: &[(&str, Option<Vec<u8>>)],
+) -> Result<W> {
+	rewrite_into(source, ArchiveWriter::new(sink)?, changes)
+}
+
+/// Copies a save file, modifying some of its members, as [`rewrite`] does, but into a
+/// [segmented](ArchiveWriter::segmented) save file.
+///
+/// The segments of members left unchanged are streamed still compressed, only as much of each
+/// being decompressed as holds its name, so rewriting a segmented save file takes little more than
+/// the time to compress the changed members. Members not in segments of their own are copied with
+/// [`copy_member`], decompressed and compressed again, each into a new segment.
+pub fn rewrite_segmented<R: Read, W: Write + Seek>(
+	source: R,
+	sink: W,
+	changes: &[(&str, Option<Vec<u8>>)],
+) -> Result<W> {
+	rewrite_into(source, ArchiveWriter::new(sink)?.segmented(), changes)
+}
+
+/// Copies a save file into a save file being written, modifying some of its members, as
+/// [`rewrite`] does, and returns the stream written to.
+fn rewrite_into<R: Read, W: Write>(
+	mut source: R,
+	mut writer: ArchiveWriter<W>,
+	changes: &[(&str, Option<Vec<u8>>)],
+) -> Result<W> {
+	let mut seen = HashSet::new();
+	// Finds the change, if any, to the member with the given name, and makes it.
+	let mut apply = |writer: &mut ArchiveWriter<W>, name: &str| -> Result<bool> {
+		let Some((name, data)) = changes.iter().find(|(i, _)| *i == name) else {
+			return Ok(false);
+		};
+		seen.insert(*name);
+		log::debug!(
+			"{} member {name}",
+			if data.is_some() {
+				"Replacing"
+			} else {
+				"Removing"
+			}
+		);
+		if let Some(data) = data {
+			writer.write_member(name, data)?;
+		}
+		Ok(true)
+	};
+	// Segments can only be copied whole into a segmented save file; whatever is not copied so is
+	// read from start to end.
+	let mut unsegmented = Some(Vec::new());
+	while writer.is_segmented() {
+		match read_segment(&mut source)? {
+			Segment::End => {
+				unsegmented = None;
+				break;
+			}
+			Segment::Member(head, length) => {
+				// Cast is sound because u64 ≥ usize.
+				let body = source.by_ref().take(length - head.len() as u64);
+				let mut recorder = Recorder {
+					inner: Cursor::new(head).chain(body),
+					record: Vec::new(),
+				};
+				let name = ArchiveReader::new(&mut recorder)?
+					.next()?
+					.map(|i| i.name().to_owned());
+				// The rest of the segment is whatever the decoder did not read.
+				let Recorder {
+					inner: mut rest,
+					record,
+				} = recorder;
+				let replaced = match name {
+					Some(name) => apply(&mut writer, &name)?,
+					None => false,
+				};
+				if replaced {
+					std::io::copy(&mut rest, &mut std::io::sink())?;
+				} else {
+					writer.copy_segment(&record, rest, length)?;
+				}
+			}
+			Segment::Other(head) => {
+				unsegmented = Some(head);
+				break;
+			}
+		}
+	}
+	if let Some(head) = unsegmented {
+		let mut reader = ArchiveReader::new(Cursor::new(head).chain(&mut source))?;
+		while let Some(mut member) = reader.next()? {
+			let name = member.name().to_owned();
+			if !apply(&mut writer, &name)? {
+				copy_member(&mut member, &mut writer)?;
+			}
+		}
+	}
+	for (name, data) in changes {
+		if let (false, Some(data)) = (seen.contains(name), data) {
+			log::debug!("Appending member {name}");
+			writer.write_member(name, data)?;
+		}
+	}
+	writer.finish()
+}
+
+/// Modifies some members of a save file, as [`rewrite`] does.
+///
+/// The new archive is written to a temporary file alongside the original, which is only replaced
+/// once the new archive is complete. A [segmented](ArchiveWriter::segmented) save file is rewritten
+/// with [`rewrite_segmented`], and stays segmented, so only the changed members are compressed
+/// again. Any other, such as one written by the game, is rewritten with [`rewrite`], and stays a
+/// single gzip member: the game’s saves give no point part way through at which compression can
+/// be picked up again, so the whole save is decompressed and compressed again on every update.
+pub fn update(filename: &OsStr, changes: &[(&str, Option<Vec<u8>>)]) -> Result<()> {
+	let mut temp_filename = filename.to_owned();
+	temp_filename.push(".tmp");
+	log::info!(
+		"Updating {} member(s) of {}",
+		changes.len(),
+		Path::new(filename).display()
+	);
+	let result = (|| {
+		let mut source = BufReader::new(File::open(filename)?);
+		let mut head = Vec::with_capacity(SEGMENT_HEAD_SIZE);
+		source
+			.by_ref()
+			.take(SEGMENT_HEAD_SIZE as u64)
+			.read_to_end(&mut head)?;
+		let segmented = segment_length(&head)?.is_some();
+		let source = Cursor::new(head).chain(source);
+		let sink = BufWriter::new(File::create(&temp_filename)?);
+		let sink = if segmented {
+			rewrite_segmented(source, sink, changes)?
+		} else {
+			rewrite(source, sink, changes)?
+		};
+		sink.into_inner()
+			.map_err(std::io::IntoInnerError::into_error)?
+			.sync_all()?;
+		Ok(())
+	})();
+	match result {
+		Ok(()) => Ok(std::fs::rename(&temp_filename, filename)?),
+		Err(e) => {
+			let _ = std::fs::remove_file(&temp_filename);
+			Err(e)
+		}
+	}
+}
+
+/// Unpacks the members of a save file chosen by a filter into the current directory.
+///
+/// On return, the filter records which of its patterns matched no member.
+pub fn unpack(filename: &OsStr, members: &mut MemberFilter) -> Result<()> {
+	unpack_to(filename, Path::new("."), members, UnpackOptions::default())
+}
+
+/// Unpacks the members of a save file chosen by a filter into a directory, which is created if it
+/// does not exist.
+///
+/// A member named with a relative path, such as `dir/file`, is extracted into a subdirectory,
+/// which is likewise created if need be. With [`MemberNames::Sanitize`], each member is extracted
+/// at its [`member_path`], and a member whose name has nothing left once made safe is refused. A
+/// member whose file already exists is dealt with as [`UnpackOptions::existing`] says. A file
+/// split across several members by [`Oversize::Split`] is put back together, and is chosen by the
+/// filter under its own name. On return, the filter records which of its patterns matched no
+/// member.
+pub fn unpack_to(
+	filename: &OsStr,
+	dir: &Path,
+	members: &mut MemberFilter,
+	options: UnpackOptions,
+) -> Result<()> {
+	unpack_from(BufReader::new(File::open(filename)?), dir, members, options)
+}
+
+/// Unpacks the members of a save file read from a stream, as [`unpack_to`] does.
+pub fn unpack_from<R: Read>(
+	source: R,
+	dir: &Path,
+	members: &mut MemberFilter,
+	options: UnpackOptions,
+) -> Result<()> {
+	unpack_reader(ArchiveReader::new(source)?, dir, members, options)
+}
+
+/// Unpacks the remaining members of a save file being read, as [`unpack_to`] does.
 ///
-/// If the `members` set is nonempty on entry, only the named members are unpacked, and, on return,
-/// it contains the subset of its original set that were not found in the archive. If the `members`
-/// set is empty, all members are unpacked.
-pub fn unpack(filename: &OsStr, members: &mut HashSet<&str>) -> Result<()> {
-	let extract_all = members.is_empty();
-	let mut reader = open_read(filename)?;
+/// This allows the reader to be set up first, such as with [`ArchiveReader::lossy_names`].
+pub fn unpack_reader<R: Read>(
+	mut reader: ArchiveReader<R>,
+	dir: &Path,
+	members: &mut MemberFilter,
+	options: UnpackOptions,
+) -> Result<()> {
+	std::fs::create_dir_all(dir)?;
+	let mut joining: Option<Joining> = None;
 	while let Some(mut member) = reader.next()? {
-		if extract_all || members.remove(member.name()) {
-			let mut writer = BufWriter::new(File::create(member.name())?);
-			std::io::copy(&mut member, &mut writer)?;
-			writer.into_inner()?.sync_all()?;
+		if let Some(mut join) = joining.take() {
+			if join.parts.front().map(String::as_str) != Some(member.name()) {
+				return Err(join.missing());
+			}
+			join.parts.pop_front();
+			if let Some(writer) = &mut join.writer {
+				join.written += std::io::copy(&mut member, writer)?;
+			}
+			if join.parts.is_empty() {
+				join.finish()?;
+			} else {
+				joining = Some(join);
+			}
+		} else if let Some(name) = member.name().strip_suffix(SPLIT_SUFFIX) {
+			let name = name.to_owned();
+			let mut join = Joining::new(&name, &member.read_to_vec()?)?;
+			if members.matches(&name) {
+				join.writer = extract(dir, &name, options)?;
+			}
+			if join.parts.is_empty() {
+				join.finish()?;
+			} else {
+				joining = Some(join);
+			}
+		} else if members.matches(member.name()) {
+			extract_member(dir, &mut member, options)?;
 		}
 	}
+	match joining {
+		Some(join) => Err(join.missing()),
+		None => Ok(()),
+	}
+}
+
+/// Unpacks the members of a save file chosen by a filter into a directory, as [`unpack_to`] does,
+/// reading only those members, each found through an index of the save file.
+///
+/// Members in segments of their own, as in save files written by a
+/// [segmented](ArchiveWriter::segmented) writer, are read without decompressing any others.
+pub fn unpack_indexed(
+	filename: &OsStr,
+	index: &Index,
+	dir: &Path,
+	members: &mut MemberFilter,
+	options: UnpackOptions,
+) -> Result<()> {
+	std::fs::create_dir_all(dir)?;
+	let mut source = BufReader::new(File::open(filename)?);
+	// The parts of split files, which are only extracted as part of those files.
+	let mut parts = HashSet::new();
+	for entry in &index.entries {
+		if parts.contains(entry.name.as_str()) {
+			continue;
+		}
+		if let Some(name) = entry.name.strip_suffix(SPLIT_SUFFIX) {
+			let mut reader = entry.open(&mut source)?;
+			let mut join = Joining::new(name, &entry.member(&mut reader)?.read_to_vec()?)?;
+			parts.extend(join.parts.iter().cloned());
+			if !members.matches(name) {
+				continue;
+			}
+			join.writer = extract(dir, name, options)?;
+			if join.writer.is_some() {
+				while let Some(part) = join.parts.front() {
+					let Some(entry) = index.find(part) else {
+						return Err(join.missing());
+					};
+					let mut reader = entry.open(&mut source)?;
+					let mut member = entry.member(&mut reader)?;
+					if let Some(writer) = &mut join.writer {
+						join.written += std::io::copy(&mut member, writer)?;
+					}
+					join.parts.pop_front();
+				}
+			}
+			join.finish()?;
+		} else if members.matches(&entry.name) {
+			let mut reader = entry.open(&mut source)?;
+			extract_member(dir, &mut entry.member(&mut reader)?, options)?;
+		}
+	}
+	Ok(())
+}
+
+/// Extracts a member into a file within `dir`, unless it is to be skipped as its file already
+/// exists.
+fn extract_member<R: Read>(
+	dir: &Path,
+	member: &mut Member<'_, R>,
+	options: UnpackOptions,
+) -> Result<()> {
+	if let Some(mut writer) = extract(dir, member.name(), options)? {
+		std::io::copy(member, &mut writer)?;
+		writer
+			.into_inner()
+			.map_err(std::io::IntoInnerError::into_error)?
+			.sync_all()?;
+	}
 	Ok(())
 }
+
+/// Creates the file that a member is extracted to, within `dir`, along with any directories it is
+/// in, or returns `None` if the member is to be skipped as its file already exists.
+fn extract(dir: &Path, name: &str, options: UnpackOptions) -> Result<Option<BufWriter<File>>> {
+	let path = options.names.path(name).ok_or_else(|| {
+		std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			format!("Member name {name} has no usable path"),
+		)
+	})?;
+	let full_path = dir.join(&path);
+	if !options.existing.should_extract(&full_path)? {
+		log::info!("Skipping {name}, as {} already exists", full_path.display());
+		return Ok(None);
+	}
+	if path == Path::new(name) {
+		log::info!("Extracting {name}");
+	} else {
+		log::warn!("Extracting {name} as {}", path.display());
+	}
+	if let Some(parent) = full_path.parent() {
+		std::fs::create_dir_all(parent)?;
+	}
+	Ok(Some(BufWriter::new(File::create(full_path)?)))
+}
+
+/// A file split across several members, as described at [`SPLIT_SUFFIX`], being put back together.
+struct Joining {
+	/// The name of the file.
+	name: String,
+
+	/// The size of the file, as given in the manifest.
+	size: u64,
+
+	/// The names of the parts still to come.
+	parts: VecDeque<String>,
+
+	/// Where the file is being extracted to, or `None` if it is not being extracted.
+	writer: Option<BufWriter<File>>,
+
+	/// The number of bytes extracted so far.
+	written: u64,
+}
+
+impl Joining {
+	/// Starts putting back together the file described by a manifest.
+	fn new(name: &str, manifest: &[u8]) -> Result<Self> {
+		let invalid = || {
+			Error::from(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				format!("The manifest of split member {name} is not valid"),
+			))
+		};
+		let manifest = std::str::from_utf8(manifest).map_err(|_| invalid())?;
+		let mut lines = manifest.lines();
+		let size = lines
+			.next()
+			.and_then(|i| i.parse().ok())
+			.ok_or_else(invalid)?;
+		Ok(Self {
+			name: name.to_owned(),
+			size,
+			parts: lines.map(str::to_owned).collect(),
+			writer: None,
+			written: 0,
+		})
+	}
+
+	/// Returns the error for a save file in which the parts of the file stop short.
+	fn missing(&self) -> Error {
+		std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			format!(
+				"Split member {} is missing part {}",
+				self.name,
+				self.parts.front().map_or("", String::as_str)
+			),
+		)
+		.into()
+	}
+
+	/// Completes the file once every part has been extracted.
+	fn finish(self) -> Result<()> {
+		if let Some(writer) = self.writer {
+			if self.written != self.size {
+				return Err(std::io::Error::new(
+					std::io::ErrorKind::InvalidData,
+					format!(
+						"Split member {} is {} bytes, not {} as its manifest says",
+						self.name, self.written, self.size
+					),
+				)
+				.into());
+			}
+			writer
+				.into_inner()
+				.map_err(std::io::IntoInnerError::into_error)?
+				.sync_all()?;
+		}
+		Ok(())
+	}
+}
+
+/// Reads a single member of a save file into memory.
+///
+/// If the archive does not contain a member with the given name, `None` is returned.
+pub fn read_member(filename: &OsStr, name: &str) -> Result<Option<Vec<u8>>> {
+	find_member(BufReader::new(File::open(filename)?), name)
+}
+
+/// Reads a single member of a save file from a stream into memory.
+///
+/// If the archive does not contain a member with the given name, `None` is returned.
+pub fn find_member<R: Read>(source: R, name: &str) -> Result<Option<Vec<u8>>> {
+	let mut reader = ArchiveReader::new(source)?;
+	while let Some(mut member) = reader.next()? {
+		if member.name() == name {
+			return member.read_to_vec().map(Some);
+		}
+	}
+	Ok(None)
+}
+
+/// Where a save file was found to be damaged, as reported by [`verify`].
+#[derive(Debug)]
+pub struct Damage {
+	/// The offset of the damage in the decompressed archive, as near as can be told: where the
+	/// member header or data that could not be read starts, or, for a member cut short, where it
+	/// ends.
+	pub offset: u64,
+
+	/// The member whose data is damaged, or `None` if the damage is in a member header, in the gzip
+	/// framing between members, or before the first member.
+	pub member: Option<String>,
+
+	/// What is wrong.
+	pub error: Error,
+}
+
+/// The outcome of checking the integrity of a save file with [`verify`].
+#[derive(Debug)]
+pub struct Verification {
+	/// The members read intact, in order.
+	pub members: Vec<MemberInfo>,
+
+	/// Where the save file is damaged, or `None` if it is intact.
+	pub damage: Option<Damage>,
+}
+
+/// Checks the integrity of a save file, reading it from start to end.
+///
+/// Every member header must be complete and hold a name that is valid UTF-16, every member must
+/// hold as many bytes as its header declares, and the gzip data must decompress with every checksum
+/// matching. Reading stops at the first damage found, which is reported along with the members read
+/// intact before it; a failure of `source` itself is reported as damage too, as it cannot be told
+/// apart.
+pub fn verify<R: Read>(source: R) -> Verification {
+	let mut members = Vec::new();
+	let mut offset = 0_u64;
+	let damage = |offset, member, error| {
+		Some(Damage {
+			offset,
+			member,
+			error,
+		})
+	};
+	let mut reader = match ArchiveReader::new(source) {
+		Ok(reader) => reader,
+		Err(e) => {
+			return Verification {
+				members,
+				damage: damage(0, None, e),
+			}
+		}
+	};
+	let mut buffer = vec![0_u8; 64 * 1024];
+	loop {
+		let mut member = match reader.next() {
+			Ok(Some(member)) => member,
+			Ok(None) => break,
+			Err(e) => {
+				return Verification {
+					members,
+					damage: damage(offset, None, e),
+				}
+			}
+		};
+		let name = member.name().to_owned();
+		let size = member.size();
+		// Cast is sound because u64 ≥ usize.
+		offset += 8 + 2 * name.encode_utf16().count() as u64;
+		let mut left = size;
+		while left != 0 {
+			match member.read(&mut buffer) {
+				Ok(0) => {
+					return Verification {
+						members,
+						damage: damage(offset, Some(name), Error::Truncated),
+					}
+				}
+				Ok(n) => {
+					left -= n;
+					// Cast is sound because u64 ≥ usize.
+					offset += n as u64;
+				}
+				Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+				Err(e) => {
+					return Verification {
+						members,
+						damage: damage(offset, Some(name), error::gzip(e)),
+					}
+				}
+			}
+		}
+		members.push(MemberInfo { name, size });
+	}
+	Verification {
+		members,
+		damage: None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_entries() {
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		writer
+			.write_member("gamesession.xml", b"<Gamesession />")
+			.unwrap();
+		writer.write_member("Orca.sub", b"").unwrap();
+		let data = writer.finish().unwrap();
+
+		let entries: Vec<MemberInfo> = ArchiveReader::new(&data[..])
+			.unwrap()
+			.entries()
+			.collect::<Result<_>>()
+			.unwrap();
+		assert_eq!(
+			entries,
+			[
+				MemberInfo {
+					name: "gamesession.xml".to_owned(),
+					size: 15,
+				},
+				MemberInfo {
+					name: "Orca.sub".to_owned(),
+					size: 0,
+				},
+			]
+		);
+
+		let mut reader = ArchiveReader::new(&data[..]).unwrap();
+		let mut member = reader.next().unwrap().unwrap();
+		assert_eq!(member.read_to_vec().unwrap(), b"<Gamesession />");
+		assert_eq!(reader.next().unwrap().unwrap().name(), "Orca.sub");
+		assert!(reader.next().unwrap().is_none());
+
+		// A save file cut off part way through a member is reported as such.
+		let mut writer = compression::encoder(Vec::new()).unwrap();
+		writer
+			.write_all(&member_header("gamesession.xml", 100).unwrap())
+			.unwrap();
+		writer.write_all(b"<Gamesession />").unwrap();
+		let data = writer.finish().unwrap();
+		let mut reader = ArchiveReader::new(&data[..]).unwrap();
+		let mut member = reader.next().unwrap().unwrap();
+		assert!(matches!(member.read_to_vec(), Err(Error::Truncated)));
+		let mut reader = ArchiveReader::new(&data[..]).unwrap();
+		let mut member = reader.next().unwrap().unwrap();
+		let mut buffer = [0_u8; 16];
+		assert_eq!(
+			member.read_exact(&mut buffer).unwrap_err().kind(),
+			std::io::ErrorKind::UnexpectedEof
+		);
+		assert!(matches!(reader.next(), Err(Error::Truncated)));
+	}
+
+	#[test]
+	fn test_rewrite() {
+		let mut writer = ArchiveWriter::new(Cursor::new(Vec::new()))
+			.unwrap()
+			.segmented();
+		writer.write_member("a", b"1").unwrap();
+		writer.write_member("b", b"2").unwrap();
+		writer.write_member("c", b"3").unwrap();
+		let data = writer.finish().unwrap().into_inner();
+		let single = rewrite(&data[..], Vec::new(), &[]).unwrap();
+		assert!(segment_length(&single[..SEGMENT_HEAD_SIZE])
+			.unwrap()
+			.is_none());
+		assert_eq!(find_member(&single[..], "b").unwrap().unwrap(), b"2");
+
+		let changes = [
+			("a", None),
+			("c", Some(b"33".to_vec())),
+			("d", Some(b"4".to_vec())),
+		];
+		let data = rewrite_segmented(&data[..], Cursor::new(Vec::new()), &changes)
+			.unwrap()
+			.into_inner();
+		let names: Vec<String> = ArchiveReader::new(&data[..])
+			.unwrap()
+			.entries()
+			.map(|i| i.unwrap().name)
+			.collect();
+		assert_eq!(names, ["b", "c", "d"]);
+		assert_eq!(find_member(&data[..], "b").unwrap().unwrap(), b"2");
+		assert_eq!(find_member(&data[..], "c").unwrap().unwrap(), b"33");
+		assert_eq!(find_member(&data[..], "a").unwrap(), None);
+
+		// Rewriting again leaves the segment of the untouched member as it was.
+		let again = rewrite_segmented(
+			&data[..],
+			Cursor::new(Vec::new()),
+			&[("c", Some(b"333".to_vec()))],
+		)
+		.unwrap()
+		.into_inner();
+		let segment = |data: &[u8]| match read_segment(&mut &data[..]).unwrap() {
+			Segment::Member(_, length) => data[..usize::try_from(length).unwrap()].to_vec(),
+			_ => panic!("expected a member segment"),
+		};
+		assert_eq!(segment(&again), segment(&data));
+		assert_eq!(find_member(&again[..], "c").unwrap().unwrap(), b"333");
+
+		// A segment cut off part way through is reported as such.
+		let cut = &data[..segment(&data).len() - 1];
+		assert!(matches!(
+			rewrite_segmented(cut, Cursor::new(Vec::new()), &[("c", None)]),
+			Err(Error::Truncated)
+		));
+	}
+
+	#[test]
+	fn test_copy_member() {
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		writer.write_member("a", b"12345").unwrap();
+		writer.write_member("b", b"6789").unwrap();
+		let data = writer.finish().unwrap();
+
+		let mut reader = ArchiveReader::new(&data[..]).unwrap();
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		let mut member = reader.next().unwrap().unwrap();
+		copy_member(&mut member, &mut writer).unwrap();
+		let mut member = reader.next().unwrap().unwrap();
+		member.read_exact(&mut [0]).unwrap();
+		assert_eq!(
+			std::io::Error::from(copy_member(&mut member, &mut writer).unwrap_err()).kind(),
+			std::io::ErrorKind::InvalidInput
+		);
+		let copy = writer.finish().unwrap();
+		assert_eq!(find_member(&copy[..], "a").unwrap().unwrap(), b"12345");
+		assert_eq!(find_member(&copy[..], "b").unwrap(), None);
+	}
+
+	#[test]
+	fn test_verify() {
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		writer.write_member("a", b"12345").unwrap();
+		writer.write_member("b", b"6789").unwrap();
+		let data = writer.finish().unwrap();
+		let verification = verify(&data[..]);
+		assert!(verification.damage.is_none());
+		assert_eq!(verification.members.len(), 2);
+
+		// A save file cut off within its second member is intact up to there.
+		let mut writer = compression::encoder(Vec::new()).unwrap();
+		writer.write_all(&member_header("a", 5).unwrap()).unwrap();
+		writer.write_all(b"12345").unwrap();
+		writer.write_all(&member_header("b", 4).unwrap()).unwrap();
+		writer.write_all(b"67").unwrap();
+		let data = writer.finish().unwrap();
+		let verification = verify(&data[..]);
+		assert_eq!(verification.members.len(), 1);
+		let damage = verification.damage.unwrap();
+		assert_eq!(damage.offset, 10 + 5 + 10 + 2);
+		assert_eq!(damage.member.as_deref(), Some("b"));
+		assert!(matches!(damage.error, Error::Truncated));
+
+		// So is one whose gzip checksum does not match.
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		writer.write_member("a", b"12345").unwrap();
+		let mut data = writer.finish().unwrap();
+		let crc = data.len() - 8;
+		data[crc] ^= 1;
+		let damage = verify(&data[..]).damage.unwrap();
+		assert!(matches!(damage.error, Error::Gzip(_)));
+	}
+
+	#[test]
+	fn test_member_filter() {
+		let mut filter = MemberFilter::new(["*.sub", "Character?ata*", "a*b*c"]);
+		assert!(filter.matches("Orca.sub"));
+		assert!(filter.matches(".sub"));
+		assert!(!filter.matches("Orca.sub.bak"));
+		assert!(filter.matches("CharacterData.xml"));
+		assert!(!filter.matches("Characterata.xml"));
+		assert!(!filter.matches("gamesession.xml"));
+		assert_eq!(filter.unmatched().collect::<Vec<_>>(), ["a*b*c"]);
+		assert!(filter.matches("aXbYbZc"));
+		assert!(!filter.matches("abcb"));
+		assert_eq!(filter.unmatched().count(), 0);
+		assert!(MemberFilter::default().matches("anything"));
+	}
+
+	#[test]
+	fn test_start_member() {
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		let mut member = writer.start_member("a", 4).unwrap();
+		member.write_all(b"12").unwrap();
+		assert_eq!(
+			member.write_all(b"345").unwrap_err().kind(),
+			std::io::ErrorKind::InvalidInput
+		);
+		member.write_all(b"34").unwrap();
+		member.finish().unwrap();
+
+		let data = writer.finish().unwrap();
+		assert_eq!(find_member(&data[..], "a").unwrap().unwrap(), b"1234");
+
+		// A member left short has already been partly written, so the save file cannot be completed.
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		let mut member = writer.start_member("b", 4).unwrap();
+		member.write_all(b"1").unwrap();
+		assert_eq!(
+			std::io::Error::from(member.finish().unwrap_err()).kind(),
+			std::io::ErrorKind::UnexpectedEof
+		);
+		assert!(writer.start_member("c", 1).is_err());
+		assert!(writer.finish().is_err());
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		let mut member = writer.start_member("c", 1).unwrap();
+		member.write_all(b"1").unwrap();
+		drop(member);
+		assert!(writer.finish().is_err());
+
+		// Sorted members are held until the end, so one left short or abandoned is left out.
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap().sorted();
+		let mut member = writer.start_member("b", 4).unwrap();
+		member.write_all(b"1").unwrap();
+		assert!(member.finish().is_err());
+		let mut member = writer.start_member("c", 1).unwrap();
+		member.write_all(b"1").unwrap();
+		drop(member);
+		writer.write_member("a", b"1").unwrap();
+		let data = writer.finish().unwrap();
+		let names: Vec<String> = ArchiveReader::new(&data[..])
+			.unwrap()
+			.entries()
+			.map(|i| i.unwrap().name)
+			.collect();
+		assert_eq!(names, ["a"]);
+	}
+
+	#[test]
+	fn test_segmented() {
+		let mut writer = ArchiveWriter::new(Cursor::new(Vec::new()))
+			.unwrap()
+			.segmented();
+		let mut member = writer.start_member("a", 3).unwrap();
+		member.write_all(b"1").unwrap();
+		member.write_all(b"23").unwrap();
+		member.finish().unwrap();
+		writer.write_member("b", b"45").unwrap();
+		let data = writer.finish().unwrap().into_inner();
+
+		// Each member's length is patched in once it is written.
+		let mut offset = 0;
+		let mut names = Vec::new();
+		while let Segment::Member(_, length) = read_segment(&mut &data[offset..]).unwrap() {
+			let end = offset + usize::try_from(length).unwrap();
+			names.extend(
+				ArchiveReader::new(&data[offset..end])
+					.unwrap()
+					.entries()
+					.map(|i| i.unwrap().name),
+			);
+			offset = end;
+		}
+		assert_eq!(offset, data.len());
+		assert_eq!(names, ["a", "b"]);
+		assert_eq!(find_member(&data[..], "a").unwrap().unwrap(), b"123");
+		assert_eq!(find_member(&data[..], "b").unwrap().unwrap(), b"45");
+
+		// Unless asked for, a save file is one gzip member, as the game writes it.
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		writer.write_member("a", b"123").unwrap();
+		let data = writer.finish().unwrap();
+		assert!(segment_length(&data[..SEGMENT_HEAD_SIZE])
+			.unwrap()
+			.is_none());
+	}
+
+	#[test]
+	fn test_split() {
+		let dir = std::env::temp_dir().join(format!("barotool-test-split-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("big");
+		std::fs::write(&path, b"0123456789").unwrap();
+		let members = [("big", path.as_path()), ("small", path.as_path())];
+		let writer = ArchiveWriter::new(Vec::new()).unwrap();
+		let data = pack_parts(writer, &members[..1], &[10], 4).unwrap();
+		let names: Vec<String> = ArchiveReader::new(&data[..])
+			.unwrap()
+			.entries()
+			.map(|i| i.unwrap().name)
+			.collect();
+		assert_eq!(
+			names,
+			[
+				"big.barotool-split",
+				"big.part000",
+				"big.part001",
+				"big.part002"
+			]
+		);
+		let writer = ArchiveWriter::new(Vec::new()).unwrap();
+		let data = pack_parts(writer, &members, &[10, 10], 4).unwrap();
+		let out = dir.join("out");
+		unpack_from(
+			&data[..],
+			&out,
+			&mut MemberFilter::default(),
+			UnpackOptions::default(),
+		)
+		.unwrap();
+		assert_eq!(std::fs::read(out.join("big")).unwrap(), b"0123456789");
+		assert!(!out.join("big.part000").exists());
+		let mut filter = MemberFilter::new(["b*"]);
+		let out = dir.join("out2");
+		unpack_from(&data[..], &out, &mut filter, UnpackOptions::default()).unwrap();
+		assert!(out.join("big").exists());
+		assert!(!out.join("small").exists());
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn test_limits() {
+		let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+		writer.write_member("a", b"1").unwrap();
+		writer.write_member("b", b"22").unwrap();
+		writer.write_member("c", b"3").unwrap();
+		let data = writer.finish().unwrap();
+		let read = |limits: Limits| -> Result<Vec<MemberInfo>> {
+			ArchiveReader::with_limits(&data[..], limits)?
+				.entries()
+				.collect()
+		};
+		assert_eq!(read(Limits::default()).unwrap().len(), 3);
+		let limit = |limits| matches!(read(limits), Err(Error::Limit(_)));
+		assert!(limit(Limits {
+			max_members: Some(2),
+			..Limits::default()
+		}));
+		assert!(limit(Limits {
+			max_member_size: Some(1),
+			..Limits::default()
+		}));
+		assert!(limit(Limits {
+			max_total_size: Some(20),
+			..Limits::default()
+		}));
+
+		// A member claiming to be huge is refused on its header alone.
+		let mut writer = compression::encoder(Vec::new()).unwrap();
+		writer
+			.write_all(&member_header("gamesession.xml", u32::MAX).unwrap())
+			.unwrap();
+		let data = writer.finish().unwrap();
+		let mut reader = ArchiveReader::with_limits(
+			&data[..],
+			Limits {
+				max_member_size: Some(1 << 20),
+				..Limits::default()
+			},
+		)
+		.unwrap();
+		assert!(matches!(reader.next(), Err(Error::Limit(_))));
+	}
+}
@@ -0,0 +1,186 @@
+use barotool::character::InventoryItem;
+use barotool::submarine::transform::Entity;
+use barotool::xml;
+use clap::{Arg, ArgMatches};
+use std::ops::RangeInclusive;
+use xmltree::Element;
+
+/// The properties of an item that selectors examine.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Candidate<'a> {
+	/// The item’s prefab identifier.
+	pub identifier: &'a str,
+
+	/// The item’s entity ID.
+	pub id: Option<u16>,
+
+	/// The item’s tags.
+	pub tags: Vec<&'a str>,
+
+	/// The name of the room the item is in, if any.
+	pub room: Option<&'a str>,
+}
+
+impl<'a> Candidate<'a> {
+	/// Describes an `Item` element.
+	///
+	/// An ID that does not parse is treated as absent, so that it matches no ID selector.
+	pub fn of_element(element: &'a Element, room: Option<&'a str>) -> Self {
+		Self {
+			identifier: xml::attribute(element, "identifier").unwrap_or_default(),
+			id: xml::parse_attribute(element, "ID").ok().flatten(),
+			tags: split_tags(xml::attribute(element, "tags")),
+			room,
+		}
+	}
+
+	/// Describes an item or structure met while streaming a submarine.
+	///
+	/// The room is not known, as the hulls may not have been read yet.
+	pub fn of_entity(entity: &'a Entity) -> Self {
+		Self {
+			identifier: entity.attribute("identifier").unwrap_or_default(),
+			id: entity.attribute("ID").and_then(|i| i.trim().parse().ok()),
+			tags: split_tags(entity.attribute("tags")),
+			room: None,
+		}
+	}
+
+	/// Describes an item in a character’s inventory.
+	///
+	/// Carried items are not in any room.
+	pub fn of_inventory_item(item: &'a InventoryItem) -> Self {
+		Self {
+			identifier: &item.identifier,
+			id: item.id,
+			tags: item.tags.iter().map(String::as_str).collect(),
+			room: None,
+		}
+	}
+}
+
+/// Splits the value of a `tags` attribute into individual tags.
+fn split_tags(tags: Option<&str>) -> Vec<&str> {
+	tags.unwrap_or_default()
+		.split(',')
+		.map(str::trim)
+		.filter(|i| !i.is_empty())
+		.collect()
+}
+
+/// A single criterion that an item can match.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Selector {
+	/// Items with a given prefab identifier.
+	Identifier(String),
+
+	/// Items with a given tag.
+	Tag(String),
+
+	/// Items whose entity IDs lie within a range.
+	Id(RangeInclusive<u16>),
+
+	/// Items within a given room.
+	Room(String),
+}
+
+impl Selector {
+	/// Parses a selector.
+	///
+	/// A selector is `identifier:IDENTIFIER`, `tag:TAG`, `id:ID`, `id:FIRST-LAST`, or `room:NAME`. A
+	/// selector with no kind is an identifier. Identifiers, tags, and room names are compared
+	/// without regard to case.
+	pub fn parse(value: &str) -> Result<Self, String> {
+		let Some((kind, value)) = value.split_once(':') else {
+			return Ok(Self::Identifier(value.to_owned()));
+		};
+		if value.is_empty() {
+			return Err(format!("expected a value after {kind}:"));
+		}
+		match kind {
+			"identifier" => Ok(Self::Identifier(value.to_owned())),
+			"tag" => Ok(Self::Tag(value.to_owned())),
+			"room" => Ok(Self::Room(value.to_owned())),
+			"id" => {
+				let parse = |i: &str| i.parse::<u16>().map_err(|e| format!("{i}: {e}"));
+				let (first, last) = if let Some((first, last)) = value.split_once('-') {
+					(parse(first)?, parse(last)?)
+				} else {
+					let id = parse(value)?;
+					(id, id)
+				};
+				if first > last {
+					return Err(format!("ID range {first}-{last} is empty"));
+				}
+				Ok(Self::Id(first..=last))
+			}
+			_ => Err(format!(
+				"unknown selector kind {kind}; expected identifier, tag, id, or room"
+			)),
+		}
+	}
+
+	/// Returns whether an item matches the selector.
+	pub fn matches(&self, item: &Candidate<'_>) -> bool {
+		match self {
+			Self::Identifier(identifier) => item.identifier.eq_ignore_ascii_case(identifier),
+			Self::Tag(tag) => item.tags.iter().any(|i| i.eq_ignore_ascii_case(tag)),
+			Self::Id(range) => item.id.is_some_and(|i| range.contains(&i)),
+			Self::Room(room) => item.room.is_some_and(|i| i.eq_ignore_ascii_case(room)),
+		}
+	}
+}
+
+/// A set of selectors choosing which items a command acts on.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Selection {
+	/// The selectors of which an item must match at least one, unless there are none.
+	pub include: Vec<Selector>,
+
+	/// The selectors of which an item must match none.
+	pub exclude: Vec<Selector>,
+}
+
+impl Selection {
+	/// Returns the `--include` and `--exclude` arguments, for adding to a subcommand.
+	pub fn args() -> [Arg<'static>; 2] {
+		[
+			Arg::new("include")
+				.help("Only act on items matching this selector (identifier:IDENTIFIER, tag:TAG, id:N, id:FIRST-LAST, or room:NAME; may be repeated)")
+				.long("include")
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.value_name("SELECTOR")
+				.value_parser(Selector::parse),
+			Arg::new("exclude")
+				.help("Do not act on items matching this selector (may be repeated)")
+				.long("exclude")
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.value_name("SELECTOR")
+				.value_parser(Selector::parse),
+		]
+	}
+
+	/// Builds a selection from the `--include` and `--exclude` arguments of a subcommand.
+	pub fn from_matches(matches: &ArgMatches) -> Self {
+		let get = |name: &str| {
+			matches
+				.get_many::<Selector>(name)
+				.map_or_else(Vec::new, |i| i.cloned().collect())
+		};
+		Self {
+			include: get("include"),
+			exclude: get("exclude"),
+		}
+	}
+
+	/// Returns whether an item is selected.
+	///
+	/// An item is selected if it matches any of the included selectors, or if there are none, and
+	/// matches none of the excluded selectors.
+	pub fn matches(&self, item: &Candidate<'_>) -> bool {
+		(self.include.is_empty() || self.include.iter().any(|i| i.matches(item)))
+			&& !self.exclude.iter().any(|i| i.matches(item))
+	}
+}
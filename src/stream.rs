@@ -0,0 +1,28 @@
+use crate::dryrun;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Result, Write};
+use std::path::Path;
+
+/// Opens a file for reading, or standard input if the filename is `-`.
+pub fn open_input(filename: &OsStr) -> Result<Box<dyn Read>> {
+	if filename == "-" {
+		Ok(Box::new(std::io::stdin().lock()))
+	} else {
+		Ok(Box::new(BufReader::new(File::open(filename)?)))
+	}
+}
+
+/// Creates a file for writing, or returns standard output if the filename is `-`.
+///
+/// In a dry run, a file is not created, and what is written to it is discarded.
+pub fn create_output(filename: &OsStr) -> Result<Box<dyn Write>> {
+	if filename == "-" {
+		Ok(Box::new(std::io::stdout().lock()))
+	} else if dryrun::is_enabled() {
+		dryrun::would(format!("write {}", Path::new(filename).display()));
+		Ok(Box::new(std::io::sink()))
+	} else {
+		Ok(Box::new(BufWriter::new(File::create(filename)?)))
+	}
+}
@@ -0,0 +1,330 @@
+//! Reading and modifying submarines, as stored in `.sub` files and in campaign saves.
+
+use crate::compression;
+use crate::diagnostic;
+#[cfg(feature = "serde")]
+use crate::element;
+use crate::error;
+use crate::xml;
+use crate::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use xmltree::{Element, XMLNode};
+
+pub mod borrowed;
+pub mod diff;
+pub mod transform;
+
+/// The number of things carried over from one revision of a submarine to another.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CarryOver {
+	/// The number of upgrade elements copied.
+	pub upgrades: usize,
+
+	/// The number of items whose state was copied.
+	pub items: usize,
+}
+
+/// A submarine-wide upgrade installed on a submarine.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Upgrade {
+	/// The upgrade prefab identifier, such as `increasehullhealth`.
+	pub identifier: String,
+
+	/// The number of levels of the upgrade that have been installed.
+	pub level: u32,
+}
+
+/// An item placed in a submarine, such as a wall, a reactor, or a fuel rod.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Item {
+	/// The item prefab identifier, such as `fuelrod`.
+	pub identifier: String,
+
+	/// The item’s ID, unique within the submarine.
+	pub id: Option<u16>,
+
+	/// The item’s condition, where 100 is usually undamaged.
+	pub condition: Option<f32>,
+
+	/// The tags given to the item, such as `smallitem`.
+	pub tags: Vec<String>,
+
+	/// The upgrades installed on the item.
+	pub upgrades: Vec<Upgrade>,
+
+	/// The item’s components, in document order.
+	pub components: Vec<Component>,
+}
+
+/// The saved state of one component of an item, such as its `ItemContainer` or `PowerContainer`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Component {
+	/// The component type, such as `Door`.
+	pub name: String,
+
+	/// The component’s saved properties, such as `charge` or `contained`.
+	pub attributes: BTreeMap<String, String>,
+}
+
+/// A submarine, as stored in a `.sub` file.
+///
+/// As with the game session, the whole document is held so that nothing is lost on a round trip.
+/// It is likewise serialized whole, as a tree of elements.
+#[derive(Clone, Debug)]
+pub struct Submarine {
+	/// The root `Submarine` element.
+	root: Element,
+}
+
+impl Submarine {
+	/// Parses a gzip-compressed submarine document.
+	pub fn parse<R: Read>(source: R) -> Result<Self> {
+		let data = compression::decompress(source)?;
+		let root = xml::parse(&data[..])?;
+		if !xml::is_named(&root, "Submarine") {
+			return Err(diagnostic::invalid(format!(
+				"Expected Submarine root element, found {}",
+				root.name
+			)));
+		}
+		Ok(Self { root })
+	}
+
+	/// Writes the submarine document, gzip-compressed.
+	pub fn write<W: Write>(&self, sink: W) -> Result<()> {
+		let mut encoder = compression::encoder(sink)?;
+		xml::write(&self.root, &mut encoder)?;
+		encoder.finish()?;
+		Ok(())
+	}
+
+	/// Sets the name of the submarine.
+	pub fn set_name(&mut self, name: &str) {
+		xml::set_attribute(&mut self.root, "name", name.to_owned());
+	}
+
+	/// Returns the submarine-wide upgrades installed on the submarine.
+	pub fn upgrades(&self) -> Result<Vec<Upgrade>> {
+		xml::children(&self.root, "Upgrade")
+			.map(upgrade_from_xml)
+			.collect()
+	}
+
+	/// Returns the items placed in the submarine.
+	pub fn items(&self) -> Result<Vec<Item>> {
+		xml::children(&self.root, "Item")
+			.map(item_from_xml)
+			.collect()
+	}
+
+	/// Sets the installed level of a submarine-wide upgrade.
+	///
+	/// A level of zero removes the upgrade; if the upgrade is not yet installed, it is added.
+	pub fn set_upgrade_level(&mut self, identifier: &str, level: u32) {
+		if level == 0 {
+			self.root.children.retain(|i| {
+				!i.as_element().is_some_and(|i| {
+					xml::is_named(i, "Upgrade")
+						&& xml::attribute(i, "identifier") == Some(identifier)
+				})
+			});
+			return;
+		}
+		let existing = xml::children_mut(&mut self.root, "Upgrade")
+			.find(|i| xml::attribute(i, "identifier") == Some(identifier));
+		if let Some(upgrade) = existing {
+			xml::set_attribute(upgrade, "level", level.to_string());
+		} else {
+			let mut upgrade = Element::new("Upgrade");
+			xml::set_attribute(&mut upgrade, "identifier", identifier.to_owned());
+			xml::set_attribute(&mut upgrade, "level", level.to_string());
+			self.root.children.push(XMLNode::Element(upgrade));
+		}
+	}
+
+	/// Copies campaign state from an older revision of the same submarine into this one.
+	///
+	/// Submarine-wide upgrades are taken wholesale from the old revision. Items are matched by ID
+	/// and identifier; for each item present in both revisions, the old item’s upgrades and
+	/// condition (which is where fuel rods, ammunition boxes, and the like keep their remaining
+	/// charge) replace the new item’s.
+	pub fn carry_over_from(&mut self, old: &Self) -> CarryOver {
+		let mut ret = CarryOver::default();
+
+		// Replace submarine-wide upgrades.
+		self.root
+			.children
+			.retain(|i| !i.as_element().is_some_and(|i| xml::is_named(i, "Upgrade")));
+		for upgrade in xml::children(&old.root, "Upgrade") {
+			self.root.children.push(XMLNode::Element(upgrade.clone()));
+			ret.upgrades += 1;
+		}
+
+		// Carry over the state of matching items.
+		let old_items: HashMap<(&str, &str), &Element> = xml::children(&old.root, "Item")
+			.filter_map(|i| {
+				Some((
+					(xml::attribute(i, "ID")?, xml::attribute(i, "identifier")?),
+					i,
+				))
+			})
+			.collect();
+		for item in xml::children_mut(&mut self.root, "Item") {
+			let key = match (
+				xml::attribute(item, "ID"),
+				xml::attribute(item, "identifier"),
+			) {
+				(Some(id), Some(identifier)) => (id.to_owned(), identifier.to_owned()),
+				_ => continue,
+			};
+			let Some(old_item) = old_items.get(&(key.0.as_str(), key.1.as_str())) else {
+				continue;
+			};
+			if let Some(condition) = xml::attribute(old_item, "condition") {
+				xml::set_attribute(item, "condition", condition.to_owned());
+			}
+			item.children
+				.retain(|i| !i.as_element().is_some_and(|i| xml::is_named(i, "Upgrade")));
+			for upgrade in xml::children(old_item, "Upgrade") {
+				item.children.push(XMLNode::Element(upgrade.clone()));
+				ret.upgrades += 1;
+			}
+			ret.items += 1;
+		}
+
+		ret
+	}
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Submarine {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		element::serialize(&self.root, serializer)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Submarine {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+		element::deserialize(deserializer, "Submarine").map(|root| Self { root })
+	}
+}
+
+/// Extracts an upgrade from an `Upgrade` element.
+fn upgrade_from_xml(element: &Element) -> Result<Upgrade> {
+	Ok(Upgrade {
+		identifier: xml::attribute(element, "identifier")
+			.unwrap_or_default()
+			.to_owned(),
+		level: xml::parse_attribute(element, "level")?.unwrap_or(0),
+	})
+}
+
+/// Extracts an item from an `Item` element.
+fn item_from_xml(element: &Element) -> Result<Item> {
+	let mut upgrades = Vec::new();
+	let mut components = Vec::new();
+	for child in xml::elements(element) {
+		if xml::is_named(child, "Upgrade") {
+			upgrades.push(upgrade_from_xml(child)?);
+		} else {
+			components.push(Component {
+				name: child.name.clone(),
+				attributes: child
+					.attributes
+					.iter()
+					.map(|(name, value)| (name.clone(), value.clone()))
+					.collect(),
+			});
+		}
+	}
+	Ok(Item {
+		identifier: xml::attribute(element, "identifier")
+			.unwrap_or_default()
+			.to_owned(),
+		id: xml::parse_attribute(element, "ID")?,
+		condition: xml::parse_attribute(element, "condition")?,
+		tags: xml::attribute(element, "tags")
+			.unwrap_or_default()
+			.split(',')
+			.filter(|i| !i.is_empty())
+			.map(str::to_owned)
+			.collect(),
+		upgrades,
+		components,
+	})
+}
+
+/// Decompresses a `.sub` file into plain XML.
+pub fn decompress<R: Read, W: Write>(source: R, mut sink: W) -> Result<()> {
+	let mut decoder = compression::Decoder::new(source)?;
+	std::io::copy(&mut decoder, &mut sink).map_err(error::gzip)?;
+	sink.flush()?;
+	Ok(())
+}
+
+/// Compresses a plain XML submarine into a `.sub` file.
+pub fn compress<R: Read, W: Write>(mut source: R, sink: W) -> Result<()> {
+	let mut encoder = compression::encoder(sink)?;
+	std::io::copy(&mut source, &mut encoder)?;
+	encoder.finish()?.flush()?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_items() {
+		let root = xml::parse(
+			&br#"<Submarine name="Test">
+  <Item identifier="fuelrod" ID="12" condition="40" tags="smallitem,reactorfuel">
+    <Holdable />
+    <Upgrade identifier="increaseitemcondition" level="2" />
+  </Item>
+  <Item identifier="reactor1" ID="13">
+    <Reactor temperature="0" />
+  </Item>
+</Submarine>"#[..],
+		)
+		.unwrap();
+		let sub = Submarine { root };
+		let items = sub.items().unwrap();
+		assert_eq!(items.len(), 2);
+		assert_eq!(items[0].id, Some(12));
+		assert_eq!(items[0].condition, Some(40.0));
+		assert_eq!(items[0].tags, ["smallitem", "reactorfuel"]);
+		assert_eq!(items[0].upgrades[0].level, 2);
+		assert_eq!(items[0].components[0].name, "Holdable");
+		assert_eq!(
+			items[1].components[0]
+				.attributes
+				.get("temperature")
+				.map(String::as_str),
+			Some("0")
+		);
+	}
+
+	#[test]
+	fn test_depth_limit() {
+		let depth = crate::limits::DEFAULT_MAX_DEPTH + 1;
+		let text = format!(
+			"<Submarine>{}{}</Submarine>",
+			"<Item>".repeat(depth - 1),
+			"</Item>".repeat(depth - 1)
+		);
+		let mut sub = Vec::new();
+		compress(text.as_bytes(), &mut sub).unwrap();
+		assert!(matches!(
+			Submarine::parse(&sub[..]),
+			Err(crate::Error::Limit(_))
+		));
+	}
+}
@@ -0,0 +1,442 @@
+//! A read-only view of a submarine that borrows its strings from the XML it was parsed from.
+//!
+//! [`Submarine`](super::Submarine) holds the whole document as a tree, which takes an allocation for
+//! every element and attribute. When only the upgrades and items are wanted, such as when surveying
+//! hundreds of Workshop submarines, [`Submarine::parse`] instead picks them straight out of the
+//! decompressed text. Strings are borrowed from the text unless they contain character or entity
+//! references, and everything beneath the items’ components is skipped without being looked at.
+//!
+//! ```
+//! use barotool::submarine::borrowed::Submarine;
+//!
+//! let xml = r#"<Submarine name="Humpback"><Item identifier="fuelrod" ID="7" /></Submarine>"#;
+//! let sub = Submarine::parse(xml)?;
+//! assert_eq!(sub.name.as_deref(), Some("Humpback"));
+//! assert_eq!(sub.items[0].identifier, "fuelrod");
+//! # Ok::<(), barotool::Error>(())
+//! ```
+
+use crate::diagnostic::{self, Position};
+use crate::xml;
+use crate::Result;
+use std::borrow::Cow;
+use std::io::Read;
+use std::str::FromStr;
+use xmlparser::{ElementEnd, StrSpan, Token, Tokenizer};
+
+/// A submarine-wide or item upgrade, borrowed from a document.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Upgrade<'a> {
+	/// The upgrade prefab identifier, such as `increasehullhealth`.
+	pub identifier: Cow<'a, str>,
+
+	/// The number of levels of the upgrade that have been installed.
+	pub level: u32,
+}
+
+impl From<Upgrade<'_>> for super::Upgrade {
+	fn from(upgrade: Upgrade<'_>) -> Self {
+		Self {
+			identifier: upgrade.identifier.into_owned(),
+			level: upgrade.level,
+		}
+	}
+}
+
+/// An item placed in a submarine, borrowed from a document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item<'a> {
+	/// The item prefab identifier, such as `fuelrod`.
+	pub identifier: Cow<'a, str>,
+
+	/// The item’s ID, unique within the submarine.
+	pub id: Option<u16>,
+
+	/// The item’s condition, where 100 is usually undamaged.
+	pub condition: Option<f32>,
+
+	/// The tags given to the item, such as `smallitem`.
+	pub tags: Vec<Cow<'a, str>>,
+
+	/// The upgrades installed on the item.
+	pub upgrades: Vec<Upgrade<'a>>,
+
+	/// The item’s components, in document order.
+	pub components: Vec<Component<'a>>,
+}
+
+impl From<Item<'_>> for super::Item {
+	fn from(item: Item<'_>) -> Self {
+		Self {
+			identifier: item.identifier.into_owned(),
+			id: item.id,
+			condition: item.condition,
+			tags: item.tags.into_iter().map(Cow::into_owned).collect(),
+			upgrades: item.upgrades.into_iter().map(Into::into).collect(),
+			components: item.components.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// The saved state of one component of an item, borrowed from a document.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Component<'a> {
+	/// The component type, such as `Door`.
+	pub name: &'a str,
+
+	/// The component’s saved properties, in document order.
+	pub attributes: Vec<(&'a str, Cow<'a, str>)>,
+}
+
+impl From<Component<'_>> for super::Component {
+	fn from(component: Component<'_>) -> Self {
+		Self {
+			name: component.name.to_owned(),
+			attributes: component
+				.attributes
+				.into_iter()
+				.map(|(name, value)| (name.to_owned(), value.into_owned()))
+				.collect(),
+		}
+	}
+}
+
+/// The upgrades and items of a submarine, borrowed from its XML.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Submarine<'a> {
+	/// The name of the submarine, if it has one.
+	pub name: Option<Cow<'a, str>>,
+
+	/// The submarine-wide upgrades installed on the submarine.
+	pub upgrades: Vec<Upgrade<'a>>,
+
+	/// The items placed in the submarine.
+	pub items: Vec<Item<'a>>,
+}
+
+impl<'a> Submarine<'a> {
+	/// Parses a plain, uncompressed submarine document.
+	///
+	/// If the document is not well-formed, it is parsed again as a tree so that the error says
+	/// where, in the same way as [`Submarine::parse`](super::Submarine::parse).
+	pub fn parse(text: &'a str) -> Result<Self> {
+		match parse(text) {
+			Ok(sub) => Ok(sub),
+			Err(Failure::Invalid(e)) => Err(e),
+			Err(Failure::Syntax(message, position)) => Err(xml::parse(text.as_bytes())
+				.map_or_else(
+					|e| e,
+					|_| diagnostic::syntax(message, Some(position), String::new()),
+				)),
+		}
+	}
+}
+
+/// Decompresses a `.sub` file into a string, ready for [`Submarine::parse`].
+pub fn read_to_string<R: Read>(source: R) -> Result<String> {
+	let mut data = Vec::new();
+	super::decompress(source, &mut data)?;
+	String::from_utf8(data).map_err(|e| diagnostic::invalid(format!("Submarine is not UTF-8: {e}")))
+}
+
+/// Why a document could not be parsed.
+enum Failure {
+	/// The document is not well-formed, as found at a position.
+	Syntax(String, Position),
+
+	/// The document is well-formed but not a submarine.
+	Invalid(crate::Error),
+}
+
+impl From<crate::Error> for Failure {
+	fn from(e: crate::Error) -> Self {
+		Self::Invalid(e)
+	}
+}
+
+/// A start tag whose attributes are being read.
+struct StartTag<'a> {
+	/// The element’s name.
+	name: StrSpan<'a>,
+
+	/// The attributes wanted from the element, in document order.
+	attributes: Vec<(StrSpan<'a>, Cow<'a, str>)>,
+}
+
+impl<'a> StartTag<'a> {
+	/// Returns the value of an attribute, comparing names case-insensitively.
+	fn attribute(&self, name: &str) -> Option<&Cow<'a, str>> {
+		self.attributes
+			.iter()
+			.find(|(k, _)| k.as_str().eq_ignore_ascii_case(name))
+			.map(|(_, v)| v)
+	}
+
+	/// Returns the value of an attribute, parsed into some type.
+	fn parse_attribute<T: FromStr>(&self, text: &str, name: &str) -> Result<Option<T>> {
+		let Some((key, value)) = self
+			.attributes
+			.iter()
+			.find(|(k, _)| k.as_str().eq_ignore_ascii_case(name))
+		else {
+			return Ok(None);
+		};
+		value.trim().parse().map(Some).map_err(|_| {
+			diagnostic::attribute_at(
+				self.name.as_str(),
+				name,
+				value,
+				format!(
+					"Invalid value “{}” for attribute {} of element {}",
+					value,
+					name,
+					self.name.as_str()
+				),
+				Position::at(text.as_bytes(), key.start()),
+			)
+		})
+	}
+
+	/// Extracts an upgrade from an `Upgrade` start tag.
+	fn upgrade(&self, text: &str) -> Result<Upgrade<'a>> {
+		Ok(Upgrade {
+			identifier: self.attribute("identifier").cloned().unwrap_or_default(),
+			level: self.parse_attribute(text, "level")?.unwrap_or(0),
+		})
+	}
+
+	/// Extracts an item, without its children, from an `Item` start tag.
+	fn item(&self, text: &str) -> Result<Item<'a>> {
+		let tags = match self.attribute("tags") {
+			None => Vec::new(),
+			Some(Cow::Borrowed(tags)) => tags
+				.split(',')
+				.filter(|i| !i.is_empty())
+				.map(Cow::Borrowed)
+				.collect(),
+			Some(Cow::Owned(tags)) => tags
+				.split(',')
+				.filter(|i| !i.is_empty())
+				.map(|i| Cow::Owned(i.to_owned()))
+				.collect(),
+		};
+		Ok(Item {
+			identifier: self.attribute("identifier").cloned().unwrap_or_default(),
+			id: self.parse_attribute(text, "ID")?,
+			condition: self.parse_attribute(text, "condition")?,
+			tags,
+			upgrades: Vec::new(),
+			components: Vec::new(),
+		})
+	}
+}
+
+/// Takes what is wanted from a start tag, given the number of elements enclosing it.
+///
+/// `in_item` tracks whether the elements at depth 2 are the children of an item.
+fn start_element<'a>(
+	sub: &mut Option<Submarine<'a>>,
+	depth: usize,
+	tag: StartTag<'a>,
+	is_open: bool,
+	in_item: &mut bool,
+	text: &str,
+) -> Result<()> {
+	match (depth, sub) {
+		(0, sub) => {
+			if !tag.name.as_str().eq_ignore_ascii_case("Submarine") {
+				return Err(diagnostic::invalid(format!(
+					"Expected Submarine root element, found {}",
+					tag.name.as_str()
+				)));
+			}
+			*sub = Some(Submarine {
+				name: tag.attribute("name").cloned(),
+				..Submarine::default()
+			});
+		}
+		(1, Some(sub)) => {
+			if tag.name.as_str().eq_ignore_ascii_case("Upgrade") {
+				sub.upgrades.push(tag.upgrade(text)?);
+			} else if tag.name.as_str().eq_ignore_ascii_case("Item") {
+				sub.items.push(tag.item(text)?);
+				*in_item = is_open;
+			}
+		}
+		(2, Some(sub)) if *in_item => {
+			if let Some(item) = sub.items.last_mut() {
+				if tag.name.as_str().eq_ignore_ascii_case("Upgrade") {
+					item.upgrades.push(tag.upgrade(text)?);
+				} else {
+					item.components.push(Component {
+						name: tag.name.as_str(),
+						attributes: tag
+							.attributes
+							.into_iter()
+							.map(|(k, v)| (k.as_str(), v))
+							.collect(),
+					});
+				}
+			}
+		}
+		_ => (),
+	}
+	Ok(())
+}
+
+/// Parses a submarine document.
+fn parse(text: &str) -> std::result::Result<Submarine<'_>, Failure> {
+	let mut sub: Option<Submarine<'_>> = None;
+	// The prefixes and names of the elements enclosing the current position.
+	let mut open: Vec<(&str, &str)> = Vec::new();
+	let mut tag: Option<(&str, StartTag<'_>)> = None;
+	let mut in_item = false;
+	let syntax = |message, offset| Failure::Syntax(message, Position::at(text.as_bytes(), offset));
+	for token in Tokenizer::from(text) {
+		let token = token.map_err(|e| {
+			let pos = e.pos();
+			Failure::Syntax(
+				e.to_string(),
+				Position::of(
+					text.as_bytes(),
+					u64::from(pos.row).saturating_sub(1),
+					u64::from(pos.col).saturating_sub(1),
+				),
+			)
+		})?;
+		match token {
+			Token::ElementStart { prefix, local, .. } => {
+				tag = Some((
+					prefix.as_str(),
+					StartTag {
+						name: local,
+						attributes: Vec::new(),
+					},
+				));
+			}
+			// Only the root, its children, and its grandchildren have anything wanted.
+			Token::Attribute {
+				local, value, span, ..
+			} if open.len() < 3 => {
+				if let Some((_, tag)) = &mut tag {
+					let value = unescape(value).ok_or_else(|| {
+						syntax(
+							format!("Invalid reference in attribute value {}", value.as_str()),
+							span.start(),
+						)
+					})?;
+					tag.attributes.push((local, value));
+				}
+			}
+			Token::ElementEnd { end, span } => match end {
+				ElementEnd::Open | ElementEnd::Empty => {
+					let Some((prefix, tag)) = tag.take() else {
+						continue;
+					};
+					let is_open = matches!(end, ElementEnd::Open);
+					let name = tag.name.as_str();
+					start_element(&mut sub, open.len(), tag, is_open, &mut in_item, text)?;
+					if is_open {
+						open.push((prefix, name));
+					}
+				}
+				ElementEnd::Close(prefix, local) => {
+					if open.pop() != Some((prefix.as_str(), local.as_str())) {
+						return Err(syntax(
+							format!("Unexpected closing tag {}", local.as_str()),
+							span.start(),
+						));
+					}
+					if open.len() == 1 {
+						in_item = false;
+					}
+				}
+			},
+			_ => (),
+		}
+	}
+	if let Some((_, name)) = open.last() {
+		return Err(syntax(format!("Element {name} is not closed"), text.len()));
+	}
+	sub.ok_or_else(|| syntax("No root element".to_owned(), text.len()))
+}
+
+/// Replaces the character and entity references in an attribute value.
+///
+/// The value is borrowed if it has no references. `None` is returned if a reference is malformed.
+fn unescape(value: StrSpan<'_>) -> Option<Cow<'_, str>> {
+	let value = value.as_str();
+	if !value.contains('&') {
+		return Some(Cow::Borrowed(value));
+	}
+	let mut unescaped = String::with_capacity(value.len());
+	let mut rest = value;
+	while let Some(amp) = rest.find('&') {
+		unescaped.push_str(&rest[..amp]);
+		let semicolon = rest[amp..].find(';')? + amp;
+		let reference = &rest[amp + 1..semicolon];
+		unescaped.push(match reference {
+			"amp" => '&',
+			"lt" => '<',
+			"gt" => '>',
+			"quot" => '"',
+			"apos" => '\'',
+			_ => {
+				let code = if let Some(hex) = reference.strip_prefix("#x") {
+					u32::from_str_radix(hex, 16).ok()?
+				} else {
+					reference.strip_prefix('#')?.parse().ok()?
+				};
+				char::from_u32(code)?
+			}
+		});
+		rest = &rest[semicolon + 1..];
+	}
+	unescaped.push_str(rest);
+	Some(Cow::Owned(unescaped))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::submarine;
+
+	#[test]
+	fn test_matches_tree() {
+		let text = r#"<?xml version="1.0" encoding="utf-8"?>
+<Submarine name="Tom &amp; Jerry">
+  <Upgrade identifier="increasehullhealth" level="3" />
+  <Item identifier="fuelrod" ID="12" condition="40" tags="small&#105;tem,reactorfuel">
+    <Holdable />
+    <Upgrade identifier="increaseitemcondition" level="2" />
+    <ItemContainer contained="1;2"><Deeper ignored="yes" /></ItemContainer>
+  </Item>
+  <Item identifier="reactor1" ID="13" />
+</Submarine>"#;
+		let sub = Submarine::parse(text).unwrap();
+		assert_eq!(sub.name.as_deref(), Some("Tom & Jerry"));
+		assert!(matches!(sub.items[1].identifier, Cow::Borrowed(_)));
+		let tree = submarine::Submarine {
+			root: xml::parse(text.as_bytes()).unwrap(),
+		};
+		assert_eq!(
+			sub.upgrades
+				.into_iter()
+				.map(Into::into)
+				.collect::<Vec<submarine::Upgrade>>(),
+			tree.upgrades().unwrap()
+		);
+		assert_eq!(
+			sub.items
+				.into_iter()
+				.map(Into::into)
+				.collect::<Vec<submarine::Item>>(),
+			tree.items().unwrap()
+		);
+
+		let e = Submarine::parse("<Submarine><Item></Submarine>").unwrap_err();
+		assert!(matches!(e, crate::Error::Xml(_)));
+		let e = Submarine::parse(r#"<Submarine><Item ID="x" /></Submarine>"#).unwrap_err();
+		assert_eq!(e.diagnostic().unwrap().to_string().lines().count(), 2);
+	}
+}
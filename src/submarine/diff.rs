@@ -0,0 +1,297 @@
+//! Comparing the items of two submarines.
+//!
+//! [`SubmarineDiff::compute`] matches up the items of two submarines, usually two revisions of the
+//! same design or the same submarine before and after a mission, and reports which items were
+//! added, which were removed, and which attributes of the rest changed, as records that a program
+//! can render or act on.
+//!
+//! ```
+//! use barotool::submarine::diff::SubmarineDiff;
+//! use barotool::submarine::{self, Submarine};
+//!
+//! # fn main() -> barotool::Result<()> {
+//! # let sub = |text: &str| -> barotool::Result<Submarine> {
+//! #     let mut data = Vec::new();
+//! #     submarine::compress(text.as_bytes(), &mut data)?;
+//! #     Submarine::parse(&data[..])
+//! # };
+//! let old = sub(r#"<Submarine><Item identifier="fuelrod" ID="7" condition="100" /></Submarine>"#)?;
+//! let new = sub(r#"<Submarine><Item identifier="fuelrod" ID="7" condition="40" /></Submarine>"#)?;
+//! let diff = SubmarineDiff::compute(&old, &new)?;
+//! let change = &diff.changed[0].changes[0];
+//! assert_eq!(change.name, "condition");
+//! assert_eq!(change.new.as_deref(), Some("40"));
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{item_from_xml, Item, Submarine};
+use crate::xml;
+use crate::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use xmltree::Element;
+
+/// One attribute whose value differs between two revisions of an item.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct AttributeChange {
+	/// The element holding the attribute: `None` for the item itself, the name of a component,
+	/// such as `ItemContainer`, or, for an upgrade, `Upgrade` followed by its identifier in
+	/// brackets, such as `Upgrade[increaseitemcondition]`.
+	pub element: Option<String>,
+
+	/// The attribute’s name.
+	pub name: String,
+
+	/// The attribute’s value in the old submarine, or `None` if it was absent.
+	pub old: Option<String>,
+
+	/// The attribute’s value in the new submarine, or `None` if it is absent.
+	pub new: Option<String>,
+}
+
+/// An item present in both submarines whose attributes differ.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ItemChange {
+	/// The item prefab identifier, such as `fuelrod`.
+	pub identifier: String,
+
+	/// The item’s ID, unique within the submarine.
+	pub id: Option<u16>,
+
+	/// The attributes that differ, those of the item itself first.
+	pub changes: Vec<AttributeChange>,
+}
+
+/// The differences between the items of two submarines.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct SubmarineDiff {
+	/// The items only in the new submarine, in its document order.
+	pub added: Vec<Item>,
+
+	/// The items only in the old submarine, in its document order.
+	pub removed: Vec<Item>,
+
+	/// The items in both submarines whose attributes differ, in the new submarine’s document order.
+	pub changed: Vec<ItemChange>,
+}
+
+/// What an item is matched by: its ID, its identifier, and how many items before it had the same
+/// ID and identifier.
+type Key<'a> = (Option<&'a str>, &'a str, usize);
+
+/// Returns the items of a submarine, each with the key it is matched by.
+fn keyed(root: &Element) -> Vec<(Key<'_>, &Element)> {
+	let mut counts: HashMap<(Option<&str>, &str), usize> = HashMap::new();
+	xml::children(root, "Item")
+		.map(|item| {
+			let id = xml::attribute(item, "ID");
+			let identifier = xml::attribute(item, "identifier").unwrap_or_default();
+			let count = counts.entry((id, identifier)).or_default();
+			*count += 1;
+			((id, identifier, *count), item)
+		})
+		.collect()
+}
+
+/// Returns the label of a child element of an item, as given in [`AttributeChange::element`].
+fn label(element: &Element) -> String {
+	if xml::is_named(element, "Upgrade") {
+		format!(
+			"Upgrade[{}]",
+			xml::attribute(element, "identifier").unwrap_or_default()
+		)
+	} else {
+		element.name.clone()
+	}
+}
+
+/// Records the differences between the attributes of two elements.
+///
+/// Either element may be absent, in which case each attribute of the other is added or removed.
+fn compare_attributes(
+	element: Option<&str>,
+	old: Option<&Element>,
+	new: Option<&Element>,
+	changes: &mut Vec<AttributeChange>,
+) {
+	let mut change = |name: &str, old: Option<&str>, new: Option<&str>| {
+		changes.push(AttributeChange {
+			element: element.map(str::to_owned),
+			name: name.to_owned(),
+			old: old.map(str::to_owned),
+			new: new.map(str::to_owned),
+		});
+	};
+	if let Some(old) = old {
+		for (name, value) in &old.attributes {
+			let new_value = new.and_then(|i| xml::attribute(i, name));
+			if new_value != Some(value) {
+				change(name, Some(value), new_value);
+			}
+		}
+	}
+	if let Some(new) = new {
+		for (name, value) in &new.attributes {
+			if old.and_then(|i| xml::attribute(i, name)).is_none() {
+				change(name, None, Some(value));
+			}
+		}
+	}
+}
+
+/// What a child element of an item is matched by: its label in lower case, and how many children
+/// before it had the same label.
+type ChildKey = (String, usize);
+
+/// Returns the child elements of an item, each with the key it is matched by and its label.
+fn children(item: &Element) -> Vec<(ChildKey, (String, &Element))> {
+	let mut counts: HashMap<String, usize> = HashMap::new();
+	xml::elements(item)
+		.map(|child| {
+			let label = label(child);
+			let key = label.to_ascii_lowercase();
+			let count = counts.entry(key.clone()).or_default();
+			*count += 1;
+			((key, *count), (label, child))
+		})
+		.collect()
+}
+
+/// Returns the differences between two revisions of an item, including its components and
+/// upgrades.
+fn compare_items(old: &Element, new: &Element) -> Vec<AttributeChange> {
+	let mut changes = Vec::new();
+	compare_attributes(None, Some(old), Some(new), &mut changes);
+	let old_children = children(old);
+	let new_children = children(new);
+	let new_keys: HashMap<_, _> = new_children
+		.iter()
+		.map(|(key, (_, child))| (key, *child))
+		.collect();
+	let old_keys: HashSet<_> = old_children.iter().map(|(key, _)| key).collect();
+	for (key, (label, child)) in &old_children {
+		compare_attributes(
+			Some(label),
+			Some(child),
+			new_keys.get(key).copied(),
+			&mut changes,
+		);
+	}
+	for (key, (label, child)) in &new_children {
+		if !old_keys.contains(key) {
+			compare_attributes(Some(label), None, Some(child), &mut changes);
+		}
+	}
+	changes
+}
+
+impl SubmarineDiff {
+	/// Compares the items of two submarines.
+	///
+	/// Items are matched by ID and identifier, as when
+	/// [carrying state over](Submarine::carry_over_from); should several items share both, they
+	/// are matched in document order. The attributes of matched items are compared, along with
+	/// those of their components and upgrades, which are matched by name, or by identifier for
+	/// upgrades, in document order; elements nested more deeply are not compared. Attribute names
+	/// are compared case-insensitively, as Barotrauma reads them.
+	pub fn compute(old: &Submarine, new: &Submarine) -> Result<Self> {
+		let old_items = keyed(&old.root);
+		let new_items = keyed(&new.root);
+		let old_by_key: HashMap<_, _> = old_items.iter().copied().collect();
+		let mut matched = HashSet::new();
+		let mut ret = Self::default();
+		for (key, item) in &new_items {
+			match old_by_key.get(key) {
+				Some(old_item) => {
+					matched.insert(*key);
+					let changes = compare_items(old_item, item);
+					if !changes.is_empty() {
+						ret.changed.push(ItemChange {
+							identifier: key.1.to_owned(),
+							id: xml::parse_attribute(item, "ID")?,
+							changes,
+						});
+					}
+				}
+				None => ret.added.push(item_from_xml(item)?),
+			}
+		}
+		for (key, item) in &old_items {
+			if !matched.contains(key) {
+				ret.removed.push(item_from_xml(item)?);
+			}
+		}
+		Ok(ret)
+	}
+
+	/// Returns whether the submarines have the same items, with the same attributes.
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Parses a plain submarine document.
+	fn sub(text: &str) -> Submarine {
+		Submarine {
+			root: xml::parse(text.as_bytes()).unwrap(),
+		}
+	}
+
+	#[test]
+	fn test_compute() {
+		let old = sub(r#"<Submarine>
+  <Item identifier="fuelrod" ID="1" condition="100">
+    <Upgrade identifier="increaseitemcondition" level="1" />
+  </Item>
+  <Item identifier="bench" ID="2" />
+  <Item identifier="lamp" ID="3"><LightComponent range="100" /></Item>
+</Submarine>"#);
+		let new = sub(r#"<Submarine>
+  <Item identifier="fuelrod" ID="1" Condition="40">
+    <Upgrade identifier="increaseitemcondition" level="2" />
+  </Item>
+  <Item identifier="lamp" ID="3"><LightComponent range="100" /></Item>
+  <Item identifier="bench" ID="4" />
+</Submarine>"#);
+		let diff = SubmarineDiff::compute(&old, &new).unwrap();
+		assert_eq!(
+			diff.added.iter().map(|i| i.id).collect::<Vec<_>>(),
+			[Some(4)]
+		);
+		assert_eq!(
+			diff.removed.iter().map(|i| i.id).collect::<Vec<_>>(),
+			[Some(2)]
+		);
+		assert_eq!(
+			diff.changed,
+			[ItemChange {
+				identifier: "fuelrod".to_owned(),
+				id: Some(1),
+				changes: vec![
+					AttributeChange {
+						element: None,
+						name: "condition".to_owned(),
+						old: Some("100".to_owned()),
+						new: Some("40".to_owned()),
+					},
+					AttributeChange {
+						element: Some("Upgrade[increaseitemcondition]".to_owned()),
+						name: "level".to_owned(),
+						old: Some("1".to_owned()),
+						new: Some("2".to_owned()),
+					},
+				],
+			}]
+		);
+		assert!(SubmarineDiff::compute(&old, &old).unwrap().is_empty());
+	}
+}
@@ -0,0 +1,348 @@
+//! Rewriting a submarine as it is read, without holding the whole document in memory.
+//!
+//! [`Submarine`](super::Submarine) parses the whole document into a tree, which for the largest
+//! submarines takes hundreds of megabytes. Edits that only touch the top-level elements of a
+//! submarine, such as setting an attribute on some items or removing them, can instead be made by
+//! [`transform`], which decompresses, parses, rewrites, and recompresses the document one event at
+//! a time, so that memory use does not grow with the size of the submarine.
+//!
+//! ```
+//! use barotool::submarine::transform::transform;
+//!
+//! # fn main() -> barotool::Result<()> {
+//! # let mut sub = Vec::new();
+//! # barotool::submarine::compress(&br#"<Submarine><Item identifier="bench" /></Submarine>"#[..], &mut sub)?;
+//! let mut output = Vec::new();
+//! let summary = transform(&sub[..], &mut output, |entity| {
+//!     if entity.attribute("identifier") == Some("bench") {
+//!         entity.set_attribute("spritecolor", "255,0,0,255");
+//!     }
+//! })?;
+//! assert_eq!(summary.changed, 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::compression;
+use crate::diagnostic;
+use crate::error;
+use crate::limits;
+use crate::Result;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use xml_rs::attribute::OwnedAttribute;
+use xml_rs::common::XmlVersion;
+use xml_rs::name::OwnedName;
+use xml_rs::namespace::Namespace;
+use xml_rs::reader::{ParserConfig, XmlEvent};
+use xml_rs::writer::{EmitterConfig, EventWriter};
+
+/// The number of top-level elements changed by a transformation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Summary {
+	/// The number of elements whose attributes were changed.
+	pub changed: usize,
+
+	/// The number of elements removed, along with everything inside them.
+	pub removed: usize,
+}
+
+/// A child of the submarine’s root element, such as an item or structure, as seen by
+/// [`transform`].
+///
+/// Only the start tag is available; the element’s own children are passed through unchanged.
+#[derive(Clone, Debug)]
+pub struct Entity {
+	/// The element’s name.
+	name: OwnedName,
+
+	/// The element’s attributes, in document order.
+	attributes: Vec<OwnedAttribute>,
+
+	/// The namespace mappings in scope at the element.
+	namespace: Namespace,
+
+	/// Whether the element is to be removed.
+	removed: bool,
+}
+
+impl Entity {
+	/// Returns the element’s name, such as `Item` or `Structure`.
+	pub fn name(&self) -> &str {
+		&self.name.local_name
+	}
+
+	/// Returns whether the element has a particular name, compared case-insensitively.
+	pub fn is_named(&self, name: &str) -> bool {
+		self.name.local_name.eq_ignore_ascii_case(name)
+	}
+
+	/// Returns the value of an attribute, comparing names case-insensitively.
+	pub fn attribute(&self, name: &str) -> Option<&str> {
+		self.attributes
+			.iter()
+			.find(|i| i.name.local_name.eq_ignore_ascii_case(name))
+			.map(|i| i.value.as_str())
+	}
+
+	/// Sets the value of an attribute, replacing any existing attribute whose name matches
+	/// case-insensitively.
+	pub fn set_attribute(&mut self, name: &str, value: impl Into<String>) {
+		let value = value.into();
+		match self
+			.attributes
+			.iter_mut()
+			.find(|i| i.name.local_name.eq_ignore_ascii_case(name))
+		{
+			Some(attribute) => attribute.value = value,
+			None => self
+				.attributes
+				.push(OwnedAttribute::new(OwnedName::local(name), value)),
+		}
+	}
+
+	/// Marks the element to be left out of the output, along with everything inside it.
+	pub fn remove(&mut self) {
+		self.removed = true;
+	}
+}
+
+/// The path to the element being read, such as `/Submarine/Item[12]`, for reporting where a
+/// syntax error was found.
+#[derive(Default)]
+struct ElementPath {
+	/// The names of the enclosing elements, numbered among their siblings of the same name.
+	names: Vec<String>,
+
+	/// How many children of each name each enclosing element has had so far.
+	counts: Vec<HashMap<String, usize>>,
+}
+
+impl ElementPath {
+	/// Returns the number of enclosing elements.
+	fn depth(&self) -> usize {
+		self.names.len()
+	}
+
+	/// Records the start of an element.
+	fn enter(&mut self, name: &str) {
+		if self.names.is_empty() {
+			self.names.push(name.to_owned());
+		} else {
+			let count = self
+				.counts
+				.last_mut()
+				.unwrap()
+				.entry(name.to_owned())
+				.or_default();
+			*count += 1;
+			self.names.push(format!("{name}[{count}]"));
+		}
+		self.counts.push(HashMap::new());
+	}
+
+	/// Records the end of an element.
+	fn leave(&mut self) {
+		self.names.pop();
+		self.counts.pop();
+	}
+}
+
+impl Display for ElementPath {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		for name in &self.names {
+			write!(f, "/{name}")?;
+		}
+		Ok(())
+	}
+}
+
+/// Converts an XML writer error into an error.
+fn write_error(e: xml_rs::writer::Error) -> crate::Error {
+	match e {
+		xml_rs::writer::Error::Io(e) => e.into(),
+		e => diagnostic::invalid(e.to_string()),
+	}
+}
+
+/// Rewrites a gzip-compressed submarine document, offering each child of the root element to
+/// `edit` before it is written.
+///
+/// The output is indented and declared in the same way as by
+/// [`Submarine::write`](super::Submarine::write); comments are kept. If the document is not
+/// well-formed, the error says where; whatever was written by then is incomplete. Elements nested
+/// more deeply than the [`max_depth`](crate::limits::Limits::max_depth) in effect are refused.
+pub fn transform<R: Read, W: Write, F: FnMut(&mut Entity)>(
+	source: R,
+	sink: W,
+	mut edit: F,
+) -> Result<Summary> {
+	let reader = ParserConfig::new()
+		.ignore_comments(false)
+		.create_reader(compression::Decoder::new(source)?);
+	let mut writer = EventWriter::new_with_config(
+		compression::encoder(sink)?,
+		EmitterConfig::new()
+			.perform_indent(true)
+			.indent_string("  "),
+	);
+	let limits = limits::limits();
+	let mut summary = Summary::default();
+	let mut path = ElementPath::default();
+	// The depth of the element being left out, while its contents are skipped.
+	let mut skipping: Option<usize> = None;
+	for event in reader {
+		let event = event.map_err(|e| match e.kind() {
+			// Failures to decompress the document surface here, as those of its source.
+			xml_rs::reader::ErrorKind::Io(io) => {
+				error::gzip(std::io::Error::new(io.kind(), e.msg().to_owned()))
+			}
+			_ => diagnostic::syntax(e.to_string(), None, path.to_string()),
+		})?;
+		match event {
+			XmlEvent::StartDocument { .. } => writer
+				.write(xml_rs::writer::XmlEvent::StartDocument {
+					version: XmlVersion::Version10,
+					encoding: None,
+					standalone: None,
+				})
+				.map_err(write_error)?,
+			XmlEvent::StartElement {
+				name,
+				attributes,
+				namespace,
+			} => {
+				let depth = path.depth();
+				path.enter(&name.local_name);
+				limits::check_depth(&limits, path.depth())?;
+				if skipping.is_some() {
+					continue;
+				}
+				if depth == 0 && !name.local_name.eq_ignore_ascii_case("Submarine") {
+					return Err(diagnostic::invalid(format!(
+						"Expected Submarine root element, found {}",
+						name.local_name
+					)));
+				}
+				let mut entity = Entity {
+					name,
+					attributes,
+					namespace,
+					removed: false,
+				};
+				if depth == 1 {
+					let before = entity.attributes.clone();
+					edit(&mut entity);
+					if entity.removed {
+						summary.removed += 1;
+						skipping = Some(depth);
+						continue;
+					}
+					if entity.attributes != before {
+						summary.changed += 1;
+					}
+				}
+				let attributes: Vec<_> = entity
+					.attributes
+					.iter()
+					.map(OwnedAttribute::borrow)
+					.collect();
+				writer
+					.write(xml_rs::writer::XmlEvent::StartElement {
+						name: entity.name.borrow(),
+						attributes: Cow::Owned(attributes),
+						namespace: Cow::Borrowed(&entity.namespace),
+					})
+					.map_err(write_error)?;
+			}
+			XmlEvent::EndElement { name } => {
+				path.leave();
+				if skipping == Some(path.depth()) {
+					skipping = None;
+				} else if skipping.is_none() {
+					writer
+						.write(xml_rs::writer::XmlEvent::EndElement {
+							name: Some(name.borrow()),
+						})
+						.map_err(write_error)?;
+				}
+			}
+			// Indentation is written afresh, as when writing a parsed document.
+			XmlEvent::Whitespace(_) | XmlEvent::EndDocument => (),
+			event => {
+				if skipping.is_none() {
+					if let Some(event) = event.as_writer_event() {
+						writer.write(event).map_err(write_error)?;
+					}
+				}
+			}
+		}
+	}
+	writer.into_inner().finish()?.flush()?;
+	Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::submarine::{self, Submarine};
+
+	#[test]
+	fn test_matches_tree() {
+		let text = br#"<?xml version="1.0" encoding="utf-8"?>
+<Submarine name="Test">
+  <!-- A comment -->
+  <Item identifier="bench" ID="1" SpriteColor="1,1,1,1"><Holdable /></Item>
+  <Item identifier="lamp" ID="2" tags="decorative,light">
+    <LightComponent range="100" />
+  </Item>
+  <Structure identifier="wall" ID="3" />
+</Submarine>"#;
+		let mut sub = Vec::new();
+		submarine::compress(&text[..], &mut sub).unwrap();
+		let mut output = Vec::new();
+		let summary = transform(&sub[..], &mut output, |entity| {
+			if entity
+				.attribute("tags")
+				.is_some_and(|i| i.contains("decorative"))
+			{
+				entity.remove();
+			} else if entity.is_named("Item") {
+				entity.set_attribute("spritecolor", "255,0,0,255");
+			}
+		})
+		.unwrap();
+		assert_eq!(
+			summary,
+			Summary {
+				changed: 1,
+				removed: 1
+			}
+		);
+
+		let mut expected = Submarine::parse(&sub[..]).unwrap();
+		expected.root.children.retain(|i| {
+			i.as_element()
+				.is_none_or(|i| crate::xml::attribute(i, "identifier") != Some("lamp"))
+		});
+		let bench = crate::xml::children_mut(&mut expected.root, "Item")
+			.next()
+			.unwrap();
+		crate::xml::set_attribute(bench, "spritecolor", "255,0,0,255".to_owned());
+		let mut text = Vec::new();
+		crate::xml::write(&expected.root, &mut text).unwrap();
+		let mut actual = Vec::new();
+		submarine::decompress(&output[..], &mut actual).unwrap();
+		assert_eq!(
+			String::from_utf8(actual).unwrap(),
+			String::from_utf8(text).unwrap()
+		);
+
+		let mut bad = Vec::new();
+		submarine::compress(&b"<Submarine><Item></Submarine>"[..], &mut bad).unwrap();
+		let e = transform(&bad[..], Vec::new(), |_| ()).unwrap_err();
+		assert!(matches!(e, crate::Error::Xml(_)));
+	}
+}
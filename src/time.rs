@@ -0,0 +1,80 @@
+use std::fmt::{Display, Formatter};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A date and time in UTC, to the second.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct DateTime {
+	/// The year.
+	pub year: u64,
+
+	/// The month, from 1 to 12.
+	pub month: u64,
+
+	/// The day of the month, from 1 to 31.
+	pub day: u64,
+
+	/// The hour, from 0 to 23.
+	pub hour: u64,
+
+	/// The minute, from 0 to 59.
+	pub minute: u64,
+
+	/// The second, from 0 to 59.
+	pub second: u64,
+}
+
+impl DateTime {
+	/// Converts a point in time to a date and time.
+	///
+	/// Times before the Unix epoch are treated as the epoch.
+	pub fn from_system_time(time: SystemTime) -> Self {
+		let secs = time.duration_since(UNIX_EPOCH).map_or(0, |i| i.as_secs());
+		let days = secs / 86400;
+		let secs = secs % 86400;
+
+		// Convert days since the epoch to a civil date, per Howard Hinnant’s days_from_civil inverse.
+		let z = days + 719_468;
+		let era = z / 146_097;
+		let doe = z % 146_097;
+		let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+		let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+		let mp = (5 * doy + 2) / 153;
+		let day = doy - (153 * mp + 2) / 5 + 1;
+		let month = if mp < 10 { mp + 3 } else { mp - 9 };
+		let year = yoe + era * 400 + u64::from(month <= 2);
+
+		Self {
+			year,
+			month,
+			day,
+			hour: secs / 3600,
+			minute: secs / 60 % 60,
+			second: secs % 60,
+		}
+	}
+
+	/// Returns the current date and time.
+	pub fn now() -> Self {
+		Self::from_system_time(SystemTime::now())
+	}
+
+	/// Formats the date and time as a compact timestamp suitable for filenames, such as
+	/// `20240131T235959Z`.
+	pub fn compact(&self) -> String {
+		format!(
+			"{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+			self.year, self.month, self.day, self.hour, self.minute, self.second
+		)
+	}
+}
+
+impl Display for DateTime {
+	/// Formats the date and time in ISO 8601 form, such as `2024-01-31T23:59:59Z`.
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+			self.year, self.month, self.day, self.hour, self.minute, self.second
+		)
+	}
+}
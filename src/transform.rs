@@ -0,0 +1,55 @@
+use crate::selector::{Candidate, Selection};
+use crate::stream;
+use barotool::submarine::transform::Entity;
+use std::ffi::OsStr;
+use std::io::Result;
+
+/// What to do to each selected item or structure of a submarine.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Edits {
+	/// The attributes to set, as names and values.
+	pub attributes: Vec<(String, String)>,
+
+	/// Whether to remove the selected items and structures instead.
+	pub strip: bool,
+}
+
+impl Edits {
+	/// Applies the edits to an element, if it is an item or structure chosen by `selection`.
+	fn apply(&self, entity: &mut Entity, selection: &Selection) {
+		if !(entity.is_named("Item") || entity.is_named("Structure"))
+			|| !selection.matches(&Candidate::of_entity(entity))
+		{
+			return;
+		}
+		if self.strip {
+			entity.remove();
+		} else {
+			for (name, value) in &self.attributes {
+				entity.set_attribute(name, value.clone());
+			}
+		}
+	}
+}
+
+/// Rewrites a `.sub` file one element at a time, applying edits to the selected items and
+/// structures.
+pub fn transform(
+	input: &OsStr,
+	output: &OsStr,
+	edits: &Edits,
+	selection: &Selection,
+) -> Result<()> {
+	let summary = barotool::submarine::transform::transform(
+		stream::open_input(input)?,
+		stream::create_output(output)?,
+		|entity| edits.apply(entity, selection),
+	)?;
+	// Standard output may be holding the submarine, so the summary goes with the diagnostics.
+	log::info!(
+		"Changed {} and removed {} items and structures",
+		summary.changed,
+		summary.removed
+	);
+	Ok(())
+}
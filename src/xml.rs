@@ -0,0 +1,400 @@
+//! Helpers for working with XML documents the way Barotrauma reads and writes them.
+
+use crate::diagnostic::{self, Position};
+use crate::limits;
+use crate::{Error, Result};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::str::FromStr;
+use xml_rs::common::Position as _;
+use xml_rs::reader::XmlEvent;
+use xmlparser::{ElementEnd, Token, Tokenizer};
+use xmltree::{Element, EmitterConfig, ParserConfig, XMLNode};
+
+/// Parses an XML document, returning its root element.
+///
+/// If the document is not well-formed, the error reports the position at which parsing failed and
+/// the path to the element being read at the time. A document nested more deeply than the
+/// [`max_depth`](crate::limits::Limits::max_depth) in effect is refused before it is parsed.
+pub fn parse<R: Read>(mut source: R) -> Result<Element> {
+	let mut data = Vec::new();
+	source.read_to_end(&mut data)?;
+	let limits = limits::limits();
+	if let Some(max) = limits.max_depth {
+		limits::check_depth(&limits, depth(&data, max))?;
+	}
+	match Element::parse(&data[..]) {
+		Ok(root) => {
+			log::trace!("Parsed XML document with root element {}", root.name);
+			Ok(root)
+		}
+		Err(e) => Err(syntax_error(&data, &e)),
+	}
+}
+
+/// Returns the depth to which the elements of a document are nested, counting no further than one
+/// past `max`.
+///
+/// The document is tokenized by `xmlparser`, which is much faster than parsing it. Anything that
+/// tokenizer cannot handle, such as text in another encoding, is instead read by the same parser
+/// that builds the tree; a document that is not well-formed counts only as deep as it got before
+/// going wrong, as the tree is never built past that point.
+fn depth(data: &[u8], max: usize) -> usize {
+	let mut depth = 0;
+	let mut deepest = 0;
+	if let Ok(text) = std::str::from_utf8(data) {
+		let mut tokenized = true;
+		for token in Tokenizer::from(text) {
+			match token {
+				Ok(Token::ElementStart { .. }) => {
+					depth += 1;
+					deepest = deepest.max(depth);
+					if deepest > max {
+						return deepest;
+					}
+				}
+				Ok(Token::ElementEnd {
+					end: ElementEnd::Close(..) | ElementEnd::Empty,
+					..
+				}) => depth = depth.saturating_sub(1),
+				Ok(_) => (),
+				Err(_) => {
+					tokenized = false;
+					break;
+				}
+			}
+		}
+		if tokenized {
+			return deepest;
+		}
+		depth = 0;
+		deepest = 0;
+	}
+	for event in ParserConfig::new().create_reader(data) {
+		match event {
+			Ok(XmlEvent::StartElement { .. }) => {
+				depth += 1;
+				deepest = deepest.max(depth);
+				if deepest > max {
+					break;
+				}
+			}
+			Ok(XmlEvent::EndElement { .. }) => depth -= 1,
+			Ok(XmlEvent::EndDocument) | Err(_) => break,
+			Ok(_) => (),
+		}
+	}
+	deepest
+}
+
+/// Works out where in a document parsing failed.
+///
+/// The document is read again, keeping track of the open elements, until the failure is reached.
+fn syntax_error(data: &[u8], e: &xmltree::ParseError) -> Error {
+	let mut path: Vec<String> = Vec::new();
+	let mut counts: Vec<HashMap<String, usize>> = vec![HashMap::new()];
+	for event in ParserConfig::new().create_reader(data) {
+		match event {
+			Ok(XmlEvent::StartElement { name, .. }) => {
+				let count = counts
+					.last_mut()
+					.unwrap()
+					.entry(name.local_name.clone())
+					.or_default();
+				*count += 1;
+				path.push(if path.is_empty() {
+					name.local_name
+				} else {
+					format!("{}[{count}]", name.local_name)
+				});
+				counts.push(HashMap::new());
+			}
+			Ok(XmlEvent::EndElement { .. }) => {
+				path.pop();
+				counts.pop();
+			}
+			Ok(XmlEvent::EndDocument) => break,
+			Ok(_) => (),
+			Err(e) => {
+				let pos = e.position();
+				let path = if path.is_empty() {
+					String::new()
+				} else {
+					format!("/{}", path.join("/"))
+				};
+				return diagnostic::syntax(
+					e.msg().to_owned(),
+					Some(Position::of(data, pos.row, pos.column)),
+					path,
+				);
+			}
+		}
+	}
+	// The document is well-formed XML, but something else is wrong with it.
+	diagnostic::syntax(e.to_string(), None, String::new())
+}
+
+/// Writes an XML document with the given root element.
+///
+/// The document is indented with two spaces, as Barotrauma itself does.
+pub fn write<W: Write>(root: &Element, sink: W) -> Result<()> {
+	let config = EmitterConfig::new()
+		.perform_indent(true)
+		.indent_string("  ");
+	root.write_with_config(sink, config).map_err(|e| match e {
+		xmltree::Error::Io(e) => e.into(),
+		e => diagnostic::invalid(e.to_string()),
+	})
+}
+
+/// Checks whether an element has a particular name.
+///
+/// Barotrauma compares element names case-insensitively, so this does too.
+pub fn is_named(element: &Element, name: &str) -> bool {
+	element.name.eq_ignore_ascii_case(name)
+}
+
+/// Returns an iterator over the child elements of an element.
+pub fn elements(element: &Element) -> impl Iterator<Item = &Element> {
+	element.children.iter().filter_map(XMLNode::as_element)
+}
+
+/// Returns an iterator over the child elements of an element, mutably.
+pub fn elements_mut(element: &mut Element) -> impl Iterator<Item = &mut Element> {
+	element
+		.children
+		.iter_mut()
+		.filter_map(XMLNode::as_mut_element)
+}
+
+/// Returns an iterator over the child elements of an element that have a particular name.
+pub fn children<'e>(element: &'e Element, name: &'e str) -> impl Iterator<Item = &'e Element> {
+	elements(element).filter(move |i| is_named(i, name))
+}
+
+/// Returns an iterator over the child elements of an element that have a particular name,
+/// mutably.
+pub fn children_mut<'e>(
+	element: &'e mut Element,
+	name: &'e str,
+) -> impl Iterator<Item = &'e mut Element> {
+	elements_mut(element).filter(move |i| is_named(i, name))
+}
+
+/// Returns all the elements beneath an element, at any depth, that have a particular name.
+///
+/// Elements with the given name are not searched further; that is, if a matching element contains
+/// another matching element, only the outer one is returned.
+pub fn descendants<'e>(element: &'e Element, name: &str) -> Vec<&'e Element> {
+	let mut ret = Vec::new();
+	for child in elements(element) {
+		if is_named(child, name) {
+			ret.push(child);
+		} else {
+			ret.extend(descendants(child, name));
+		}
+	}
+	ret
+}
+
+/// Returns the largest entity ID of any `Item` element beneath an element, at any depth.
+pub fn max_item_id(element: &Element) -> Result<u16> {
+	let mut ret = 0;
+	for child in elements(element) {
+		if is_named(child, "Item") {
+			ret = ret.max(parse_attribute(child, "ID")?.unwrap_or(0));
+		}
+		ret = ret.max(max_item_id(child)?);
+	}
+	Ok(ret)
+}
+
+/// Returns the first child element of an element that has a particular name.
+pub fn child<'e>(element: &'e Element, name: &str) -> Option<&'e Element> {
+	elements(element).find(|i| is_named(i, name))
+}
+
+/// Returns the first child element of an element that has a particular name, mutably.
+pub fn child_mut<'e>(element: &'e mut Element, name: &str) -> Option<&'e mut Element> {
+	elements_mut(element).find(|i| is_named(i, name))
+}
+
+/// Returns the value of an attribute.
+///
+/// Attribute names are compared case-insensitively.
+pub fn attribute<'e>(element: &'e Element, name: &str) -> Option<&'e str> {
+	element
+		.attributes
+		.iter()
+		.find(|(k, _)| k.eq_ignore_ascii_case(name))
+		.map(|(_, v)| v.as_str())
+}
+
+/// Sets the value of an attribute.
+///
+/// If the attribute already exists under a name differing only in case, its value is replaced in
+/// place; otherwise the attribute is added at the end.
+pub fn set_attribute(element: &mut Element, name: &str, value: String) {
+	match element
+		.attributes
+		.iter_mut()
+		.find(|(k, _)| k.eq_ignore_ascii_case(name))
+	{
+		Some((_, v)) => *v = value,
+		None => {
+			element.attributes.insert(name.to_owned(), value);
+		}
+	}
+}
+
+/// Removes an attribute, returning whether it was present.
+///
+/// Attribute names are compared case-insensitively. The order of the remaining attributes is kept.
+pub fn remove_attribute(element: &mut Element, name: &str) -> bool {
+	let before = element.attributes.len();
+	element
+		.attributes
+		.retain(|k, _| !k.eq_ignore_ascii_case(name));
+	element.attributes.len() != before
+}
+
+/// Returns the value of an attribute, parsed into some type.
+///
+/// If the attribute is absent, `None` is returned. If it is present but cannot be parsed,
+/// [`Error::Invalid`] is returned, carrying a diagnostic that identifies the attribute.
+pub fn parse_attribute<T: FromStr>(element: &Element, name: &str) -> Result<Option<T>> {
+	match attribute(element, name) {
+		None => Ok(None),
+		Some(value) => match value.trim().parse() {
+			Ok(v) => Ok(Some(v)),
+			Err(_) => Err(diagnostic::attribute(
+				element,
+				name,
+				value,
+				format!(
+					"Invalid value “{}” for attribute {} of element {}",
+					value, name, element.name
+				),
+			)),
+		},
+	}
+}
+
+/// Formats a single-precision number as Barotrauma writes it.
+///
+/// .NET’s `ToString()` writes the shortest representation that reads back as the same number, as
+/// Rust’s `Display` does, but switches to scientific notation, such as `1E+07`, for numbers below
+/// 0.0001 and for those whose integer part has more than seven digits and more digits than are
+/// significant. Numbers formatted here are written as the game would write them, so that saves
+/// edited by barotool differ from the game’s own only where their values do.
+pub fn format_f32(value: f32) -> String {
+	format_general(&format!("{value:e}"), 7)
+}
+
+/// Formats a double-precision number as Barotrauma writes it.
+///
+/// This is as [`format_f32`], but switching to scientific notation past 15 digits.
+pub fn format_f64(value: f64) -> String {
+	format_general(&format!("{value:e}"), 15)
+}
+
+/// Formats a number, given in Rust’s shortest scientific notation, as .NET’s `ToString("G")` does
+/// for a type with the given default precision.
+fn format_general(scientific: &str, precision: usize) -> String {
+	let (negative, magnitude) = match scientific.strip_prefix('-') {
+		Some(magnitude) => (true, magnitude),
+		None => (false, scientific),
+	};
+	let sign = if negative { "-" } else { "" };
+	let Some((mantissa, exponent)) = magnitude.split_once('e') else {
+		return match magnitude {
+			"inf" => format!("{sign}Infinity"),
+			_ => "NaN".to_owned(),
+		};
+	};
+	let digits: String = mantissa.chars().filter(|&i| i != '.').collect();
+	if digits == "0" {
+		return format!("{sign}0");
+	}
+	// The position of the decimal point relative to the first digit.
+	let scale = exponent.parse::<isize>().unwrap() + 1;
+	let count = digits.len();
+	// Cast is sound because a number has far fewer digits than isize::MAX.
+	#[allow(clippy::cast_possible_wrap)]
+	let max_digits = count.max(precision) as isize;
+	if scale > max_digits || scale < -3 {
+		let exponent = scale - 1;
+		let exponent_sign = if exponent < 0 { '-' } else { '+' };
+		let fraction = if count > 1 {
+			format!(".{}", &digits[1..])
+		} else {
+			String::new()
+		};
+		format!(
+			"{sign}{}{fraction}E{exponent_sign}{:02}",
+			&digits[..1],
+			exponent.abs()
+		)
+	} else if scale > 0 {
+		let scale = scale.unsigned_abs();
+		if scale >= count {
+			format!("{sign}{digits}{}", "0".repeat(scale - count))
+		} else {
+			format!("{sign}{}.{}", &digits[..scale], &digits[scale..])
+		}
+	} else {
+		format!("{sign}0.{}{digits}", "0".repeat(scale.unsigned_abs()))
+	}
+}
+
+/// Returns the value of a Boolean attribute.
+///
+/// Barotrauma writes Booleans as `true` or `false` in any case; anything else is treated as
+/// invalid data.
+pub fn bool_attribute(element: &Element, name: &str) -> Result<Option<bool>> {
+	match attribute(element, name) {
+		None => Ok(None),
+		Some(value) if value.trim().eq_ignore_ascii_case("true") => Ok(Some(true)),
+		Some(value) if value.trim().eq_ignore_ascii_case("false") => Ok(Some(false)),
+		Some(value) => Err(diagnostic::attribute(
+			element,
+			name,
+			value,
+			format!(
+				"Invalid Boolean “{}” for attribute {} of element {}",
+				value, name, element.name
+			),
+		)),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_format_float() {
+		for (value, expected) in [
+			(0.0, "0"),
+			(-0.0, "-0"),
+			(1.0, "1"),
+			(-2.5, "-2.5"),
+			(0.1, "0.1"),
+			(0.0001, "0.0001"),
+			(0.000_012_5, "1.25E-05"),
+			(1_234_567.0, "1234567"),
+			(10_000_000.0, "1E+07"),
+			(16_777_216.0, "16777216"),
+			(123_456_790.0, "1.2345679E+08"),
+			(f32::INFINITY, "Infinity"),
+			(f32::NEG_INFINITY, "-Infinity"),
+			(f32::NAN, "NaN"),
+		] {
+			assert_eq!(format_f32(value), expected, "{value}");
+		}
+		assert_eq!(format_f64(0.1 + 0.2), "0.30000000000000004");
+		assert_eq!(format_f64(1e14), "100000000000000");
+		assert_eq!(format_f64(1e15), "1E+15");
+		assert_eq!(format_f64(1e-300), "1E-300");
+	}
+}